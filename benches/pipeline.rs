@@ -0,0 +1,78 @@
+//! Criterion benchmarks for the hot stages of the capture pipeline.
+//!
+//! Each stage runs on small/medium/large synthetic traces. The sizes can be
+//! scaled with `STYLUS_TRACE_BENCH_SCALE` (a float multiplier, default 1.0),
+//! e.g. `STYLUS_TRACE_BENCH_SCALE=0.1 cargo bench` for a quick pass.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde_json::{json, Value};
+use stylus_trace_studio::aggregator::build_collapsed_stacks;
+use stylus_trace_studio::flamegraph::generate_flamegraph;
+use stylus_trace_studio::parser::parse_trace;
+
+/// Base step counts before scaling
+const SIZES: [(&str, usize); 3] = [("small", 1_000), ("medium", 10_000), ("large", 100_000)];
+
+/// Opcode mix cycled through by the synthetic traces
+const OPS: [&str; 8] = ["PUSH1", "ADD", "MLOAD", "SLOAD", "MUL", "SSTORE", "CALL", "RETURN"];
+
+/// Read the fixture-size multiplier from the environment
+fn scale() -> f64 {
+    std::env::var("STYLUS_TRACE_BENCH_SCALE")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|scale: &f64| *scale > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// Build a synthetic trace with nested calls and HostIO events
+fn synthetic_trace(steps: usize) -> Value {
+    let struct_logs: Vec<Value> = (0..steps)
+        .map(|i| {
+            json!({
+                "pc": i,
+                "op": OPS[i % OPS.len()],
+                "gasCost": 3 + (i % 97) as u64,
+                "depth": 1 + (i / 50 % 4),
+                "function": format!("fn_{}", i / 200 % 16),
+            })
+        })
+        .collect();
+    let hostio: Vec<Value> = (0..steps / 20)
+        .map(|i| json!({ "type": if i % 2 == 0 { "storage_load" } else { "call" }, "gas": 100 + i }))
+        .collect();
+
+    json!({ "gasUsed": steps * 50, "structLogs": struct_logs, "hostio": hostio })
+}
+
+fn bench_stages(c: &mut Criterion) {
+    let scale = scale();
+
+    for (label, base) in SIZES {
+        let steps = ((base as f64 * scale) as usize).max(1);
+        let raw = synthetic_trace(steps);
+        let parsed = parse_trace("0xbench", &raw).expect("synthetic trace parses");
+        let stacks = build_collapsed_stacks(&parsed);
+
+        let mut group = c.benchmark_group(label);
+        group.throughput(Throughput::Elements(steps as u64));
+        if steps >= 100_000 {
+            group.sample_size(10);
+        }
+
+        group.bench_with_input(BenchmarkId::new("parse_trace", steps), &raw, |b, raw| {
+            b.iter(|| parse_trace("0xbench", black_box(raw)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("build_collapsed_stacks", steps), &parsed, |b, parsed| {
+            b.iter(|| build_collapsed_stacks(black_box(parsed)))
+        });
+        group.bench_with_input(BenchmarkId::new("generate_flamegraph", steps), &stacks, |b, stacks| {
+            b.iter(|| generate_flamegraph(black_box(stacks), None).unwrap())
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_stages);
+criterion_main!(benches);
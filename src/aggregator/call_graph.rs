@@ -0,0 +1,110 @@
+//! Caller→callee edge list derived from collapsed stacks.
+//!
+//! Complements the tree-shaped flamegraph with a graph view: every pair of
+//! consecutive frames in a stack is an edge carrying that stack's gas.
+//! Edges are counted once per stack, so recursion (`a;b;a;b`) does not
+//! double-count, and self-edges (`a;a`) are kept and flagged explicitly.
+
+use super::stack_builder::{build_collapsed_stacks, CollapsedStack};
+use crate::parser::ParsedTrace;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A gas-weighted edge in the call graph
+///
+/// **Public** - returned by `build_call_graph`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallEdge {
+    /// Calling frame
+    pub caller: String,
+
+    /// Called frame
+    pub callee: String,
+
+    /// Gas of all stacks passing through this edge
+    pub gas: u64,
+}
+
+impl CallEdge {
+    /// Whether the edge is a direct recursive call (caller == callee)
+    pub fn is_self_edge(&self) -> bool {
+        self.caller == self.callee
+    }
+}
+
+/// Build the call graph of a parsed trace
+///
+/// **Public** - main entry point for graph export
+///
+/// # Arguments
+/// * `parsed_trace` - Parsed trace data
+///
+/// # Returns
+/// Edges sorted by gas (descending), then caller/callee
+pub fn build_call_graph(parsed_trace: &ParsedTrace) -> Vec<CallEdge> {
+    call_graph_from_stacks(&build_collapsed_stacks(parsed_trace))
+}
+
+/// Build a call graph from already-collapsed stacks
+///
+/// **Public** - use when stacks were built with custom options
+pub fn call_graph_from_stacks(stacks: &[CollapsedStack]) -> Vec<CallEdge> {
+    let mut edges: BTreeMap<(String, String), u64> = BTreeMap::new();
+
+    for stack in stacks {
+        let frames: Vec<&str> = stack.stack.split(';').collect();
+
+        // Count each distinct edge once per stack
+        let unique: BTreeSet<(&str, &str)> = frames
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect();
+
+        for (caller, callee) in unique {
+            *edges.entry((caller.to_string(), callee.to_string())).or_insert(0) += stack.weight;
+        }
+    }
+
+    let mut graph: Vec<CallEdge> = edges
+        .into_iter()
+        .map(|((caller, callee), gas)| CallEdge { caller, callee, gas })
+        .collect();
+
+    graph.sort_by(|a, b| {
+        b.gas
+            .cmp(&a.gas)
+            .then_with(|| a.caller.cmp(&b.caller))
+            .then_with(|| a.callee.cmp(&b.callee))
+    });
+
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edges_aggregate_and_recursion_counted_once() {
+        let stacks = vec![
+            CollapsedStack::new("main;a;b".to_string(), 10),
+            CollapsedStack::new("main;a".to_string(), 5),
+            CollapsedStack::new("main;a;b;a;b".to_string(), 4),
+            CollapsedStack::new("main;f;f".to_string(), 3),
+        ];
+
+        let graph = call_graph_from_stacks(&stacks);
+        let gas = |caller: &str, callee: &str| {
+            graph
+                .iter()
+                .find(|e| e.caller == caller && e.callee == callee)
+                .map(|e| e.gas)
+        };
+
+        assert_eq!(gas("main", "a"), Some(19));
+        assert_eq!(gas("a", "b"), Some(14));
+        assert_eq!(gas("b", "a"), Some(4));
+        assert_eq!(gas("f", "f"), Some(3));
+        assert!(graph.iter().find(|e| e.caller == "f").unwrap().is_self_edge());
+        assert_eq!(graph[0].gas, 19);
+    }
+}
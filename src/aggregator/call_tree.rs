@@ -0,0 +1,115 @@
+//! Nested call tree derived from collapsed stacks.
+//!
+//! Collapsed stacks flatten the hierarchy into one line per path; this
+//! rebuilds it as `{name, gas, children}` nodes for consumers that want
+//! to walk the tree (custom visualizations, JSON tooling). Gas is
+//! inclusive, so a node's children never sum to more than the node.
+
+use super::stack_builder::{CollapsedStack, DEFAULT_FRAME_SEPARATOR};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Name of the synthetic node every top-level frame hangs under
+pub const CALL_TREE_ROOT: &str = "root";
+
+/// A frame in the call tree
+///
+/// **Public** - returned by `build_tree` and stored in profiles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallTreeNode {
+    /// Frame name
+    pub name: String,
+
+    /// Inclusive gas: this frame's own gas plus all of its children
+    pub gas: u64,
+
+    /// Callees, heaviest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<CallTreeNode>,
+}
+
+/// Build the nested call tree of a set of collapsed stacks
+///
+/// **Public** - main entry point
+///
+/// Stacks are split on the frame separator; each stack's weight is added
+/// to every frame along its path. Siblings are ordered by gas (heaviest
+/// first), then by name, so the tree is deterministic.
+///
+/// # Returns
+/// A `CALL_TREE_ROOT` node whose gas is the total weight
+pub fn build_tree(stacks: &[CollapsedStack]) -> CallTreeNode {
+    let mut root = Builder::default();
+
+    for stack in stacks {
+        root.gas += stack.weight;
+        let mut node = &mut root;
+        for frame in stack.stack.split(DEFAULT_FRAME_SEPARATOR) {
+            node = node.children.entry(frame.to_string()).or_default();
+            node.gas += stack.weight;
+        }
+    }
+
+    root.finish(CALL_TREE_ROOT.to_string())
+}
+
+/// Mutable node used while merging stacks
+///
+/// **Private** - children keyed by name so shared prefixes merge
+#[derive(Default)]
+struct Builder {
+    gas: u64,
+    children: BTreeMap<String, Builder>,
+}
+
+impl Builder {
+    fn finish(self, name: String) -> CallTreeNode {
+        let mut children: Vec<CallTreeNode> = self
+            .children
+            .into_iter()
+            .map(|(name, child)| child.finish(name))
+            .collect();
+        children.sort_by(|a, b| b.gas.cmp(&a.gas).then_with(|| a.name.cmp(&b.name)));
+
+        CallTreeNode {
+            name,
+            gas: self.gas,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_children_within_parent(node: &CallTreeNode) {
+        let child_gas: u64 = node.children.iter().map(|child| child.gas).sum();
+        assert!(child_gas <= node.gas, "{} children exceed parent", node.name);
+        node.children.iter().for_each(assert_children_within_parent);
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let stacks = vec![
+            CollapsedStack::new("main".to_string(), 10),
+            CollapsedStack::new("main;a;SLOAD".to_string(), 2100),
+            CollapsedStack::new("main;a".to_string(), 5),
+            CollapsedStack::new("main;b".to_string(), 300),
+        ];
+
+        let tree = build_tree(&stacks);
+
+        assert_eq!(tree.name, CALL_TREE_ROOT);
+        assert_eq!(tree.gas, 2415);
+        let main = &tree.children[0];
+        assert_eq!((main.name.as_str(), main.gas), ("main", 2415));
+        assert_eq!(main.children[0].name, "a");
+        assert_eq!(main.children[0].gas, 2105);
+        assert_eq!(main.children[0].children[0].gas, 2100);
+        assert_eq!(main.children[1].name, "b");
+        assert_children_within_parent(&tree);
+
+        assert_eq!(build_tree(&[]).gas, 0);
+    }
+}
@@ -0,0 +1,43 @@
+//! Demangling of Rust symbol names in frame labels.
+//!
+//! Stylus contracts are compiled from Rust, so `function` fields in traces
+//! often carry mangled symbols such as `_ZN8contract8transfer17h…E`.
+//! These are rewritten to their readable path (`contract::transfer`),
+//! dropping the trailing hash. Non-mangled names pass through unchanged.
+
+/// Demangle a Rust symbol into its readable path
+///
+/// **Public** - used by the stack builder, usable as a frame transformer
+///
+/// # Arguments
+/// * `name` - Raw frame name (mangled or not)
+///
+/// # Returns
+/// Demangled path without hash suffix, or `name` unchanged if it is not
+/// a mangled Rust symbol
+pub fn demangle_frame(name: &str) -> String {
+    match rustc_demangle::try_demangle(name) {
+        Ok(symbol) => format!("{:#}", symbol),
+        Err(_) => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_demangle_legacy_symbol() {
+        assert_eq!(
+            demangle_frame("_ZN8contract8transfer17h0123456789abcdefE"),
+            "contract::transfer"
+        );
+    }
+
+    #[test]
+    fn test_plain_names_pass_through() {
+        assert_eq!(demangle_frame("transfer"), "transfer");
+        assert_eq!(demangle_frame("SLOAD"), "SLOAD");
+        assert_eq!(demangle_frame("_Z"), "_Z");
+    }
+}
@@ -0,0 +1,257 @@
+//! Plain-language explanations of why a hot path is hot.
+//!
+//! Combines each hot path with the execution steps that ran under it (via
+//! `stacks_per_step`) and names the operation holding most of its gas,
+//! e.g. "dominated by 3 storage writes (SSTORE) at 20,000 gas each". Usage
+//! is indexed by every stack prefix once, so each hot path is one lookup. The
+//! synthetic `hostio;<Type>` paths are explained from the HostIO
+//! breakdown instead, since HostIO events carry no position in the trace.
+
+use super::stack_builder::{stacks_per_step, StackBuildOptions, HOSTIO_ROOT};
+use crate::output::Redactor;
+use crate::parser::schema::HotPath;
+use crate::parser::ParsedTrace;
+use crate::utils::format::format_gas;
+use crate::utils::opcodes::opcode_category;
+use std::collections::{BTreeMap, HashMap};
+
+/// Calls and gas of one operation within a path
+#[derive(Debug, Clone, Copy, Default)]
+struct OpUsage {
+    count: u64,
+    gas: u64,
+
+    /// Whether every call cost the same
+    uniform: bool,
+}
+
+/// Explain each hot path in `hot_paths`
+///
+/// **Public** - formatting layer for `capture --explain`
+///
+/// # Arguments
+/// * `parsed_trace` - Parsed trace the hot paths were computed from
+/// * `options` - Stack options the hot paths were built with
+/// * `hot_paths` - Hot paths to explain
+/// * `redactor` - Redactor the hot paths' frames went through, if any
+///
+/// # Returns
+/// One entry per hot path; `None` where no steps ran under the path
+/// (e.g. the merged `other` stack or coalesced `op (×N)` frames)
+pub fn explain_hot_paths(
+    parsed_trace: &ParsedTrace,
+    options: &StackBuildOptions,
+    hot_paths: &[HotPath],
+    redactor: Option<&Redactor>,
+) -> Vec<Option<String>> {
+    let usage = op_usage_by_prefix(parsed_trace, options, redactor);
+
+    hot_paths
+        .iter()
+        .map(|path| match path.stack.strip_prefix(HOSTIO_ROOT) {
+            Some(rest) if rest.is_empty() || rest.starts_with(';') => {
+                explain_hostio_path(parsed_trace, rest.trim_start_matches(';'))
+            }
+            _ => usage.get(&path.stack).and_then(explain_step_path),
+        })
+        .collect()
+}
+
+/// Per-op usage of the steps under every stack prefix (a frame and its
+/// subtree), keyed like the hot paths
+///
+/// **Private** - built once, then looked up per hot path
+fn op_usage_by_prefix(
+    parsed_trace: &ParsedTrace,
+    options: &StackBuildOptions,
+    redactor: Option<&Redactor>,
+) -> HashMap<String, BTreeMap<String, OpUsage>> {
+    let mut by_stack: HashMap<String, BTreeMap<String, OpUsage>> = HashMap::new();
+
+    let stacks = stacks_per_step(parsed_trace, options);
+    for (stack, step) in stacks.into_iter().zip(&parsed_trace.execution_steps) {
+        let Some(op) = step.op.as_deref() else {
+            continue;
+        };
+        let entry = by_stack.entry(stack).or_default().entry(op.to_string()).or_default();
+        entry.uniform = entry.count == 0 || (entry.uniform && entry.gas == step.gas_cost * entry.count);
+        entry.count += 1;
+        entry.gas += step.gas_cost;
+    }
+
+    let mut by_prefix: HashMap<String, BTreeMap<String, OpUsage>> = HashMap::new();
+    for (stack, ops) in by_stack {
+        let stack = match redactor {
+            Some(redactor) => redactor.redact_str(&stack),
+            None => stack,
+        };
+        let prefix_ends = stack.match_indices(';').map(|(i, _)| i).chain([stack.len()]);
+        for end in prefix_ends {
+            let merged = by_prefix.entry(stack[..end].to_string()).or_default();
+            for (op, op_usage) in &ops {
+                merge_usage(merged.entry(op.clone()).or_default(), *op_usage);
+            }
+        }
+    }
+
+    by_prefix
+}
+
+/// Fold `usage` into `merged`
+///
+/// **Private** - stays uniform only if both were uniform at the same cost
+fn merge_usage(merged: &mut OpUsage, usage: OpUsage) {
+    let same_average = merged.gas * usage.count == usage.gas * merged.count;
+    merged.uniform = usage.uniform && (merged.count == 0 || (merged.uniform && same_average));
+    merged.count += usage.count;
+    merged.gas += usage.gas;
+}
+
+/// Explanation of a path from the per-op usage of its subtree
+///
+/// **Private** - names the op with the most gas
+fn explain_step_path(ops: &BTreeMap<String, OpUsage>) -> Option<String> {
+    let total: u64 = ops.values().map(|op| op.gas).sum();
+    let (op, top) = ops
+        .iter()
+        .max_by(|a, b| a.1.gas.cmp(&b.1.gas).then_with(|| b.0.cmp(a.0)))?;
+
+    let lead = if top.gas * 2 >= total { "dominated by" } else { "led by" };
+    Some(format!("{} {} {}", lead, describe_op(op, top.count), cost_phrase(*top)))
+}
+
+/// Explanation of a synthetic HostIO path from the HostIO breakdown
+///
+/// **Private** - `io_type` is the `{:?}` name after `hostio;`, or empty
+/// for the collapsed `hostio` frame
+fn explain_hostio_path(parsed_trace: &ParsedTrace, io_type: &str) -> Option<String> {
+    let stats = &parsed_trace.hostio_stats;
+
+    if io_type.is_empty() {
+        let count = stats.total_calls();
+        return (count > 0).then(|| {
+            format!("{} HostIO calls averaging {} gas", count, format_gas(stats.total_gas() / count))
+        });
+    }
+
+    let (kind, gas) = stats
+        .gas_by_type()
+        .into_iter()
+        .find(|(kind, _)| format!("{:?}", kind) == io_type)?;
+    let count = stats.count_for_type(kind);
+
+    (count > 0).then(|| {
+        format!("{} {} HostIO calls averaging {} gas", count, io_type, format_gas(gas / count))
+    })
+}
+
+/// What `count` executions of `op` are, in words
+///
+/// **Private** - well-known costly ops get a name, others their category
+fn describe_op(op: &str, count: u64) -> String {
+    let plural = count != 1;
+    let noun = match op.to_ascii_uppercase().as_str() {
+        "SSTORE" | "TSTORE" => if plural { "storage writes" } else { "storage write" },
+        "SLOAD" | "TLOAD" => if plural { "storage reads" } else { "storage read" },
+        "CALL" | "STATICCALL" | "DELEGATECALL" | "CALLCODE" => {
+            if plural { "external calls" } else { "external call" }
+        }
+        "CREATE" | "CREATE2" => if plural { "contract creations" } else { "contract creation" },
+        "KECCAK256" | "SHA3" => if plural { "hashes" } else { "hash" },
+        _ => {
+            let category = opcode_category(op).name();
+            return format!("{} {} op{} ({})", count, category, if plural { "s" } else { "" }, op);
+        }
+    };
+    format!("{} {} ({})", count, noun, op)
+}
+
+/// `at N gas each` for uniform costs, `averaging N gas` otherwise
+///
+/// **Private** - internal formatting
+fn cost_phrase(usage: OpUsage) -> String {
+    let average = usage.gas / usage.count.max(1);
+    if usage.uniform || usage.count == 1 {
+        format!("at {} gas each", format_gas(average))
+    } else {
+        format!("averaging {} gas", format_gas(average))
+    }
+}
+
+/// Render hot paths with their explanations as text
+///
+/// **Public** - the `--explain` section of the capture summary
+pub fn format_explanations(hot_paths: &[HotPath], explanations: &[Option<String>]) -> String {
+    let mut output = String::from("Why these paths are hot:\n");
+
+    for (i, (path, explanation)) in hot_paths.iter().zip(explanations).enumerate() {
+        output.push_str(&format!(
+            "{:>3}. {} ({} gas, {}%)\n",
+            i + 1,
+            path.stack,
+            format_gas(path.gas),
+            path.percentage
+        ));
+        output.push_str(&format!(
+            "     {}\n",
+            explanation.as_deref().unwrap_or("no per-step data for this path")
+        ));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregator::{build_collapsed_stacks, calculate_hot_paths};
+
+    #[test]
+    fn test_explain_hot_paths() {
+        let raw = serde_json::json!({ "gasUsed": 60000, "structLogs": [
+            { "pc": 0, "op": "SSTORE", "gasCost": 20000, "depth": 1 },
+            { "pc": 1, "op": "SSTORE", "gasCost": 20000, "depth": 1 },
+            { "pc": 2, "op": "SSTORE", "gasCost": 20000, "depth": 1 },
+            { "pc": 3, "op": "ADD", "gasCost": 3, "depth": 1 },
+            { "pc": 4, "op": "ADD", "gasCost": 5, "depth": 1 }
+        ]});
+        let trace = crate::parser::parse_trace("0x1", &raw).unwrap();
+        let hot_paths = calculate_hot_paths(&build_collapsed_stacks(&trace), trace.total_gas_used, 2);
+
+        let explanations = explain_hot_paths(&trace, &StackBuildOptions::default(), &hot_paths, None);
+
+        assert_eq!(
+            explanations[0].as_deref(),
+            Some("dominated by 3 storage writes (SSTORE) at 20,000 gas each")
+        );
+        assert_eq!(
+            explanations[1].as_deref(),
+            Some("dominated by 2 arithmetic ops (ADD) averaging 4 gas")
+        );
+        assert!(format_explanations(&hot_paths, &explanations).contains("  1. call;SSTORE"));
+    }
+
+    #[test]
+    fn test_explain_redacted_hot_paths() {
+        let address = "0x000000000000000000000000c0ffee0000000000000000000000000000000001";
+        let raw = serde_json::json!({ "gasUsed": 40000, "structLogs": [
+            { "pc": 0, "op": "CREATE2", "gasCost": 32000, "depth": 1, "stack": [] },
+            { "pc": 0, "op": "SSTORE", "gasCost": 5000, "depth": 2, "stack": [] },
+            { "pc": 1, "op": "POP", "gasCost": 2, "depth": 1, "stack": [address] }
+        ]});
+        let with_state = crate::parser::ParseOptions { capture_state: true, ..Default::default() };
+        let trace = crate::parser::parse_trace_with_options("0x1", &raw, &with_state).unwrap();
+        let stacks = build_collapsed_stacks(&trace);
+        let redactor = Redactor::from_stacks(&stacks, "0x1");
+        let mut hot_paths = calculate_hot_paths(&redactor.redact_stacks(&stacks), trace.total_gas_used, 3);
+        hot_paths.retain(|path| path.stack.starts_with("call;create:"));
+        hot_paths[0].stack = "call;create:contract_1".to_string();
+
+        let explanations = explain_hot_paths(&trace, &StackBuildOptions::default(), &hot_paths, Some(&redactor));
+
+        assert_eq!(
+            explanations[0].as_deref(),
+            Some("dominated by 1 storage write (SSTORE) at 5,000 gas each")
+        );
+    }
+}
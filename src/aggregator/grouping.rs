@@ -0,0 +1,98 @@
+//! Regex rules for grouping similarly-named frames.
+//!
+//! A rule has the form `pattern=>replacement`, e.g.
+//! `storage_load_.*=>storage_load`. The replacement may reference
+//! capture groups (`$1`, `${name}`). Rules are applied in order to each
+//! frame name before aggregation, so grouped frames sum together.
+
+use crate::utils::error::ConfigError;
+use regex::Regex;
+
+/// Separator between pattern and replacement in a rule string
+const RULE_SEPARATOR: &str = "=>";
+
+/// A single frame-renaming rule
+///
+/// **Public** - stored in `StackBuildOptions::group_rules`
+#[derive(Debug, Clone)]
+pub struct GroupRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl GroupRule {
+    /// Create a rule from a pattern and replacement
+    ///
+    /// **Public** - constructor
+    pub fn new(pattern: &str, replacement: impl Into<String>) -> Result<Self, ConfigError> {
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            replacement: replacement.into(),
+        })
+    }
+    
+    /// Parse a `pattern=>replacement` rule string
+    ///
+    /// **Public** - used for `--group-regex` CLI values
+    pub fn parse(rule: &str) -> Result<Self, ConfigError> {
+        let (pattern, replacement) = rule.split_once(RULE_SEPARATOR).ok_or_else(|| {
+            ConfigError::InvalidRule(format!(
+                "'{}' (expected 'pattern{}replacement')",
+                rule, RULE_SEPARATOR
+            ))
+        })?;
+        
+        if pattern.is_empty() {
+            return Err(ConfigError::InvalidRule(format!("'{}' has an empty pattern", rule)));
+        }
+        
+        Self::new(pattern, replacement)
+    }
+    
+    /// Apply this rule to a frame name
+    ///
+    /// **Public** - returns the name unchanged if the pattern doesn't match
+    pub fn apply(&self, name: &str) -> String {
+        self.pattern.replace_all(name, self.replacement.as_str()).into_owned()
+    }
+}
+
+/// Apply rules in order, each to the output of the previous one
+///
+/// **Public** - used by the stack builder for every frame name
+pub fn apply_group_rules(rules: &[GroupRule], name: &str) -> String {
+    rules
+        .iter()
+        .fold(name.to_string(), |current, rule| rule.apply(&current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = GroupRule::parse("storage_load_.*=>storage_load").unwrap();
+        assert_eq!(rule.apply("storage_load_0xabc"), "storage_load");
+        assert_eq!(rule.apply("call"), "call");
+    }
+
+    #[test]
+    fn test_parse_rule_invalid() {
+        assert!(GroupRule::parse("no separator").is_err());
+        assert!(GroupRule::parse("=>empty").is_err());
+        assert!(GroupRule::parse("([unclosed=>x").is_err());
+    }
+
+    #[test]
+    fn test_capture_groups_and_ordering() {
+        let rules = vec![
+            GroupRule::parse(r"^(\w+)_0x[0-9a-f]+$=>$1").unwrap(),
+            GroupRule::parse("^storage_(load|store)$=>storage").unwrap(),
+        ];
+        
+        assert_eq!(apply_group_rules(&rules, "storage_load_0xdead"), "storage");
+        assert_eq!(apply_group_rules(&rules, "transfer_0xbeef"), "transfer");
+        assert_eq!(apply_group_rules(&rules, "main"), "main");
+    }
+}
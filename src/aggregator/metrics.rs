@@ -4,9 +4,16 @@
 //! These are the primary targets for optimization.
 
 use crate::parser::schema::HotPath;
+use crate::utils::hash::sha256_hex;
 use super::stack_builder::CollapsedStack;
 use log::debug;
 
+/// Decimal places kept in `HotPath.percentage` by default
+pub const DEFAULT_PERCENT_PRECISION: u32 = 2;
+
+/// Most decimal places a percentage is rounded to
+pub const MAX_PERCENT_PRECISION: u32 = 10;
+
 /// Calculate hot paths from collapsed stacks
 ///
 /// **Public** - main entry point for metrics calculation
@@ -14,33 +21,72 @@ use log::debug;
 /// # Arguments
 /// * `stacks` - Collapsed stacks from stack_builder
 /// * `total_gas` - Total gas used by transaction
-/// * `top_n` - Number of top paths to return (e.g., 10)
+/// * `top_n` - Number of top paths to return (e.g., 10); `0` means all
 ///
 /// # Returns
-/// Vector of hot paths, sorted by gas consumption (descending)
+/// Vector of hot paths, sorted by gas consumption (descending). With
+/// `top_n == 0` every stack becomes a hot path.
 pub fn calculate_hot_paths(
     stacks: &[CollapsedStack],
     total_gas: u64,
     top_n: usize,
+) -> Vec<HotPath> {
+    calculate_hot_paths_with_precision(stacks, total_gas, top_n, DEFAULT_PERCENT_PRECISION)
+}
+
+/// Calculate hot paths with percentages rounded to `precision` decimals
+///
+/// **Public** - like `calculate_hot_paths`, for `--percent-precision`
+///
+/// # Arguments
+/// * `stacks` - Collapsed stacks from stack_builder
+/// * `total_gas` - Total gas used by transaction
+/// * `top_n` - Number of top paths to return; `0` means all
+/// * `precision` - Decimal places, see `round_percentage`
+pub fn calculate_hot_paths_with_precision(
+    stacks: &[CollapsedStack],
+    total_gas: u64,
+    top_n: usize,
+    precision: u32,
 ) -> Vec<HotPath> {
     debug!("Calculating top {} hot paths from {} stacks", top_n, stacks.len());
     
+    let limit = if top_n == 0 { stacks.len() } else { top_n };
+    
     // Stacks are already sorted by weight from stack_builder
     // Just take the top N and convert to HotPath format
     stacks
         .iter()
-        .take(top_n)
-        .map(|stack| create_hot_path(stack, total_gas))
+        .take(limit)
+        .map(|stack| create_hot_path(stack, total_gas, precision))
         .collect()
 }
 
+/// Round a percentage to `precision` decimal places
+///
+/// **Public** - values too small to show at that precision keep two
+/// significant digits instead (0.000123 stays 0.00012 rather than 0.00),
+/// up to `MAX_PERCENT_PRECISION` decimals
+pub fn round_percentage(value: f64, precision: u32) -> f64 {
+    if value <= 0.0 || !value.is_finite() {
+        return value;
+    }
+    
+    let significant = 1 - value.log10().floor() as i32;
+    let decimals = significant
+        .max(precision as i32)
+        .min(MAX_PERCENT_PRECISION as i32);
+    let scale = 10f64.powi(decimals);
+    (value * scale).round() / scale
+}
+
 /// Create a HotPath from a CollapsedStack
 ///
 /// **Private** - internal conversion
-fn create_hot_path(stack: &CollapsedStack, total_gas: u64) -> HotPath {
+fn create_hot_path(stack: &CollapsedStack, total_gas: u64, precision: u32) -> HotPath {
     // Calculate percentage of total gas
     let percentage = if total_gas > 0 {
-        (stack.weight as f64 / total_gas as f64) * 100.0
+        round_percentage((stack.weight as f64 / total_gas as f64) * 100.0, precision)
     } else {
         0.0
     };
@@ -164,6 +210,31 @@ impl GasDistribution {
     }
 }
 
+/// Compute a deterministic fingerprint for a profile
+///
+/// **Public** - stored as `Profile::fingerprint`
+///
+/// Stacks are canonicalized (sorted by name, one `stack weight` line
+/// each) before hashing, so input order never matters. Timestamps are
+/// excluded, so two captures of the same trace always match.
+///
+/// # Arguments
+/// * `stacks` - Collapsed stacks the profile was built from
+/// * `total_gas` - Total gas used by transaction
+///
+/// # Returns
+/// Hex-encoded SHA-256 digest
+pub fn compute_fingerprint(stacks: &[CollapsedStack], total_gas: u64) -> String {
+    let mut lines: Vec<String> = stacks
+        .iter()
+        .map(|stack| format!("{} {}", stack.stack, stack.weight))
+        .collect();
+    lines.sort();
+    
+    let canonical = format!("total_gas {}\n{}", total_gas, lines.join("\n"));
+    sha256_hex(canonical.as_bytes())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,6 +254,21 @@ mod tests {
         assert_eq!(hot_paths[0].stack, "main;execute");
         assert_eq!(hot_paths[0].gas, 5000);
         assert_eq!(hot_paths[0].percentage, 50.0);
+        
+        // 0 means all paths
+        assert_eq!(calculate_hot_paths(&stacks, 10000, 0).len(), 3);
+    }
+
+    #[test]
+    fn test_round_percentage() {
+        assert_eq!(round_percentage(12.3456789012, 2), 12.35);
+        assert_eq!(round_percentage(12.3456789012, 0), 12.0);
+        assert_eq!(round_percentage(0.000123456, 2), 0.00012);
+        assert_eq!(round_percentage(0.0, 2), 0.0);
+        
+        let stacks = vec![CollapsedStack::new("main".to_string(), 1), CollapsedStack::new("tail".to_string(), 2)];
+        let hot_paths = calculate_hot_paths_with_precision(&stacks, 3, 0, 1);
+        assert_eq!(hot_paths[0].percentage, 33.3);
     }
 
     #[test]
@@ -213,11 +299,24 @@ mod tests {
     #[test]
     fn test_create_hot_path() {
         let stack = CollapsedStack::new("test;path".to_string(), 2500);
-        let hot_path = create_hot_path(&stack, 10000);
+        let hot_path = create_hot_path(&stack, 10000, DEFAULT_PERCENT_PRECISION);
         
         assert_eq!(hot_path.stack, "test;path");
         assert_eq!(hot_path.gas, 2500);
         assert_eq!(hot_path.percentage, 25.0);
         assert!(hot_path.source_hint.is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_fingerprint_ignores_stack_order() {
+        let a = vec![
+            CollapsedStack::new("main;execute".to_string(), 5000),
+            CollapsedStack::new("main;storage".to_string(), 3000),
+        ];
+        let b: Vec<CollapsedStack> = a.iter().rev().cloned().collect();
+        
+        assert_eq!(compute_fingerprint(&a, 8000), compute_fingerprint(&b, 8000));
+        assert_ne!(compute_fingerprint(&a, 8000), compute_fingerprint(&a, 8001));
+        assert_eq!(compute_fingerprint(&a, 8000).len(), 64);
+    }
+}
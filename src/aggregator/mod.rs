@@ -4,10 +4,38 @@
 //! - Collapsed stack format (for flamegraph generation)
 //! - Hot path analysis (top gas consumers)
 //! - Gas distribution statistics
+//! - Cumulative aggregates persisted as folded stacks
+//! - Structural similarity between two stack trees
+//! - Nested call tree for hierarchical consumers
+//! - Plain-language explanations of hot paths
 
+pub mod call_graph;
+pub mod call_tree;
+pub mod demangle;
+pub mod explain;
+pub mod grouping;
+pub mod similarity;
+pub mod stack_aggregator;
 pub mod stack_builder;
 pub mod metrics;
 
 // Re-export main types and functions
-pub use stack_builder::{CollapsedStack, build_collapsed_stacks, merge_small_stacks};
-pub use metrics::{calculate_hot_paths, calculate_gas_distribution, GasDistribution};
\ No newline at end of file
+pub use call_graph::{build_call_graph, call_graph_from_stacks, CallEdge};
+pub use call_tree::{build_tree, CallTreeNode, CALL_TREE_ROOT};
+pub use demangle::demangle_frame;
+pub use explain::{explain_hot_paths, format_explanations};
+pub use grouping::{apply_group_rules, GroupRule};
+pub use similarity::tree_similarity;
+pub use stack_aggregator::{StackAggregator, StackSource};
+pub use stack_builder::{
+    build_collapsed_stacks, build_collapsed_stacks_with_coverage,
+    build_collapsed_stacks_with_options, drop_small_stacks, escape_frame, merge_small_stacks,
+    merge_to_max_stacks, parse_pc_frame, pc_frame, sanitize_frame_name, stacks_per_step, subtree,
+    summarize_unattributed, AggregateBy, CollapsedStack, FrameTransformer, StackBuildOptions,
+    StackWeight, CREATE_FRAME_PREFIX, DEFAULT_FRAME_SEPARATOR, DEFAULT_MAX_DEPTH, HOSTIO_ROOT,
+    OTHER_FRAME, UNKNOWN_FRAME,
+};
+pub use metrics::{
+    calculate_hot_paths, calculate_hot_paths_with_precision, calculate_gas_distribution, compute_fingerprint,
+    round_percentage, GasDistribution, DEFAULT_PERCENT_PRECISION, MAX_PERCENT_PRECISION,
+};
\ No newline at end of file
@@ -0,0 +1,106 @@
+//! Structural comparison of collapsed-stack trees.
+//!
+//! Two profiles can cost the same while taking different paths. The
+//! similarity score here compares the *shape* of their call trees: each
+//! tree node (stack prefix) is weighted by its share of the tree's total
+//! gas, and the two weightings are compared with a weighted Jaccard index.
+//! Absolute cost cancels out, so a uniform slowdown still scores 1.0.
+
+use super::stack_builder::{CollapsedStack, DEFAULT_FRAME_SEPARATOR};
+use std::collections::HashMap;
+
+/// Similarity of two collapsed-stack trees, from 0.0 (disjoint) to 1.0
+///
+/// **Public** - coarse "did the execution path change?" signal
+///
+/// Every prefix of every stack is a node whose weight is the inclusive
+/// gas below it, divided by the tree's total. The score is
+/// `Σ min(a, b) / Σ max(a, b)` over all nodes, so both a moved hot path
+/// and a redistributed share lower it.
+///
+/// # Returns
+/// 1.0 for two empty trees, 0.0 if exactly one is empty
+pub fn tree_similarity(a: &[CollapsedStack], b: &[CollapsedStack]) -> f64 {
+    let a_nodes = node_shares(a);
+    let b_nodes = node_shares(b);
+
+    if a_nodes.is_empty() && b_nodes.is_empty() {
+        return 1.0;
+    }
+
+    let mut min_sum = 0.0;
+    let mut max_sum = 0.0;
+    for (node, &a_share) in &a_nodes {
+        let b_share = b_nodes.get(node).copied().unwrap_or(0.0);
+        min_sum += a_share.min(b_share);
+        max_sum += a_share.max(b_share);
+    }
+    for (node, &b_share) in &b_nodes {
+        if !a_nodes.contains_key(node) {
+            max_sum += b_share;
+        }
+    }
+
+    if max_sum == 0.0 {
+        0.0
+    } else {
+        min_sum / max_sum
+    }
+}
+
+/// Inclusive gas share of every tree node (stack prefix)
+///
+/// **Private** - empty if the tree has no weight
+fn node_shares(stacks: &[CollapsedStack]) -> HashMap<&str, f64> {
+    let total: u64 = stacks.iter().map(|stack| stack.weight).sum();
+    let mut shares: HashMap<&str, f64> = HashMap::new();
+    if total == 0 {
+        return shares;
+    }
+
+    for stack in stacks {
+        let share = stack.weight as f64 / total as f64;
+        let prefixes = stack
+            .stack
+            .match_indices(DEFAULT_FRAME_SEPARATOR)
+            .map(|(end, _)| &stack.stack[..end])
+            .chain(std::iter::once(stack.stack.as_str()));
+        for prefix in prefixes {
+            *shares.entry(prefix).or_insert(0.0) += share;
+        }
+    }
+
+    shares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stacks(entries: &[(&str, u64)]) -> Vec<CollapsedStack> {
+        entries
+            .iter()
+            .map(|(stack, weight)| CollapsedStack::new(stack.to_string(), *weight))
+            .collect()
+    }
+
+    #[test]
+    fn test_tree_similarity() {
+        let base = stacks(&[("main;a;SLOAD", 600), ("main;b", 400)]);
+
+        // Same shape at twice the cost
+        let scaled = stacks(&[("main;a;SLOAD", 1200), ("main;b", 800)]);
+        assert!((tree_similarity(&base, &scaled) - 1.0).abs() < 1e-9);
+
+        // Only the root is shared
+        let moved = stacks(&[("main;c", 1000)]);
+        assert!((tree_similarity(&base, &moved) - 1.0 / 3.6).abs() < 1e-9);
+
+        let partial = stacks(&[("main;a;SLOAD", 600), ("main;c", 400)]);
+        let score = tree_similarity(&base, &partial);
+        assert!(score > 1.0 / 3.6 && score < 1.0);
+
+        assert_eq!(tree_similarity(&[], &[]), 1.0);
+        assert_eq!(tree_similarity(&base, &[]), 0.0);
+    }
+}
@@ -0,0 +1,338 @@
+//! Cumulative stack aggregation across transactions.
+//!
+//! `StackAggregator` keeps a running `stack -> weight` total that can be
+//! loaded from and persisted to the folded format, so a long-running
+//! watcher can maintain one cumulative flamegraph across restarts.
+//!
+//! With source tracking on, the aggregator also remembers how much of each
+//! stack came from which source (transaction), so the aggregate can answer
+//! which transactions drive a hot path. With a source limit, each stack
+//! keeps only its heaviest sources and folds the rest into `other`, so a
+//! long-running watcher's attribution stays bounded.
+
+use super::stack_builder::{CollapsedStack, OTHER_FRAME};
+use crate::utils::error::ParseError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+/// One source's share of a stack's weight
+///
+/// **Public** - returned by `StackAggregator::top_sources`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackSource {
+    /// Source identifier (a transaction hash in watch mode)
+    pub source: String,
+
+    /// Weight this source contributed to the stack
+    pub weight: u64,
+}
+
+/// Running total of collapsed stack weights
+///
+/// **Public** - used by watch mode for cumulative flamegraphs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StackAggregator {
+    weights: BTreeMap<String, u64>,
+
+    /// `stack -> source -> weight`, when tracking sources
+    sources: Option<BTreeMap<String, BTreeMap<String, u64>>>,
+
+    /// Sources kept per stack (besides `other`); None = unbounded
+    max_sources: Option<usize>,
+}
+
+impl StackAggregator {
+    /// Create an empty aggregator
+    ///
+    /// **Public** - constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record which source contributed each stack's weight
+    ///
+    /// **Public** - builder pattern; only stacks added with a source
+    /// (`add_stacks_from`, `merge_source_folded`) are attributed
+    pub fn with_track_sources(mut self, enabled: bool) -> Self {
+        self.sources = enabled.then(BTreeMap::new);
+        self
+    }
+
+    /// Keep at most `limit` sources per stack, folding lighter ones into
+    /// an `other` source after each merge
+    ///
+    /// **Public** - builder pattern; bounds memory when tracking sources
+    pub fn with_max_sources(mut self, limit: usize) -> Self {
+        self.max_sources = Some(limit);
+        self
+    }
+
+    /// Whether sources are being tracked
+    pub fn tracks_sources(&self) -> bool {
+        self.sources.is_some()
+    }
+
+    /// Add stacks to the running totals
+    ///
+    /// **Public** - call once per profiled transaction
+    pub fn add_stacks(&mut self, stacks: &[CollapsedStack]) {
+        for stack in stacks {
+            self.add_weight(&stack.stack, stack.weight, None);
+        }
+    }
+
+    /// Add stacks contributed by `source` to the running totals
+    ///
+    /// **Public** - like `add_stacks`, also attributing the weight to
+    /// `source` when tracking sources
+    pub fn add_stacks_from(&mut self, source: &str, stacks: &[CollapsedStack]) {
+        for stack in stacks {
+            self.add_weight(&stack.stack, stack.weight, Some(source));
+        }
+        self.prune_sources();
+    }
+
+    /// Add every line of a folded file to the running totals
+    ///
+    /// **Public** - loads an aggregate persisted with `to_folded`
+    ///
+    /// # Returns
+    /// Number of lines merged (blank lines are skipped)
+    ///
+    /// # Errors
+    /// * `ParseError::Io` - Reading failed
+    /// * `ParseError::InvalidFormat` - A line is not `stack weight`
+    pub fn merge_from_folded(&mut self, reader: impl BufRead) -> Result<usize, ParseError> {
+        self.merge_folded(reader, None)
+    }
+
+    /// Add a folded file contributed by `source` to the running totals
+    ///
+    /// **Public** - `merge_from_folded` with source attribution
+    ///
+    /// # Errors
+    /// Same as `merge_from_folded`
+    pub fn merge_source_folded(&mut self, source: &str, reader: impl BufRead) -> Result<usize, ParseError> {
+        let merged = self.merge_folded(reader, Some(source));
+        self.prune_sources();
+        merged
+    }
+
+    /// Restore source attribution persisted with `top_sources_by_stack`
+    ///
+    /// **Public** - resumes tracking after a restart; stack totals are not
+    /// changed (load them with `merge_from_folded`)
+    pub fn merge_sources(&mut self, sources: BTreeMap<String, Vec<StackSource>>) {
+        if let Some(tracked) = self.sources.as_mut() {
+            for (stack, entries) in sources {
+                let by_source = tracked.entry(stack).or_default();
+                for entry in entries {
+                    let weight = by_source.entry(entry.source).or_insert(0);
+                    *weight = weight.saturating_add(entry.weight);
+                }
+            }
+        }
+        self.prune_sources();
+    }
+
+    /// Fold all but the `max_sources` heaviest sources of each stack into
+    /// `other`
+    ///
+    /// **Private** - run after every merge that attributes weight
+    fn prune_sources(&mut self) {
+        let (Some(tracked), Some(limit)) = (self.sources.as_mut(), self.max_sources) else {
+            return;
+        };
+
+        for by_source in tracked.values_mut() {
+            let other = by_source.remove(OTHER_FRAME);
+            if by_source.len() <= limit {
+                if let Some(other) = other {
+                    by_source.insert(OTHER_FRAME.to_string(), other);
+                }
+                continue;
+            }
+
+            let mut entries: Vec<(String, u64)> = std::mem::take(by_source).into_iter().collect();
+            entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            let folded = entries
+                .drain(limit..)
+                .map(|(_, weight)| weight)
+                .fold(other.unwrap_or(0), u64::saturating_add);
+
+            by_source.extend(entries);
+            by_source.insert(OTHER_FRAME.to_string(), folded);
+        }
+    }
+
+    /// Parse folded lines into the totals, attributing them to `source`
+    ///
+    /// **Private** - shared by the `merge_*folded` entry points
+    fn merge_folded(&mut self, reader: impl BufRead, source: Option<&str>) -> Result<usize, ParseError> {
+        let mut merged = 0;
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (stack, weight) = line
+                .rsplit_once(' ')
+                .and_then(|(stack, weight)| Some((stack, weight.trim().parse::<u64>().ok()?)))
+                .filter(|(stack, _)| !stack.is_empty())
+                .ok_or_else(|| ParseError::InvalidFormat(format!(
+                    "folded line {}: expected 'stack weight', got '{}'",
+                    number + 1,
+                    line
+                )))?;
+
+            self.add_weight(stack, weight, source);
+            merged += 1;
+        }
+
+        Ok(merged)
+    }
+
+    /// Add weight to one stack (and its source, if tracked)
+    ///
+    /// **Private** - single place totals are updated
+    fn add_weight(&mut self, stack: &str, weight: u64, source: Option<&str>) {
+        let total = self.weights.entry(stack.to_string()).or_insert(0);
+        *total = total.saturating_add(weight);
+
+        if let (Some(tracked), Some(source)) = (self.sources.as_mut(), source) {
+            let by_source = tracked.entry(stack.to_string()).or_default();
+            let contributed = by_source.entry(source.to_string()).or_insert(0);
+            *contributed = contributed.saturating_add(weight);
+        }
+    }
+
+    /// Heaviest contributors to `stack`, at most `limit`
+    ///
+    /// **Public** - empty when sources are not tracked or the stack
+    /// only has unattributed weight; ties are ordered by source
+    pub fn top_sources(&self, stack: &str, limit: usize) -> Vec<StackSource> {
+        let Some(by_source) = self.sources.as_ref().and_then(|tracked| tracked.get(stack)) else {
+            return Vec::new();
+        };
+
+        let mut entries: Vec<StackSource> = by_source
+            .iter()
+            .map(|(source, weight)| StackSource { source: source.clone(), weight: *weight })
+            .collect();
+        entries.sort_by(|a, b| b.weight.cmp(&a.weight).then_with(|| a.source.cmp(&b.source)));
+        entries.truncate(limit);
+        entries
+    }
+
+    /// Top contributors of every attributed stack, keyed by stack
+    ///
+    /// **Public** - the `sources` map persisted next to the aggregate
+    pub fn top_sources_by_stack(&self, limit: usize) -> BTreeMap<String, Vec<StackSource>> {
+        self.sources
+            .iter()
+            .flat_map(BTreeMap::keys)
+            .map(|stack| (stack.clone(), self.top_sources(stack, limit)))
+            .collect()
+    }
+
+    /// Render the totals as folded text
+    ///
+    /// **Public** - persists the aggregate; lines are ordered like
+    /// `stacks()` and end with a newline
+    pub fn to_folded(&self) -> String {
+        self.stacks()
+            .iter()
+            .map(|stack| format!("{}\n", stack.to_line()))
+            .collect()
+    }
+
+    /// Totals as collapsed stacks, heaviest first (ties by stack)
+    ///
+    /// **Public** - input for flamegraph generation
+    pub fn stacks(&self) -> Vec<CollapsedStack> {
+        let mut stacks: Vec<CollapsedStack> = self
+            .weights
+            .iter()
+            .map(|(stack, weight)| CollapsedStack::new(stack.clone(), *weight))
+            .collect();
+        // BTreeMap order already sorts ties by stack; the sort is stable
+        stacks.sort_by_key(|stack| std::cmp::Reverse(stack.weight));
+        stacks
+    }
+
+    /// Number of distinct stacks
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Whether no stacks have been added
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// Sum of all stack weights
+    pub fn total_weight(&self) -> u64 {
+        self.weights.values().fold(0, |total, weight| total.saturating_add(*weight))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_folded_round_trip_and_append() {
+        let folded = "call;transfer;SSTORE 5000\ncall;PUSH_32 3\ncall;a%3Bb 3\n";
+
+        let mut aggregator = StackAggregator::new();
+        assert_eq!(aggregator.merge_from_folded(folded.as_bytes()).unwrap(), 3);
+        assert_eq!(aggregator.to_folded(), folded);
+
+        aggregator.add_stacks(&[CollapsedStack::new("call;PUSH_32".to_string(), 7)]);
+        assert_eq!(aggregator.stacks()[1].to_line(), "call;PUSH_32 10");
+        assert_eq!(aggregator.total_weight(), 5013);
+    }
+
+    #[test]
+    fn test_track_sources() {
+        let mut aggregator = StackAggregator::new().with_track_sources(true);
+        aggregator.add_stacks_from("0xa", &[CollapsedStack::new("call;SSTORE".to_string(), 5000)]);
+        aggregator.merge_source_folded("0xb", "call;SSTORE 20000\ncall;ADD 3\n".as_bytes()).unwrap();
+        aggregator.add_stacks(&[CollapsedStack::new("call;SSTORE".to_string(), 1)]);
+
+        assert_eq!(aggregator.stacks()[0].weight, 25001);
+        assert_eq!(aggregator.top_sources("call;SSTORE", 1), vec![
+            StackSource { source: "0xb".to_string(), weight: 20000 },
+        ]);
+        assert_eq!(aggregator.top_sources_by_stack(5)["call;SSTORE"].len(), 2);
+
+        let mut resumed = StackAggregator::new().with_track_sources(true);
+        resumed.merge_sources(aggregator.top_sources_by_stack(5));
+        assert_eq!(resumed.top_sources("call;ADD", 5), aggregator.top_sources("call;ADD", 5));
+        assert!(StackAggregator::new().top_sources("call;ADD", 5).is_empty());
+    }
+
+    #[test]
+    fn test_max_sources_folds_into_other() {
+        let mut aggregator = StackAggregator::new().with_track_sources(true).with_max_sources(2);
+        for (source, weight) in [("0xa", 10), ("0xb", 30), ("0xc", 20), ("0xd", 5)] {
+            aggregator.add_stacks_from(source, &[CollapsedStack::new("call;SSTORE".to_string(), weight)]);
+        }
+
+        assert_eq!(aggregator.top_sources("call;SSTORE", 5), vec![
+            StackSource { source: "0xb".to_string(), weight: 30 },
+            StackSource { source: "0xc".to_string(), weight: 20 },
+            StackSource { source: OTHER_FRAME.to_string(), weight: 15 },
+        ]);
+    }
+
+    #[test]
+    fn test_merge_from_folded_rejects_malformed_lines() {
+        let mut aggregator = StackAggregator::new();
+        assert!(aggregator.merge_from_folded("call;ADD three\n".as_bytes()).is_err());
+        assert!(aggregator.merge_from_folded("no_weight\n".as_bytes()).is_err());
+    }
+}
@@ -5,15 +5,55 @@
 //!
 //! Example: "main;execute_tx;storage_read 1000"
 //! This means: main called execute_tx which called storage_read, consuming 1000 gas.
+//!
+//! Stacks are always joined with `;` internally. A different output
+//! separator is substituted when writing (`CollapsedStack::to_line_with_separator`);
+//! frame names containing either separator are percent-escaped so neither
+//! form can be corrupted.
+
+use super::demangle::demangle_frame;
+use super::grouping::{apply_group_rules, GroupRule};
+use crate::utils::opcodes::opcode_category;
+use crate::utils::symbols::SymbolTable;
+use crate::parser::stylus_trace::ExecutionStep;
+use crate::parser::{annotate_frame, ParsedTrace, HostIoType, SourceHint, SourceMap, UnattributedGas};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Root frame name for synthetic HostIO stacks
+pub const HOSTIO_ROOT: &str = "hostio";
+
+/// Placeholder frame name for a call level with no better label
+const CALL_FRAME: &str = "call";
+
+/// Frame name for an unnamed frame entered by delegatecall
+const DELEGATE_CALL_FRAME: &str = "delegatecall";
+
+/// Prefix of constructor frames entered by CREATE/CREATE2 (`create:<address>`)
+pub const CREATE_FRAME_PREFIX: &str = "create";
+
+/// Separator between frames in collapsed stacks
+pub const DEFAULT_FRAME_SEPARATOR: &str = ";";
+
+/// Default cap on call depth trusted from a trace (see `StackBuildOptions::max_depth`)
+pub const DEFAULT_MAX_DEPTH: usize = 1024;
 
-use crate::parser::{ParsedTrace, HostIoType};
-use log::debug;
-use std::collections::HashMap;
+/// Frame name for the aggregate of stacks merged by `merge_small_stacks`
+pub const OTHER_FRAME: &str = "other";
+
+/// Frame name for steps whose op could not be identified
+pub const UNKNOWN_FRAME: &str = "unknown";
+
+/// Key used in `UnattributedGas::ops` for steps with no op or function
+const MISSING_OP: &str = "<missing>";
 
 /// A single collapsed stack entry
 ///
 /// **Public** - used by flamegraph generator
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollapsedStack {
     /// Stack trace as semicolon-separated string
     pub stack: String,
@@ -39,6 +79,406 @@ impl CollapsedStack {
     pub fn to_line(&self) -> String {
         format!("{} {}", self.stack, self.weight)
     }
+    
+    /// Format as a collapsed stack line joined by a custom separator
+    ///
+    /// **Public** - for tools expecting a delimiter other than `;`
+    pub fn to_line_with_separator(&self, separator: &str) -> String {
+        if separator == DEFAULT_FRAME_SEPARATOR {
+            return self.to_line();
+        }
+        format!("{} {}", self.stack.replace(DEFAULT_FRAME_SEPARATOR, separator), self.weight)
+    }
+}
+
+/// Escape frame separators inside a frame name
+///
+/// **Public** - applied to every frame the stack builder emits
+///
+/// Occurrences of `;` and of `separator` are replaced by their
+/// percent-encoded bytes (`;` becomes `%3B`), so a name can never split
+/// into two frames.
+pub fn escape_frame<'a>(name: &'a str, separator: &str) -> Cow<'a, str> {
+    let custom = (!separator.is_empty() && separator != DEFAULT_FRAME_SEPARATOR).then_some(separator);
+    if !name.contains(DEFAULT_FRAME_SEPARATOR) && custom.is_none_or(|sep| !name.contains(sep)) {
+        return Cow::Borrowed(name);
+    }
+    
+    let mut escaped = name.replace(DEFAULT_FRAME_SEPARATOR, &percent_encode(DEFAULT_FRAME_SEPARATOR));
+    if let Some(sep) = custom {
+        escaped = escaped.replace(sep, &percent_encode(sep));
+    }
+    Cow::Owned(escaped)
+}
+
+/// Replace whitespace inside a raw frame name with underscores
+///
+/// **Public** - applied by the stack builder to names taken from the trace
+///
+/// Collapsed lines end in ` weight`, so a tab, newline or space-separated
+/// number inside a name can split the line or be misread as the weight.
+/// Leading and trailing whitespace is trimmed first.
+pub fn sanitize_frame_name(name: &str) -> Cow<'_, str> {
+    let trimmed = name.trim();
+    if !trimmed.contains(char::is_whitespace) {
+        return Cow::Borrowed(trimmed);
+    }
+    Cow::Owned(trimmed.replace(char::is_whitespace, "_"))
+}
+
+/// Percent-encode every byte of `value`
+fn percent_encode(value: &str) -> String {
+    value.bytes().map(|byte| format!("%{:02X}", byte)).collect()
+}
+
+/// What identifies a frame when aggregating steps
+///
+/// **Public** - set via `StackBuildOptions::with_aggregate_by`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AggregateBy {
+    /// Best available name: symbol, then function, then op (default)
+    #[default]
+    Auto,
+    
+    /// Every instruction address is its own frame (`OP@0xpc`)
+    Pc,
+    
+    /// Raw opcode only, ignoring function names and symbols
+    Op,
+    
+    /// Function only; steps without one belong to the function last seen
+    /// at their depth
+    Function,
+    
+    /// Opcode category (`arithmetic`, `storage`, ...; unknown ops are `other`)
+    Category,
+}
+
+impl AggregateBy {
+    /// Parse a CLI name (`auto`, `pc`, `op`, `function`, `category`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "pc" => Some(Self::Pc),
+            "op" => Some(Self::Op),
+            "function" | "fn" => Some(Self::Function),
+            "category" => Some(Self::Category),
+            _ => None,
+        }
+    }
+}
+
+/// What a stack's weight measures
+///
+/// **Public** - set via `StackBuildOptions::with_weight`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StackWeight {
+    /// Summed gas cost of the steps (default)
+    #[default]
+    Gas,
+    
+    /// Number of steps executed, a proxy for wall-clock time: a tight
+    /// loop of cheap ops is heavy here even when its gas is low
+    Steps,
+}
+
+impl StackWeight {
+    /// Parse a CLI name (`gas`, `steps`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "gas" => Some(Self::Gas),
+            "steps" | "step" => Some(Self::Steps),
+            _ => None,
+        }
+    }
+    
+    /// Unit label for flamegraph tooltips
+    pub fn unit_name(&self) -> &'static str {
+        match self {
+            Self::Gas => "gas",
+            Self::Steps => "steps",
+        }
+    }
+}
+
+/// Name of a frame under `AggregateBy::Pc` (`OP@0xpc`)
+///
+/// **Public** - counterpart of `parse_pc_frame`
+pub fn pc_frame(op: &str, pc: u64) -> String {
+    format!("{}@0x{:x}", op, pc)
+}
+
+/// Split an `OP@0xpc` frame into op and pc
+///
+/// **Public** - used to match instructions across profiles; any
+/// ` (file:line)` source annotation after the frame is ignored
+pub fn parse_pc_frame(frame: &str) -> Option<(&str, u64)> {
+    let frame = frame.split(' ').next()?;
+    let (op, pc) = frame.rsplit_once("@0x")?;
+    Some((op, u64::from_str_radix(pc, 16).ok()?))
+}
+
+/// Custom frame-name rewriter applied before aggregation
+///
+/// **Public** - plugin hook for arbitrary renaming logic (demangling, etc.)
+pub type FrameTransformer = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Options controlling how collapsed stacks are built
+///
+/// **Public** - passed to `build_collapsed_stacks_with_options`
+#[derive(Clone)]
+pub struct StackBuildOptions {
+    /// Coalesce consecutive identical `(op, depth)` steps into a single
+    /// `op (×N)` frame whose weight is the summed gas of the run
+    pub coalesce_runs: bool,
+    
+    /// Regex rules rewriting frame names before aggregation (applied in order)
+    pub group_rules: Vec<GroupRule>,
+    
+    /// Source map used to label frames as `name (file:line)` (`OP@0xpc (file:line)`
+    /// under `AggregateBy::Pc`)
+    pub source_map: Option<Arc<SourceMap>>,
+    
+    /// Symbols naming frames by pc range (takes precedence over function/op)
+    pub symbols: Option<Arc<SymbolTable>>,
+    
+    /// Emit a single `hostio` frame with all HostIO gas instead of a
+    /// per-type breakdown
+    pub collapse_hostio: bool,
+    
+    /// Custom transformers applied in order to each raw frame name
+    pub frame_transformers: Vec<FrameTransformer>,
+    
+    /// Demangle Rust symbols in `function` fields (on by default)
+    pub demangle: bool,
+    
+    /// Add synthetic `hostio` stacks alongside execution stacks (on by default)
+    pub hostio_frames: bool,
+    
+    /// Keep stacks whose total weight is zero (e.g. `JUMPDEST`, markers;
+    /// on by default)
+    ///
+    /// Zero-gas steps always take part in building the call hierarchy;
+    /// this only controls whether their own frames appear in the output.
+    pub include_zero_gas: bool,
+    
+    /// What becomes a frame (name, pc, op or function)
+    pub aggregate_by: AggregateBy,
+    
+    /// Whether stacks weigh gas or step counts
+    pub weight: StackWeight,
+    
+    /// Separator used when writing folded stacks (frame names are escaped
+    /// against it; stacks stay `;`-joined internally)
+    pub frame_separator: String,
+    
+    /// Cap on distinct stacks held while aggregating (None = unbounded)
+    ///
+    /// When the cap is exceeded the lightest stacks are folded into a
+    /// single `other` stack. Total gas is preserved, but a stack folded
+    /// early and seen again later is split between `other` and its own
+    /// entry, so per-stack weights near the cut are underestimated.
+    pub max_unique_stacks: Option<usize>,
+    
+    /// Name unattributed steps `op@0xpc` from their raw trace fields
+    /// instead of merging them all into `unknown` (parser debugging)
+    pub keep_raw_ops: bool,
+    
+    /// Deepest call level materialized in a stack; deeper steps are
+    /// attributed to this depth so a malformed `depth` field cannot make
+    /// the builder allocate millions of placeholder frames
+    pub max_depth: usize,
+}
+
+impl Default for StackBuildOptions {
+    fn default() -> Self {
+        Self {
+            coalesce_runs: false,
+            group_rules: Vec::new(),
+            source_map: None,
+            symbols: None,
+            collapse_hostio: false,
+            frame_transformers: Vec::new(),
+            demangle: true,
+            hostio_frames: true,
+            include_zero_gas: true,
+            aggregate_by: AggregateBy::Auto,
+            weight: StackWeight::Gas,
+            frame_separator: DEFAULT_FRAME_SEPARATOR.to_string(),
+            max_unique_stacks: None,
+            keep_raw_ops: false,
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+}
+
+impl std::fmt::Debug for StackBuildOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StackBuildOptions")
+            .field("coalesce_runs", &self.coalesce_runs)
+            .field("group_rules", &self.group_rules)
+            .field("source_map", &self.source_map.as_ref().map(|m| m.len()))
+            .field("symbols", &self.symbols.as_ref().map(|s| s.len()))
+            .field("collapse_hostio", &self.collapse_hostio)
+            .field("frame_transformers", &self.frame_transformers.len())
+            .field("demangle", &self.demangle)
+            .field("hostio_frames", &self.hostio_frames)
+            .field("include_zero_gas", &self.include_zero_gas)
+            .field("aggregate_by", &self.aggregate_by)
+            .field("weight", &self.weight)
+            .field("frame_separator", &self.frame_separator)
+            .field("max_unique_stacks", &self.max_unique_stacks)
+            .field("keep_raw_ops", &self.keep_raw_ops)
+            .field("max_depth", &self.max_depth)
+            .finish()
+    }
+}
+
+impl StackBuildOptions {
+    /// Create options with default values
+    ///
+    /// **Public** - constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Enable or disable run coalescing
+    ///
+    /// **Public** - builder pattern
+    pub fn with_coalesce_runs(mut self, enabled: bool) -> Self {
+        self.coalesce_runs = enabled;
+        self
+    }
+    
+    /// Set frame grouping rules
+    ///
+    /// **Public** - builder pattern
+    pub fn with_group_rules(mut self, rules: Vec<GroupRule>) -> Self {
+        self.group_rules = rules;
+        self
+    }
+    
+    /// Set source map for frame annotation
+    ///
+    /// **Public** - builder pattern
+    pub fn with_source_map(mut self, source_map: SourceMap) -> Self {
+        self.source_map = Some(Arc::new(source_map));
+        self
+    }
+    
+    /// Enable or disable folding all HostIO into one frame
+    ///
+    /// **Public** - builder pattern
+    pub fn with_collapse_hostio(mut self, enabled: bool) -> Self {
+        self.collapse_hostio = enabled;
+        self
+    }
+    
+    /// Set the symbol table used to name frames by pc range
+    ///
+    /// **Public** - builder pattern
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = Some(Arc::new(symbols));
+        self
+    }
+    
+    /// Include or exclude the synthetic HostIO stacks
+    ///
+    /// **Public** - builder pattern; excluding them leaves only real
+    /// execution-step stacks (HostIO stays in the profile summary)
+    pub fn with_hostio_frames(mut self, enabled: bool) -> Self {
+        self.hostio_frames = enabled;
+        self
+    }
+    
+    /// Keep or drop zero-weight stacks in the output
+    ///
+    /// **Public** - builder pattern
+    pub fn with_include_zero_gas(mut self, enabled: bool) -> Self {
+        self.include_zero_gas = enabled;
+        self
+    }
+    
+    /// Choose what becomes a frame
+    ///
+    /// **Public** - builder pattern
+    pub fn with_aggregate_by(mut self, aggregate_by: AggregateBy) -> Self {
+        self.aggregate_by = aggregate_by;
+        self
+    }
+    
+    /// Choose whether stacks weigh gas or step counts
+    ///
+    /// **Public** - builder pattern
+    pub fn with_weight(mut self, weight: StackWeight) -> Self {
+        self.weight = weight;
+        self
+    }
+    
+    /// Set the separator for folded output
+    ///
+    /// **Public** - builder pattern
+    pub fn with_frame_separator(mut self, separator: impl Into<String>) -> Self {
+        self.frame_separator = separator.into();
+        self
+    }
+    
+    /// Bound the number of distinct stacks kept while aggregating
+    ///
+    /// **Public** - builder pattern; protects long-running services from
+    /// traces with huge numbers of unique stacks
+    pub fn with_max_unique_stacks(mut self, max: usize) -> Self {
+        self.max_unique_stacks = Some(max.max(1));
+        self
+    }
+    
+    /// Keep distinct raw `op@0xpc` frames for unattributed steps
+    ///
+    /// **Public** - builder pattern
+    pub fn with_keep_raw_ops(mut self, enabled: bool) -> Self {
+        self.keep_raw_ops = enabled;
+        self
+    }
+    
+    /// Clamp call depths deeper than `max_depth`
+    ///
+    /// **Public** - builder pattern
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth.max(1);
+        self
+    }
+    
+    /// Enable or disable demangling of Rust `function` names
+    ///
+    /// **Public** - builder pattern
+    pub fn with_demangle(mut self, enabled: bool) -> Self {
+        self.demangle = enabled;
+        self
+    }
+    
+    /// Add a custom frame transformer (applied after any added earlier)
+    ///
+    /// **Public** - builder pattern
+    ///
+    /// # Example
+    /// ```ignore
+    /// let options = StackBuildOptions::new()
+    ///     .with_frame_transformer(|name| name.trim_start_matches("contract::").to_string());
+    /// ```
+    pub fn with_frame_transformer(
+        mut self,
+        transformer: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.frame_transformers.push(Arc::new(transformer));
+        self
+    }
+    
+    /// Run all frame transformers over a name
+    fn transform_frame(&self, name: &str) -> String {
+        self.frame_transformers
+            .iter()
+            .fold(name.to_string(), |current, transform| transform(&current))
+    }
 }
 
 /// Stack frame representing a function call
@@ -62,6 +502,31 @@ impl StackFrame {
     }
 }
 
+/// A frame with the gas attributed to it, before stack aggregation
+///
+/// **Private** - output of the pre-aggregation pass
+#[derive(Debug, Clone)]
+struct WeightedFrame {
+    frame: StackFrame,
+    gas: u64,
+    
+    /// Number of consecutive steps folded into this frame
+    run_length: u64,
+    
+    /// Whether the name came from a function symbol rather than an opcode
+    is_function: bool,
+    
+    /// Whether the step had a usable name (not an `unknown` or raw-op
+    /// fallback), for attribution coverage
+    attributed: bool,
+    
+    /// Whether this step starts a frame entered by delegatecall
+    delegated: bool,
+    
+    /// Address deployed when this step starts a constructor frame
+    created: Option<String>,
+}
+
 /// Build collapsed stacks from parsed trace
 ///
 /// **Public** - main entry point for stack building
@@ -78,77 +543,479 @@ impl StackFrame {
 /// 3. Build stack strings for each gas-consuming operation
 /// 4. Aggregate by unique stack (sum weights)
 pub fn build_collapsed_stacks(parsed_trace: &ParsedTrace) -> Vec<CollapsedStack> {
+    build_collapsed_stacks_with_options(parsed_trace, &StackBuildOptions::default())
+}
+
+/// Build collapsed stacks from parsed trace with custom options
+///
+/// **Public** - same as `build_collapsed_stacks` but configurable
+///
+/// # Arguments
+/// * `parsed_trace` - Parsed trace data from parser
+/// * `options` - Stack building options
+///
+/// # Returns
+/// Vector of collapsed stacks, sorted by weight (descending) with ties
+/// ordered by stack string, so the same trace always yields the same order
+pub fn build_collapsed_stacks_with_options(
+    parsed_trace: &ParsedTrace,
+    options: &StackBuildOptions,
+) -> Vec<CollapsedStack> {
+    build_collapsed_stacks_with_coverage(parsed_trace, options).0
+}
+
+/// Build collapsed stacks along with the share of gas attributed to
+/// named frames
+///
+/// **Public** - like `build_collapsed_stacks_with_options`; the coverage
+/// is attached to the profile as `metrics.coverage`
+///
+/// A step counts as attributed when it was named by a function, symbol,
+/// source-map function or defined opcode, rather than falling back to
+/// `unknown`, a `--keep-raw-ops` label or an undefined-opcode
+/// placeholder. HostIO frames count as named. Gas outside any step
+/// (intrinsic, L1 data) lowers coverage, since it is part of the
+/// trace's total gas but no frame.
+///
+/// # Returns
+/// The stacks, and coverage as a fraction in `0.0..=1.0` (0.0 when the
+/// trace's total gas is zero)
+pub fn build_collapsed_stacks_with_coverage(
+    parsed_trace: &ParsedTrace,
+    options: &StackBuildOptions,
+) -> (Vec<CollapsedStack>, f64) {
     debug!("Building collapsed stacks from {} execution steps", 
            parsed_trace.execution_steps.len());
     
     // Map to aggregate stacks: stack_string -> total_weight
     let mut stack_map: HashMap<String, u64> = HashMap::new();
+    let root = root_frame_name(parsed_trace, options);
+    
+    let mut frames = collect_frames(parsed_trace, options);
+    let mut attributed_gas: u64 = frames
+        .iter()
+        .filter(|weighted| weighted.attributed)
+        .map(|weighted| weighted.gas)
+        .sum();
+    if options.coalesce_runs {
+        let before = frames.len();
+        frames = coalesce_runs(frames);
+        debug!("Coalesced {} steps into {} frames", before, frames.len());
+    }
+    
+    walk_frame_stacks(&frames, &root, options.max_depth, |stack_str, weighted| {
+        // Zero-weight steps are added too; `include_zero_gas` decides below
+        // whether their stacks are kept
+        let weight = match options.weight {
+            StackWeight::Gas => weighted.gas,
+            StackWeight::Steps => weighted.run_length,
+        };
+        *stack_map.entry(stack_str).or_insert(0) += weight;
+        
+        // Fold down to half the cap so folding stays amortized
+        if let Some(max) = options.max_unique_stacks {
+            if stack_map.len() > max {
+                fold_lightest_stacks(&mut stack_map, max / 2);
+            }
+        }
+    });
+    
+    // Also add HostIO stacks if we have HostIO events (unless excluded)
+    if options.hostio_frames {
+        add_hostio_stacks(&mut stack_map, parsed_trace, options.collapse_hostio, options.weight);
+        attributed_gas += parsed_trace.hostio_stats.total_gas();
+    }
+    
+    // Zero-gas steps have already shaped the hierarchy above; only their
+    // own zero-weight frames are dropped here
+    if !options.include_zero_gas {
+        stack_map.retain(|_, weight| *weight > 0);
+    }
     
+    if let Some(max) = options.max_unique_stacks {
+        if stack_map.len() > max {
+            fold_lightest_stacks(&mut stack_map, max - 1);
+            debug!("Stack cap {} reached; lightest stacks folded into '{}'", max, OTHER_FRAME);
+        }
+    }
+    
+    // Convert map to vector and sort by weight (descending). Ties are
+    // broken by stack string so output never depends on HashMap order.
+    let mut stacks: Vec<CollapsedStack> = stack_map
+        .into_iter()
+        .map(|(stack, weight)| CollapsedStack::new(stack, weight))
+        .collect();
+    
+    stacks.sort_by(|a, b| b.weight.cmp(&a.weight).then_with(|| a.stack.cmp(&b.stack)));
+    
+    debug!("Built {} unique collapsed stacks", stacks.len());
+    
+    let coverage = match parsed_trace.total_gas_used {
+        0 => 0.0,
+        total_gas => (attributed_gas as f64 / total_gas as f64).min(1.0),
+    };
+    
+    (stacks, coverage)
+}
+
+/// Full stack string of every execution step, in trace order
+///
+/// **Public** - for analyses that need to know where each step ran (e.g.
+/// `lint`); entry `i` is the stack step `i` is aggregated under by
+/// `build_collapsed_stacks_with_options` (run coalescing is not applied)
+pub fn stacks_per_step(parsed_trace: &ParsedTrace, options: &StackBuildOptions) -> Vec<String> {
+    let root = root_frame_name(parsed_trace, options);
+    let frames = collect_frames(parsed_trace, options);
+    
+    let mut stacks = Vec::with_capacity(frames.len());
+    walk_frame_stacks(&frames, &root, options.max_depth, |stack_str, _| stacks.push(stack_str));
+    stacks
+}
+
+/// Replay the call stack over `frames`, passing each frame's full stack
+/// string to `visit`
+///
+/// **Private** - shared by stack building and `stacks_per_step`; depths
+/// beyond `max_depth` are clamped to it (with one warning per walk)
+fn walk_frame_stacks(
+    frames: &[WeightedFrame],
+    root: &str,
+    max_depth: usize,
+    mut visit: impl FnMut(String, &WeightedFrame),
+) {
     // Current call stack (tracks function hierarchy)
     let mut call_stack: Vec<String> = Vec::new();
-    let mut prev_depth = 0u32;
     
-    // Process each execution step
-    for step in &parsed_trace.execution_steps {
-        // Get operation name
-        let operation = step.function.as_deref()
-            .or(step.op.as_deref())
-            .unwrap_or("unknown");
+    // Function currently executing at each depth, used to name the
+    // caller frame when execution descends
+    let mut active_functions: Vec<Option<String>> = Vec::new();
+    
+    let mut clamped = 0usize;
+    let mut deepest = 0usize;
+    
+    for weighted in frames {
+        let operation = if weighted.run_length > 1 {
+            format!("{} (×{})", weighted.frame.name, weighted.run_length)
+        } else {
+            weighted.frame.name.clone()
+        };
         
         // Handle depth changes properly
-        let current_depth = step.depth as usize;
-        
-        // If depth decreased, we returned from function calls
-        if current_depth < call_stack.len() {
-            call_stack.truncate(current_depth);
+        let mut depth = weighted.frame.depth as usize;
+        if depth > max_depth {
+            clamped += 1;
+            deepest = deepest.max(depth);
+            depth = max_depth;
         }
+        update_call_stack(
+            &mut call_stack,
+            depth,
+            root,
+            &active_functions,
+            weighted.delegated,
+            weighted.created.as_deref(),
+        );
         
-        // If depth increased, we entered a new call
-        while call_stack.len() < current_depth {
-            call_stack.push("call".to_string());
+        // A new function at the same depth is a sibling transition (tail
+        // call or sequential call): it replaces the active function there
+        active_functions.truncate(depth + 1);
+        active_functions.resize(depth + 1, None);
+        if weighted.is_function {
+            active_functions[depth] = Some(weighted.frame.name.clone());
         }
         
         // Build the full stack string with current operation
-        let stack_str = if call_stack.is_empty() {
-            operation.to_string()
-        } else {
-            format!("{};{}", call_stack.join(";"), operation)
-        };
+        visit(build_stack_string(&call_stack, &operation), weighted);
+    }
+    
+    if clamped > 0 {
+        warn!(
+            "{} steps claimed call depth up to {}; clamped to max depth {}",
+            clamped, deepest, max_depth
+        );
+    }
+}
+
+/// Fold all but the `keep` heaviest stacks into the `other` stack
+///
+/// **Private** - enforces `StackBuildOptions::max_unique_stacks`; the map
+/// ends with at most `keep + 1` entries
+fn fold_lightest_stacks(stack_map: &mut HashMap<String, u64>, keep: usize) {
+    let mut other = stack_map.remove(OTHER_FRAME).unwrap_or(0);
+    let mut entries: Vec<(String, u64)> = stack_map.drain().collect();
+    
+    if entries.len() > keep {
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        other += entries.drain(keep..).map(|(_, weight)| weight).sum::<u64>();
+    }
+    
+    stack_map.extend(entries);
+    *stack_map.entry(OTHER_FRAME.to_string()).or_insert(0) += other;
+}
+
+/// Summarize gas from steps that fall through to the `unknown` frame
+///
+/// **Public** - attached to the profile as `unattributed`
+///
+/// # Arguments
+/// * `parsed_trace` - Parsed trace data
+///
+/// # Returns
+/// Unattributed gas total, its share of all step gas, and the distinct
+/// raw op names responsible
+pub fn summarize_unattributed(parsed_trace: &ParsedTrace) -> UnattributedGas {
+    let mut ops = BTreeMap::new();
+    let mut total_gas = 0u64;
+    let mut step_gas = 0u64;
+    
+    for step in &parsed_trace.execution_steps {
+        step_gas = step_gas.saturating_add(step.gas_cost);
         
-        // FIXED: Always add to map, accumulate all gas costs (even 0)
-        *stack_map.entry(stack_str).or_insert(0) += step.gas_cost;
+        let raw = step.function.as_deref().or(step.op.as_deref());
+        if raw.is_some_and(|name| !is_unattributed(name)) {
+            continue;
+        }
         
-        prev_depth = step.depth;
+        let key = match raw {
+            Some(name) if !name.is_empty() => name,
+            _ => MISSING_OP,
+        };
+        *ops.entry(key.to_string()).or_insert(0u64) += step.gas_cost;
+        total_gas = total_gas.saturating_add(step.gas_cost);
     }
     
-    // Also add HostIO stacks if we have HostIO events
-    add_hostio_stacks(&mut stack_map, parsed_trace);
+    let percentage = if step_gas == 0 {
+        0.0
+    } else {
+        total_gas as f64 / step_gas as f64 * 100.0
+    };
     
-    // Convert map to vector and sort by weight (descending)
-    let mut stacks: Vec<CollapsedStack> = stack_map
-        .into_iter()
-        .map(|(stack, weight)| CollapsedStack::new(stack, weight))
-        .collect();
+    UnattributedGas { total_gas, percentage, ops }
+}
+
+/// Whether a raw op name carries no usable attribution
+///
+/// **Private** - covers empty names, explicit `unknown` labels and geth's
+/// `opcode 0x.. not defined` placeholder for undefined opcodes
+fn is_unattributed(name: &str) -> bool {
+    let name = name.trim();
+    name.is_empty()
+        || name.eq_ignore_ascii_case(UNKNOWN_FRAME)
+        || name.starts_with("opcode 0x")
+}
+
+/// Frame for a step with no usable name, from whatever the trace holds
+///
+/// **Private** - `--keep-raw-ops` fallback: the raw op (or function)
+/// text, else `unknown`, suffixed with the pc so steps stay distinct
+fn raw_op_frame(step: &ExecutionStep) -> String {
+    let raw = step.op.as_deref()
+        .or(step.function.as_deref())
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(UNKNOWN_FRAME);
+    pc_frame(raw, step.pc)
+}
+
+/// Turn execution steps into named, weighted frames
+///
+/// **Private** - first pass of stack building
+///
+/// A symbols-file name for the step's pc wins outright; otherwise mangled
+/// Rust `function` symbols are demangled (if enabled). Custom frame
+/// transformers then run on the chosen name.
+/// Steps whose pc resolves through the source map are then labelled
+/// `name (file:line)` so steps on the same line aggregate together;
+/// unresolved steps keep the (transformed) function/op name.
+fn collect_frames(parsed_trace: &ParsedTrace, options: &StackBuildOptions) -> Vec<WeightedFrame> {
+    // Last function seen at each depth, for `AggregateBy::Function`
+    let mut last_function: Vec<Option<String>> = Vec::new();
     
-    stacks.sort_by(|a, b| b.weight.cmp(&a.weight));
+    parsed_trace
+        .execution_steps
+        .iter()
+        .map(|step| {
+            // Get operation name (demangling only applies to function symbols)
+            let symbol = options.symbols
+                .as_ref()
+                .and_then(|symbols| symbols.lookup_pc(step.pc));
+            let demangled = step.function.as_deref()
+                .filter(|_| options.demangle)
+                .map(demangle_frame);
+            let named = symbol
+                .or(demangled.as_deref())
+                .or(step.function.as_deref());
+            let function = named.filter(|name| !is_unattributed(name));
+            let op = step.op.as_deref().filter(|name| !is_unattributed(name));
+            let auto_name = named
+                .or(step.op.as_deref())
+                .filter(|name| !is_unattributed(name));
+            let raw = options.keep_raw_ops.then(|| raw_op_frame(step));
+            
+            let (keyed, attributed) = match options.aggregate_by {
+                AggregateBy::Auto => (None, auto_name.is_some()),
+                AggregateBy::Pc => (Some(pc_frame(op.unwrap_or(UNKNOWN_FRAME), step.pc)), op.is_some()),
+                AggregateBy::Op => (
+                    Some(
+                        op.map(str::to_string)
+                            .or_else(|| raw.clone())
+                            .unwrap_or_else(|| UNKNOWN_FRAME.to_string()),
+                    ),
+                    op.is_some(),
+                ),
+                AggregateBy::Function => {
+                    // Clamped like the call stack, so a bogus depth can't
+                    // size this vector
+                    let depth = (step.depth as usize).min(options.max_depth);
+                    // Deeper entries belong to calls that have returned;
+                    // a later call at that depth must not inherit them
+                    last_function.truncate(depth + 1);
+                    last_function.resize(depth + 1, None);
+                    if let Some(name) = function {
+                        last_function[depth] = Some(name.to_string());
+                    }
+                    match &last_function[depth] {
+                        Some(name) => (Some(name.clone()), true),
+                        None => (Some(UNKNOWN_FRAME.to_string()), false),
+                    }
+                }
+                AggregateBy::Category => (
+                    Some(opcode_category(op.unwrap_or(UNKNOWN_FRAME)).name().to_string()),
+                    op.is_some(),
+                ),
+            };
+            let operation = match &keyed {
+                Some(name) => name.as_str(),
+                None => auto_name.or(raw.as_deref()).unwrap_or(UNKNOWN_FRAME),
+            };
+            
+            let is_function = operation != UNKNOWN_FRAME && match options.aggregate_by {
+                AggregateBy::Auto => named.is_some() && auto_name.is_some(),
+                AggregateBy::Function => true,
+                AggregateBy::Pc | AggregateBy::Op | AggregateBy::Category => false,
+            };
+            
+            let transformed = (!options.frame_transformers.is_empty())
+                .then(|| options.transform_frame(operation));
+            let operation = transformed.as_deref().unwrap_or(operation);
+            
+            // Whitespace from tracer descriptions would break `stack weight` lines
+            let sanitized = sanitize_frame_name(operation);
+            let operation = sanitized.as_ref();
+            
+            // Under `--aggregate-by pc` the `OP@0xpc` label stays the frame's
+            // name so pc diffs still match it; the hint only adds the location
+            let hint = options.source_map.as_ref().and_then(|map| map.lookup(step.pc));
+            let hint_named = hint.is_some_and(|hint| hint.function.is_some());
+            let attributed = attributed || (hint_named && options.aggregate_by != AggregateBy::Pc);
+            let resolved = hint
+                .map(|hint| match options.aggregate_by {
+                    AggregateBy::Pc => annotate_frame(&SourceHint { function: None, ..hint.clone() }, operation),
+                    _ => annotate_frame(hint, operation),
+                });
+            let operation = resolved.as_deref().unwrap_or(operation);
+            
+            let name = if options.group_rules.is_empty() {
+                operation.to_string()
+            } else {
+                apply_group_rules(&options.group_rules, operation)
+            };
+            let name = escape_frame(&name, &options.frame_separator).into_owned();
+            
+            WeightedFrame {
+                frame: StackFrame::new(name, step.depth),
+                gas: step.gas_cost,
+                run_length: 1,
+                is_function,
+                attributed,
+                delegated: step.delegated,
+                created: step.created.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Merge consecutive frames with identical name and depth into runs
+///
+/// **Private** - optional pre-aggregation pass
+///
+/// Loops produce long runs of the same opcode; folding them keeps the
+/// flamegraph readable while preserving total gas.
+fn coalesce_runs(frames: Vec<WeightedFrame>) -> Vec<WeightedFrame> {
+    let mut runs: Vec<WeightedFrame> = Vec::with_capacity(frames.len());
     
-    debug!("Built {} unique collapsed stacks", stacks.len());
+    for weighted in frames {
+        match runs.last_mut() {
+            Some(last) if last.frame == weighted.frame => {
+                last.gas += weighted.gas;
+                last.run_length += weighted.run_length;
+            }
+            _ => runs.push(weighted),
+        }
+    }
     
-    stacks
+    runs
+}
+
+/// Name for the outermost call frame
+///
+/// **Private** - the entry method when a selector was detected (resolved
+/// through the symbols file, else the raw `0x` selector), otherwise the
+/// generic `call` placeholder
+fn root_frame_name(parsed_trace: &ParsedTrace, options: &StackBuildOptions) -> String {
+    match parsed_trace.entry_selector {
+        Some(selector) => options.symbols
+            .as_ref()
+            .and_then(|symbols| symbols.lookup_selector(selector))
+            .map(|name| escape_frame(&sanitize_frame_name(name), &options.frame_separator).into_owned())
+            .unwrap_or_else(|| format!("0x{:08x}", selector)),
+        None => CALL_FRAME.to_string(),
+    }
 }
 
 /// Update call stack based on current depth
 ///
 /// **Private** - internal stack management
-fn update_call_stack(call_stack: &mut Vec<String>, new_depth: usize) {
+///
+/// The first frame pushed is `root`. Deeper frames are named after the
+/// function active at that depth (see `active_functions`), else `call`.
+/// When `delegated`, the innermost pushed frame is marked as a
+/// delegatecall (`delegatecall`, or `name (delegatecall)`), since the
+/// callee code runs in the caller's context. When `created` is set, the
+/// innermost pushed frame is the constructor, named `create:<address>`.
+fn update_call_stack(
+    call_stack: &mut Vec<String>,
+    new_depth: usize,
+    root: &str,
+    active_functions: &[Option<String>],
+    delegated: bool,
+    created: Option<&str>,
+) {
     // Ensure call stack has correct depth
     if new_depth < call_stack.len() {
         // We've returned from function(s), pop the stack
         call_stack.truncate(new_depth);
     } else if new_depth > call_stack.len() {
-        // We've entered new function(s), add placeholders
+        // We've entered new function(s), add the callers
         while call_stack.len() < new_depth {
-            call_stack.push(format!("frame_{}", call_stack.len()));
+            let name = if call_stack.is_empty() {
+                root
+            } else {
+                active_functions
+                    .get(call_stack.len())
+                    .and_then(Option::as_deref)
+                    .unwrap_or(CALL_FRAME)
+            };
+            let innermost = call_stack.len() + 1 == new_depth;
+            let name = match (name, created) {
+                (_, Some(address)) if innermost && !call_stack.is_empty() => {
+                    format!("{}:{}", CREATE_FRAME_PREFIX, address)
+                }
+                (name, _) if !delegated || !innermost || call_stack.is_empty() => name.to_string(),
+                (CALL_FRAME, _) => DELEGATE_CALL_FRAME.to_string(),
+                (caller, _) => format!("{} ({})", caller, DELEGATE_CALL_FRAME),
+            };
+            call_stack.push(name);
         }
     }
     // If equal, we're at the same depth (sequential operations)
@@ -173,14 +1040,30 @@ fn build_stack_string(call_stack: &[String], operation: &str) -> String {
 ///
 /// **Private** - internal HostIO stack generation
 ///
-/// HostIO events are important enough to show separately in the flamegraph
+/// HostIO events are important enough to show separately in the flamegraph.
+/// With `collapse` set, all HostIO gas goes to a single opaque `hostio`
+/// frame, giving a clean compute-vs-IO split at the top level. Weighted
+/// by `StackWeight::Steps`, each HostIO call counts as one step.
 fn add_hostio_stacks(
     stack_map: &mut HashMap<String, u64>,
     parsed_trace: &ParsedTrace,
+    collapse: bool,
+    weight: StackWeight,
 ) {
     // Create a synthetic "hostio" root for all HostIO operations
     let hostio_counts = &parsed_trace.hostio_stats;
     
+    if collapse {
+        if hostio_counts.total_calls() > 0 {
+            let total = match weight {
+                StackWeight::Gas => hostio_counts.total_gas(),
+                StackWeight::Steps => hostio_counts.total_calls(),
+            };
+            *stack_map.entry(HOSTIO_ROOT.to_string()).or_insert(0) += total;
+        }
+        return;
+    }
+    
     // For each HostIO type with non-zero count, add a stack
     for hostio_type in [
         HostIoType::StorageLoad,
@@ -197,10 +1080,13 @@ fn add_hostio_stacks(
     ] {
         let count = hostio_counts.count_for_type(hostio_type);
         if count > 0 {
-            let stack_name = format!("hostio;{:?}", hostio_type);
+            let stack_name = format!("{};{:?}", HOSTIO_ROOT, hostio_type);
             // We don't have per-event gas, so distribute total HostIO gas proportionally
-            let weight = (hostio_counts.total_gas() * count) / hostio_counts.total_calls().max(1);
-            *stack_map.entry(stack_name).or_insert(0) += weight;
+            let stack_weight = match weight {
+                StackWeight::Gas => (hostio_counts.total_gas() * count) / hostio_counts.total_calls().max(1),
+                StackWeight::Steps => count,
+            };
+            *stack_map.entry(stack_name).or_insert(0) += stack_weight;
         }
     }
 }
@@ -214,7 +1100,7 @@ fn add_hostio_stacks(
 ///
 /// # Arguments
 /// * `stacks` - Original collapsed stacks
-/// * `threshold` - Minimum weight to keep (stacks below this are merged into "other")
+/// * `threshold` - Minimum weight to keep (stacks below this are merged into `OTHER_FRAME`)
 ///
 /// # Returns
 /// Merged stacks
@@ -232,16 +1118,130 @@ pub fn merge_small_stacks(stacks: Vec<CollapsedStack>, threshold: u64) -> Vec<Co
     
     // Add merged "other" stack if it has weight
     if other_weight > 0 {
-        merged.push(CollapsedStack::new("other".to_string(), other_weight));
+        merged.push(CollapsedStack::new(OTHER_FRAME.to_string(), other_weight));
     }
     
     merged
 }
 
+/// Starting `--max-frames` merge threshold, as a percentage of total gas
+const MAX_STACKS_START_PCT: f64 = 0.01;
+
+/// Merge small stacks with a rising threshold until at most `max_stacks` remain
+///
+/// **Public** - adaptive `merge_small_stacks` that guarantees a renderable
+/// stack count whatever the input size
+///
+/// The threshold starts at `MAX_STACKS_START_PCT` of total gas and
+/// doubles each round. If the cap is still exceeded at 100%, everything
+/// is merged into `other`; so is a set of stacks with no weight at all,
+/// which no threshold can split.
+///
+/// # Arguments
+/// * `stacks` - Collapsed stacks
+/// * `max_stacks` - Stack count to stay under (at least 1)
+///
+/// # Returns
+/// `None` if `stacks` is already small enough, otherwise the merged stacks
+/// and the threshold used (percent of total gas)
+pub fn merge_to_max_stacks(stacks: &[CollapsedStack], max_stacks: usize) -> Option<(Vec<CollapsedStack>, f64)> {
+    let max_stacks = max_stacks.max(1);
+    if stacks.len() <= max_stacks {
+        return None;
+    }
+    
+    let total: u64 = stacks.iter().map(|stack| stack.weight).sum();
+    if total == 0 {
+        return Some((vec![CollapsedStack::new(OTHER_FRAME.to_string(), 0)], 100.0));
+    }
+    
+    let mut pct = MAX_STACKS_START_PCT;
+    while pct < 100.0 {
+        let threshold = (total as f64 * pct / 100.0).ceil() as u64;
+        let merged = merge_small_stacks(stacks.to_vec(), threshold);
+        if merged.len() <= max_stacks {
+            return Some((merged, pct));
+        }
+        pct *= 2.0;
+    }
+    
+    Some((merge_small_stacks(stacks.to_vec(), total.saturating_add(1)), 100.0))
+}
+
+/// Drop stacks lighter than an absolute gas minimum
+///
+/// **Public** - gas-native decluttering for flamegraphs
+///
+/// Unlike `merge_small_stacks`, the dropped weight is discarded rather
+/// than rolled into `OTHER_FRAME`, so the rendered total shrinks.
+///
+/// # Arguments
+/// * `stacks` - Collapsed stacks
+/// * `min_gas` - Minimum weight to keep
+pub fn drop_small_stacks(stacks: &[CollapsedStack], min_gas: u64) -> Vec<CollapsedStack> {
+    stacks
+        .iter()
+        .filter(|stack| stack.weight >= min_gas)
+        .cloned()
+        .collect()
+}
+
+/// Keep only the stacks passing through `root_frame`, re-rooted at it
+///
+/// **Public** - "zoom" view for focused flamegraphs
+///
+/// Each stack is cut at the first occurrence of `root_frame`, so its
+/// callers disappear and `root_frame` becomes the root. Stacks that end up
+/// identical are merged. Since only the subtree's weight remains, the
+/// flamegraph's percentages become relative to the subtree total.
+///
+/// # Arguments
+/// * `stacks` - Collapsed stacks
+/// * `root_frame` - Exact frame name to focus on
+///
+/// # Returns
+/// Re-rooted stacks sorted by weight (descending), ties by stack; empty
+/// if no stack contains `root_frame`
+pub fn subtree(stacks: &[CollapsedStack], root_frame: &str) -> Vec<CollapsedStack> {
+    let mut merged: BTreeMap<String, u64> = BTreeMap::new();
+    
+    for stack in stacks {
+        let frames: Vec<&str> = stack.stack.split(DEFAULT_FRAME_SEPARATOR).collect();
+        if let Some(start) = frames.iter().position(|frame| *frame == root_frame) {
+            *merged.entry(frames[start..].join(DEFAULT_FRAME_SEPARATOR)).or_insert(0) += stack.weight;
+        }
+    }
+    
+    let mut focused: Vec<CollapsedStack> = merged
+        .into_iter()
+        .map(|(stack, weight)| CollapsedStack::new(stack, weight))
+        .collect();
+    focused.sort_by_key(|stack| std::cmp::Reverse(stack.weight));
+    focused
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::hostio::HostIoStats;
+    use crate::parser::stylus_trace::{ExecutionStep, ParseOptions};
+
+    #[test]
+    fn test_subtree_reroots_and_merges() {
+        let stacks = vec![
+            CollapsedStack::new("call;transfer;storage_store;SSTORE".to_string(), 5000),
+            CollapsedStack::new("call;mint;storage_store;SSTORE".to_string(), 2000),
+            CollapsedStack::new("call;transfer;storage_store".to_string(), 10),
+            CollapsedStack::new("call;transfer;ADD".to_string(), 3),
+        ];
+        
+        let focused = subtree(&stacks, "storage_store");
+        
+        assert_eq!(focused.len(), 2);
+        assert_eq!(focused[0].to_line(), "storage_store;SSTORE 7000");
+        assert_eq!(focused[1].to_line(), "storage_store 10");
+        assert!(subtree(&stacks, "missing").is_empty());
+    }
 
     #[test]
     fn test_collapsed_stack_to_line() {
@@ -266,18 +1266,535 @@ mod tests {
     #[test]
     fn test_update_call_stack_deeper() {
         let mut stack = vec!["main".to_string()];
-        update_call_stack(&mut stack, 3);
+        update_call_stack(&mut stack, 3, CALL_FRAME, &[], false, None);
         assert_eq!(stack.len(), 3);
     }
 
+    #[test]
+    fn test_absurd_depth_is_clamped() {
+        let trace = trace_with_steps(vec![
+            step("CALL", 1, 700),
+            step("SLOAD", 1_000_000, 2100),
+        ]);
+        let options = StackBuildOptions::new().with_max_depth(4);
+        
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        let deep = stacks.iter().find(|s| s.stack.ends_with("SLOAD")).unwrap();
+        
+        assert_eq!(deep.stack.split(';').count(), 5);
+        assert_eq!(deep.weight, 2100);
+        assert_eq!(StackBuildOptions::default().max_depth, DEFAULT_MAX_DEPTH);
+        
+        let mut trace = trace;
+        trace.execution_steps[1].function = Some("deep_fn".to_string());
+        let options = options.with_aggregate_by(AggregateBy::Function);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        let deep = stacks.iter().find(|s| s.stack.ends_with("deep_fn")).unwrap();
+        
+        assert_eq!(deep.stack.split(';').count(), 5);
+        assert_eq!(deep.weight, 2100);
+    }
+
     #[test]
     fn test_update_call_stack_shallower() {
         let mut stack = vec!["main".to_string(), "child".to_string(), "grandchild".to_string()];
-        update_call_stack(&mut stack, 1);
+        update_call_stack(&mut stack, 1, CALL_FRAME, &[], false, None);
         assert_eq!(stack.len(), 1);
         assert_eq!(stack[0], "main");
     }
 
+    fn step(op: &str, depth: u32, gas_cost: u64) -> ExecutionStep {
+        step_at(0, op, depth, gas_cost)
+    }
+
+    fn step_at(pc: u64, op: &str, depth: u32, gas_cost: u64) -> ExecutionStep {
+        ExecutionStep {
+            pc,
+            gas: 0,
+            gas_cost,
+            op: Some(op.to_string()),
+            depth,
+            function: None,
+            stack: None,
+            memory: None,
+            storage: None,
+            delegated: false,
+            created: None,
+        }
+    }
+
+    fn trace_with_steps(steps: Vec<ExecutionStep>) -> ParsedTrace {
+        ParsedTrace {
+            transaction_hash: "0xtest".to_string(),
+            total_gas_used: steps.iter().map(|s| s.gas_cost).sum(),
+            execution_steps: steps,
+            hostio_stats: HostIoStats::new(),
+            entry_selector: None,
+            contract_address: None,
+            gas_refund: None,
+        }
+    }
+
+    #[test]
+    fn test_same_depth_function_transition_names_callers() {
+        let function_step = |function: &str, depth: u32, gas_cost: u64| ExecutionStep {
+            function: Some(function.to_string()),
+            ..step("CALL", depth, gas_cost)
+        };
+        
+        // `validate` then `transfer` at depth 1, each calling into depth 2
+        let trace = trace_with_steps(vec![
+            function_step("validate", 1, 10),
+            step("SLOAD", 2, 2100),
+            step("JUMP", 1, 8),
+            function_step("transfer", 1, 20),
+            step("SSTORE", 2, 5000),
+        ]);
+        
+        let stacks = build_collapsed_stacks(&trace);
+        let weight = |stack: &str| stacks.iter().find(|s| s.stack == stack).map(|s| s.weight);
+        
+        assert_eq!(weight("call;validate;SLOAD"), Some(2100));
+        assert_eq!(weight("call;transfer;SSTORE"), Some(5000));
+        assert_eq!(weight("call;JUMP"), Some(8));
+        assert!(stacks.iter().all(|s| !s.stack.starts_with("call;call")));
+    }
+
+    #[test]
+    fn test_delegatecall_frame_naming() {
+        let raw = serde_json::json!({ "gasUsed": 10000, "structLogs": [
+            { "pc": 0, "op": "DELEGATECALL", "gasCost": 700, "depth": 1 },
+            { "pc": 0, "op": "SLOAD", "gasCost": 2100, "depth": 2 },
+            { "pc": 4, "op": "CALL", "gasCost": 700, "depth": 1 },
+            { "pc": 0, "op": "SLOAD", "gasCost": 2100, "depth": 2 }
+        ]});
+        let trace = crate::parser::parse_trace("0x1", &raw).unwrap();
+        
+        let stacks = build_collapsed_stacks(&trace);
+        
+        assert!(stacks.iter().any(|s| s.stack == "call;delegatecall;SLOAD" && s.weight == 2100));
+        assert!(stacks.iter().any(|s| s.stack == "call;call;SLOAD" && s.weight == 2100));
+    }
+
+    #[test]
+    fn test_create_frame_naming() {
+        let address = "0x000000000000000000000000c0ffee0000000000000000000000000000000001";
+        let raw = serde_json::json!({ "gasUsed": 40000, "structLogs": [
+            { "pc": 0, "op": "CREATE2", "gasCost": 32000, "depth": 1, "stack": [] },
+            { "pc": 0, "op": "SSTORE", "gasCost": 5000, "depth": 2, "stack": [] },
+            { "pc": 1, "op": "POP", "gasCost": 2, "depth": 1, "stack": [address] }
+        ]});
+        let with_state = ParseOptions { capture_state: true, ..Default::default() };
+        let trace = crate::parser::parse_trace_with_options("0x1", &raw, &with_state).unwrap();
+        
+        let stacks = build_collapsed_stacks(&trace);
+        assert!(stacks.iter().any(|s| {
+            s.stack == "call;create:0xc0ffee0000000000000000000000000000000001;SSTORE" && s.weight == 5000
+        }));
+        
+        let trace = crate::parser::parse_trace("0x1", &raw).unwrap();
+        let stacks = build_collapsed_stacks(&trace);
+        assert!(stacks.iter().any(|s| s.stack == "call;create:unknown;SSTORE"));
+    }
+
+    #[test]
+    fn test_include_zero_gas_keeps_zero_weight_frames() {
+        let trace = trace_with_steps(vec![
+            step("JUMPDEST", 1, 0),
+            step("SLOAD", 2, 2100),
+        ]);
+        
+        let stacks = build_collapsed_stacks(&trace);
+        assert_eq!(stacks.len(), 2);
+        assert!(stacks.iter().any(|s| s.stack == "call;JUMPDEST" && s.weight == 0));
+        
+        let options = StackBuildOptions::new().with_include_zero_gas(false);
+        let dropped = build_collapsed_stacks_with_options(&trace, &options);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].stack, "call;call;SLOAD");
+    }
+
+    #[test]
+    fn test_max_unique_stacks_caps_and_preserves_gas() {
+        let steps: Vec<ExecutionStep> = (0..50u64)
+            .map(|pc| step_at(pc, "ADD", 1, pc + 1))
+            .collect();
+        let trace = trace_with_steps(steps);
+        let options = StackBuildOptions::new()
+            .with_aggregate_by(AggregateBy::Pc)
+            .with_max_unique_stacks(8);
+        
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        
+        assert!(stacks.len() <= 8);
+        assert_eq!(stacks.iter().map(|s| s.weight).sum::<u64>(), (1..=50).sum::<u64>());
+        assert_eq!(stacks[0].stack, OTHER_FRAME);
+        assert!(stacks.iter().any(|s| s.stack == "call;ADD@0x31" && s.weight == 50));
+    }
+
+    #[test]
+    fn test_aggregate_by_keys() {
+        let trace = trace_with_steps(vec![
+            ExecutionStep { function: Some("transfer".to_string()), ..step_at(4, "SLOAD", 1, 100) },
+            step_at(8, "SLOAD", 1, 200),
+            step_at(12, "ADD", 1, 3),
+        ]);
+        let build = |aggregate_by| {
+            let options = StackBuildOptions::new().with_aggregate_by(aggregate_by);
+            let mut stacks: Vec<(String, u64)> = build_collapsed_stacks_with_options(&trace, &options)
+                .into_iter()
+                .map(|s| (s.stack, s.weight))
+                .collect();
+            stacks.sort();
+            stacks
+        };
+        
+        assert_eq!(build(AggregateBy::Op), vec![
+            ("call;ADD".to_string(), 3),
+            ("call;SLOAD".to_string(), 300),
+        ]);
+        assert_eq!(build(AggregateBy::Pc).len(), 3);
+        assert!(build(AggregateBy::Pc).contains(&("call;SLOAD@0x8".to_string(), 200)));
+        assert_eq!(build(AggregateBy::Function), vec![("call;transfer".to_string(), 303)]);
+        assert_eq!(build(AggregateBy::Category), vec![
+            ("call;arithmetic".to_string(), 3),
+            ("call;storage".to_string(), 300),
+        ]);
+        assert_eq!(AggregateBy::parse("PC"), Some(AggregateBy::Pc));
+        
+        // A later call at depth 2 doesn't inherit the returned `inner`
+        let named = |name: &str, depth, gas| ExecutionStep { function: Some(name.to_string()), ..step("ADD", depth, gas) };
+        let trace = trace_with_steps(vec![
+            named("outer", 1, 1),
+            named("inner", 2, 10),
+            step("ADD", 1, 100),
+            step("ADD", 2, 1000),
+        ]);
+        let options = StackBuildOptions::new().with_aggregate_by(AggregateBy::Function);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        assert!(stacks.iter().any(|s| s.stack.ends_with(UNKNOWN_FRAME) && s.weight == 1000));
+        
+        // A source-mapped pc frame still parses back to its opcode and pc
+        let map = SourceMap::from_json(r#"[{ "pc": 0, "end_pc": 10, "file": "lib.rs", "line": 3 }]"#).unwrap();
+        let options = StackBuildOptions::new().with_aggregate_by(AggregateBy::Pc).with_source_map(map);
+        let stacks = build_collapsed_stacks_with_options(&trace_with_steps(vec![step_at(8, "SLOAD", 1, 200)]), &options);
+        let leaf = stacks[0].stack.rsplit(';').next().unwrap();
+        assert_eq!(leaf, "SLOAD@0x8 (lib.rs:3)");
+        assert_eq!(parse_pc_frame(leaf), Some(("SLOAD", 8)));
+        assert_eq!(parse_pc_frame("SLOAD"), None);
+        assert_eq!(AggregateBy::parse("line"), None);
+    }
+
+    #[test]
+    fn test_weight_by_steps() {
+        let trace = trace_with_steps(vec![
+            step("ADD", 1, 3),
+            step("ADD", 1, 3),
+            step("ADD", 1, 3),
+            step("SSTORE", 1, 5000),
+        ]);
+        let options = StackBuildOptions::new()
+            .with_weight(StackWeight::Steps)
+            .with_coalesce_runs(true);
+        
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        
+        assert!(stacks.iter().any(|s| s.stack == "call;ADD (×3)" && s.weight == 3));
+        assert!(stacks.iter().any(|s| s.stack == "call;SSTORE" && s.weight == 1));
+        assert_eq!(StackWeight::parse("STEPS"), Some(StackWeight::Steps));
+        assert_eq!(StackWeight::parse("time"), None);
+    }
+
+    #[test]
+    fn test_frame_separator_escapes_names() {
+        let trace = trace_with_steps(vec![
+            ExecutionStep { function: Some("Vault;withdraw".to_string()), ..step("CALL", 1, 10) },
+            ExecutionStep { function: Some("a|b".to_string()), ..step("CALL", 1, 5) },
+        ]);
+        
+        let options = StackBuildOptions::new().with_frame_separator("|");
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        
+        assert_eq!(stacks[0].stack, "call;Vault%3Bwithdraw");
+        assert_eq!(stacks[0].to_line_with_separator("|"), "call|Vault%3Bwithdraw 10");
+        assert_eq!(stacks[1].to_line_with_separator("|"), "call|a%7Cb 5");
+        assert_eq!(escape_frame("plain", ";"), Cow::Borrowed("plain"));
+    }
+
+    #[test]
+    fn test_frame_names_with_whitespace_are_sanitized() {
+        // Unsanitized, this line would read "call;PUSH 32 3": a parser
+        // splitting on the first space sees frame "call;PUSH" and weight "32"
+        let trace = trace_with_steps(vec![
+            step("PUSH 32", 1, 3),
+            ExecutionStep { function: Some(" read\tslot\n".to_string()), ..step("SLOAD", 1, 100) },
+        ]);
+        
+        let stacks = build_collapsed_stacks(&trace);
+        let lines: Vec<String> = stacks.iter().map(CollapsedStack::to_line).collect();
+        
+        assert_eq!(lines, vec!["call;read_slot 100", "call;PUSH_32 3"]);
+        assert!(lines.iter().all(|line| line.split(' ').count() == 2));
+    }
+
+    #[test]
+    fn test_coalesce_runs_merges_adjacent_identical_ops() {
+        let trace = trace_with_steps(vec![
+            step("ADD", 1, 3),
+            step("ADD", 1, 3),
+            step("ADD", 1, 3),
+            step("MUL", 1, 5),
+            step("ADD", 2, 3),
+            step("ADD", 1, 3),
+        ]);
+        
+        let options = StackBuildOptions::new().with_coalesce_runs(true);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        
+        let find = |name: &str| stacks.iter().find(|s| s.stack == name).map(|s| s.weight);
+        assert_eq!(find("call;ADD (×3)"), Some(9));
+        assert_eq!(find("call;MUL"), Some(5));
+        assert_eq!(find("call;call;ADD"), Some(3));
+        assert_eq!(find("call;ADD"), Some(3));
+        
+        let total: u64 = stacks.iter().map(|s| s.weight).sum();
+        assert_eq!(total, trace.total_gas_used);
+    }
+
+    #[test]
+    fn test_coalesce_runs_disabled_by_default() {
+        let trace = trace_with_steps(vec![step("ADD", 1, 3), step("ADD", 1, 3)]);
+        
+        let stacks = build_collapsed_stacks(&trace);
+        
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].stack, "call;ADD");
+        assert_eq!(stacks[0].weight, 6);
+    }
+
+    #[test]
+    fn test_group_rules_aggregate_frames() {
+        let trace = trace_with_steps(vec![
+            step("storage_load_0xaa", 1, 100),
+            step("storage_load_0xbb", 1, 200),
+            step("ADD", 1, 3),
+        ]);
+        
+        let options = StackBuildOptions::new()
+            .with_group_rules(vec![GroupRule::parse("storage_load_.*=>storage_load").unwrap()]);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        
+        assert_eq!(stacks.len(), 2);
+        assert_eq!(stacks[0].stack, "call;storage_load");
+        assert_eq!(stacks[0].weight, 300);
+    }
+
+    #[test]
+    fn test_source_map_annotates_and_aggregates_by_line() {
+        let trace = trace_with_steps(vec![
+            step_at(0, "PUSH1", 1, 3),
+            step_at(1, "SLOAD", 1, 2100),
+            step_at(2, "ADD", 1, 3),
+            step_at(99, "MUL", 1, 5),
+        ]);
+        let map = SourceMap::from_json(
+            r#"[{ "pc": 0, "end_pc": 10, "file": "lib.rs", "line": 42, "function": "transfer" }]"#,
+        ).unwrap();
+        
+        let options = StackBuildOptions::new().with_source_map(map);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        
+        assert_eq!(stacks.len(), 2);
+        assert_eq!(stacks[0].stack, "call;transfer (lib.rs:42)");
+        assert_eq!(stacks[0].weight, 2106);
+        assert_eq!(stacks[1].stack, "call;MUL");
+    }
+
+    #[test]
+    fn test_source_mapped_pc_frames_diff_by_pc() {
+        use crate::aggregator::calculate_hot_paths;
+        use crate::diff::diff_profiles_by_pc;
+        use crate::parser::to_profile;
+        
+        let map = SourceMap::from_json(
+            r#"[{ "pc": 0, "end_pc": 10, "file": "lib.rs", "line": 3, "function": "transfer" }]"#,
+        ).unwrap();
+        let options = StackBuildOptions::new()
+            .with_aggregate_by(AggregateBy::Pc)
+            .with_source_map(map);
+        let profile = |sload_gas| {
+            let trace = trace_with_steps(vec![step_at(8, "SLOAD", 1, sload_gas), step_at(20, "ADD", 1, 3)]);
+            let stacks = build_collapsed_stacks_with_options(&trace, &options);
+            to_profile(&trace, calculate_hot_paths(&stacks, trace.total_gas_used, 0))
+        };
+        
+        let base = profile(2100);
+        let leaf = base.hot_paths[0].stack.rsplit(';').next().unwrap();
+        assert_eq!(leaf, "SLOAD@0x8 (lib.rs:3)");
+        assert_eq!(parse_pc_frame(leaf), Some(("SLOAD", 8)));
+        
+        let diff = diff_profiles_by_pc(&base, &profile(100)).unwrap();
+        assert_eq!(diff.paths[0].stack, "SLOAD@0x8");
+        assert_eq!(diff.paths[0].delta(), -2000);
+    }
+
+    fn trace_with_hostio() -> ParsedTrace {
+        use crate::parser::hostio::HostIoEvent;
+        
+        let mut trace = trace_with_steps(vec![step("ADD", 1, 3)]);
+        for (io_type, gas_cost) in [
+            (HostIoType::StorageLoad, 2100),
+            (HostIoType::StorageStore, 20000),
+        ] {
+            trace.hostio_stats.add_event(HostIoEvent::new(io_type, gas_cost));
+        }
+        trace
+    }
+
+    #[test]
+    fn test_hostio_breakdown_by_default() {
+        let stacks = build_collapsed_stacks(&trace_with_hostio());
+        
+        assert!(stacks.iter().any(|s| s.stack == "hostio;StorageLoad"));
+        assert!(stacks.iter().any(|s| s.stack == "hostio;StorageStore"));
+    }
+
+    #[test]
+    fn test_symbols_name_frames_by_pc() {
+        let symbols = SymbolTable::from_json(r#"{ "0-10": "dispatch" }"#).unwrap();
+        let trace = trace_with_steps(vec![step_at(4, "ADD", 1, 3), step_at(20, "MUL", 1, 5)]);
+        
+        let options = StackBuildOptions::new().with_symbols(symbols);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        
+        assert_eq!(stacks[0].stack, "call;MUL");
+        assert_eq!(stacks[1].stack, "call;dispatch");
+    }
+
+    #[test]
+    fn test_root_frame_labelled_by_selector() {
+        let mut trace = trace_with_steps(vec![step("ADD", 1, 3), step("MUL", 2, 5)]);
+        trace.entry_selector = Some(0xa9059cbb);
+        
+        let stacks = build_collapsed_stacks(&trace);
+        assert_eq!(stacks[0].stack, "0xa9059cbb;call;MUL");
+        assert_eq!(stacks[1].stack, "0xa9059cbb;ADD");
+        
+        let symbols = SymbolTable::from_json(r#"{ "0xa9059cbb": "transfer" }"#).unwrap();
+        let options = StackBuildOptions::new().with_symbols(symbols);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        assert_eq!(stacks[1].stack, "transfer;ADD");
+    }
+
+    #[test]
+    fn test_hostio_frames_can_be_excluded() {
+        let options = StackBuildOptions::new().with_hostio_frames(false);
+        let stacks = build_collapsed_stacks_with_options(&trace_with_hostio(), &options);
+        
+        assert!(!stacks.is_empty());
+        assert!(stacks.iter().all(|s| !s.stack.starts_with(HOSTIO_ROOT)));
+    }
+
+    #[test]
+    fn test_collapse_hostio() {
+        let options = StackBuildOptions::new().with_collapse_hostio(true);
+        let stacks = build_collapsed_stacks_with_options(&trace_with_hostio(), &options);
+        
+        let hostio: Vec<_> = stacks.iter().filter(|s| s.stack.starts_with("hostio")).collect();
+        assert_eq!(hostio.len(), 1);
+        assert_eq!(hostio[0].stack, "hostio");
+        assert_eq!(hostio[0].weight, 22100);
+    }
+
+    #[test]
+    fn test_build_collapsed_stacks_deterministic_order() {
+        let steps: Vec<ExecutionStep> = ["MUL", "ADD", "SUB", "DIV", "AND", "OR", "XOR", "NOT"]
+            .iter()
+            .map(|op| step(op, 1, 3))
+            .collect();
+        let trace = trace_with_steps(steps);
+        
+        let first = build_collapsed_stacks(&trace);
+        for _ in 0..10 {
+            let again = build_collapsed_stacks(&trace);
+            let names: Vec<_> = again.iter().map(|s| &s.stack).collect();
+            assert_eq!(names, first.iter().map(|s| &s.stack).collect::<Vec<_>>());
+        }
+        
+        assert_eq!(first[0].stack, "call;ADD");
+        assert_eq!(first[7].stack, "call;XOR");
+    }
+
+    #[test]
+    fn test_mangled_function_names_demangled_by_default() {
+        let mut mangled = step("CALL", 1, 10);
+        mangled.function = Some("_ZN8contract8transfer17h0123456789abcdefE".to_string());
+        let trace = trace_with_steps(vec![mangled]);
+        
+        let stacks = build_collapsed_stacks(&trace);
+        assert_eq!(stacks[0].stack, "call;contract::transfer");
+        
+        let options = StackBuildOptions::new().with_demangle(false);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        assert_eq!(stacks[0].stack, "call;_ZN8contract8transfer17h0123456789abcdefE");
+    }
+
+    #[test]
+    fn test_summarize_unattributed() {
+        let mut missing = step("ADD", 1, 10);
+        missing.op = None;
+        let trace = trace_with_steps(vec![
+            step("ADD", 1, 50),
+            missing,
+            step("opcode 0x0c not defined", 1, 25),
+            step("", 1, 15),
+        ]);
+        
+        let summary = summarize_unattributed(&trace);
+        assert_eq!(summary.total_gas, 50);
+        assert!((summary.percentage - 50.0).abs() < 1e-9);
+        assert_eq!(summary.ops.get("<missing>"), Some(&25));
+        assert_eq!(summary.ops.get("opcode 0x0c not defined"), Some(&25));
+        
+        let stacks = build_collapsed_stacks(&trace);
+        let unknown = stacks.iter().find(|s| s.stack == "call;unknown").unwrap();
+        assert_eq!(unknown.weight, 50);
+    }
+
+    #[test]
+    fn test_keep_raw_ops_keeps_unattributed_steps_distinct() {
+        let mut missing = step_at(5, "ADD", 1, 10);
+        missing.op = None;
+        let trace = trace_with_steps(vec![
+            step_at(1, "ADD", 1, 50),
+            missing,
+            step_at(9, "", 1, 15),
+        ]);
+        
+        let options = StackBuildOptions::new().with_keep_raw_ops(true);
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        let names: Vec<&str> = stacks.iter().map(|s| s.stack.as_str()).collect();
+        
+        assert!(names.contains(&"call;ADD"));
+        assert!(names.contains(&"call;unknown@0x5"));
+        assert!(names.contains(&"call;unknown@0x9"));
+        assert!(!names.contains(&"call;unknown"));
+    }
+
+    #[test]
+    fn test_frame_transformers_applied_in_order() {
+        let trace = trace_with_steps(vec![step("add", 1, 3), step("ADD", 1, 4)]);
+        
+        let options = StackBuildOptions::new()
+            .with_frame_transformer(|name| name.to_uppercase())
+            .with_frame_transformer(|name| format!("op:{}", name));
+        let stacks = build_collapsed_stacks_with_options(&trace, &options);
+        
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].stack, "call;op:ADD");
+        assert_eq!(stacks[0].weight, 7);
+    }
+
     #[test]
     fn test_merge_small_stacks() {
         let stacks = vec![
@@ -287,6 +1804,9 @@ mod tests {
             CollapsedStack::new("medium_stack".to_string(), 500),
         ];
         
+        let dropped = drop_small_stacks(&stacks, 100);
+        assert!(dropped.iter().all(|stack| stack.weight >= 100 && stack.stack != OTHER_FRAME));
+        
         let merged = merge_small_stacks(stacks, 100);
         
         // Should have: big_stack (1000), medium_stack (500), other (25)
@@ -295,4 +1815,64 @@ mod tests {
         let other = merged.iter().find(|s| s.stack == "other").unwrap();
         assert_eq!(other.weight, 25);
     }
+
+    #[test]
+    fn test_attribution_coverage() {
+        let mut trace = trace_with_steps(vec![
+            step_at(0, "SLOAD", 1, 600),
+            step_at(1, "", 1, 200),
+            step_at(2, "opcode 0x0c not defined", 1, 100),
+            step_at(20, "", 1, 100),
+        ]);
+        trace.total_gas_used = 1000;
+        let map = SourceMap::from_json(
+            r#"[{ "pc": 20, "end_pc": 30, "file": "lib.rs", "line": 10, "function": "transfer" }]"#,
+        ).unwrap();
+        let coverage = |options: &StackBuildOptions, trace: &ParsedTrace| {
+            build_collapsed_stacks_with_coverage(trace, &options.clone().with_source_map(map.clone())).1
+        };
+        
+        let options = StackBuildOptions::new();
+        assert!((coverage(&options, &trace) - 0.7).abs() < 1e-9);
+        
+        // A custom separator only changes how stacks are written
+        assert!((coverage(&options.clone().with_frame_separator("|"), &trace) - 0.7).abs() < 1e-9);
+        
+        // Raw-op labels keep the frames distinct but are not attributed
+        let raw = StackBuildOptions::new().with_keep_raw_ops(true);
+        let stacks = build_collapsed_stacks_with_options(&trace, &raw);
+        assert!(stacks.iter().any(|s| s.stack.ends_with("opcode_0x0c_not_defined@0x2")));
+        assert!((coverage(&raw, &trace) - 0.7).abs() < 1e-9);
+        
+        trace.total_gas_used = 2000;
+        assert!((coverage(&options, &trace) - 0.35).abs() < 1e-9);
+        trace.total_gas_used = 0;
+        assert_eq!(coverage(&options, &trace), 0.0);
+    }
+
+    #[test]
+    fn test_merge_to_max_stacks() {
+        let stacks: Vec<CollapsedStack> = (1..=100)
+            .map(|i| CollapsedStack::new(format!("f{}", i), i))
+            .collect();
+        
+        assert!(merge_to_max_stacks(&stacks, 100).is_none());
+        
+        let (capped, pct) = merge_to_max_stacks(&stacks, 10).unwrap();
+        assert!(capped.len() <= 10);
+        assert!(pct > MAX_STACKS_START_PCT);
+        assert_eq!(capped.iter().map(|s| s.weight).sum::<u64>(), 5050);
+        assert!(capped.iter().any(|s| s.stack == OTHER_FRAME));
+        
+        let (single, _) = merge_to_max_stacks(&stacks, 0).unwrap();
+        assert_eq!(single.len(), 1);
+        
+        let weightless: Vec<CollapsedStack> = ["a", "b", "c"]
+            .iter()
+            .map(|name| CollapsedStack::new(name.to_string(), 0))
+            .collect();
+        let (merged, _) = merge_to_max_stacks(&weightless, 1).unwrap();
+        assert_eq!(merged.len(), 1);
+        assert_eq!((merged[0].stack.as_str(), merged[0].weight), (OTHER_FRAME, 0));
+    }
 }
\ No newline at end of file
@@ -8,16 +8,41 @@
 //! 5. Calculates metrics
 //! 6. Writes output files
 
-use crate::aggregator::{build_collapsed_stacks, calculate_hot_paths, calculate_gas_distribution};
-use crate::flamegraph::{generate_flamegraph, generate_text_summary, FlamegraphConfig};
-use crate::output::{write_profile, write_svg};
-use crate::parser::{parse_trace, to_profile};
-use crate::rpc::RpcClient;
-use crate::utils::config::SCHEMA_VERSION;
+use crate::aggregator::{
+    build_collapsed_stacks_with_coverage, build_collapsed_stacks_with_options, build_tree, calculate_hot_paths_with_precision, calculate_gas_distribution,
+    call_graph_from_stacks, compute_fingerprint, explain_hot_paths, format_explanations, merge_to_max_stacks, subtree,
+    summarize_unattributed, StackBuildOptions, StackWeight, DEFAULT_PERCENT_PRECISION, MAX_PERCENT_PRECISION, OTHER_FRAME,
+};
+use crate::flamegraph::{
+    generate_diff_flamegraph, generate_flamegraph, generate_hostio_summary, generate_text_summary,
+    FlamegraphConfig, FlamegraphPalette,
+};
+use crate::diff::{diff_profiles, profile_stacks};
+use crate::output::{
+    read_profile, write_dot, write_folded_sorted, write_frames_csv, write_manifest,
+    write_profile_with_opts, write_speedscope, write_svg, FoldedSort, JsonWriteOptions, Manifest, OutputFormat,
+    Redactor,
+};
+use crate::parser::{
+    collect_hot_steps, is_stdin, parse_raw_trace, parse_step_stream, read_trace_file, to_profile,
+    parse_cast_trace_with_options, validate_hostio_costs, GasBreakdown, HostIoRules, HostIoStats, HostIoType,
+    ParseOptions, Profile, StepStream, TraceFormat, TraceInput, TraceSource, TransactionInput,
+};
+use crate::rpc::{RawTraceData, RpcClient, TraceConfig, Transaction, TransactionReceipt};
+use crate::utils::config::CONFIRMATION_POLL_INTERVAL;
+use crate::utils::{format_gas, format_gas_as, AbiSelectors, GasFormat};
 use anyhow::{Context, Result};
-use log::{info, debug};
-use std::path::PathBuf;
-use std::time::Instant;
+use log::{info, debug, warn};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Unattributed gas share (percent) above which capture warns
+const UNATTRIBUTED_WARN_PERCENT: f64 = 10.0;
+
+/// Attribution coverage (percent) below which the summary flags the profile
+const LOW_COVERAGE_PERCENT: f64 = 50.0;
 
 /// Arguments for the capture command
 ///
@@ -39,14 +64,129 @@ pub struct CaptureArgs {
     /// Number of top hot paths to include in profile
     pub top_paths: usize,
     
+    /// Decimal places kept in hot path percentages
+    pub percent_precision: u32,
+    
     /// Flamegraph configuration
     pub flamegraph_config: Option<FlamegraphConfig>,
     
+    /// Options for building collapsed stacks
+    pub stack_options: StackBuildOptions,
+    
+    /// Merge small stacks until rendered outputs have at most this many
+    /// (the profile JSON keeps them all)
+    pub max_frames: Option<usize>,
+    
+    /// Line order of the folded output (name order diffs cleanly in git)
+    pub folded_sort: FoldedSort,
+    
     /// Print text summary to stdout
     pub print_summary: bool,
+    
+    /// Add the N costliest HostIO types to the summary
+    pub top_hostio: Option<usize>,
+    
+    /// Explain in words why each hot path is hot (in the summary)
+    pub explain: bool,
+
+    /// Write minified JSON instead of pretty-printed
+    pub compact_json: bool,
 
     /// Optional tracer name (None = default opcode tracer)
     pub tracer: Option<String>,
+    
+    /// Tracer config options (state the node should leave out of the trace)
+    pub trace_config: TraceConfig,
+    
+    /// Baseline profile to compare the captured profile against
+    pub baseline: Option<PathBuf>,
+    
+    /// Fail if total gas rose more than this percentage over the baseline
+    pub fail_on_regression: Option<f64>,
+    
+    /// Overwrite the baseline with the new profile when the check passes
+    pub update_baseline: bool,
+    
+    /// Write a differential flamegraph (baseline vs captured) here
+    pub diff_svg: Option<PathBuf>,
+    
+    /// Replace addresses and hashes with stable pseudonyms in all outputs
+    pub redact: bool,
+    
+    /// Artifacts to produce, named from `output_json` as the base path
+    ///
+    /// Empty means the legacy behavior: JSON at `output_json` plus SVG at
+    /// `output_svg` if set. Either way a set `output_svg` adds (or places)
+    /// the SVG.
+    pub formats: Vec<OutputFormat>,
+    
+    /// Flag HostIO events priced outside their known gas range
+    pub check_hostio_costs: bool,
+    
+    /// Read the raw trace from this file (`-` for stdin) instead of the RPC
+    pub trace_file: Option<PathBuf>,
+    
+    /// Only trace once the transaction has this many confirmations
+    pub confirmations: Option<u64>,
+    
+    /// How long to wait for `confirmations` before failing (zero fails
+    /// immediately if the transaction is not yet confirmed enough)
+    pub confirmation_timeout: Duration,
+    
+    /// Parser for the raw trace (`Auto` detects cast output by shape)
+    pub trace_format: TraceFormat,
+    
+    /// Fetch the receipt to split total gas into L1 data and L2 execution
+    pub gas_breakdown: bool,
+    
+    /// Fetch the transaction and record its calldata and entry function
+    pub calldata: bool,
+    
+    /// ABI naming the entry function of recorded calldata (checked before
+    /// the symbols table)
+    pub abi: Option<AbiSelectors>,
+    
+    /// Keep geth stack/memory/storage per step and record them for hot steps
+    pub capture_state: bool,
+    
+    /// Record the raw trace's content hash (and file path) in the profile
+    pub embed_raw: bool,
+    
+    /// Embed the full raw trace in the profile (implies `embed_raw`)
+    pub embed_raw_full: bool,
+    
+    /// Render the flamegraph re-rooted at this frame (subtree only)
+    pub focus: Option<String>,
+    
+    /// Store every collapsed stack in the profile JSON
+    pub full_stacks: bool,
+    
+    /// Store the nested call tree in the profile JSON
+    pub call_tree: bool,
+    
+    /// Extra HostIO classification rules (`--hostio-rules`)
+    pub hostio_rules: Option<HostIoRules>,
+    
+    /// Fail if gas remaining rises within a call frame
+    pub strict_validate: bool,
+    
+    /// Fail if the trace has no execution steps (tracer likely didn't run)
+    pub require_steps: bool,
+    
+    /// Fail if the RPC returns a trace naming a different transaction
+    pub strict_hash_check: bool,
+    
+    /// Print the wall-clock time of each capture phase to stderr
+    pub timings: bool,
+    
+    /// Scenario name stored in the profile (keys `compare` columns)
+    pub label: Option<String>,
+    
+    /// Write a JSON index of the artifacts produced here
+    pub manifest: Option<PathBuf>,
+    
+    /// Write per-frame total/self gas as CSV for spreadsheets
+    pub frames_csv: Option<PathBuf>,
 }
 
 impl Default for CaptureArgs {
@@ -57,9 +197,45 @@ impl Default for CaptureArgs {
             output_json: PathBuf::from("profile.json"),
             output_svg: Some(PathBuf::from("flamegraph.svg")),
             top_paths: 20,
+            percent_precision: DEFAULT_PERCENT_PRECISION,
             flamegraph_config: None,
+            stack_options: StackBuildOptions::default(),
+            max_frames: None,
+            folded_sort: FoldedSort::Weight,
             print_summary: false,
+            top_hostio: None,
+            explain: false,
+            compact_json: false,
             tracer: None,  // FIXED: Use default opcode tracer
+            trace_config: TraceConfig::default(),
+            baseline: None,
+            fail_on_regression: None,
+            update_baseline: false,
+            diff_svg: None,
+            redact: false,
+            formats: Vec::new(),
+            check_hostio_costs: false,
+            trace_file: None,
+            confirmations: None,
+            confirmation_timeout: Duration::ZERO,
+            trace_format: TraceFormat::Auto,
+            gas_breakdown: false,
+            calldata: false,
+            abi: None,
+            capture_state: false,
+            embed_raw: false,
+            embed_raw_full: false,
+            focus: None,
+            full_stacks: false,
+            call_tree: false,
+            hostio_rules: None,
+            strict_validate: false,
+            require_steps: false,
+            strict_hash_check: false,
+            timings: false,
+            label: None,
+            manifest: None,
+            frames_csv: None,
         }
     }
 }
@@ -87,115 +263,816 @@ impl Default for CaptureArgs {
 ///     output_json: PathBuf::from("profile.json"),
 ///     output_svg: Some(PathBuf::from("flamegraph.svg")),
 ///     top_paths: 20,
+///     percent_precision: 2,
 ///     flamegraph_config: None,
+///     stack_options: StackBuildOptions::default(),
+///     max_frames: None,
+///     folded_sort: FoldedSort::Weight,
 ///     print_summary: true,
+///     top_hostio: None,
+///     explain: false,
+///     compact_json: false,
 ///     tracer: None,
+///     trace_config: TraceConfig::default(),
+///     baseline: None,
+///     fail_on_regression: None,
+///     update_baseline: false,
+///     diff_svg: None,
+///     redact: false,
+///     formats: Vec::new(),
+///     check_hostio_costs: false,
+///     trace_file: None,
+///     confirmations: None,
+///     confirmation_timeout: Duration::ZERO,
+///     trace_format: TraceFormat::Auto,
+///     gas_breakdown: false,
+///     calldata: false,
+///     abi: None,
+///     capture_state: false,
+///     embed_raw: false,
+///     embed_raw_full: false,
+///     focus: None,
+///     full_stacks: false,
+///     call_tree: false,
+///     hostio_rules: None,
+///     strict_validate: false,
+///     require_steps: false,
+///     strict_hash_check: false,
+///     timings: false,
+///     label: None,
+///     manifest: None,
+///     frames_csv: None,
 /// };
 /// 
 /// execute_capture(args)?;
 /// ```
 pub fn execute_capture(args: CaptureArgs) -> Result<()> {
     let start_time = Instant::now();
+    let mut timings = PhaseTimings::new();
     
     info!("Starting capture for transaction: {}", args.transaction_hash);
-    info!("RPC endpoint: {}", args.rpc_url);
     
-    // Step 1: Fetch trace from RPC
-    info!("Step 1/6: Fetching trace from RPC...");
-    let raw_trace = fetch_trace(&args.rpc_url, &args.transaction_hash, args.tracer.as_deref())
-        .context("Failed to fetch trace from RPC")?;
+    let parse_options = ParseOptions {
+        capture_state: args.capture_state,
+        hostio_rules: args.hostio_rules.clone(),
+        strict_validate: args.strict_validate,
+    };
     
-    // Step 2: Parse trace
+    // Step 1: Fetch trace from RPC (or load it from a file/stdin); JSON
+    // files keep their JSON for cast parsing and `--embed-raw`
+    let loaded = match &args.trace_file {
+        Some(path) => {
+            info!("Step 1/6: Loading trace from {}...", path.display());
+            let input = read_trace_file(path, &parse_options)
+                .context(format!("Failed to read trace file {}", path.display()))?;
+            match input {
+                TraceInput::Json(json) => {
+                    let trace = RawTraceData::deserialize(&json)
+                        .context("Trace must be a JSON object or array")?;
+                    LoadedTrace::Json(trace, Some(json))
+                }
+                TraceInput::Steps(stream) => LoadedTrace::Steps(stream),
+            }
+        }
+        None => {
+            info!("RPC endpoint: {}", args.rpc_url);
+            if let Some(required) = args.confirmations {
+                wait_for_confirmations(&args, required)?;
+            }
+            info!("Step 1/6: Fetching trace from RPC...");
+            let trace = fetch_trace(
+                &args.rpc_url,
+                &args.transaction_hash,
+                args.tracer.as_deref(),
+                args.trace_config,
+                args.strict_hash_check,
+            )
+            .context("Failed to fetch trace from RPC")?;
+            LoadedTrace::Json(trace, None)
+        }
+    };
+    timings.mark("fetch");
+    
+    // Step 2: Parse trace, describing the raw trace first so its JSON can
+    // be dropped before the profile is built
     info!("Step 2/6: Parsing trace data...");
-    let parsed_trace = parse_trace(&args.transaction_hash, &raw_trace)
-        .context("Failed to parse trace data")?;
+    let embed = args.embed_raw || args.embed_raw_full;
+    let source_path = args.trace_file.as_deref().filter(|path| !is_stdin(path));
+    let (parsed_trace, source) = match loaded {
+        LoadedTrace::Json(raw_trace, raw_json) => {
+            let source = embed.then(|| {
+                let json = trace_json(&raw_json, &raw_trace);
+                TraceSource::from_raw(&json, source_path, args.embed_raw_full)
+            });
+            let parsed_trace = match args.trace_format.resolve(&raw_trace) {
+                TraceFormat::Cast => {
+                    let json = trace_json(&raw_json, &raw_trace);
+                    parse_cast_trace_with_options(&args.transaction_hash, &json, &parse_options)
+                        .context("Failed to parse cast trace")?
+                }
+                _ => parse_raw_trace(&args.transaction_hash, &raw_trace, &parse_options)
+                    .context("Failed to parse trace data")?,
+            };
+            (parsed_trace, source)
+        }
+        LoadedTrace::Steps(stream) => {
+            if args.trace_format == TraceFormat::Cast {
+                anyhow::bail!("NDJSON step traces cannot be parsed as cast traces");
+            }
+            if args.embed_raw_full {
+                anyhow::bail!(
+                    "--embed-raw-full needs a JSON trace; NDJSON steps are not kept \
+                     (use --embed-raw to record their hash)"
+                );
+            }
+            let source = embed.then(|| TraceSource {
+                sha256: stream.sha256.clone(),
+                path: source_path.map(|path| path.display().to_string()),
+                raw: None,
+            });
+            let parsed_trace = parse_step_stream(&args.transaction_hash, stream, &parse_options)
+                .context("Failed to parse trace data")?;
+            (parsed_trace, source)
+        }
+    };
     
     debug!("Parsed trace: {} gas used, {} execution steps",
            parsed_trace.total_gas_used,
            parsed_trace.execution_steps.len());
     
+    if args.require_steps && parsed_trace.execution_steps.is_empty() {
+        anyhow::bail!(
+            "Trace for {} has no execution steps; the tracer may not have run \
+             (not a Stylus transaction, or wrong --tracer?)",
+            args.transaction_hash
+        );
+    }
+    
+    let unclassified = parsed_trace.hostio_stats.unclassified();
+    if !unclassified.is_empty() {
+        warn!(
+            "{} HostIO names classified as Other (add them to --hostio-rules): {}",
+            unclassified.len(),
+            format_unclassified(unclassified)
+        );
+    }
+    
+    if args.check_hostio_costs {
+        let anomalies = validate_hostio_costs(&parsed_trace.hostio_stats);
+        if anomalies.is_empty() {
+            info!("HostIO costs within expected ranges");
+        } else {
+            warn!("{} HostIO events priced outside expected ranges (ink/gas mismatch?)",
+                  anomalies.len());
+            for anomaly in anomalies.iter().take(10) {
+                warn!("  {}", anomaly);
+            }
+        }
+    }
+    
+    timings.mark("parse");
+    
     // Step 3: Build collapsed stacks
     info!("Step 3/6: Building collapsed stacks...");
-    let stacks = build_collapsed_stacks(&parsed_trace);
+    // The profile (hot paths, fingerprint, coverage) is always in gas;
+    // `--weight steps` only re-weights the rendered and folded outputs
+    let weight = args.stack_options.weight;
+    let (mut stacks, coverage) = if weight == StackWeight::Gas {
+        build_collapsed_stacks_with_coverage(&parsed_trace, &args.stack_options)
+    } else {
+        let gas_options = args.stack_options.clone().with_weight(StackWeight::Gas);
+        build_collapsed_stacks_with_coverage(&parsed_trace, &gas_options)
+    };
+    let mut weighted_stacks = (weight != StackWeight::Gas)
+        .then(|| build_collapsed_stacks_with_options(&parsed_trace, &args.stack_options));
     
     debug!("Built {} unique stacks", stacks.len());
     
+    // Redact addresses/hashes before anything is derived from frame names
+    let redactor = args.redact.then(|| {
+        info!("Redacting addresses and hashes");
+        Redactor::from_stacks(&stacks, &args.transaction_hash)
+    });
+    if let Some(redactor) = &redactor {
+        stacks = redactor.redact_stacks(&stacks);
+        weighted_stacks = weighted_stacks.map(|weighted| redactor.redact_stacks(&weighted));
+    }
+    
     // Calculate gas distribution statistics
     let gas_dist = calculate_gas_distribution(&stacks);
     info!("Gas distribution: {}", gas_dist.summary());
     
     // Step 4: Calculate hot paths
-    info!("Step 4/6: Calculating top {} hot paths...", args.top_paths);
-    let hot_paths = calculate_hot_paths(&stacks, parsed_trace.total_gas_used, args.top_paths);
+    if args.top_paths == 0 {
+        info!("Step 4/6: Calculating all hot paths...");
+    } else {
+        info!("Step 4/6: Calculating top {} hot paths...", args.top_paths);
+    }
+    let hot_paths = calculate_hot_paths_with_precision(
+        &stacks,
+        parsed_trace.total_gas_used,
+        args.top_paths,
+        args.percent_precision,
+    );
     
     debug!("Top 3 hot paths:");
     for (i, path) in hot_paths.iter().take(3).enumerate() {
-        debug!("  {}. {} gas ({:.1}%): {}", 
+        debug!("  {}. {} gas ({}%): {}", 
                i + 1, path.gas, path.percentage, path.stack);
     }
     
+    // Explain hot paths; per-step stacks are redacted to match their frames
+    let explanations = args.explain.then(|| {
+        let explained = explain_hot_paths(&parsed_trace, &args.stack_options, &hot_paths, redactor.as_ref());
+        format_explanations(&hot_paths, &explained)
+    });
+    
+    // Cap what gets rendered; hot paths above and the profile use every stack
+    let weighted = weighted_stacks.as_deref().unwrap_or(&stacks);
+    let capped = args.max_frames.and_then(|max_frames| {
+        let (capped, pct) = merge_to_max_stacks(weighted, max_frames)?;
+        warn!(
+            "{} stacks exceed --max-frames {}; merged stacks under {}% of {} into '{}' ({} left)",
+            weighted.len(), max_frames, pct, weight.unit_name(), OTHER_FRAME, capped.len()
+        );
+        Some(capped)
+    });
+    let render_stacks = capped.as_deref().unwrap_or(weighted);
+    
+    let outputs = resolve_outputs(&args);
+    timings.mark("aggregate");
+    
     // Step 5: Generate flamegraph (if requested)
-    let svg_content = if args.output_svg.is_some() {
+    let svg_content = if outputs.iter().any(|(format, _)| *format == OutputFormat::Svg) {
         info!("Step 5/6: Generating flamegraph...");
-        let config = args.flamegraph_config.as_ref();
-        let svg = generate_flamegraph(&stacks, config)
-            .context("Failed to generate flamegraph")?;
+        let mut resolved = args.flamegraph_config.clone().unwrap_or_default();
+        if matches!(resolved.palette, FlamegraphPalette::Auto) {
+            let palette = auto_palette(&parsed_trace.hostio_stats);
+            info!("Auto palette: {:?}", palette);
+            resolved = resolved.with_palette(palette);
+        }
+        if resolved.has_default_title() {
+            resolved = resolved.with_title(default_flamegraph_title(
+                &args.transaction_hash,
+                parsed_trace.total_gas_used,
+                redactor.as_ref(),
+            ));
+        }
+        let config = Some(&resolved);
+        let svg = match &args.focus {
+            Some(frame) => {
+                let focused = subtree(render_stacks, frame);
+                if focused.is_empty() {
+                    anyhow::bail!("Focus frame '{}' does not appear in any stack", frame);
+                }
+                info!("Focusing flamegraph on '{}' ({} stacks)", frame, focused.len());
+                generate_flamegraph(&focused, config)
+            }
+            None => generate_flamegraph(render_stacks, config),
+        }
+        .context("Failed to generate flamegraph")?;
         Some(svg)
     } else {
         info!("Step 5/6: Skipping flamegraph generation (not requested)");
         None
     };
+    timings.mark("flamegraph");
     
     // Step 6: Write outputs
     info!("Step 6/6: Writing output files...");
     
     // Create profile
-    let profile = to_profile(&parsed_trace, hot_paths);
-    
-    // Write JSON profile
-    write_profile(&profile, &args.output_json)
-        .context("Failed to write profile JSON")?;
+    let mut profile = to_profile(&parsed_trace, hot_paths);
+    profile.label = args.label.clone();
+    profile.fingerprint = compute_fingerprint(&stacks, parsed_trace.total_gas_used);
+    profile.unattributed = summarize_unattributed(&parsed_trace);
+    profile.metrics.coverage = Some(coverage);
+    if args.capture_state {
+        profile.hot_steps = collect_hot_steps(&parsed_trace, args.top_paths);
+    }
+    if args.full_stacks {
+        profile.collapsed_stacks = stacks.clone();
+    }
+    if args.call_tree {
+        profile.call_tree = Some(build_tree(&stacks));
+    }
+    profile.source = source;
+    // The receipt fills in what the trace lacks: the L1/L2 split, and the
+    // contract address for RPC traces whose tracer omits `to`
+    let need_address = profile.contract_address.is_none() && args.trace_file.is_none();
+    if args.gas_breakdown || need_address {
+        match fetch_receipt(&args.rpc_url, &args.transaction_hash) {
+            Ok(receipt) => {
+                if need_address {
+                    profile.contract_address = receipt.target_address().map(str::to_lowercase);
+                }
+                if args.gas_breakdown {
+                    match receipt.l1_l2_gas() {
+                        Ok((l1_gas, l2_gas)) => {
+                            profile.gas_breakdown = Some(GasBreakdown { l1_gas, l2_gas });
+                        }
+                        Err(e) => warn!("Skipping L1/L2 gas breakdown: {:#}", e),
+                    }
+                }
+            }
+            Err(e) if args.gas_breakdown => warn!("Skipping L1/L2 gas breakdown: {:#}", e),
+            Err(e) => warn!("Could not determine contract address: {:#}", e),
+        }
+    }
+    if args.calldata {
+        match fetch_transaction(&args.rpc_url, &args.transaction_hash) {
+            Ok(transaction) => {
+                let mut calldata = TransactionInput::new(&transaction.input);
+                calldata.function = calldata.selector_value().and_then(|selector| {
+                    args.abi
+                        .as_ref()
+                        .and_then(|abi| abi.lookup(selector))
+                        .or_else(|| args.stack_options.symbols.as_ref()?.lookup_selector(selector))
+                        .map(str::to_string)
+                });
+                profile.calldata = Some(calldata);
+            }
+            Err(e) => warn!("Skipping calldata: {:#}", e),
+        }
+    }
+    if profile.unattributed.percentage >= UNATTRIBUTED_WARN_PERCENT {
+        warn!(
+            "{:.1}% of step gas is unattributed (ops: {}); the profile may be unreliable",
+            profile.unattributed.percentage,
+            profile.unattributed.ops.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+    if let Some(redactor) = &redactor {
+        redactor.redact_profile(&mut profile);
+    }
     
-    info!("✓ Profile written to: {}", args.output_json.display());
+    let json_opts = if args.compact_json {
+        JsonWriteOptions::compact()
+    } else {
+        JsonWriteOptions::default()
+    };
     
-    // Write SVG flamegraph (if generated)
-    if let (Some(svg), Some(svg_path)) = (svg_content, &args.output_svg) {
-        write_svg(&svg, svg_path)
-            .context("Failed to write flamegraph SVG")?;
+    for (format, path) in &outputs {
+        match format {
+            OutputFormat::Json => write_profile_with_opts(&profile, path, json_opts)
+                .context("Failed to write profile JSON")?,
+            OutputFormat::Svg => {
+                if let Some(svg) = &svg_content {
+                    write_svg(svg, path).context("Failed to write flamegraph SVG")?;
+                }
+            }
+            OutputFormat::Folded => {
+                let separator = &args.stack_options.frame_separator;
+                write_folded_sorted(render_stacks, path, separator, args.folded_sort)
+                    .context("Failed to write folded stacks")?
+            }
+            OutputFormat::Speedscope => write_speedscope(render_stacks, &profile.transaction_hash, path)
+                .context("Failed to write speedscope profile")?,
+            OutputFormat::Dot => write_dot(&call_graph_from_stacks(render_stacks), path)
+                .context("Failed to write call graph")?,
+        }
         
-        info!("✓ Flamegraph written to: {}", svg_path.display());
+        info!("✓ {:?} written to: {}", format, path.display());
+    }
+    
+    if let Some(csv_path) = &args.frames_csv {
+        write_frames_csv(&stacks, csv_path).context("Failed to write frame CSV")?;
+        info!("✓ Frame CSV written to: {}", csv_path.display());
+    }
+    
+    if let Some(manifest_path) = &args.manifest {
+        let mut manifest = Manifest::new(&profile);
+        for (format, path) in &outputs {
+            manifest.add_artifact(*format, path)
+                .context(format!("Failed to read back {}", path.display()))?;
+        }
+        write_manifest(&manifest, manifest_path).context("Failed to write manifest")?;
+        info!("✓ Manifest written to: {}", manifest_path.display());
     }
+    timings.mark("write");
     
     // Print text summary (if requested)
     if args.print_summary {
         println!("\n{}", "=".repeat(80));
         println!("PROFILE SUMMARY");
         println!("{}", "=".repeat(80));
-        println!("Transaction: {}", args.transaction_hash);
-        println!("Total Gas:   {}", parsed_trace.total_gas_used);
+        println!("Transaction: {}", profile.transaction_hash);
+        if let Some(address) = &profile.contract_address {
+            println!("Contract:    {}", address);
+        }
+        println!("Total Gas:   {}", format_gas(parsed_trace.total_gas_used));
+        if let Some(coverage) = profile.metrics.coverage {
+            let hint = if coverage * 100.0 < LOW_COVERAGE_PERCENT {
+                "  <- LOW: flamegraph is unreliable, try --symbols or a debug build"
+            } else {
+                ""
+            };
+            println!("Coverage:    {:.1}% of gas in named frames{}", coverage * 100.0, hint);
+        }
         println!("HostIO Calls: {}", parsed_trace.hostio_stats.total_calls());
         println!("Unique Stacks: {}", stacks.len());
+        println!("Gas/Step:     {:.2}", profile.metrics.gas_per_step);
+        println!("Gas/HostIO:   {:.2}", profile.metrics.gas_per_hostio_call);
+        if let Some(breakdown) = &profile.gas_breakdown {
+            println!("L1 Gas:       {}", format_gas(breakdown.l1_gas));
+            println!("L2 Gas:       {}", format_gas(breakdown.l2_gas));
+        }
+        if let Some(calldata) = &profile.calldata {
+            println!(
+                "Entry:        {} {}",
+                calldata.selector.as_deref().unwrap_or("(no selector)"),
+                calldata.function.as_deref().unwrap_or("")
+            );
+        }
+        println!(
+            "Unattributed: {} gas ({:.1}%)",
+            format_gas(profile.unattributed.total_gas), profile.unattributed.percentage
+        );
         println!("\n{}", generate_text_summary(&stacks, 10));
+        if let Some(explanations) = &explanations {
+            println!("\n{}", explanations);
+        }
+        if let Some(top_hostio) = args.top_hostio {
+            println!("\n{}", generate_hostio_summary(&parsed_trace.hostio_stats, top_hostio));
+        }
         println!("{}", "=".repeat(80));
     }
     
+    // Compare against baseline (if requested)
+    if let Some(baseline_path) = &args.baseline {
+        check_baseline(&profile, baseline_path, &args, json_opts)?;
+    }
+    
     let elapsed = start_time.elapsed();
     info!("Capture completed in {:.2}s", elapsed.as_secs_f64());
     
+    if args.timings {
+        eprint!("{}", timings.format_table());
+    }
+    
     Ok(())
 }
 
-/// Fetch trace from RPC endpoint
+/// Palette matching the dominant kind of HostIO gas
+///
+/// **Private** - resolves `--palette auto`: mem when storage ops hold
+/// most HostIO gas, io when calls/creates do, hot otherwise
+fn auto_palette(stats: &HostIoStats) -> FlamegraphPalette {
+    let gas_by_type = stats.gas_by_type();
+    let gas_of = |types: &[HostIoType]| -> u64 {
+        types.iter().filter_map(|io_type| gas_by_type.get(io_type)).sum()
+    };
+    
+    let total = stats.total_gas();
+    let storage = gas_of(&[HostIoType::StorageLoad, HostIoType::StorageStore]);
+    let calls = gas_of(&[
+        HostIoType::Call,
+        HostIoType::StaticCall,
+        HostIoType::DelegateCall,
+        HostIoType::Create,
+    ]);
+    
+    if total > 0 && storage * 2 > total {
+        FlamegraphPalette::Mem
+    } else if total > 0 && calls * 2 > total {
+        FlamegraphPalette::Io
+    } else {
+        FlamegraphPalette::Hot
+    }
+}
+
+/// Wall-clock time spent in each capture phase
+///
+/// **Private** - instrumentation of the tool itself for `--timings`
+/// (not the trace's gas)
+struct PhaseTimings {
+    phases: Vec<(&'static str, Duration)>,
+    last: Instant,
+}
+
+impl PhaseTimings {
+    fn new() -> Self {
+        Self { phases: Vec::new(), last: Instant::now() }
+    }
+    
+    /// Record the time since the previous mark as `phase`
+    fn mark(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+    
+    /// Phase / milliseconds / share table, with a total row
+    fn format_table(&self) -> String {
+        let total: Duration = self.phases.iter().map(|(_, duration)| *duration).sum();
+        let total_ms = total.as_secs_f64() * 1000.0;
+        
+        let mut out = format!("{:<12} {:>10} {:>7}\n", "Phase", "ms", "%");
+        for (phase, duration) in &self.phases {
+            let ms = duration.as_secs_f64() * 1000.0;
+            let share = if total_ms > 0.0 { ms / total_ms * 100.0 } else { 0.0 };
+            out.push_str(&format!("{:<12} {:>10.1} {:>6.1}%\n", phase, ms, share));
+        }
+        out.push_str(&format!("{:<12} {:>10.1} {:>6.1}%\n", "total", total_ms, 100.0));
+        out
+    }
+}
+
+/// Compare the new profile against a stored baseline
 ///
 /// **Private** - internal helper for execute_capture
-fn fetch_trace(rpc_url: &str, tx_hash: &str, tracer: Option<&str>) -> Result<serde_json::Value> {
+///
+/// Fails if total gas rose beyond `fail_on_regression` percent, printing
+/// the hot paths that regressed. On success, `update_baseline` overwrites
+/// the baseline file. A missing baseline is created when
+/// `update_baseline` is set.
+///
+/// With `diff_svg`, a differential flamegraph of the baseline's and the
+/// new profile's hot paths is written before the regression check, so it
+/// is available when the check fails.
+///
+/// The baseline is written with `json_opts`, like the main profile, so
+/// `--compact-json` applies to it too.
+fn check_baseline(
+    profile: &Profile,
+    baseline_path: &Path,
+    args: &CaptureArgs,
+    json_opts: JsonWriteOptions,
+) -> Result<()> {
+    if !baseline_path.exists() {
+        if args.update_baseline {
+            info!("Baseline {} not found, creating it", baseline_path.display());
+            if args.diff_svg.is_some() {
+                warn!("No baseline to compare against yet; skipping differential flamegraph");
+            }
+            return write_profile_with_opts(profile, baseline_path, json_opts)
+                .context("Failed to write baseline profile");
+        }
+        anyhow::bail!("Baseline file not found: {}", baseline_path.display());
+    }
+    
+    let baseline = read_profile(baseline_path)
+        .context(format!("Failed to read baseline {}", baseline_path.display()))?;
+    
+    let diff = diff_profiles(&baseline, profile);
+    info!("Baseline comparison: {}", diff.summary());
+    
+    if let Some(diff_svg) = &args.diff_svg {
+        let svg = generate_diff_flamegraph(
+            &profile_stacks(&baseline),
+            &profile_stacks(profile),
+            args.flamegraph_config.as_ref(),
+        )
+        .context("Failed to generate differential flamegraph")?;
+        write_svg(&svg, diff_svg).context("Failed to write differential flamegraph")?;
+        info!("✓ Differential flamegraph written to: {}", diff_svg.display());
+    }
+    
+    let threshold = args.fail_on_regression.unwrap_or(f64::INFINITY);
+    let total_change = diff.total_percent_change();
+    
+    if total_change > threshold {
+        println!("Gas regression vs baseline {}: {}", baseline_path.display(), diff.summary());
+        println!("Regressed paths (> {:.2}%):", threshold);
+        for path in diff.regressions(threshold) {
+            match path.percent_change() {
+                Some(pct) => println!("  {:+} gas ({:+.2}%) | {}", path.delta(), pct, path.stack),
+                None => println!("  {:+} gas (new) | {}", path.delta(), path.stack),
+            }
+        }
+        anyhow::bail!(
+            "Total gas rose {:.2}% over baseline (threshold {:.2}%)",
+            total_change,
+            threshold
+        );
+    }
+    
+    if args.update_baseline {
+        write_profile_with_opts(profile, baseline_path, json_opts)
+            .context("Failed to update baseline profile")?;
+        info!("✓ Baseline updated: {}", baseline_path.display());
+    }
+    
+    Ok(())
+}
+
+/// List unclassified HostIO names as `name (count)`
+///
+/// **Private** - internal helper for log and summary lines
+pub(crate) fn format_unclassified(unclassified: &std::collections::BTreeMap<String, u64>) -> String {
+    unclassified
+        .iter()
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Work out which artifacts to write and where
+///
+/// **Private** - internal helper for execute_capture
+///
+/// With no explicit `formats`, keeps the original behavior (JSON plus
+/// optional SVG at its own path). Otherwise every requested format is
+/// named from `output_json` with the format's extension, and the SVG goes
+/// to `output_svg` when that is set (whether or not `svg` was listed).
+fn resolve_outputs(args: &CaptureArgs) -> Vec<(OutputFormat, PathBuf)> {
+    if args.formats.is_empty() {
+        let mut outputs = vec![(OutputFormat::Json, args.output_json.clone())];
+        if let Some(svg_path) = &args.output_svg {
+            outputs.push((OutputFormat::Svg, svg_path.clone()));
+        }
+        return outputs;
+    }
+    
+    let mut outputs: Vec<(OutputFormat, PathBuf)> = Vec::new();
+    for format in &args.formats {
+        if outputs.iter().all(|(existing, _)| existing != format) {
+            let path = match (format, &args.output_svg) {
+                (OutputFormat::Svg, Some(svg_path)) => svg_path.clone(),
+                _ => format.path_from_base(&args.output_json),
+            };
+            outputs.push((*format, path));
+        }
+    }
+    if let Some(svg_path) = &args.output_svg {
+        if !outputs.iter().any(|(format, _)| *format == OutputFormat::Svg) {
+            outputs.push((OutputFormat::Svg, svg_path.clone()));
+        }
+    }
+    outputs
+}
+
+/// Flamegraph title when `--title` is not given
+///
+/// **Private** - names the transaction (hash abbreviated) and its total
+/// gas, e.g. `Stylus tx 0x1234… — 1.20M gas`, so saved flamegraphs are
+/// told apart at a glance. Under `--redact` the hash's pseudonym is used.
+fn default_flamegraph_title(tx_hash: &str, total_gas: u64, redactor: Option<&Redactor>) -> String {
+    let hex = tx_hash.strip_prefix("0x").unwrap_or(tx_hash);
+    let short = match (redactor, hex.get(..4)) {
+        (Some(redactor), _) => redactor.redact_tx_hash(tx_hash),
+        (None, Some(prefix)) if hex.len() > 4 => format!("0x{}…", prefix),
+        _ => format!("0x{}", hex),
+    };
+    format!("Stylus tx {} — {} gas", short, format_gas_as(total_gas, GasFormat::Compact))
+}
+
+/// Fetch trace from RPC endpoint
+///
+/// **Private** - shared by capture and the lighter commands
+pub(crate) fn fetch_trace(
+    rpc_url: &str,
+    tx_hash: &str,
+    tracer: Option<&str>,
+    trace_config: TraceConfig,
+    strict_hash_check: bool,
+) -> Result<RawTraceData> {
+    let client = RpcClient::new(rpc_url)
+        .context("Failed to create RPC client")?
+        .with_trace_config(trace_config)
+        .with_strict_hash_check(strict_hash_check);
+    
+    client.debug_trace_transaction_with_tracer(tx_hash, tracer)
+        .context(format!("Failed to fetch trace for transaction {}", tx_hash))
+}
+
+/// A trace as loaded, before parsing
+///
+/// **Private** - JSON traces keep the file's JSON (None for RPC traces);
+/// NDJSON steps arrive already deserialized
+enum LoadedTrace {
+    Json(RawTraceData, Option<serde_json::Value>),
+    Steps(StepStream),
+}
+
+/// JSON of a trace: the file as read, or the RPC trace converted back
+///
+/// **Private** - only cast parsing and `--embed-raw` need the JSON
+fn trace_json<'a>(raw_json: &'a Option<serde_json::Value>, trace: &RawTraceData) -> Cow<'a, serde_json::Value> {
+    match raw_json {
+        Some(json) => Cow::Borrowed(json),
+        None => Cow::Owned(trace.clone().into()),
+    }
+}
+
+/// Block until the transaction has `required` confirmations
+///
+/// **Private** - `--confirmations` guard against profiling a transaction
+/// that may still be reorged out. Polls every `CONFIRMATION_POLL_INTERVAL`
+/// until `confirmation_timeout` runs out; RPC errors (e.g. no receipt
+/// yet) count as unconfirmed until then.
+///
+/// # Errors
+/// * Still short of `required` when the timeout expires
+fn wait_for_confirmations(args: &CaptureArgs, required: u64) -> Result<()> {
+    let client = RpcClient::new(&args.rpc_url)
+        .context("Failed to create RPC client")?;
+    let deadline = Instant::now() + args.confirmation_timeout;
+    
+    loop {
+        let status = client.confirmations(&args.transaction_hash);
+        if let Ok(confirmations) = status {
+            if confirmations >= required {
+                info!("Transaction has {} confirmations (need {})", confirmations, required);
+                return Ok(());
+            }
+        }
+        
+        let now = Instant::now();
+        if now >= deadline {
+            let have = match status {
+                Ok(confirmations) => format!("{} of {} confirmations", confirmations, required),
+                Err(e) => format!("no receipt ({})", e),
+            };
+            anyhow::bail!(
+                "Transaction {} is not confirmed enough to profile: {} after waiting {:?} \
+                 (raise --confirmation-timeout to wait longer)",
+                args.transaction_hash, have, args.confirmation_timeout
+            );
+        }
+        
+        info!("Waiting for {} confirmations of {}...", required, args.transaction_hash);
+        std::thread::sleep(CONFIRMATION_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Resolve a transaction hash from its block number and index
+///
+/// **Public** - used by main.rs for `capture --block N --index I`
+///
+/// # Errors
+/// * RPC failures, or the index is past the end of the block
+pub fn resolve_tx_hash(rpc_url: &str, block: u64, index: usize) -> Result<String> {
     let client = RpcClient::new(rpc_url)
         .context("Failed to create RPC client")?;
     
-    let trace = client.debug_trace_transaction_with_tracer(tx_hash, tracer)
-        .context(format!("Failed to fetch trace for transaction {}", tx_hash))?;
+    let tx_hash = client.get_tx_hash_by_block_index(block, index)
+        .context(format!("Failed to resolve transaction {} in block {}", index, block))?;
+    info!("Resolved block {} index {} to {}", block, index, tx_hash);
     
-    Ok(trace)
+    Ok(tx_hash)
+}
+
+/// Fetch the transaction receipt
+///
+/// **Private** - internal helper for execute_capture (gas breakdown and
+/// contract address)
+fn fetch_receipt(rpc_url: &str, tx_hash: &str) -> Result<TransactionReceipt> {
+    let client = RpcClient::new(rpc_url)
+        .context("Failed to create RPC client")?;
+    
+    let receipt = client.transaction_receipt(tx_hash)
+        .context(format!("Failed to fetch receipt for transaction {}", tx_hash))?;
+    
+    Ok(receipt)
+}
+
+/// Fetch the transaction itself
+///
+/// **Private** - internal helper for execute_capture (`--calldata`)
+fn fetch_transaction(rpc_url: &str, tx_hash: &str) -> Result<Transaction> {
+    let client = RpcClient::new(rpc_url)
+        .context("Failed to create RPC client")?;
+    
+    let transaction = client.get_transaction(tx_hash)
+        .context(format!("Failed to fetch transaction {}", tx_hash))?;
+    
+    Ok(transaction)
+}
+
+/// Check that a transaction hash is 32 bytes of hex
+///
+/// **Public** - fast-fail before any RPC round-trip; the `0x` prefix is
+/// optional
+///
+/// # Errors
+/// * Empty hash, wrong length, or a non-hex character (reported with its
+///   position)
+pub fn validate_tx_hash(tx_hash: &str) -> Result<()> {
+    if tx_hash.is_empty() {
+        anyhow::bail!("Transaction hash cannot be empty");
+    }
+    
+    let hex = tx_hash
+        .strip_prefix("0x")
+        .or_else(|| tx_hash.strip_prefix("0X"))
+        .unwrap_or(tx_hash);
+    
+    if let Some((position, c)) = hex.chars().enumerate().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        anyhow::bail!(
+            "Transaction hash '{}' has non-hex character '{}' at position {}",
+            tx_hash, c, position
+        );
+    }
+    
+    if hex.len() != 64 {
+        anyhow::bail!(
+            "Transaction hash '{}' has {} hex characters; expected 64 (32 bytes)",
+            tx_hash, hex.len()
+        );
+    }
+    
+    Ok(())
 }
 
 /// Validate capture arguments
@@ -217,30 +1094,40 @@ pub fn validate_args(args: &CaptureArgs) -> Result<()> {
         anyhow::bail!("RPC URL must start with http:// or https://");
     }
     
-    // Validate transaction hash
-    if args.transaction_hash.is_empty() {
-        anyhow::bail!("Transaction hash cannot be empty");
+    validate_tx_hash(&args.transaction_hash)?;
+    
+    // Validate top_paths (0 means all paths)
+    if args.top_paths > 1000 {
+        anyhow::bail!("top_paths is too large (max 1000)");
     }
     
-    // Basic hex validation (with or without 0x prefix)
-    let tx_hash = args.transaction_hash.strip_prefix("0x")
-        .unwrap_or(&args.transaction_hash);
+    if args.percent_precision > MAX_PERCENT_PRECISION {
+        anyhow::bail!("percent_precision is too large (max {})", MAX_PERCENT_PRECISION);
+    }
     
-    if tx_hash.len() != 64 {
-        anyhow::bail!("Transaction hash must be 32 bytes (64 hex characters)");
+    // Validate regression threshold
+    if let Some(threshold) = args.fail_on_regression {
+        if !threshold.is_finite() || threshold < 0.0 {
+            anyhow::bail!("fail_on_regression must be a non-negative percentage");
+        }
+        if args.baseline.is_none() {
+            anyhow::bail!("fail_on_regression requires a baseline file");
+        }
     }
     
-    if !tx_hash.chars().all(|c| c.is_ascii_hexdigit()) {
-        anyhow::bail!("Transaction hash contains invalid characters");
+    // State capture needs the parts of the trace these options drop
+    if args.capture_state && args.trace_config.disables_state() {
+        anyhow::bail!("capture_state cannot be combined with disabling storage, memory or stack");
     }
     
-    // Validate top_paths
-    if args.top_paths == 0 {
-        anyhow::bail!("top_paths must be greater than 0");
+    // A full raw trace would leak everything redaction hides
+    if args.redact && args.embed_raw_full {
+        anyhow::bail!("embed_raw_full cannot be combined with redact");
     }
     
-    if args.top_paths > 1000 {
-        anyhow::bail!("top_paths is too large (max 1000)");
+    // Stack/memory/storage words hold unprefixed addresses redaction can't find
+    if args.redact && args.capture_state {
+        anyhow::bail!("capture_state cannot be combined with redact");
     }
     
     Ok(())
@@ -264,8 +1151,11 @@ pub fn quick_capture(rpc_url: &str, tx_hash: &str) -> Result<(PathBuf, PathBuf)>
         output_svg: Some(PathBuf::from("flamegraph.svg")),
         top_paths: 20,
         flamegraph_config: None,
+        stack_options: StackBuildOptions::default(),
         print_summary: false,
+        compact_json: false,
         tracer: None,  // FIXED: Use default opcode tracer
+        ..Default::default()
     };
     
     execute_capture(args.clone())?;
@@ -354,6 +1244,34 @@ mod tests {
         assert!(validate_args(&args).is_ok());
     }
 
+    #[test]
+    fn test_validate_tx_hash_messages() {
+        let hash = "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef";
+        assert!(validate_tx_hash(&format!("0X{}", hash)).is_ok());
+        
+        let short = validate_tx_hash("0x1234").unwrap_err().to_string();
+        assert!(short.contains("4 hex characters"));
+        
+        let typo = validate_tx_hash(&format!("0x{}g", &hash[..63])).unwrap_err().to_string();
+        assert!(typo.contains("'g' at position 63"));
+    }
+
+    #[test]
+    fn test_default_flamegraph_title() {
+        assert_eq!(
+            default_flamegraph_title("0x1234abcd", 1_234_567, None),
+            "Stylus tx 0x1234… — 1.23M gas"
+        );
+        assert_eq!(default_flamegraph_title("ab", 900, None), "Stylus tx 0xab — 900 gas");
+        
+        let hash = format!("0x{}", "ab".repeat(32));
+        let redactor = Redactor::from_stacks(&[], &hash);
+        assert_eq!(
+            default_flamegraph_title(&hash, 900, Some(&redactor)),
+            "Stylus tx tx_1 — 900 gas"
+        );
+    }
+
     #[test]
     fn test_validate_args_top_paths_zero() {
         let args = CaptureArgs {
@@ -363,7 +1281,8 @@ mod tests {
             ..Default::default()
         };
         
-        assert!(validate_args(&args).is_err());
+        // 0 means "all paths"
+        assert!(validate_args(&args).is_ok());
     }
 
     #[test]
@@ -377,4 +1296,246 @@ mod tests {
         
         assert!(validate_args(&args).is_err());
     }
+
+    fn baseline_profile(total_gas: u64) -> Profile {
+        crate::utils::testgen::profile_with_paths(total_gas, &[("main;execute", total_gas)])
+    }
+
+    #[test]
+    fn test_check_baseline_creates_missing_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        let args = CaptureArgs { update_baseline: true, ..Default::default() };
+        
+        check_baseline(&baseline_profile(1000), &path, &args, JsonWriteOptions::default()).unwrap();
+        
+        assert_eq!(read_profile(&path).unwrap().total_gas, 1000);
+    }
+
+    #[test]
+    fn test_check_baseline_fails_on_regression() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        crate::output::write_profile(&baseline_profile(1000), &path).unwrap();
+        
+        let args = CaptureArgs {
+            fail_on_regression: Some(5.0),
+            update_baseline: true,
+            ..Default::default()
+        };
+        
+        assert!(check_baseline(&baseline_profile(1040), &path, &args, JsonWriteOptions::default()).is_ok());
+        assert_eq!(read_profile(&path).unwrap().total_gas, 1040);
+        
+        assert!(check_baseline(&baseline_profile(1200), &path, &args, JsonWriteOptions::default()).is_err());
+        assert_eq!(read_profile(&path).unwrap().total_gas, 1040);
+    }
+
+    #[test]
+    fn test_check_baseline_writes_compact_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        let args = CaptureArgs { update_baseline: true, ..Default::default() };
+        
+        check_baseline(&baseline_profile(1000), &path, &args, JsonWriteOptions::compact()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        
+        check_baseline(&baseline_profile(1010), &path, &args, JsonWriteOptions::compact()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+        assert_eq!(read_profile(&path).unwrap().total_gas, 1010);
+    }
+
+    #[test]
+    fn test_resolve_outputs_legacy() {
+        let args = CaptureArgs::default();
+        let outputs = resolve_outputs(&args);
+        
+        assert_eq!(outputs, vec![
+            (OutputFormat::Json, PathBuf::from("profile.json")),
+            (OutputFormat::Svg, PathBuf::from("flamegraph.svg")),
+        ]);
+    }
+
+    #[test]
+    fn test_resolve_outputs_from_base_path() {
+        let args = CaptureArgs {
+            output_json: PathBuf::from("out/run.json"),
+            output_svg: None,
+            formats: vec![OutputFormat::Folded, OutputFormat::Json, OutputFormat::Folded],
+            ..Default::default()
+        };
+        let outputs = resolve_outputs(&args);
+        
+        assert_eq!(outputs, vec![
+            (OutputFormat::Folded, PathBuf::from("out/run.folded")),
+            (OutputFormat::Json, PathBuf::from("out/run.json")),
+        ]);
+    }
+
+    #[test]
+    fn test_resolve_outputs_formats_with_flamegraph_path() {
+        let args = CaptureArgs {
+            output_json: PathBuf::from("out/run.json"),
+            output_svg: Some(PathBuf::from("graphs/tx.svg")),
+            formats: vec![OutputFormat::Json],
+            ..Default::default()
+        };
+        assert_eq!(resolve_outputs(&args), vec![
+            (OutputFormat::Json, PathBuf::from("out/run.json")),
+            (OutputFormat::Svg, PathBuf::from("graphs/tx.svg")),
+        ]);
+        
+        let args = CaptureArgs { formats: vec![OutputFormat::Svg, OutputFormat::Json], ..args };
+        assert_eq!(resolve_outputs(&args), vec![
+            (OutputFormat::Svg, PathBuf::from("graphs/tx.svg")),
+            (OutputFormat::Json, PathBuf::from("out/run.json")),
+        ]);
+    }
+
+    #[test]
+    fn test_embed_raw_records_hash_and_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace_path = dir.path().join("trace.json");
+        let output_json = dir.path().join("profile.json");
+        std::fs::write(
+            &trace_path,
+            r#"{"gasUsed": 10, "structLogs": [{"op": "ADD", "gasCost": 3, "depth": 1}]}"#,
+        )
+        .unwrap();
+        
+        let args = CaptureArgs {
+            transaction_hash: "0xtest".to_string(),
+            output_json: output_json.clone(),
+            output_svg: None,
+            trace_file: Some(trace_path.clone()),
+            embed_raw: true,
+            ..Default::default()
+        };
+        execute_capture(args.clone()).unwrap();
+        
+        let source = read_profile(&output_json).unwrap().source.unwrap();
+        assert_eq!(source.sha256.len(), 64);
+        assert_eq!(source.path, Some(trace_path.display().to_string()));
+        assert!(source.raw.is_none());
+        
+        execute_capture(CaptureArgs { embed_raw_full: true, ..args }).unwrap();
+        let full = read_profile(&output_json).unwrap().source.unwrap();
+        assert_eq!(full.sha256, source.sha256);
+        assert_eq!(full.raw.unwrap()["gasUsed"], 10);
+    }
+
+    #[test]
+    fn test_weight_steps_keeps_profile_in_gas() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace_path = dir.path().join("trace.json");
+        let output_json = dir.path().join("profile.json");
+        std::fs::write(&trace_path, r#"{"gasUsed": 5009, "structLogs": [
+            {"op": "ADD", "gasCost": 3, "depth": 1},
+            {"op": "ADD", "gasCost": 3, "depth": 1},
+            {"op": "ADD", "gasCost": 3, "depth": 1},
+            {"op": "SSTORE", "gasCost": 5000, "depth": 1}
+        ]}"#).unwrap();
+        
+        execute_capture(CaptureArgs {
+            transaction_hash: "0xtest".to_string(),
+            output_json: output_json.clone(),
+            output_svg: None,
+            trace_file: Some(trace_path),
+            formats: vec![OutputFormat::Json, OutputFormat::Folded],
+            stack_options: StackBuildOptions::new().with_weight(StackWeight::Steps),
+            ..Default::default()
+        })
+        .unwrap();
+        
+        let profile = read_profile(&output_json).unwrap();
+        assert_eq!(profile.hot_paths[0].stack, "call;SSTORE");
+        assert_eq!(profile.hot_paths[0].gas, 5000);
+        assert_eq!(profile.hot_paths[0].percentage, 99.82);
+        assert_eq!(profile.hot_paths[1].percentage, 0.18);
+        
+        let folded = std::fs::read_to_string(OutputFormat::Folded.path_from_base(&output_json)).unwrap();
+        assert_eq!(folded, "call;ADD 3\ncall;SSTORE 1\n");
+    }
+
+    #[test]
+    fn test_require_steps_rejects_empty_trace() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace_path = dir.path().join("trace.json");
+        std::fs::write(&trace_path, r#"{"gasUsed": 21000, "structLogs": []}"#).unwrap();
+        
+        let args = CaptureArgs {
+            transaction_hash: "0xtest".to_string(),
+            output_json: dir.path().join("profile.json"),
+            output_svg: None,
+            trace_file: Some(trace_path),
+            ..Default::default()
+        };
+        execute_capture(args.clone()).unwrap();
+        
+        let err = execute_capture(CaptureArgs { require_steps: true, ..args }).unwrap_err();
+        assert!(err.to_string().contains("no execution steps"));
+    }
+
+    #[test]
+    fn test_auto_palette_follows_dominant_hostio() {
+        use crate::parser::hostio::HostIoEvent;
+        
+        let stats = |events: &[(HostIoType, u64)]| {
+            let mut stats = HostIoStats::new();
+            for &(io_type, gas_cost) in events {
+                stats.add_event(HostIoEvent::new(io_type, gas_cost));
+            }
+            stats
+        };
+        
+        let storage = stats(&[(HostIoType::StorageStore, 20000), (HostIoType::Call, 700)]);
+        let calls = stats(&[(HostIoType::Call, 9000), (HostIoType::StorageLoad, 2100)]);
+        let mixed = stats(&[(HostIoType::Log, 5000), (HostIoType::StorageLoad, 2100)]);
+        
+        assert!(matches!(auto_palette(&storage), FlamegraphPalette::Mem));
+        assert!(matches!(auto_palette(&calls), FlamegraphPalette::Io));
+        assert!(matches!(auto_palette(&mixed), FlamegraphPalette::Hot));
+        assert!(matches!(auto_palette(&HostIoStats::new()), FlamegraphPalette::Hot));
+    }
+
+    #[test]
+    fn test_phase_timings_table() {
+        let timings = PhaseTimings {
+            phases: vec![
+                ("fetch", Duration::from_millis(300)),
+                ("parse", Duration::from_millis(100)),
+            ],
+            last: Instant::now(),
+        };
+        
+        let table = timings.format_table();
+        
+        assert!(table.contains("fetch"));
+        assert!(table.contains("75.0%"));
+        assert!(table.lines().last().unwrap().contains("400.0"));
+    }
+
+    #[test]
+    fn test_validate_args_regression_requires_baseline() {
+        let args = CaptureArgs {
+            transaction_hash: "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef".to_string(),
+            fail_on_regression: Some(5.0),
+            ..Default::default()
+        };
+        
+        assert!(validate_args(&args).is_err());
+    }
+
+    #[test]
+    fn test_validate_args_capture_state_with_redact() {
+        let args = CaptureArgs {
+            transaction_hash: "0x1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef".to_string(),
+            capture_state: true,
+            redact: true,
+            ..Default::default()
+        };
+        
+        let err = validate_args(&args).unwrap_err().to_string();
+        assert!(err.contains("capture_state cannot be combined with redact"));
+    }
 }
\ No newline at end of file
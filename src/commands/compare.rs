@@ -0,0 +1,139 @@
+//! Compare command implementation.
+//!
+//! Lines up any number of saved profiles side by side, one column per
+//! profile keyed by its `--label` (or file name when unlabelled): totals
+//! first, then the heaviest hot paths. The first profile is the reference
+//! for the "vs first" row.
+
+use crate::diff::{compare_profiles, PathComparison};
+use crate::output::read_profile;
+use crate::parser::Profile;
+use crate::utils::format_gas;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Arguments for the compare command
+///
+/// **Public** - used by main.rs to construct from CLI args
+#[derive(Debug, Clone)]
+pub struct CompareArgs {
+    /// Profile JSON files, one column each (the first is the reference)
+    pub inputs: Vec<PathBuf>,
+
+    /// Maximum number of hot paths to print
+    pub top: usize,
+}
+
+/// Execute the compare command
+///
+/// **Public** - main entry point called from main.rs
+///
+/// # Errors
+/// * Fewer than two inputs
+/// * Any profile cannot be read or parsed
+pub fn execute_compare(args: CompareArgs) -> Result<()> {
+    if args.inputs.len() < 2 {
+        anyhow::bail!("compare needs at least two profiles");
+    }
+
+    let mut labels = Vec::with_capacity(args.inputs.len());
+    let mut profiles = Vec::with_capacity(args.inputs.len());
+    for path in &args.inputs {
+        let profile = read_profile(path)
+            .context(format!("Failed to read profile {}", path.display()))?;
+        labels.push(column_label(&profile, path));
+        profiles.push(profile);
+    }
+
+    print!("{}", format_comparison(&labels, &profiles, args.top));
+
+    Ok(())
+}
+
+/// Column heading for a profile: its label, else the file stem
+///
+/// **Private** - internal helper
+fn column_label(profile: &Profile, path: &Path) -> String {
+    profile.label.clone().unwrap_or_else(|| {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string())
+    })
+}
+
+/// Render profiles as a side-by-side table
+///
+/// **Public** - reusable by other text reports
+///
+/// # Arguments
+/// * `labels` - Column headings, one per profile
+/// * `profiles` - Profiles in column order (the first is the reference)
+/// * `top` - Maximum number of hot paths to list
+pub fn format_comparison(labels: &[String], profiles: &[Profile], top: usize) -> String {
+    let width = labels.iter().map(String::len).max().unwrap_or(0).max(13);
+    let row = |name: &str, cells: Vec<String>| {
+        let cells: Vec<String> = cells.iter().map(|cell| format!("{:>width$}", cell)).collect();
+        format!("{:<16} {}\n", name, cells.join("  "))
+    };
+
+    let reference = profiles.first().map_or(0, |profile| profile.total_gas);
+    let mut output = row("", labels.to_vec());
+    output.push_str(&row(
+        "Total gas",
+        profiles.iter().map(|p| format_gas(p.total_gas)).collect(),
+    ));
+    output.push_str(&row(
+        "vs first",
+        profiles
+            .iter()
+            .map(|p| {
+                let change = (p.total_gas as f64 - reference as f64) / reference.max(1) as f64 * 100.0;
+                format!("{:+.1}%", change)
+            })
+            .collect(),
+    ));
+    output.push_str(&row(
+        "HostIO gas",
+        profiles.iter().map(|p| format_gas(p.hostio_summary.total_hostio_gas)).collect(),
+    ));
+    output.push_str(&row(
+        "Steps",
+        profiles.iter().map(|p| p.metrics.total_steps.to_string()).collect(),
+    ));
+
+    output.push_str("\nHot paths:\n");
+    for PathComparison { stack, gas } in compare_profiles(profiles).into_iter().take(top) {
+        let cells: Vec<String> = gas.iter().map(|&gas| format_gas(gas)).collect();
+        let cells: Vec<String> = cells.iter().map(|cell| format!("{:>width$}", cell)).collect();
+        output.push_str(&format!("  {}  {}\n", cells.join("  "), stack));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_comparison_keyed_by_label() {
+        let raw = serde_json::json!({
+            "gasUsed": 100,
+            "structLogs": [{ "op": "SLOAD", "gasCost": 100, "depth": 1 }]
+        });
+        let parsed = crate::parser::parse_trace("0xabc", &raw).unwrap();
+        let mut cold = crate::parser::to_profile(&parsed, Vec::new());
+        cold.label = Some("cold cache".to_string());
+        let mut warm = cold.clone();
+        warm.label = Some("warm cache".to_string());
+        warm.total_gas = 50;
+
+        let labels: Vec<String> = [&cold, &warm].iter().map(|p| column_label(p, Path::new("x.json"))).collect();
+        let text = format_comparison(&labels, &[cold, warm], 5);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert!(lines[0].contains("cold cache") && lines[0].contains("warm cache"));
+        assert!(lines[2].ends_with("-50.0%"));
+        assert_eq!(column_label(&crate::parser::to_profile(&parsed, Vec::new()), Path::new("a/run1.json")), "run1");
+    }
+}
@@ -0,0 +1,145 @@
+//! Diff command implementation.
+//!
+//! Compares two saved profiles and prints the total change plus the hot
+//! paths that moved the most. With `normalize`, paths are compared by
+//! share of their own profile's total, which surfaces structural shifts
+//! between transactions of different sizes. A final "shape changed" line
+//! scores how much the call tree itself moved (see `tree_similarity`).
+//! `only_new` narrows the listing to paths that did not exist in base.
+
+use crate::aggregator::tree_similarity;
+use crate::diff::{
+    diff_profiles, diff_profiles_by_pc, diff_profiles_normalized, normalize_profile,
+    normalized_percent, profile_stacks, ProfileDiff,
+};
+use crate::output::read_profile;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+/// Arguments for the diff command
+///
+/// **Public** - used by main.rs to construct from CLI args
+#[derive(Debug, Clone)]
+pub struct DiffArgs {
+    /// Reference profile JSON
+    pub base: PathBuf,
+
+    /// Profile to compare against the reference
+    pub head: PathBuf,
+
+    /// Compare each path's share of total gas instead of absolute gas
+    pub normalize: bool,
+
+    /// Match instructions by pc (profiles built with `--aggregate-by pc`)
+    pub by_pc: bool,
+
+    /// Maximum number of changed paths to print
+    pub top: usize,
+
+    /// Only print paths present in head but absent from base
+    pub only_new: bool,
+
+    /// With `only_new`, ignore new paths lighter than this (in the diff's
+    /// units, i.e. normalized shares with `normalize`)
+    pub min_gas: u64,
+}
+
+/// Execute the diff command
+///
+/// **Public** - main entry point called from main.rs
+///
+/// # Errors
+/// * Either profile cannot be read or parsed
+pub fn execute_diff(args: DiffArgs) -> Result<()> {
+    let base = read_profile(&args.base)
+        .context(format!("Failed to read base profile {}", args.base.display()))?;
+    let head = read_profile(&args.head)
+        .context(format!("Failed to read head profile {}", args.head.display()))?;
+
+    // Structure of the stack trees, independent of absolute cost
+    let similarity = tree_similarity(&profile_stacks(&base), &profile_stacks(&head));
+
+    let mut diff = match (args.by_pc, args.normalize) {
+        (true, normalize) => {
+            let (base, head) = if normalize {
+                (normalize_profile(&base), normalize_profile(&head))
+            } else {
+                (base, head)
+            };
+            let mut diff = diff_profiles_by_pc(&base, &head).context(
+                "--by-pc needs both profiles captured with --aggregate-by pc",
+            )?;
+            diff.normalized = normalize;
+            diff
+        }
+        (false, true) => diff_profiles_normalized(&base, &head),
+        (false, false) => diff_profiles(&base, &head),
+    };
+    if args.only_new {
+        diff.retain_new_paths(args.min_gas);
+    }
+
+    print!("{}", format_diff(&diff, args.top));
+    println!("Shape changed by {:.1}%", (1.0 - similarity) * 100.0);
+
+    Ok(())
+}
+
+/// Render a profile diff as text
+///
+/// **Public** - reusable by other text reports
+///
+/// # Arguments
+/// * `diff` - Comparison result
+/// * `top` - Maximum number of changed paths to list
+///
+/// # Returns
+/// A summary line followed by one line per changed path
+pub fn format_diff(diff: &ProfileDiff, top: usize) -> String {
+    let mut output = format!("{}\n", diff.summary());
+
+    for path in diff.paths.iter().filter(|p| p.delta() != 0).take(top) {
+        let change = if diff.normalized {
+            format!(
+                "{:>6.2}% -> {:>6.2}% ({:+.2} pts)",
+                normalized_percent(path.base_gas as i128),
+                normalized_percent(path.head_gas as i128),
+                normalized_percent(path.delta())
+            )
+        } else {
+            match path.percent_change() {
+                Some(pct) => format!("{:+} gas ({:+.2}%)", path.delta(), pct),
+                None => format!("{:+} gas (new)", path.delta()),
+            }
+        };
+        output.push_str(&format!("  {} | {}\n", change, path.stack));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::PathDelta;
+
+    #[test]
+    fn test_format_diff_normalized() {
+        let diff = ProfileDiff {
+            base_total_gas: 1_000_000,
+            head_total_gas: 1_000_000,
+            paths: vec![
+                PathDelta { stack: "main;a".to_string(), base_gas: 750_000, head_gas: 500_000 },
+                PathDelta { stack: "main;b".to_string(), base_gas: 250_000, head_gas: 250_000 },
+            ],
+            normalized: true,
+        };
+
+        let text = format_diff(&diff, 10);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("75.00% ->  50.00% (-25.00 pts)"));
+        assert!(lines[1].ends_with("main;a"));
+    }
+}
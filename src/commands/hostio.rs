@@ -0,0 +1,143 @@
+//! HostIO command implementation.
+//!
+//! A fast health check: fetches the trace, extracts only HostIO events and
+//! prints the per-type breakdown. Skips step parsing, stack building and
+//! flamegraph generation entirely, so it stays quick on large traces.
+
+use super::capture::{fetch_trace, format_unclassified, validate_tx_hash};
+use crate::parser::{
+    extract_hostio_events_with_rules, extract_trace_hostio_events, read_trace_file, HostIoRules, HostIoStats,
+    ParseOptions, TraceInput,
+};
+use crate::rpc::TraceConfig;
+use crate::utils::format_gas;
+use anyhow::{Context, Result};
+use log::info;
+use std::path::PathBuf;
+
+/// Arguments for the hostio command
+///
+/// **Public** - used by main.rs to construct from CLI args
+#[derive(Debug, Clone)]
+pub struct HostioArgs {
+    /// RPC endpoint URL
+    pub rpc_url: String,
+
+    /// Transaction hash to inspect
+    pub transaction_hash: String,
+
+    /// Read the raw trace from this file (`-` for stdin) instead of the RPC
+    pub trace_file: Option<PathBuf>,
+
+    /// Optional tracer name (None = default opcode tracer)
+    pub tracer: Option<String>,
+
+    /// Extra HostIO classification rules (`--hostio-rules`)
+    pub hostio_rules: Option<HostIoRules>,
+}
+
+/// Execute the hostio command
+///
+/// **Public** - main entry point called from main.rs
+///
+/// # Arguments
+/// * `args` - HostIO command arguments
+///
+/// # Errors
+/// * RPC or trace file read failures
+pub fn execute_hostio(args: HostioArgs) -> Result<()> {
+    let rules = args.hostio_rules.as_ref();
+    let stats = match &args.trace_file {
+        Some(path) => {
+            let input = read_trace_file(path, &ParseOptions::default())
+                .context(format!("Failed to read trace file {}", path.display()))?;
+            match input {
+                TraceInput::Json(raw_trace) => extract_hostio_events_with_rules(&raw_trace, rules),
+                // NDJSON step streams carry no HostIO events
+                TraceInput::Steps(_) => HostIoStats::new(),
+            }
+        }
+        None => {
+            validate_tx_hash(&args.transaction_hash)?;
+            let trace = fetch_trace(
+                &args.rpc_url,
+                &args.transaction_hash,
+                args.tracer.as_deref(),
+                TraceConfig::default(),
+                false,
+            )
+            .context("Failed to fetch trace from RPC")?;
+            extract_trace_hostio_events(&trace, rules)
+        }
+    };
+    info!("Extracted {} HostIO events", stats.total_calls());
+
+    println!("Transaction: {}", args.transaction_hash);
+    print!("{}", format_hostio_summary(&stats));
+
+    Ok(())
+}
+
+/// Render the HostIO breakdown as a text table
+///
+/// **Public** - reusable by other text reports
+///
+/// # Arguments
+/// * `stats` - Extracted HostIO statistics
+///
+/// # Returns
+/// One line per type (sorted by gas, descending) followed by a total line,
+/// plus a line listing unclassified names if there are any
+pub fn format_hostio_summary(stats: &HostIoStats) -> String {
+    let gas_by_type = stats.gas_by_type();
+    let mut rows: Vec<_> = gas_by_type.iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0))));
+
+    let mut output = format!("{:<16} {:>8} {:>12}\n", "Type", "Calls", "Gas");
+    for (io_type, gas) in rows {
+        output.push_str(&format!(
+            "{:<16} {:>8} {:>12}\n",
+            format!("{:?}", io_type),
+            stats.count_for_type(*io_type),
+            format_gas(*gas)
+        ));
+    }
+    output.push_str(&format!(
+        "{:<16} {:>8} {:>12}\n",
+        "Total",
+        stats.total_calls(),
+        format_gas(stats.total_gas())
+    ));
+    if !stats.unclassified().is_empty() {
+        output.push_str(&format!("Unclassified: {}\n", format_unclassified(stats.unclassified())));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_hostio_summary() {
+        let trace = serde_json::json!({
+            "hostio": [
+                { "type": "storage_load", "gas": 2100 },
+                { "type": "storage_load", "gas": 100 },
+                { "type": "log", "gas": 375 }
+            ]
+        });
+        let stats = crate::parser::extract_hostio_events(&trace);
+
+        let summary = format_hostio_summary(&stats);
+        let lines: Vec<&str> = summary.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("StorageLoad"));
+        assert!(lines[1].ends_with("2,200"));
+        assert!(lines[2].starts_with("Log"));
+        assert!(lines[3].starts_with("Total"));
+        assert!(lines[3].ends_with("2,575"));
+    }
+}
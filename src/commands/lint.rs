@@ -0,0 +1,471 @@
+//! Lint command implementation.
+//!
+//! Replays the ordered storage and call accesses of a trace and flags
+//! known gas anti-patterns:
+//! - `repeated-sload`: a slot loaded again in the same call frame, where
+//!   the first value could have been cached
+//! - `unchanged-sstore`: a slot written with the value it already holds
+//! - `call-in-loop`: one call site calling the same target many times in
+//!   a frame, where a batched call would pay the call overhead once
+//!
+//! Slots, values and call targets are read from the EVM stack (geth
+//! structLogs without `disableStack`) and from the `args`/`outs` of Stylus
+//! HostIO storage and call events. HostIO events carry no position in the
+//! trace, so they are replayed as one frame under their `hostio;<Type>`
+//! stacks. `--file` takes a raw trace, or a profile captured with
+//! `--embed-raw-full`. Wasted gas is an estimate of what the fix saves.
+
+use crate::aggregator::{stacks_per_step, StackBuildOptions, HOSTIO_ROOT};
+use crate::parser::{
+    parse_step_stream, parse_trace_with_options, read_trace_file, HostIoType, ParseOptions, ParsedTrace,
+    Profile, TraceInput,
+};
+use crate::utils::format_gas;
+use anyhow::{Context, Result};
+use log::info;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Calls from one site to one target before it counts as a loop
+pub const CALL_IN_LOOP_MIN: u64 = 3;
+
+/// Overhead each extra call in a loop pays (warm account access)
+const CALL_OVERHEAD_GAS: u64 = 100;
+
+/// Frame id of HostIO accesses (distinct from every step frame)
+const HOSTIO_FRAME: usize = usize::MAX;
+
+/// Arguments for the lint command
+///
+/// **Public** - used by main.rs to construct from CLI args
+#[derive(Debug, Clone)]
+pub struct LintArgs {
+    /// Raw trace JSON, or a profile with an embedded trace (`-` for stdin)
+    pub file: PathBuf,
+}
+
+/// An anti-pattern the linter looks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LintRule {
+    /// The same slot loaded more than once in a frame
+    RepeatedLoad,
+
+    /// A slot written with the value it already holds
+    UnchangedStore,
+
+    /// One call site calling the same target repeatedly
+    CallInLoop,
+}
+
+impl LintRule {
+    /// Short name shown in reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::RepeatedLoad => "repeated-sload",
+            Self::UnchangedStore => "unchanged-sstore",
+            Self::CallInLoop => "call-in-loop",
+        }
+    }
+
+    /// Suggested fix
+    pub fn hint(self) -> &'static str {
+        match self {
+            Self::RepeatedLoad => "cache the value in a local variable",
+            Self::UnchangedStore => "skip the write when the value is unchanged",
+            Self::CallInLoop => "batch the calls or hoist them out of the loop",
+        }
+    }
+}
+
+/// What a step in the access sequence does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    /// Storage read (SLOAD)
+    Load,
+
+    /// Storage write (SSTORE)
+    Store,
+
+    /// External call (CALL, STATICCALL, DELEGATECALL, CALLCODE)
+    Call,
+}
+
+/// One storage or call access, in trace order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Access {
+    pub kind: AccessKind,
+
+    /// Call frame the access ran in (each entered frame gets a new id)
+    pub frame: usize,
+
+    pub pc: u64,
+
+    /// Full stack of the step, as in the flamegraph
+    pub stack: String,
+
+    /// Storage slot, or call target address
+    pub key: String,
+
+    /// Value loaded or stored (None for calls, or a load whose result
+    /// is not visible)
+    pub value: Option<String>,
+
+    pub gas_cost: u64,
+}
+
+/// One flagged pattern, aggregated over its occurrences
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintFinding {
+    pub rule: LintRule,
+
+    /// Stack of the offending step
+    pub stack: String,
+
+    /// Storage slot or call target involved
+    pub subject: String,
+
+    pub occurrences: u64,
+
+    /// Estimated gas the fix would save
+    pub wasted_gas: u64,
+}
+
+/// Execute the lint command
+///
+/// **Public** - main entry point called from main.rs
+///
+/// # Errors
+/// * The file cannot be read, or is a profile without an embedded trace
+/// * The trace cannot be parsed or carries no stack words
+pub fn execute_lint(args: LintArgs) -> Result<()> {
+    let parsed = load_trace(&args.file)?;
+
+    let accesses = access_sequence(&parsed, &StackBuildOptions::default());
+    let has_hostio_data = accesses.iter().any(|access| access.frame == HOSTIO_FRAME);
+    if !has_hostio_data && parsed.execution_steps.iter().all(|step| step.stack.is_none()) {
+        anyhow::bail!(
+            "Trace has no stack words or HostIO arguments, so storage slots and call targets \
+             are unknown; trace without disableStack"
+        );
+    }
+    info!("Replaying {} storage and call accesses", accesses.len());
+
+    let findings = lint_accesses(&accesses);
+    print!("{}", format_lint_report(&findings));
+
+    Ok(())
+}
+
+/// Ordered storage and call accesses of a parsed trace
+///
+/// **Public** - the sequence the lint rules run over. Needs steps parsed
+/// with `capture_state`; steps without stack words are skipped. HostIO
+/// storage and call events with arguments follow the steps.
+pub fn access_sequence(parsed: &ParsedTrace, options: &StackBuildOptions) -> Vec<Access> {
+    let stacks = stacks_per_step(parsed, options);
+    let steps = &parsed.execution_steps;
+
+    let mut accesses = Vec::new();
+    let mut frames: Vec<usize> = Vec::new();
+    let mut next_frame = 0;
+
+    for (index, step) in steps.iter().enumerate() {
+        let depth = (step.depth as usize).clamp(1, options.max_depth);
+        frames.truncate(depth);
+        while frames.len() < depth {
+            frames.push(next_frame);
+            next_frame += 1;
+        }
+
+        let (Some(op), Some(words)) = (step.op.as_deref(), step.stack.as_deref()) else {
+            continue;
+        };
+        // geth lists the stack bottom first
+        let word = |from_top: usize| words.iter().rev().nth(from_top).map(|word| normalize_word(word));
+
+        let (kind, key, value) = match op.to_uppercase().as_str() {
+            "SLOAD" => {
+                let result = steps
+                    .get(index + 1)
+                    .filter(|next| next.depth == step.depth)
+                    .and_then(|next| next.stack.as_deref()?.last())
+                    .map(|word| normalize_word(word));
+                (AccessKind::Load, word(0), result)
+            }
+            "SSTORE" => (AccessKind::Store, word(0), word(1)),
+            "CALL" | "CALLCODE" | "STATICCALL" | "DELEGATECALL" => (AccessKind::Call, word(1), None),
+            _ => continue,
+        };
+        let Some(key) = key else {
+            continue;
+        };
+
+        accesses.push(Access {
+            kind,
+            frame: frames[depth - 1],
+            pc: step.pc,
+            stack: stacks.get(index).cloned().unwrap_or_default(),
+            key,
+            value,
+            gas_cost: step.gas_cost,
+        });
+    }
+
+    accesses.extend(hostio_accesses(parsed));
+    accesses
+}
+
+/// Storage and call accesses of the trace's HostIO events
+///
+/// **Private** - keys and values come from the event's hex `args`/`outs`:
+/// a 32-byte key (then value, for stores) or a 20-byte call target.
+/// Events without them are skipped.
+fn hostio_accesses(parsed: &ParsedTrace) -> Vec<Access> {
+    let hex = |data: Option<&str>| data.map(|data| data.strip_prefix("0x").unwrap_or(data).to_string());
+    let word = |data: &Option<String>, from: usize, len: usize| {
+        data.as_deref().and_then(|data| data.get(from..from + len)).map(normalize_word)
+    };
+
+    parsed
+        .hostio_stats
+        .events()
+        .iter()
+        .filter_map(|event| {
+            let (args, outs) = (hex(event.args.as_deref()), hex(event.outs.as_deref()));
+            let (kind, key, value) = match event.io_type {
+                HostIoType::StorageLoad => (AccessKind::Load, word(&args, 0, 64), word(&outs, 0, 64)),
+                HostIoType::StorageStore => (AccessKind::Store, word(&args, 0, 64), word(&args, 64, 64)),
+                HostIoType::Call | HostIoType::StaticCall | HostIoType::DelegateCall => {
+                    (AccessKind::Call, word(&args, 0, 40), None)
+                }
+                _ => return None,
+            };
+
+            Some(Access {
+                kind,
+                frame: HOSTIO_FRAME,
+                pc: 0,
+                stack: format!("{};{:?}", HOSTIO_ROOT, event.io_type),
+                key: key?,
+                value,
+                gas_cost: event.gas_cost,
+            })
+        })
+        .collect()
+}
+
+/// Run the lint rules over an access sequence
+///
+/// **Public** - findings sorted by estimated wasted gas (descending)
+pub fn lint_accesses<'a>(accesses: &'a [Access]) -> Vec<LintFinding> {
+    // Slot values known per frame (loaded or written so far)
+    let mut known: HashMap<(usize, &str), Option<&str>> = HashMap::new();
+    // Ordered so the call site reported per (stack, target) is deterministic
+    let mut calls: BTreeMap<(usize, u64, &str), (u64, &str)> = BTreeMap::new();
+    let mut grouped: BTreeMap<(LintRule, &str, &str), (u64, u64)> = BTreeMap::new();
+    let mut record = |rule, access: &'a Access| {
+        let entry = grouped.entry((rule, access.stack.as_str(), access.key.as_str())).or_default();
+        entry.0 += 1;
+        entry.1 += access.gas_cost;
+    };
+
+    for access in accesses {
+        let slot = (access.frame, access.key.as_str());
+        match access.kind {
+            AccessKind::Load => {
+                if known.contains_key(&slot) {
+                    record(LintRule::RepeatedLoad, access);
+                }
+                let value = access.value.as_deref().or_else(|| known.get(&slot).copied().flatten());
+                known.insert(slot, value);
+            }
+            AccessKind::Store => {
+                let previous = known.get(&slot).copied().flatten();
+                if previous.is_some() && previous == access.value.as_deref() {
+                    record(LintRule::UnchangedStore, access);
+                }
+                known.insert(slot, access.value.as_deref());
+            }
+            AccessKind::Call => {
+                let entry = calls.entry((access.frame, access.pc, &access.key)).or_insert((0, &access.stack));
+                entry.0 += 1;
+            }
+        }
+    }
+
+    let mut findings: Vec<LintFinding> = grouped
+        .into_iter()
+        .map(|((rule, stack, subject), (occurrences, wasted_gas))| LintFinding {
+            rule,
+            stack: stack.to_string(),
+            subject: subject.to_string(),
+            occurrences,
+            wasted_gas,
+        })
+        .collect();
+
+    let mut looped: BTreeSet<(&str, &str)> = BTreeSet::new();
+    for ((_, _, target), (count, stack)) in calls {
+        if count >= CALL_IN_LOOP_MIN && looped.insert((stack, target)) {
+            findings.push(LintFinding {
+                rule: LintRule::CallInLoop,
+                stack: stack.to_string(),
+                subject: target.to_string(),
+                occurrences: count,
+                wasted_gas: (count - 1) * CALL_OVERHEAD_GAS,
+            });
+        }
+    }
+
+    findings.sort_by(|a, b| {
+        b.wasted_gas
+            .cmp(&a.wasted_gas)
+            .then_with(|| a.rule.cmp(&b.rule))
+            .then_with(|| a.stack.cmp(&b.stack))
+            .then_with(|| a.subject.cmp(&b.subject))
+    });
+    findings
+}
+
+/// Render findings as a text report
+///
+/// **Public** - one block per finding with its stack and suggested fix,
+/// after a total line
+pub fn format_lint_report(findings: &[LintFinding]) -> String {
+    if findings.is_empty() {
+        return "No issues found\n".to_string();
+    }
+
+    let wasted: u64 = findings.iter().map(|finding| finding.wasted_gas).sum();
+    let mut output = format!(
+        "{} issue(s), ~{} gas wasted\n",
+        findings.len(),
+        format_gas(wasted)
+    );
+    for finding in findings {
+        output.push_str(&format!(
+            "\n[{}] {}× {}, ~{} gas\n    {}\n    hint: {}\n",
+            finding.rule.name(),
+            finding.occurrences,
+            finding.subject,
+            format_gas(finding.wasted_gas),
+            finding.stack,
+            finding.rule.hint()
+        ));
+    }
+
+    output
+}
+
+/// Parse the trace in a trace file or a profile's embedded source
+///
+/// **Private** - a JSON object with `hot_paths` is taken as a profile;
+/// stack words are always kept
+fn load_trace(path: &Path) -> Result<ParsedTrace> {
+    let options = ParseOptions { capture_state: true, ..Default::default() };
+    let value = match read_trace_file(path, &options)
+        .context(format!("Failed to read {}", path.display()))?
+    {
+        TraceInput::Json(value) => value,
+        TraceInput::Steps(stream) => {
+            return parse_step_stream("lint", stream, &options).context("Failed to parse trace data");
+        }
+    };
+
+    let raw_trace = if value.get("hot_paths").is_none() {
+        value
+    } else {
+        let profile: Profile = serde_json::from_value(value)
+            .context(format!("Failed to read profile {}", path.display()))?;
+        profile.source.and_then(|source| source.raw).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Profile {} has no embedded trace; re-capture it with --embed-raw-full",
+                path.display()
+            )
+        })?
+    };
+    parse_trace_with_options("lint", &raw_trace, &options).context("Failed to parse trace data")
+}
+
+/// Canonical form of a stack word: lowercase `0x` hex without leading zeros
+fn normalize_word(word: &str) -> String {
+    let hex = word.strip_prefix("0x").unwrap_or(word).trim_start_matches('0');
+    if hex.is_empty() {
+        "0x0".to_string()
+    } else {
+        format!("0x{}", hex.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lint_flags_patterns() {
+        let step = |pc: u64, op: &str, cost: u64, stack: &[&str]| {
+            json!({ "pc": pc, "op": op, "gas": 100000, "gasCost": cost, "depth": 1, "stack": stack })
+        };
+        let target = "0x000000000000000000000000000000000000beef";
+        let mut steps = vec![
+            step(0, "SLOAD", 2100, &["0x01"]),
+            step(1, "PUSH1", 3, &["0x2a"]),
+            step(2, "SLOAD", 100, &["0x1"]),
+            step(3, "PUSH1", 3, &["0x2a"]),
+            step(4, "SSTORE", 100, &["0x2a", "0x1"]),
+        ];
+        for _ in 0..3 {
+            steps.push(step(5, "CALL", 700, &["0x0", target, "0xffff"]));
+        }
+        let raw = json!({ "gas": 10000, "structLogs": steps });
+        let options = ParseOptions { capture_state: true, ..Default::default() };
+        let parsed = parse_trace_with_options("0xtx", &raw, &options).unwrap();
+
+        let findings = lint_accesses(&access_sequence(&parsed, &StackBuildOptions::default()));
+
+        let summary: Vec<(LintRule, &str, u64, u64)> = findings
+            .iter()
+            .map(|f| (f.rule, f.subject.as_str(), f.occurrences, f.wasted_gas))
+            .collect();
+        assert_eq!(summary, vec![
+            (LintRule::CallInLoop, "0xbeef", 3, 200),
+            (LintRule::RepeatedLoad, "0x1", 1, 100),
+            (LintRule::UnchangedStore, "0x1", 1, 100),
+        ]);
+        assert_eq!(findings[1].stack, "call;SLOAD");
+
+        let report = format_lint_report(&findings);
+        assert!(report.starts_with("3 issue(s), ~400 gas wasted"));
+        assert!(report.contains("[call-in-loop] 3× 0xbeef"));
+    }
+
+    #[test]
+    fn test_lint_hostio_events() {
+        let slot = format!("{:064x}", 7);
+        let value = format!("{:064x}", 42);
+        let target = format!("{:040x}", 0xbeef);
+        let raw = json!({ "gasUsed": 10000, "structLogs": [], "hostio": [
+            { "type": "storage_load", "gas": 2100, "args": format!("0x{}", slot), "outs": format!("0x{}", value) },
+            { "type": "storage_load", "gas": 100, "args": format!("0x{}", slot), "outs": format!("0x{}", value) },
+            { "type": "storage_store", "gas": 100, "args": format!("0x{}{}", slot, value) },
+            { "type": "call", "gas": 700, "args": format!("0x{}", target) },
+            { "type": "call", "gas": 700, "args": format!("0x{}", target) },
+            { "type": "call", "gas": 700, "args": format!("0x{}", target) },
+            { "type": "storage_load", "gas": 2100 }
+        ]});
+        let parsed = parse_trace_with_options("0xtx", &raw, &ParseOptions::default()).unwrap();
+
+        let findings = lint_accesses(&access_sequence(&parsed, &StackBuildOptions::default()));
+
+        let summary: Vec<(LintRule, &str, &str, u64)> = findings
+            .iter()
+            .map(|f| (f.rule, f.stack.as_str(), f.subject.as_str(), f.occurrences))
+            .collect();
+        assert_eq!(summary, vec![
+            (LintRule::CallInLoop, "hostio;Call", "0xbeef", 3),
+            (LintRule::RepeatedLoad, "hostio;StorageLoad", "0x7", 1),
+            (LintRule::UnchangedStore, "hostio;StorageStore", "0x7", 1),
+        ]);
+    }
+}
@@ -4,6 +4,24 @@
 //! Commands orchestrate the various library components to perform user tasks.
 
 pub mod capture;
+pub mod compare;
+pub mod diff;
+pub mod hostio;
+pub mod lint;
+pub mod range;
+pub mod render;
+#[cfg(feature = "server")]
+pub mod serve;
+pub mod watch;
 
 // Re-export main command functions
-pub use capture::{execute_capture, validate_args, quick_capture, CaptureArgs};
\ No newline at end of file
+pub use capture::{execute_capture, validate_args, validate_tx_hash, quick_capture, resolve_tx_hash, CaptureArgs};
+pub use compare::{execute_compare, format_comparison, CompareArgs};
+pub use diff::{execute_diff, format_diff, DiffArgs};
+pub use hostio::{execute_hostio, format_hostio_summary, HostioArgs};
+pub use lint::{execute_lint, format_lint_report, LintArgs, LintFinding, LintRule};
+pub use range::{execute_range, format_range, validate_range_args, RangeArgs, RangeEntry};
+pub use render::{execute_render, RenderArgs};
+#[cfg(feature = "server")]
+pub use serve::{execute_serve, validate_serve_args, ServeArgs};
+pub use watch::{execute_watch, validate_watch_args, WatchArgs};
\ No newline at end of file
@@ -0,0 +1,333 @@
+//! Range command implementation.
+//!
+//! The range command:
+//! 1. Enumerates a contract's transactions in a block range via `eth_getLogs`
+//!    (in chunks, since nodes cap the range of one query)
+//! 2. Profiles each transaction with the capture pipeline
+//! 3. Folds every transaction into `aggregate.folded` (and `aggregate.svg`)
+//! 4. Prints total gas per transaction in block order
+//!
+//! Only transactions in which the contract emitted a log are found; calls
+//! that emit nothing are invisible to `eth_getLogs`.
+
+use super::capture::{execute_capture, CaptureArgs};
+use crate::aggregator::{StackAggregator, StackBuildOptions};
+use crate::flamegraph::{generate_flamegraph, FlamegraphConfig};
+use crate::output::{read_profile, write_svg, OutputFormat};
+use crate::rpc::types::parse_hex_quantity;
+use crate::rpc::{LogEntry, RpcClient};
+use crate::utils::config::LOG_BLOCK_CHUNK;
+use crate::utils::format_gas;
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::collections::HashSet;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+/// Base name of the aggregate artifacts in the output directory
+const AGGREGATE_BASENAME: &str = "aggregate";
+
+/// Arguments for the range command
+///
+/// **Public** - used by main.rs to construct from CLI args
+#[derive(Debug, Clone)]
+pub struct RangeArgs {
+    /// RPC endpoint URL
+    pub rpc_url: String,
+
+    /// Contract address whose transactions are profiled
+    pub address: String,
+
+    /// First block of the range (inclusive)
+    pub from_block: u64,
+
+    /// Last block of the range (inclusive)
+    pub to_block: u64,
+
+    /// Directory receiving per-transaction and aggregate artifacts
+    pub out_dir: PathBuf,
+
+    /// Number of top hot paths to include in each profile
+    pub top_paths: usize,
+
+    /// Flamegraph configuration (None = JSON profiles only)
+    pub flamegraph_config: Option<FlamegraphConfig>,
+
+    /// Options for building collapsed stacks
+    pub stack_options: StackBuildOptions,
+
+    /// Optional tracer name (None = default opcode tracer)
+    pub tracer: Option<String>,
+}
+
+impl Default for RangeArgs {
+    fn default() -> Self {
+        Self {
+            rpc_url: "http://localhost:8547".to_string(),
+            address: String::new(),
+            from_block: 0,
+            to_block: 0,
+            out_dir: PathBuf::from("."),
+            top_paths: 20,
+            flamegraph_config: None,
+            stack_options: StackBuildOptions::default(),
+            tracer: None,
+        }
+    }
+}
+
+/// A profiled transaction in the range
+///
+/// **Public** - returned from `execute_range` in block order
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeEntry {
+    /// Block the transaction was included in
+    pub block: u64,
+
+    /// Transaction hash
+    pub tx_hash: String,
+
+    /// Total gas used by the transaction
+    pub total_gas: u64,
+}
+
+/// Execute the range command
+///
+/// **Public** - main entry point called from main.rs
+///
+/// # Returns
+/// Profiled transactions in block order
+///
+/// # Errors
+/// * Invalid arguments, or RPC client creation failures
+/// * `eth_getLogs` failures (the enumeration would be incomplete)
+/// * Output directory or aggregate write failures
+///
+/// Failures for individual transactions are logged and skipped.
+pub fn execute_range(args: RangeArgs) -> Result<Vec<RangeEntry>> {
+    validate_range_args(&args)?;
+
+    let client = RpcClient::new(&args.rpc_url)
+        .context("Failed to create RPC client")?;
+
+    std::fs::create_dir_all(&args.out_dir)
+        .with_context(|| format!("Failed to create output directory {}", args.out_dir.display()))?;
+
+    let mut seen = HashSet::new();
+    let mut transactions = Vec::new();
+    for (start, end) in block_windows(args.from_block, args.to_block, LOG_BLOCK_CHUNK) {
+        let logs = client.get_logs(&args.address, start, end)
+            .with_context(|| format!("Failed to fetch logs for blocks {}-{}", start, end))?;
+        debug!("Blocks {}-{}: {} logs", start, end, logs.len());
+        transactions.extend(transactions_from_logs(&logs, &mut seen)?);
+    }
+
+    info!(
+        "Found {} transactions to {} in blocks {}-{}",
+        transactions.len(), args.address, args.from_block, args.to_block
+    );
+
+    let mut aggregator = StackAggregator::new();
+    let mut entries = Vec::new();
+    for (block, tx_hash) in transactions {
+        match profile_transaction(&args, &tx_hash, &mut aggregator) {
+            Ok(total_gas) => entries.push(RangeEntry { block, tx_hash, total_gas }),
+            Err(e) => warn!("Failed to profile {}: {:#}", tx_hash, e),
+        }
+    }
+
+    if !aggregator.is_empty() {
+        write_aggregate(&args, &aggregator)?;
+    }
+
+    print!("{}", format_range(&entries));
+
+    Ok(entries)
+}
+
+/// Profile one transaction and merge its stacks into the aggregate
+///
+/// **Private** - returns the transaction's total gas
+fn profile_transaction(args: &RangeArgs, tx_hash: &str, aggregator: &mut StackAggregator) -> Result<u64> {
+    info!("Profiling transaction {}", tx_hash);
+
+    let output_json = args.out_dir.join(format!("{}.json", tx_hash));
+    let mut formats = vec![OutputFormat::Json, OutputFormat::Folded];
+    if args.flamegraph_config.is_some() {
+        formats.push(OutputFormat::Svg);
+    }
+
+    execute_capture(CaptureArgs {
+        rpc_url: args.rpc_url.clone(),
+        transaction_hash: tx_hash.to_string(),
+        output_json: output_json.clone(),
+        output_svg: None,
+        top_paths: args.top_paths,
+        flamegraph_config: args.flamegraph_config.clone(),
+        stack_options: args.stack_options.clone(),
+        tracer: args.tracer.clone(),
+        formats,
+        ..Default::default()
+    })?;
+
+    let folded = OutputFormat::Folded.path_from_base(&output_json);
+    let file = std::fs::File::open(&folded)
+        .with_context(|| format!("Failed to open {}", folded.display()))?;
+    aggregator.merge_from_folded(BufReader::new(file))?;
+
+    Ok(read_profile(&output_json)?.total_gas)
+}
+
+/// Write `aggregate.folded` (and `aggregate.svg`) into the output directory
+///
+/// **Private** - runs once after every transaction is profiled
+fn write_aggregate(args: &RangeArgs, aggregator: &StackAggregator) -> Result<()> {
+    let base = args.out_dir.join(AGGREGATE_BASENAME);
+    let path = OutputFormat::Folded.path_from_base(&base);
+    std::fs::write(&path, aggregator.to_folded())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    if let Some(config) = &args.flamegraph_config {
+        let svg = generate_flamegraph(&aggregator.stacks(), Some(config))
+            .context("Failed to generate aggregate flamegraph")?;
+        write_svg(&svg, OutputFormat::Svg.path_from_base(&base))
+            .context("Failed to write aggregate flamegraph")?;
+    }
+
+    info!("✓ Aggregate of {} stacks written to: {}", aggregator.len(), path.display());
+    Ok(())
+}
+
+/// Split an inclusive block range into windows of at most `chunk` blocks
+///
+/// **Private** - keeps each `eth_getLogs` query under node range caps
+fn block_windows(from_block: u64, to_block: u64, chunk: u64) -> Vec<(u64, u64)> {
+    let mut windows = Vec::new();
+    let mut start = from_block;
+
+    while start <= to_block {
+        let end = start.saturating_add(chunk - 1).min(to_block);
+        windows.push((start, end));
+        if end == u64::MAX {
+            break;
+        }
+        start = end + 1;
+    }
+
+    windows
+}
+
+/// Unseen `(block, tx_hash)` pairs from a page of logs, in log order
+///
+/// **Private** - a transaction emitting several logs is profiled once
+fn transactions_from_logs(logs: &[LogEntry], seen: &mut HashSet<String>) -> Result<Vec<(u64, String)>> {
+    let mut transactions = Vec::new();
+
+    for log in logs {
+        if !seen.insert(log.transaction_hash.to_lowercase()) {
+            continue;
+        }
+        let block = parse_hex_quantity(&log.block_number)?;
+        transactions.push((block, log.transaction_hash.clone()));
+    }
+
+    Ok(transactions)
+}
+
+/// Format the per-transaction gas table
+///
+/// **Public** - the change column is relative to the first transaction
+pub fn format_range(entries: &[RangeEntry]) -> String {
+    let Some(first) = entries.first() else {
+        return "No transactions profiled\n".to_string();
+    };
+
+    let mut out = format!("{:>10}  {:<66}  {:>13}  {:>9}\n", "Block", "Transaction", "Gas", "Change");
+    for entry in entries {
+        let change = (entry.total_gas as f64 - first.total_gas as f64)
+            / first.total_gas.max(1) as f64
+            * 100.0;
+        out.push_str(&format!(
+            "{:>10}  {:<66}  {:>13}  {:>+8.1}%\n",
+            entry.block,
+            entry.tx_hash,
+            format_gas(entry.total_gas),
+            change
+        ));
+    }
+
+    out
+}
+
+/// Validate range arguments
+///
+/// **Public** - can be called before execute_range for early validation
+///
+/// # Arguments
+/// * `args` - Arguments to validate
+///
+/// # Returns
+/// Ok if arguments are valid, Err with message if not
+pub fn validate_range_args(args: &RangeArgs) -> Result<()> {
+    if !args.rpc_url.starts_with("http://") && !args.rpc_url.starts_with("https://") {
+        anyhow::bail!("RPC URL must start with http:// or https://");
+    }
+
+    let address = args.address.strip_prefix("0x").unwrap_or(&args.address);
+    if address.len() != 40 || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("Contract address must be 20 bytes (40 hex characters)");
+    }
+
+    if args.from_block > args.to_block {
+        anyhow::bail!(
+            "--from-block ({}) must not be after --to-block ({})",
+            args.from_block, args.to_block
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(hash: &str, block: &str) -> LogEntry {
+        LogEntry {
+            transaction_hash: hash.to_string(),
+            block_number: block.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_block_windows() {
+        assert_eq!(block_windows(10, 14, 2), vec![(10, 11), (12, 13), (14, 14)]);
+        assert_eq!(block_windows(5, 5, 2000), vec![(5, 5)]);
+        assert_eq!(block_windows(u64::MAX - 1, u64::MAX, 10), vec![(u64::MAX - 1, u64::MAX)]);
+    }
+
+    #[test]
+    fn test_transactions_from_logs_dedups() {
+        let logs = vec![log("0xAA", "0x10"), log("0xaa", "0x10"), log("0xbb", "0x11")];
+        let mut seen = HashSet::new();
+
+        let transactions = transactions_from_logs(&logs, &mut seen).unwrap();
+
+        assert_eq!(transactions, vec![(16, "0xAA".to_string()), (17, "0xbb".to_string())]);
+        assert!(transactions_from_logs(&logs, &mut seen).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_range_args() {
+        let args = RangeArgs {
+            address: "0x00000000000000000000000000000000000000aa".to_string(),
+            from_block: 10,
+            to_block: 20,
+            ..Default::default()
+        };
+        assert!(validate_range_args(&args).is_ok());
+
+        let reversed = RangeArgs { from_block: 30, ..args };
+        assert!(validate_range_args(&reversed).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Render command implementation.
+//!
+//! Regenerates a flamegraph from an existing artifact instead of a trace:
+//! either a profile JSON captured with `--full-stacks` or a folded stacks
+//! file. No RPC access is needed, so archived profiles can be re-styled.
+
+use crate::aggregator::{subtree, CollapsedStack, StackAggregator};
+use crate::flamegraph::{generate_flamegraph, FlamegraphConfig};
+use crate::output::{read_profile, write_svg};
+use anyhow::{Context, Result};
+use log::info;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Arguments for the render command
+///
+/// **Public** - used by main.rs to construct from CLI args
+#[derive(Debug, Clone)]
+pub struct RenderArgs {
+    /// Profile JSON (`.json`) or folded stacks file to render
+    pub input: PathBuf,
+
+    /// Output path for the SVG flamegraph
+    pub output_svg: PathBuf,
+
+    /// Flamegraph configuration (None = defaults)
+    pub flamegraph_config: Option<FlamegraphConfig>,
+
+    /// Render the flamegraph re-rooted at this frame (subtree only)
+    pub focus: Option<String>,
+}
+
+/// Execute the render command
+///
+/// **Public** - main entry point called from main.rs
+///
+/// # Errors
+/// * The input cannot be read or parsed
+/// * A profile has no stored stacks (captured without `--full-stacks`)
+/// * The focus frame does not appear in any stack
+pub fn execute_render(args: RenderArgs) -> Result<()> {
+    let mut stacks = load_stacks(&args.input)?;
+    info!("Loaded {} stacks from {}", stacks.len(), args.input.display());
+
+    if let Some(frame) = &args.focus {
+        stacks = subtree(&stacks, frame);
+        if stacks.is_empty() {
+            anyhow::bail!("Focus frame '{}' does not appear in any stack", frame);
+        }
+    }
+
+    let svg = generate_flamegraph(&stacks, args.flamegraph_config.as_ref())
+        .context("Failed to generate flamegraph")?;
+    write_svg(&svg, &args.output_svg).context("Failed to write flamegraph SVG")?;
+
+    info!("✓ Flamegraph written to: {}", args.output_svg.display());
+
+    Ok(())
+}
+
+/// Read collapsed stacks from a profile JSON or folded file
+///
+/// **Private** - `.json` files are read as profiles, anything else as folded
+fn load_stacks(path: &Path) -> Result<Vec<CollapsedStack>> {
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+    if is_json {
+        let profile = read_profile(path)
+            .context(format!("Failed to read profile {}", path.display()))?;
+        if profile.collapsed_stacks.is_empty() {
+            anyhow::bail!(
+                "Profile {} has no stored stacks; re-capture it with --full-stacks",
+                path.display()
+            );
+        }
+        return Ok(profile.collapsed_stacks);
+    }
+
+    let file = File::open(path).context(format!("Failed to open {}", path.display()))?;
+    let mut aggregator = StackAggregator::new();
+    aggregator
+        .merge_from_folded(BufReader::new(file))
+        .context(format!("Failed to read folded stacks {}", path.display()))?;
+    if aggregator.is_empty() {
+        anyhow::bail!("No stacks in {}", path.display());
+    }
+
+    Ok(aggregator.stacks())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_load_stacks_from_folded() {
+        let mut file = tempfile::Builder::new().suffix(".folded").tempfile().unwrap();
+        writeln!(file, "main;SLOAD 2100\nmain;ADD 3").unwrap();
+
+        let stacks = load_stacks(file.path()).unwrap();
+
+        assert_eq!(stacks.len(), 2);
+        assert_eq!(stacks[0].stack, "main;SLOAD");
+        assert_eq!(stacks[0].weight, 2100);
+    }
+
+    #[test]
+    fn test_load_stacks_requires_stored_stacks() {
+        let file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        let raw = serde_json::json!({ "gasUsed": 1, "structLogs": [] });
+        let parsed = crate::parser::parse_trace("0xabc", &raw).unwrap();
+        let mut profile = crate::parser::to_profile(&parsed, Vec::new());
+        crate::output::write_profile(&profile, file.path()).unwrap();
+
+        let err = load_stacks(file.path()).unwrap_err();
+        assert!(err.to_string().contains("--full-stacks"));
+
+        profile.collapsed_stacks = vec![CollapsedStack::new("main".to_string(), 1)];
+        crate::output::write_profile(&profile, file.path()).unwrap();
+        assert_eq!(load_stacks(file.path()).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,316 @@
+//! Serve command implementation (requires the `server` feature).
+//!
+//! Exposes the profiling pipeline over HTTP for dashboards:
+//! - `POST /profile` with `{"tx": "0x...", "rpc": "http://..."}` returns
+//!   the profile JSON (`rpc` is optional)
+//! - `GET /flamegraph?tx=0x...&rpc=...` returns the SVG flamegraph
+//!
+//! A request may only name the configured RPC endpoint or one of the
+//! `allowed_rpcs`, so the server can't be pointed at arbitrary hosts.
+//!
+//! The pipeline is blocking, so each request runs on tokio's blocking
+//! pool. At most `max_concurrent` profiles run at once; a request that
+//! does not finish (including time spent waiting for a slot) within
+//! `request_timeout` gets a 504.
+
+use super::capture::validate_tx_hash;
+use crate::flamegraph::FlamegraphConfig;
+use crate::parser::Profile;
+use crate::Pipeline;
+use anyhow::{Context, Result};
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use log::{info, warn};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// Arguments for the serve command
+///
+/// **Public** - used by main.rs to construct from CLI args
+#[derive(Debug, Clone)]
+pub struct ServeArgs {
+    /// Address to listen on
+    pub listen: SocketAddr,
+
+    /// RPC endpoint used when a request does not name one
+    pub rpc_url: String,
+
+    /// Further RPC endpoints a request may name (besides `rpc_url`)
+    pub allowed_rpcs: Vec<String>,
+
+    /// Number of top hot paths to include in each profile (0 = all paths)
+    pub top_paths: usize,
+
+    /// Maximum number of profiles computed concurrently
+    pub max_concurrent: usize,
+
+    /// Deadline for each request, including waiting for a free slot
+    pub request_timeout: Duration,
+}
+
+impl Default for ServeArgs {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddr::from(([127, 0, 0, 1], 8080)),
+            rpc_url: "http://localhost:8547".to_string(),
+            allowed_rpcs: Vec::new(),
+            top_paths: 20,
+            max_concurrent: 4,
+            request_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Shared state for request handlers
+#[derive(Clone)]
+struct AppState {
+    rpc_url: String,
+    allowed_rpcs: Vec<String>,
+    top_paths: usize,
+    request_timeout: Duration,
+    permits: Arc<Semaphore>,
+}
+
+/// Body of `POST /profile`
+#[derive(Debug, Deserialize)]
+struct ProfileRequest {
+    tx: String,
+    #[serde(default)]
+    rpc: Option<String>,
+}
+
+/// Query of `GET /flamegraph`
+#[derive(Debug, Deserialize)]
+struct FlamegraphQuery {
+    tx: String,
+    #[serde(default)]
+    rpc: Option<String>,
+}
+
+/// Request failure mapped to an HTTP status
+#[derive(Debug)]
+enum ApiError {
+    /// Malformed request (400)
+    BadRequest(String),
+
+    /// Pipeline failed, typically RPC or trace errors (502)
+    Upstream(String),
+
+    /// Deadline exceeded (504)
+    Timeout,
+
+    /// Worker panicked or was cancelled (500)
+    Internal(String),
+}
+
+impl ApiError {
+    /// HTTP status for this error
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Upstream(_) => StatusCode::BAD_GATEWAY,
+            Self::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let message = match &self {
+            Self::BadRequest(msg) | Self::Upstream(msg) | Self::Internal(msg) => msg.clone(),
+            Self::Timeout => "request timed out".to_string(),
+        };
+        (self.status(), Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+/// Execute the serve command
+///
+/// **Public** - main entry point called from main.rs; runs until the
+/// process is stopped
+///
+/// # Errors
+/// * Invalid arguments, runtime creation or bind failures
+pub fn execute_serve(args: ServeArgs) -> Result<()> {
+    validate_serve_args(&args)?;
+
+    let state = AppState {
+        rpc_url: args.rpc_url.clone(),
+        allowed_rpcs: args.allowed_rpcs.clone(),
+        top_paths: args.top_paths,
+        request_timeout: args.request_timeout,
+        permits: Arc::new(Semaphore::new(args.max_concurrent)),
+    };
+    let app = router(state);
+
+    let runtime = tokio::runtime::Runtime::new()
+        .context("Failed to start async runtime")?;
+
+    runtime.block_on(async move {
+        let listener = tokio::net::TcpListener::bind(args.listen)
+            .await
+            .with_context(|| format!("Failed to bind {}", args.listen))?;
+        info!("Serving on http://{} (default RPC {})", args.listen, args.rpc_url);
+
+        axum::serve(listener, app).await.context("Server error")
+    })
+}
+
+/// Validate serve arguments
+///
+/// **Public** - can be called before execute_serve for early validation
+pub fn validate_serve_args(args: &ServeArgs) -> Result<()> {
+    for url in std::iter::once(&args.rpc_url).chain(&args.allowed_rpcs) {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            anyhow::bail!("RPC URL must start with http:// or https://");
+        }
+    }
+    if args.max_concurrent == 0 {
+        anyhow::bail!("max_concurrent must be greater than 0");
+    }
+    if args.request_timeout.is_zero() {
+        anyhow::bail!("request timeout must be greater than 0");
+    }
+    Ok(())
+}
+
+/// Build the HTTP routes
+fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/profile", post(profile_handler))
+        .route("/flamegraph", get(flamegraph_handler))
+        .with_state(state)
+}
+
+/// `POST /profile`
+async fn profile_handler(
+    State(state): State<AppState>,
+    Json(request): Json<ProfileRequest>,
+) -> Result<Json<Profile>, ApiError> {
+    let (profile, _) = run_pipeline(&state, request.tx, request.rpc, false).await?;
+    Ok(Json(profile))
+}
+
+/// `GET /flamegraph`
+async fn flamegraph_handler(
+    State(state): State<AppState>,
+    Query(query): Query<FlamegraphQuery>,
+) -> Result<Response, ApiError> {
+    let (_, svg) = run_pipeline(&state, query.tx, query.rpc, true).await?;
+    let svg = svg.ok_or_else(|| ApiError::Internal("flamegraph was not generated".to_string()))?;
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response())
+}
+
+/// Run the pipeline on the blocking pool under the concurrency limit and deadline
+async fn run_pipeline(
+    state: &AppState,
+    tx: String,
+    rpc: Option<String>,
+    flamegraph: bool,
+) -> Result<(Profile, Option<String>), ApiError> {
+    if tx.trim().is_empty() {
+        return Err(ApiError::BadRequest("missing transaction hash 'tx'".to_string()));
+    }
+    validate_tx_hash(tx.trim()).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let rpc = resolve_rpc(state, rpc)?;
+
+    let mut pipeline = Pipeline::new()
+        .rpc(rpc)
+        .tx(tx.trim())
+        .top_paths(state.top_paths);
+    if flamegraph {
+        pipeline = pipeline.flamegraph(FlamegraphConfig::default());
+    }
+
+    let permits = Arc::clone(&state.permits);
+    let work = async move {
+        let permit = permits
+            .acquire_owned()
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+        // The permit moves into the worker so the slot stays taken until
+        // the pipeline really finishes, even after a timeout response
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            pipeline.run()
+        })
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .map_err(|e| ApiError::Upstream(format!("{:#}", e)))
+    };
+
+    match tokio::time::timeout(state.request_timeout, work).await {
+        Ok(result) => result,
+        Err(_) => {
+            warn!("Request for {} timed out after {:?}", tx, state.request_timeout);
+            Err(ApiError::Timeout)
+        }
+    }
+}
+
+/// RPC endpoint for a request: the configured one unless it names an
+/// allowed endpoint
+fn resolve_rpc(state: &AppState, rpc: Option<String>) -> Result<String, ApiError> {
+    let Some(requested) = rpc else {
+        return Ok(state.rpc_url.clone());
+    };
+    let requested = requested.trim();
+    let same = |allowed: &String| allowed.trim_end_matches('/') == requested.trim_end_matches('/');
+
+    if same(&state.rpc_url) || state.allowed_rpcs.iter().any(same) {
+        Ok(requested.to_string())
+    } else {
+        Err(ApiError::BadRequest(format!(
+            "RPC endpoint '{}' is not allowed (start the server with --allow-rpc to permit it)",
+            requested
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_error_status_and_empty_tx() {
+        assert_eq!(ApiError::Timeout.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(ApiError::Upstream(String::new()).status(), StatusCode::BAD_GATEWAY);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let state = AppState {
+            rpc_url: "http://localhost:8547".to_string(),
+            allowed_rpcs: vec!["https://arb1.example/rpc".to_string()],
+            top_paths: 20,
+            request_timeout: Duration::from_secs(1),
+            permits: Arc::new(Semaphore::new(1)),
+        };
+        let result = runtime.block_on(run_pipeline(&state, " ".to_string(), None, false));
+        assert!(matches!(result, Err(ApiError::BadRequest(_))));
+
+        let result = runtime.block_on(run_pipeline(&state, "0x1234".to_string(), None, false));
+        assert!(matches!(result, Err(ApiError::BadRequest(ref m)) if m.contains("expected 64")));
+
+        let tx = format!("0x{}", "ab".repeat(32));
+        let internal = Some("http://169.254.169.254/".to_string());
+        let result = runtime.block_on(run_pipeline(&state, tx, internal, false));
+        assert!(matches!(result, Err(ApiError::BadRequest(ref m)) if m.contains("not allowed")));
+
+        assert_eq!(resolve_rpc(&state, None).unwrap(), "http://localhost:8547");
+        assert!(resolve_rpc(&state, Some("https://arb1.example/rpc/".to_string())).is_ok());
+    }
+
+    #[test]
+    fn test_validate_serve_args() {
+        // 0 top paths means "all paths", as for capture
+        assert!(validate_serve_args(&ServeArgs { top_paths: 0, ..Default::default() }).is_ok());
+        assert!(validate_serve_args(&ServeArgs { max_concurrent: 0, ..Default::default() }).is_err());
+    }
+}
@@ -0,0 +1,447 @@
+//! Watch command implementation.
+//!
+//! The watch command:
+//! 1. Polls the RPC for new blocks
+//! 2. Selects transactions sent to the watched contract
+//! 3. Profiles each new transaction with the capture pipeline
+//! 4. Writes artifacts into the output directory as they arrive
+//! 5. Optionally folds every transaction into `cumulative.folded` (and
+//!    `cumulative.svg`), resuming from an existing file on restart
+//! 6. With `track_sources`, also keeps `cumulative.sources.json`: the
+//!    transactions contributing most to each cumulative stack
+//!
+//! Runs until the stop flag is raised (Ctrl-C in the CLI).
+
+use super::capture::{execute_capture, CaptureArgs};
+use crate::aggregator::{StackAggregator, StackBuildOptions};
+use crate::flamegraph::{generate_flamegraph, FlamegraphConfig};
+use crate::output::{write_svg, OutputFormat};
+use crate::rpc::{BlockTransaction, RpcClient};
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::collections::HashSet;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Granularity at which the poll sleep checks the stop flag
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Base name of the cumulative artifacts in the output directory
+const CUMULATIVE_BASENAME: &str = "cumulative";
+
+/// Contributing transactions kept per stack (in memory and in
+/// `cumulative.sources.json`); the rest are summed as `other`
+const MAX_STACK_SOURCES: usize = 5;
+
+/// Arguments for the watch command
+///
+/// **Public** - used by main.rs to construct from CLI args
+#[derive(Debug, Clone)]
+pub struct WatchArgs {
+    /// RPC endpoint URL
+    pub rpc_url: String,
+
+    /// Contract address whose incoming transactions are profiled
+    pub address: String,
+
+    /// Directory receiving `<tx_hash>.json` (and `.svg`) artifacts
+    pub out_dir: PathBuf,
+
+    /// Delay between polls for new blocks
+    pub poll_interval: Duration,
+
+    /// First block to scan (None = start from the current head)
+    pub from_block: Option<u64>,
+
+    /// Number of top hot paths to include in each profile
+    pub top_paths: usize,
+
+    /// Flamegraph configuration (None = JSON profiles only)
+    pub flamegraph_config: Option<FlamegraphConfig>,
+
+    /// Options for building collapsed stacks
+    pub stack_options: StackBuildOptions,
+
+    /// Optional tracer name (None = default opcode tracer)
+    pub tracer: Option<String>,
+
+    /// Maintain `cumulative.folded` (and `.svg`) across all transactions
+    pub cumulative: bool,
+
+    /// Record the top contributing transactions of each cumulative stack
+    /// (requires `cumulative`)
+    pub track_sources: bool,
+}
+
+impl Default for WatchArgs {
+    fn default() -> Self {
+        Self {
+            rpc_url: "http://localhost:8547".to_string(),
+            address: String::new(),
+            out_dir: PathBuf::from("."),
+            poll_interval: Duration::from_secs(2),
+            from_block: None,
+            top_paths: 20,
+            flamegraph_config: None,
+            stack_options: StackBuildOptions::default(),
+            tracer: None,
+            cumulative: false,
+            track_sources: false,
+        }
+    }
+}
+
+/// Execute the watch command
+///
+/// **Public** - main entry point called from main.rs
+///
+/// # Arguments
+/// * `args` - Watch command arguments
+/// * `stop` - Flag checked between blocks and during sleeps; set it to
+///   shut down gracefully after the transaction in progress
+///
+/// # Returns
+/// Number of transactions profiled before shutdown
+///
+/// # Errors
+/// * RPC client creation or head-block lookup failures on startup
+/// * Output directory creation failures
+///
+/// Failures for individual transactions or polls are logged and skipped
+/// so a flaky node does not end the session.
+pub fn execute_watch(args: WatchArgs, stop: &AtomicBool) -> Result<usize> {
+    validate_watch_args(&args)?;
+
+    let client = RpcClient::new(&args.rpc_url)
+        .context("Failed to create RPC client")?;
+
+    std::fs::create_dir_all(&args.out_dir)
+        .with_context(|| format!("Failed to create output directory {}", args.out_dir.display()))?;
+
+    let mut next_block = match args.from_block {
+        Some(block) => block,
+        None => client.block_number().context("Failed to fetch latest block")? + 1,
+    };
+
+    info!(
+        "Watching {} from block {} (poll every {:?})",
+        args.address, next_block, args.poll_interval
+    );
+
+    let mut seen = HashSet::new();
+    let mut profiled = 0;
+
+    let mut cumulative = if args.cumulative {
+        Some(load_cumulative(&cumulative_path(&args.out_dir), args.track_sources)?)
+    } else {
+        None
+    };
+
+    while !stop.load(Ordering::SeqCst) {
+        let head = match client.block_number() {
+            Ok(head) => head,
+            Err(e) => {
+                warn!("Failed to poll latest block: {}", e);
+                sleep_unless_stopped(args.poll_interval, stop);
+                continue;
+            }
+        };
+
+        while next_block <= head && !stop.load(Ordering::SeqCst) {
+            let transactions = match client.block_transactions(next_block) {
+                Ok(transactions) => transactions,
+                Err(e) => {
+                    warn!("Failed to fetch block {}: {}", next_block, e);
+                    break;
+                }
+            };
+
+            debug!("Block {}: {} transactions", next_block, transactions.len());
+
+            for tx_hash in new_transactions_to(&transactions, &args.address, &mut seen) {
+                match profile_transaction(&args, &tx_hash) {
+                    Ok(()) => profiled += 1,
+                    Err(e) => {
+                        warn!("Failed to profile {}: {:#}", tx_hash, e);
+                        continue;
+                    }
+                }
+
+                if let Some(aggregator) = cumulative.as_mut() {
+                    if let Err(e) = update_cumulative(&args, aggregator, &tx_hash) {
+                        warn!("Failed to update cumulative flamegraph: {:#}", e);
+                    }
+                }
+            }
+
+            next_block += 1;
+        }
+
+        sleep_unless_stopped(args.poll_interval, stop);
+    }
+
+    info!("Watch stopped after profiling {} transactions", profiled);
+
+    Ok(profiled)
+}
+
+/// Profile one transaction into the output directory
+///
+/// **Private** - runs the capture pipeline per transaction
+fn profile_transaction(args: &WatchArgs, tx_hash: &str) -> Result<()> {
+    info!("Profiling transaction {}", tx_hash);
+
+    let capture = CaptureArgs {
+        rpc_url: args.rpc_url.clone(),
+        transaction_hash: tx_hash.to_string(),
+        output_json: args.out_dir.join(format!("{}.json", tx_hash)),
+        output_svg: args.flamegraph_config
+            .as_ref()
+            .map(|_| args.out_dir.join(format!("{}.svg", tx_hash))),
+        top_paths: args.top_paths,
+        flamegraph_config: args.flamegraph_config.clone(),
+        stack_options: args.stack_options.clone(),
+        tracer: args.tracer.clone(),
+        formats: transaction_formats(args),
+        ..Default::default()
+    };
+
+    execute_capture(capture)
+}
+
+/// Artifacts written per transaction
+///
+/// **Private** - empty keeps capture's default (JSON plus optional SVG);
+/// cumulative mode also needs the folded stacks to merge
+fn transaction_formats(args: &WatchArgs) -> Vec<OutputFormat> {
+    if !args.cumulative {
+        return Vec::new();
+    }
+
+    let mut formats = vec![OutputFormat::Json, OutputFormat::Folded];
+    if args.flamegraph_config.is_some() {
+        formats.push(OutputFormat::Svg);
+    }
+    formats
+}
+
+/// Path of the cumulative folded file
+///
+/// **Private** - internal helper
+fn cumulative_path(out_dir: &Path) -> PathBuf {
+    OutputFormat::Folded.path_from_base(&out_dir.join(CUMULATIVE_BASENAME))
+}
+
+/// Path of the per-stack sources file next to the cumulative folded file
+///
+/// **Private** - internal helper
+fn sources_path(cumulative: &Path) -> PathBuf {
+    cumulative.with_extension("sources.json")
+}
+
+/// Load the cumulative aggregate, starting empty if the file does not exist
+///
+/// **Private** - resumes a previous watch session, including its source
+/// attribution when `track_sources` is set (only the persisted top
+/// contributors survive a restart)
+fn load_cumulative(path: &Path, track_sources: bool) -> Result<StackAggregator> {
+    let mut aggregator = StackAggregator::new()
+        .with_track_sources(track_sources)
+        .with_max_sources(MAX_STACK_SOURCES);
+    if path.exists() {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let lines = aggregator.merge_from_folded(BufReader::new(file))
+            .with_context(|| format!("Failed to load cumulative stacks {}", path.display()))?;
+        info!("Resumed cumulative aggregate from {} ({} stacks)", path.display(), lines);
+    }
+
+    let sources = sources_path(path);
+    if track_sources && sources.exists() {
+        let file = std::fs::File::open(&sources)
+            .with_context(|| format!("Failed to open {}", sources.display()))?;
+        let persisted = serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to load stack sources {}", sources.display()))?;
+        aggregator.merge_sources(persisted);
+    }
+    Ok(aggregator)
+}
+
+/// Merge one transaction's folded stacks and rewrite the cumulative artifacts
+///
+/// **Private** - runs after each successfully profiled transaction
+fn update_cumulative(args: &WatchArgs, aggregator: &mut StackAggregator, tx_hash: &str) -> Result<()> {
+    let tx_folded = OutputFormat::Folded.path_from_base(&args.out_dir.join(format!("{}.json", tx_hash)));
+    let file = std::fs::File::open(&tx_folded)
+        .with_context(|| format!("Failed to open {}", tx_folded.display()))?;
+    aggregator.merge_source_folded(tx_hash, BufReader::new(file))?;
+
+    let path = cumulative_path(&args.out_dir);
+    std::fs::write(&path, aggregator.to_folded())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    if aggregator.tracks_sources() {
+        let sources = sources_path(&path);
+        let json = serde_json::to_string_pretty(&aggregator.top_sources_by_stack(MAX_STACK_SOURCES))?;
+        std::fs::write(&sources, json)
+            .with_context(|| format!("Failed to write {}", sources.display()))?;
+    }
+
+    if let Some(config) = &args.flamegraph_config {
+        let svg = generate_flamegraph(&aggregator.stacks(), Some(config))
+            .context("Failed to generate cumulative flamegraph")?;
+        write_svg(&svg, OutputFormat::Svg.path_from_base(&path))
+            .context("Failed to write cumulative flamegraph")?;
+    }
+
+    debug!("Cumulative aggregate: {} stacks, {} gas", aggregator.len(), aggregator.total_weight());
+    Ok(())
+}
+
+/// Select unseen transactions sent to `address`
+///
+/// **Private** - address match is case-insensitive; returned hashes are
+/// recorded in `seen` so re-scanned blocks are not profiled twice
+fn new_transactions_to(
+    transactions: &[BlockTransaction],
+    address: &str,
+    seen: &mut HashSet<String>,
+) -> Vec<String> {
+    transactions
+        .iter()
+        .filter(|tx| {
+            tx.to
+                .as_deref()
+                .is_some_and(|to| to.eq_ignore_ascii_case(address))
+        })
+        .filter(|tx| seen.insert(tx.hash.to_lowercase()))
+        .map(|tx| tx.hash.clone())
+        .collect()
+}
+
+/// Sleep for `duration`, returning early once `stop` is set
+///
+/// **Private** - keeps Ctrl-C responsive with long poll intervals
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let deadline = Instant::now() + duration;
+
+    while !stop.load(Ordering::SeqCst) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        std::thread::sleep(remaining.min(STOP_CHECK_INTERVAL));
+    }
+}
+
+/// Validate watch arguments
+///
+/// **Public** - can be called before execute_watch for early validation
+///
+/// # Arguments
+/// * `args` - Arguments to validate
+///
+/// # Returns
+/// Ok if arguments are valid, Err with message if not
+pub fn validate_watch_args(args: &WatchArgs) -> Result<()> {
+    if !args.rpc_url.starts_with("http://") && !args.rpc_url.starts_with("https://") {
+        anyhow::bail!("RPC URL must start with http:// or https://");
+    }
+
+    let address = args.address.strip_prefix("0x").unwrap_or(&args.address);
+    if address.len() != 40 || !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("Contract address must be 20 bytes (40 hex characters)");
+    }
+
+    if args.poll_interval.is_zero() {
+        anyhow::bail!("poll interval must be greater than 0");
+    }
+
+    if args.track_sources && !args.cumulative {
+        anyhow::bail!("--track-sources requires --cumulative");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTRACT: &str = "0x00000000000000000000000000000000000000aa";
+
+    fn tx(hash: &str, to: Option<&str>) -> BlockTransaction {
+        BlockTransaction {
+            hash: hash.to_string(),
+            to: to.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_new_transactions_to_filters_and_dedups() {
+        let block = vec![
+            tx("0x01", Some(CONTRACT)),
+            tx("0x02", Some("0x00000000000000000000000000000000000000bb")),
+            tx("0x03", None),
+            tx("0x04", Some(&CONTRACT.to_uppercase().replacen("0X", "0x", 1))),
+        ];
+        let mut seen = HashSet::new();
+
+        assert_eq!(new_transactions_to(&block, CONTRACT, &mut seen), vec!["0x01", "0x04"]);
+        assert!(new_transactions_to(&block, CONTRACT, &mut seen).is_empty());
+    }
+
+    #[test]
+    fn test_validate_watch_args() {
+        let args = WatchArgs {
+            address: CONTRACT.to_string(),
+            ..Default::default()
+        };
+        assert!(validate_watch_args(&args).is_ok());
+
+        let bad_address = WatchArgs {
+            address: "0x1234".to_string(),
+            ..args.clone()
+        };
+        assert!(validate_watch_args(&bad_address).is_err());
+
+        let zero_interval = WatchArgs {
+            poll_interval: Duration::ZERO,
+            ..args.clone()
+        };
+        assert!(validate_watch_args(&zero_interval).is_err());
+
+        let sources_only = WatchArgs {
+            track_sources: true,
+            ..args
+        };
+        assert!(validate_watch_args(&sources_only).is_err());
+    }
+
+    #[test]
+    fn test_load_cumulative_resumes_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = cumulative_path(dir.path());
+        assert!(load_cumulative(&path, false).unwrap().is_empty());
+
+        std::fs::write(&path, "call;SLOAD 2100\n").unwrap();
+        std::fs::write(sources_path(&path), r#"{"call;SLOAD": [{"source": "0x01", "weight": 2100}]}"#).unwrap();
+        let aggregator = load_cumulative(&path, true).unwrap();
+        assert_eq!(aggregator.total_weight(), 2100);
+        assert_eq!(aggregator.top_sources("call;SLOAD", 5)[0].source, "0x01");
+        assert_eq!(path.file_name().unwrap(), "cumulative.folded");
+        assert_eq!(sources_path(&path).file_name().unwrap(), "cumulative.sources.json");
+    }
+
+    #[test]
+    fn test_sleep_returns_immediately_when_stopped() {
+        let stop = AtomicBool::new(true);
+        let start = Instant::now();
+
+        sleep_unless_stopped(Duration::from_secs(10), &stop);
+
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
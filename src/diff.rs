@@ -0,0 +1,424 @@
+//! Comparison of two profiles (base vs head).
+//!
+//! Matches hot paths by stack string and reports per-path gas deltas.
+//! Used by the capture command's baseline check and anything else that
+//! needs to answer "did gas go up, and where?".
+//!
+//! Profiles of different sizes can be compared by shape instead of absolute
+//! gas: `normalize_profile` rescales every hot path to parts-per-million of
+//! its own total before diffing.
+//!
+//! Profiles built with `--aggregate-by pc` can also be compared per
+//! instruction (`diff_profiles_by_pc`), ignoring the call path.
+//!
+//! `compare_profiles` lines up hot paths across any number of profiles
+//! (e.g. labelled scenarios) instead of a base/head pair.
+
+use crate::aggregator::{parse_pc_frame, pc_frame, CollapsedStack, DEFAULT_FRAME_SEPARATOR};
+use crate::parser::schema::Profile;
+use std::collections::BTreeMap;
+
+/// Total every normalized profile is rescaled to (parts per million)
+pub const NORMALIZED_TOTAL: u64 = 1_000_000;
+
+/// Gas change for a single hot path
+///
+/// **Public** - element of `ProfileDiff::paths`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathDelta {
+    /// Collapsed stack string
+    pub stack: String,
+    
+    /// Gas in the base profile (0 if the path is new)
+    pub base_gas: u64,
+    
+    /// Gas in the head profile (0 if the path disappeared)
+    pub head_gas: u64,
+}
+
+impl PathDelta {
+    /// Signed gas difference (head - base)
+    pub fn delta(&self) -> i128 {
+        self.head_gas as i128 - self.base_gas as i128
+    }
+    
+    /// Percent change relative to base, `None` if the path is new
+    pub fn percent_change(&self) -> Option<f64> {
+        if self.base_gas == 0 {
+            None
+        } else {
+            Some(self.delta() as f64 / self.base_gas as f64 * 100.0)
+        }
+    }
+    
+    /// Whether the path exists only in head
+    pub fn is_new(&self) -> bool {
+        self.base_gas == 0 && self.head_gas > 0
+    }
+}
+
+/// Result of comparing two profiles
+///
+/// **Public** - returned from `diff_profiles`
+#[derive(Debug, Clone)]
+pub struct ProfileDiff {
+    /// Total gas of the base profile
+    pub base_total_gas: u64,
+    
+    /// Total gas of the head profile
+    pub head_total_gas: u64,
+    
+    /// Per-path deltas, largest absolute change first
+    pub paths: Vec<PathDelta>,
+    
+    /// Whether gas values are shares of `NORMALIZED_TOTAL` rather than gas
+    pub normalized: bool,
+}
+
+impl ProfileDiff {
+    /// Percent change of total gas relative to base
+    ///
+    /// A zero-gas base with nonzero head counts as a 100% increase.
+    pub fn total_percent_change(&self) -> f64 {
+        if self.base_total_gas == 0 {
+            if self.head_total_gas == 0 { 0.0 } else { 100.0 }
+        } else {
+            (self.head_total_gas as f64 - self.base_total_gas as f64)
+                / self.base_total_gas as f64 * 100.0
+        }
+    }
+    
+    /// Paths whose gas rose by more than `threshold_pct` (new paths always count)
+    pub fn regressions(&self, threshold_pct: f64) -> Vec<&PathDelta> {
+        self.paths
+            .iter()
+            .filter(|p| p.delta() > 0)
+            .filter(|p| p.percent_change().is_none_or(|pct| pct > threshold_pct))
+            .collect()
+    }
+    
+    /// Keep only paths absent from base that reach `min_gas` in head
+    ///
+    /// **Public** - "what did my change add" view; totals are untouched
+    pub fn retain_new_paths(&mut self, min_gas: u64) {
+        self.paths.retain(|p| p.base_gas == 0 && p.head_gas > 0 && p.head_gas >= min_gas);
+    }
+    
+    /// Get human-readable summary
+    ///
+    /// **Public** - for logging and terminal output
+    pub fn summary(&self) -> String {
+        if self.normalized {
+            return format!(
+                "Normalized comparison: {} paths ({} changed)",
+                self.paths.len(),
+                self.paths.iter().filter(|p| p.delta() != 0).count()
+            );
+        }
+        
+        format!(
+            "Base: {} gas | Head: {} gas | Change: {:+.2}%",
+            self.base_total_gas,
+            self.head_total_gas,
+            self.total_percent_change()
+        )
+    }
+}
+
+/// Compare two profiles by their hot paths
+///
+/// **Public** - main entry point for profile comparison
+///
+/// # Arguments
+/// * `base` - Reference profile (e.g., committed baseline)
+/// * `head` - Profile to check against the reference
+///
+/// # Returns
+/// Per-path deltas sorted by absolute change (descending), ties by stack
+pub fn diff_profiles(base: &Profile, head: &Profile) -> ProfileDiff {
+    let mut by_stack: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+    
+    for path in &base.hot_paths {
+        by_stack.entry(&path.stack).or_default().0 += path.gas;
+    }
+    for path in &head.hot_paths {
+        by_stack.entry(&path.stack).or_default().1 += path.gas;
+    }
+    
+    let mut paths: Vec<PathDelta> = by_stack
+        .into_iter()
+        .map(|(stack, (base_gas, head_gas))| PathDelta {
+            stack: stack.to_string(),
+            base_gas,
+            head_gas,
+        })
+        .collect();
+    
+    // BTreeMap iteration is already ordered by stack, so a stable sort
+    // keeps ties deterministic
+    paths.sort_by_key(|p| std::cmp::Reverse(p.delta().unsigned_abs()));
+    
+    ProfileDiff {
+        base_total_gas: base.total_gas,
+        head_total_gas: head.total_gas,
+        paths,
+        normalized: false,
+    }
+}
+
+/// Gas of one hot path in each of several profiles
+///
+/// **Public** - row of `compare_profiles`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathComparison {
+    /// Collapsed stack string
+    pub stack: String,
+    
+    /// Gas per profile, in input order (0 where the path is absent)
+    pub gas: Vec<u64>,
+}
+
+/// Line up the hot paths of several profiles
+///
+/// **Public** - N-way counterpart of `diff_profiles`
+///
+/// # Returns
+/// One row per stack present in any profile, heaviest (by maximum gas
+/// across profiles) first, ties by stack
+pub fn compare_profiles(profiles: &[Profile]) -> Vec<PathComparison> {
+    let mut by_stack: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    
+    for (index, profile) in profiles.iter().enumerate() {
+        for path in &profile.hot_paths {
+            by_stack.entry(&path.stack).or_insert_with(|| vec![0; profiles.len()])[index] += path.gas;
+        }
+    }
+    
+    let mut rows: Vec<PathComparison> = by_stack
+        .into_iter()
+        .map(|(stack, gas)| PathComparison { stack: stack.to_string(), gas })
+        .collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.gas.iter().copied().max().unwrap_or(0)));
+    
+    rows
+}
+
+/// Collapsed stacks of a stored profile
+///
+/// **Public** - input for differential flamegraphs of stored profiles
+///
+/// Uses the full `collapsed_stacks` when the profile was captured with
+/// `--full-stacks`. Otherwise stacks are reconstructed from the top hot
+/// paths, so gas outside them is missing; compare such profiles with
+/// the same `top_paths`.
+pub fn profile_stacks(profile: &Profile) -> Vec<CollapsedStack> {
+    if !profile.collapsed_stacks.is_empty() {
+        return profile.collapsed_stacks.clone();
+    }
+    
+    profile
+        .hot_paths
+        .iter()
+        .map(|path| CollapsedStack::new(path.stack.clone(), path.gas))
+        .collect()
+}
+
+/// Compare two profiles instruction by instruction
+///
+/// **Public** - finest-grained regression view, for profiles built with
+/// `--aggregate-by pc`
+///
+/// Stacks are keyed by the pc of their leaf `OP@0xpc` frame, so gas for
+/// one instruction reached through different call paths is summed. Each
+/// delta's `stack` is the instruction label (head's op when the op at a
+/// pc differs). Stacks come from `profile_stacks`, so every instruction
+/// is compared for `--full-stacks` profiles and only those on the top
+/// hot paths otherwise.
+///
+/// # Returns
+/// `None` if either profile has no pc-keyed hot paths
+pub fn diff_profiles_by_pc(base: &Profile, head: &Profile) -> Option<ProfileDiff> {
+    let base_by_pc = gas_by_pc(base)?;
+    let head_by_pc = gas_by_pc(head)?;
+    
+    let mut by_pc: BTreeMap<u64, (String, u64, u64)> = BTreeMap::new();
+    for (pc, (op, gas)) in base_by_pc {
+        by_pc.insert(pc, (op, gas, 0));
+    }
+    for (pc, (op, gas)) in head_by_pc {
+        let entry = by_pc.entry(pc).or_insert_with(|| (op.clone(), 0, 0));
+        entry.0 = op;
+        entry.2 = gas;
+    }
+    
+    let mut paths: Vec<PathDelta> = by_pc
+        .into_iter()
+        .map(|(pc, (op, base_gas, head_gas))| PathDelta {
+            stack: pc_frame(&op, pc),
+            base_gas,
+            head_gas,
+        })
+        .collect();
+    paths.sort_by_key(|p| std::cmp::Reverse(p.delta().unsigned_abs()));
+    
+    Some(ProfileDiff {
+        base_total_gas: base.total_gas,
+        head_total_gas: head.total_gas,
+        paths,
+        normalized: false,
+    })
+}
+
+/// Sum stack gas by the pc of each stack's leaf frame
+///
+/// **Private** - `None` if no stack ends in an `OP@0xpc` frame
+fn gas_by_pc(profile: &Profile) -> Option<BTreeMap<u64, (String, u64)>> {
+    let mut by_pc: BTreeMap<u64, (String, u64)> = BTreeMap::new();
+    
+    for stack in profile_stacks(profile) {
+        let leaf = stack.stack.rsplit(DEFAULT_FRAME_SEPARATOR).next().unwrap_or_default();
+        if let Some((op, pc)) = parse_pc_frame(leaf) {
+            let entry = by_pc.entry(pc).or_insert_with(|| (op.to_string(), 0));
+            entry.1 += stack.weight;
+        }
+    }
+    
+    (!by_pc.is_empty()).then_some(by_pc)
+}
+
+/// Compare two profiles by the share of gas each hot path takes
+///
+/// **Public** - shape comparison for transactions of different sizes
+///
+/// Both profiles are rescaled with `normalize_profile` first, so path
+/// deltas are shifts in parts-per-million of each profile's own total.
+pub fn diff_profiles_normalized(base: &Profile, head: &Profile) -> ProfileDiff {
+    let mut diff = diff_profiles(&normalize_profile(base), &normalize_profile(head));
+    diff.normalized = true;
+    diff
+}
+
+/// Rescale a profile's hot-path gas to parts-per-million of its total
+///
+/// **Public** - transform applied before a normalized diff
+///
+/// A profile with zero total gas is returned with all paths at zero.
+pub fn normalize_profile(profile: &Profile) -> Profile {
+    let total = profile.total_gas;
+    let mut normalized = profile.clone();
+    
+    for path in &mut normalized.hot_paths {
+        path.gas = if total == 0 {
+            0
+        } else {
+            (path.gas as u128 * NORMALIZED_TOTAL as u128 / total as u128) as u64
+        };
+    }
+    normalized.total_gas = if total == 0 { 0 } else { NORMALIZED_TOTAL };
+    
+    normalized
+}
+
+/// Express a normalized gas value as a percentage of the total
+///
+/// **Public** - display helper for normalized diffs
+pub fn normalized_percent(value: i128) -> f64 {
+    value as f64 / NORMALIZED_TOTAL as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testgen::profile_with_paths as profile;
+
+    #[test]
+    fn test_compare_profiles_aligns_paths() {
+        let cold = profile(3000, &[("main;SLOAD", 2100), ("main;ADD", 900)]);
+        let warm = profile(1000, &[("main;SLOAD", 100), ("main;ADD", 900)]);
+        let extra = profile(500, &[("main;LOG", 500)]);
+        
+        let rows = compare_profiles(&[cold, warm, extra]);
+        
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], PathComparison { stack: "main;SLOAD".to_string(), gas: vec![2100, 100, 0] });
+        assert_eq!(rows[1].gas, vec![900, 900, 0]);
+        assert_eq!(rows[2].gas, vec![0, 0, 500]);
+    }
+
+    #[test]
+    fn test_diff_profiles_matches_paths() {
+        let base = profile(1000, &[("main;a", 600), ("main;b", 400)]);
+        let head = profile(1300, &[("main;a", 900), ("main;c", 400)]);
+        
+        let diff = diff_profiles(&base, &head);
+        
+        assert_eq!(diff.paths.len(), 3);
+        assert_eq!(diff.paths[0].stack, "main;b");
+        assert_eq!(diff.paths[0].delta(), -400);
+        assert!((diff.total_percent_change() - 30.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_regressions_respect_threshold() {
+        let base = profile(1000, &[("main;a", 1000), ("main;b", 1000)]);
+        let head = profile(1100, &[("main;a", 1040), ("main;b", 1100), ("main;new", 10)]);
+        
+        let diff = diff_profiles(&base, &head);
+        let regressed: Vec<&str> = diff.regressions(5.0).iter().map(|p| p.stack.as_str()).collect();
+        
+        assert_eq!(regressed, vec!["main;b", "main;new"]);
+        
+        let mut added = diff_profiles(&base, &head);
+        added.retain_new_paths(10);
+        assert_eq!(added.paths.len(), 1);
+        assert_eq!(added.paths[0].stack, "main;new");
+        added.retain_new_paths(11);
+        assert!(added.paths.is_empty());
+    }
+
+    #[test]
+    fn test_normalized_diff_compares_shape() {
+        // Same shape at double the size: no change once normalized
+        let base = profile(1000, &[("main;a", 750), ("main;b", 250)]);
+        let head = profile(2000, &[("main;a", 1500), ("main;b", 500)]);
+        assert!(diff_profiles_normalized(&base, &head).paths.iter().all(|p| p.delta() == 0));
+        
+        // Similar total, shifted shape
+        let head = profile(1000, &[("main;a", 500), ("main;b", 500)]);
+        let diff = diff_profiles_normalized(&base, &head);
+        assert!(diff.normalized);
+        assert_eq!(diff.paths[0].head_gas, 500_000);
+        assert!((normalized_percent(diff.paths[0].delta()).abs() - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_diff_by_pc_matches_instructions() {
+        let base = profile(3000, &[("call;f;SLOAD@0x10", 2100), ("call;g;SLOAD@0x10", 100), ("call;ADD@0x14", 3)]);
+        let head = profile(3000, &[("call;f;SLOAD@0x10", 100), ("call;MUL@0x14", 5)]);
+        
+        let diff = diff_profiles_by_pc(&base, &head).unwrap();
+        
+        assert_eq!(diff.paths.len(), 2);
+        assert_eq!(diff.paths[0].stack, "SLOAD@0x10");
+        assert_eq!(diff.paths[0].delta(), -2100);
+        assert_eq!(diff.paths[1].stack, "MUL@0x14");
+        assert_eq!(diff.paths[1].delta(), 2);
+        assert!(diff_profiles_by_pc(&profile(10, &[("call;SLOAD", 10)]), &head).is_none());
+        
+        // Full stacks reach instructions outside the top hot paths
+        let mut full_head = head.clone();
+        full_head.collapsed_stacks = vec![
+            CollapsedStack::new("call;f;SLOAD@0x10".to_string(), 100),
+            CollapsedStack::new("call;MUL@0x14".to_string(), 5),
+            CollapsedStack::new("call;POP@0x20".to_string(), 2),
+        ];
+        let diff = diff_profiles_by_pc(&base, &full_head).unwrap();
+        assert!(diff.paths.iter().any(|p| p.stack == "POP@0x20" && p.head_gas == 2));
+    }
+
+    #[test]
+    fn test_total_percent_change_zero_base() {
+        assert_eq!(diff_profiles(&profile(0, &[]), &profile(0, &[])).total_percent_change(), 0.0);
+        assert_eq!(diff_profiles(&profile(0, &[]), &profile(10, &[])).total_percent_change(), 100.0);
+    }
+}
@@ -3,10 +3,15 @@
 //! Converts collapsed stacks into interactive SVG flamegraphs.
 //! The inferno crate handles all the heavy lifting (layout, colors, interactivity).
 
-use crate::aggregator::stack_builder::CollapsedStack;
+use crate::aggregator::stack_builder::{drop_small_stacks, CollapsedStack, OTHER_FRAME};
+use crate::parser::{HostIoStats, HostIoType};
 use crate::utils::error::FlamegraphError;
+use crate::utils::format_gas;
+use inferno::flamegraph::color::{Color, PaletteMap};
 use inferno::flamegraph::{self, Options, Palette};
-use log::{debug, info};
+use log::{debug, info, warn};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::io::{BufWriter, Cursor};
 use std::str::FromStr; 
 /// Flamegraph configuration
@@ -17,6 +22,9 @@ pub struct FlamegraphConfig {
     /// Title displayed at the top of the flamegraph
     pub title: String,
     
+    /// Whether `title` was set explicitly (via `with_title`)
+    pub custom_title: bool,
+    
     /// What the "weight" represents (e.g., "gas", "samples", "time")
     pub count_name: String,
     
@@ -31,6 +39,82 @@ pub struct FlamegraphConfig {
     
     /// Reverse stack order (root at bottom vs top)
     pub reverse: bool,
+    
+    /// Unit weights are divided into before rendering
+    pub weight_scale: WeightScale,
+    
+    /// RGB fill for the merged "other" frame, so the aggregate is not
+    /// mistaken for real work
+    pub other_frame_color: (u8, u8, u8),
+    
+    /// Maximum number of frames rendered per stack (None = unlimited);
+    /// deeper frames are folded into their ancestor at this depth
+    pub max_depth: Option<usize>,
+    
+    /// Stacks lighter than this many gas are dropped before rendering
+    /// (None = keep all)
+    pub min_gas: Option<u64>,
+    
+    /// Sort input lines and derive colors from frame names so the same
+    /// stacks always produce byte-identical SVGs
+    pub deterministic: bool,
+    
+    /// Abbreviate `0x` hex strings longer than this many characters to
+    /// `0x1234…cdef` in rendered frame names (None = full length)
+    pub trim_hex: Option<usize>,
+}
+
+/// Muted gray used for the merged "other" frame by default
+pub const DEFAULT_OTHER_FRAME_COLOR: (u8, u8, u8) = (160, 160, 160);
+
+/// Unit used to scale flamegraph weights for display
+///
+/// **Public** - keeps huge aggregated gas values manageable for inferno
+/// and downstream tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeightScale {
+    /// Raw gas (no scaling)
+    #[default]
+    Gas,
+    
+    /// Thousands of gas
+    KGas,
+    
+    /// Millions of gas
+    MGas,
+}
+
+impl WeightScale {
+    /// Factor weights are divided by
+    pub fn divisor(&self) -> u64 {
+        match self {
+            Self::Gas => 1,
+            Self::KGas => 1_000,
+            Self::MGas => 1_000_000,
+        }
+    }
+    
+    /// Unit label used as the flamegraph `count_name`
+    pub fn unit_name(&self) -> &'static str {
+        match self {
+            Self::Gas => "gas",
+            Self::KGas => "kgas",
+            Self::MGas => "Mgas",
+        }
+    }
+    
+    /// Scale a raw weight, rounding half up
+    ///
+    /// The same rounding rule is applied to every line so relative
+    /// proportions in the tree are preserved.
+    pub fn scale(&self, weight: u64) -> u64 {
+        let divisor = self.divisor();
+        if divisor == 1 {
+            weight
+        } else {
+            weight / divisor + u64::from(weight % divisor >= divisor.div_ceil(2))
+        }
+    }
 }
 
 /// Color palettes for flamegraph
@@ -52,17 +136,31 @@ pub enum FlamegraphPalette {
     
     /// Consistent colors based on function name hash
     Consistent,
+    
+    /// Chosen from the dominant HostIO type at capture time (mem for
+    /// storage-heavy, io for call-heavy); renders as hot if unresolved
+    Auto,
 }
 
+/// Title used when none is set
+pub const DEFAULT_FLAMEGRAPH_TITLE: &str = "Stylus Transaction Profile";
+
 impl Default for FlamegraphConfig {
     fn default() -> Self {
         Self {
-            title: "Stylus Transaction Profile".to_string(),
+            title: DEFAULT_FLAMEGRAPH_TITLE.to_string(),
+            custom_title: false,
             count_name: "gas".to_string(),
             palette: FlamegraphPalette::Hot,
             min_width: 0.1,
             image_width: Some(1200),
             reverse: false,
+            weight_scale: WeightScale::Gas,
+            other_frame_color: DEFAULT_OTHER_FRAME_COLOR,
+            max_depth: None,
+            min_gas: None,
+            deterministic: false,
+            trim_hex: None,
         }
     }
 }
@@ -80,9 +178,16 @@ impl FlamegraphConfig {
     /// **Public** - builder pattern
     pub fn with_title(mut self, title: impl Into<String>) -> Self {
         self.title = title.into();
+        self.custom_title = true;
         self
     }
     
+    /// Whether the title was left unset (even a title equal to
+    /// `DEFAULT_FLAMEGRAPH_TITLE` counts as set once passed to `with_title`)
+    pub fn has_default_title(&self) -> bool {
+        !self.custom_title
+    }
+    
     /// Set color palette
     ///
     /// **Public** - builder pattern
@@ -98,6 +203,135 @@ impl FlamegraphConfig {
         self.image_width = Some(width);
         self
     }
+    
+    /// Set weight scale (also updates `count_name` to the scaled unit)
+    ///
+    /// **Public** - builder pattern
+    pub fn with_weight_scale(mut self, scale: WeightScale) -> Self {
+        self.weight_scale = scale;
+        self.count_name = scale.unit_name().to_string();
+        self
+    }
+    
+    /// Set the fill color of the merged "other" frame
+    ///
+    /// **Public** - builder pattern
+    pub fn with_other_frame_color(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.other_frame_color = (r, g, b);
+        self
+    }
+    
+    /// Limit rendering to the first `max_depth` frames of each stack
+    ///
+    /// **Public** - builder pattern; keeps the SVG height bounded for
+    /// very deep traces (gas of deeper frames stays in their ancestor)
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth.max(1));
+        self
+    }
+    
+    /// Drop stacks lighter than `min_gas` before rendering
+    ///
+    /// **Public** - builder pattern; unlike `min_width` the cut-off is in
+    /// gas, and the dropped weight is not kept as "other"
+    pub fn with_min_gas(mut self, min_gas: u64) -> Self {
+        self.min_gas = Some(min_gas);
+        self
+    }
+    
+    /// Set the unit label shown in tooltips (e.g. "ink", "samples")
+    ///
+    /// **Public** - builder pattern; call after `with_weight_scale` to
+    /// override the scaled unit name
+    pub fn with_count_name(mut self, count_name: impl Into<String>) -> Self {
+        self.count_name = count_name.into();
+        self
+    }
+    
+    /// Abbreviate long `0x` hex strings in rendered frame names
+    ///
+    /// **Public** - builder pattern; display only, stacks and profile
+    /// JSON keep full values. Lengths below `MIN_TRIM_HEX_LEN` are raised
+    /// to it, since shorter strings would not get any shorter.
+    pub fn with_trim_hex(mut self, max_len: usize) -> Self {
+        self.trim_hex = Some(max_len.max(MIN_TRIM_HEX_LEN));
+        self
+    }
+    
+    /// Produce byte-reproducible SVGs (stable line order and colors)
+    ///
+    /// **Public** - builder pattern; intended for golden-file tests
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+}
+
+/// Hex digits kept on each side of an abbreviated hex string
+const TRIM_HEX_KEEP: usize = 4;
+
+/// Shortest `trim_hex` length (`0x` + both kept ends + the ellipsis)
+pub const MIN_TRIM_HEX_LEN: usize = 2 + 2 * TRIM_HEX_KEEP + 1;
+
+/// Image width inferno uses when none is configured
+const DEFAULT_IMAGE_WIDTH: usize = 1200;
+
+/// Horizontal padding inferno reserves on each side of the image
+const INFERNO_XPAD: usize = 10;
+
+/// Statistics about what the rendered flamegraph can actually show
+///
+/// **Public** - returned from `generate_flamegraph_with_stats`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlamegraphStats {
+    /// Number of stacks passed to the renderer
+    pub total_stacks: usize,
+    
+    /// Stacks narrower than `min_width` that inferno will drop
+    pub hidden_stacks: usize,
+    
+    /// Gas carried by the hidden stacks
+    pub hidden_gas: u64,
+    
+    /// Smallest weight that still renders at the configured width
+    pub min_visible_weight: f64,
+}
+
+impl FlamegraphStats {
+    /// Compute how many stacks fall below the effective minimum frame width
+    ///
+    /// **Public** - can be used without rendering
+    pub fn compute(stacks: &[CollapsedStack], config: &FlamegraphConfig) -> Self {
+        let total_weight: u64 = stacks.iter().map(|s| s.weight).sum();
+        let drawable_width = config.image_width
+            .unwrap_or(DEFAULT_IMAGE_WIDTH)
+            .saturating_sub(2 * INFERNO_XPAD)
+            .max(1) as f64;
+        
+        // Pixel width = weight / total * drawable_width
+        let min_visible_weight = config.min_width / drawable_width * total_weight as f64;
+        
+        let (hidden_stacks, hidden_gas) = stacks
+            .iter()
+            .filter(|s| (s.weight as f64) < min_visible_weight)
+            .fold((0, 0u64), |(count, gas), s| (count + 1, gas + s.weight));
+        
+        Self {
+            total_stacks: stacks.len(),
+            hidden_stacks,
+            hidden_gas,
+            min_visible_weight,
+        }
+    }
+    
+    /// Percentage of stacks that will not be drawn
+    pub fn hidden_percentage(&self) -> f64 {
+        if self.total_stacks == 0 {
+            0.0
+        } else {
+            self.hidden_stacks as f64 / self.total_stacks as f64 * 100.0
+        }
+    }
 }
 
 /// Generate SVG flamegraph from collapsed stacks
@@ -125,20 +359,73 @@ pub fn generate_flamegraph(
     stacks: &[CollapsedStack],
     config: Option<&FlamegraphConfig>,
 ) -> Result<String, FlamegraphError> {
+    generate_flamegraph_with_stats(stacks, config).map(|(svg, _)| svg)
+}
+
+/// Generate SVG flamegraph and report how many stacks were too narrow to draw
+///
+/// **Public** - same as `generate_flamegraph` plus `FlamegraphStats`
+///
+/// Logs a warning when stacks fall below the configured `min_width`,
+/// since inferno drops them silently and the SVG under-represents them.
+pub fn generate_flamegraph_with_stats(
+    stacks: &[CollapsedStack],
+    config: Option<&FlamegraphConfig>,
+) -> Result<(String, FlamegraphStats), FlamegraphError> {
     if stacks.is_empty() {
         return Err(FlamegraphError::EmptyStacks);
     }
     
     let config = config.cloned().unwrap_or_default();
     
+    let filtered;
+    let stacks = match config.min_gas {
+        Some(min_gas) => {
+            filtered = drop_small_stacks(stacks, min_gas);
+            debug!("Dropped {} stacks under {} gas", stacks.len() - filtered.len(), min_gas);
+            if filtered.is_empty() {
+                return Err(FlamegraphError::EmptyStacks);
+            }
+            filtered.as_slice()
+        }
+        None => stacks,
+    };
+    
+    let truncated;
+    let stacks = match config.max_depth {
+        Some(max_depth) => {
+            truncated = truncate_stacks(stacks, max_depth);
+            truncated.as_slice()
+        }
+        None => stacks,
+    };
+    
     info!("Generating flamegraph with {} stacks", stacks.len());
     debug!("Flamegraph config: {:?}", config);
     
+    let stats = FlamegraphStats::compute(stacks, &config);
+    if stats.hidden_stacks > 0 {
+        warn!(
+            "{} of {} stacks ({:.1}%, {} gas) are narrower than min_width and will not be drawn; \
+             consider merge_small_stacks or a wider image",
+            stats.hidden_stacks,
+            stats.total_stacks,
+            stats.hidden_percentage(),
+            stats.hidden_gas
+        );
+    }
+    
     // Convert stacks to collapsed format (one line per stack)
-    let collapsed_input = stacks_to_collapsed_format(stacks);
+    let collapsed_input = stacks_to_collapsed_format(
+        stacks,
+        config.weight_scale,
+        config.deterministic,
+        config.trim_hex,
+    );
     
     // Create inferno options
-    let mut options = create_inferno_options(&config);
+    let mut palette_map = other_frame_palette(&config);
+    let mut options = create_inferno_options(&config, &mut palette_map);
     
     // Prepare input/output buffers
     let input_reader = Cursor::new(collapsed_input.as_bytes());
@@ -158,7 +445,77 @@ pub fn generate_flamegraph(
     
     info!("Flamegraph generated successfully ({} bytes)", svg_content.len());
     
-    Ok(svg_content)
+    Ok((svg_content, stats))
+}
+
+/// Generate a differential flamegraph (base vs head)
+///
+/// **Public** - regression view for baseline comparisons
+///
+/// Frames are sized by head gas and colored by the change from base:
+/// red where gas rose, blue where it fell. Stacks only present in base
+/// have no width in head and are not drawn.
+///
+/// # Errors
+/// * `FlamegraphError::EmptyStacks` - Head has no stacks to visualize
+/// * `FlamegraphError::GenerationFailed` - Inferno failed to generate SVG
+pub fn generate_diff_flamegraph(
+    base: &[CollapsedStack],
+    head: &[CollapsedStack],
+    config: Option<&FlamegraphConfig>,
+) -> Result<String, FlamegraphError> {
+    if head.is_empty() {
+        return Err(FlamegraphError::EmptyStacks);
+    }
+    
+    let config = config.cloned().unwrap_or_default();
+    let (base, head) = match config.max_depth {
+        Some(max_depth) => (truncate_stacks(base, max_depth), truncate_stacks(head, max_depth)),
+        None => (base.to_vec(), head.to_vec()),
+    };
+    
+    info!("Generating differential flamegraph ({} base, {} head stacks)", base.len(), head.len());
+    
+    let collapsed_input = diff_stacks_to_collapsed_format(&base, &head, config.weight_scale, config.trim_hex);
+    
+    let mut palette_map = other_frame_palette(&config);
+    let mut options = create_inferno_options(&config, &mut palette_map);
+    let mut output_buffer = Vec::new();
+    
+    flamegraph::from_reader(
+        &mut options,
+        Cursor::new(collapsed_input.as_bytes()),
+        BufWriter::new(&mut output_buffer),
+    )
+    .map_err(|e| FlamegraphError::GenerationFailed(format!("Inferno error: {}", e)))?;
+    
+    String::from_utf8(output_buffer)
+        .map_err(|e| FlamegraphError::GenerationFailed(format!("Invalid UTF-8: {}", e)))
+}
+
+/// Cut stacks to at most `max_depth` frames, merging those that collide
+///
+/// **Private** - pre-render pass for `FlamegraphConfig::max_depth`
+///
+/// Weights of cut frames are added to the surviving prefix, so total gas
+/// and every visible frame's width are unchanged.
+fn truncate_stacks(stacks: &[CollapsedStack], max_depth: usize) -> Vec<CollapsedStack> {
+    let mut merged: BTreeMap<String, u64> = BTreeMap::new();
+    
+    for stack in stacks {
+        let prefix = stack
+            .stack
+            .split(';')
+            .take(max_depth)
+            .collect::<Vec<_>>()
+            .join(";");
+        *merged.entry(prefix).or_insert(0) += stack.weight;
+    }
+    
+    merged
+        .into_iter()
+        .map(|(stack, weight)| CollapsedStack::new(stack, weight))
+        .collect()
 }
 
 /// Convert CollapsedStack vector to collapsed format string
@@ -167,18 +524,127 @@ pub fn generate_flamegraph(
 ///
 /// Format: one line per stack
 /// "stack_trace weight\n"
-fn stacks_to_collapsed_format(stacks: &[CollapsedStack]) -> String {
-    stacks
+///
+/// Weights are divided by the scale's divisor; stacks that round to
+/// zero are dropped since inferno would ignore them anyway. With `sorted`
+/// the lines are ordered by stack string so input order cannot leak
+/// into the output. With `trim_hex`, long hex strings are abbreviated
+/// (stacks that become identical are merged by inferno).
+fn stacks_to_collapsed_format(
+    stacks: &[CollapsedStack],
+    scale: WeightScale,
+    sorted: bool,
+    trim_hex: Option<usize>,
+) -> String {
+    let mut lines: Vec<(Cow<str>, u64)> = stacks
         .iter()
-        .map(|stack| stack.to_line())
+        .filter_map(|stack| {
+            let weight = scale.scale(stack.weight);
+            (weight > 0).then(|| (display_stack(&stack.stack, trim_hex), weight))
+        })
+        .collect();
+    
+    if sorted {
+        lines.sort();
+    }
+    
+    lines
+        .into_iter()
+        .map(|(stack, weight)| format!("{} {}", stack, weight))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Stack string as rendered: abbreviated if `trim_hex` is set
+///
+/// **Private** - internal helper
+fn display_stack(stack: &str, trim_hex: Option<usize>) -> Cow<'_, str> {
+    match trim_hex {
+        Some(max_len) => abbreviate_hex(stack, max_len),
+        None => Cow::Borrowed(stack),
+    }
+}
+
+/// Shorten every `0x` hex string longer than `max_len` to `0x1234…cdef`
+///
+/// **Public** - display transform behind `FlamegraphConfig::trim_hex`;
+/// a `0x` only starts a hex string at a word boundary, so names like
+/// `f0x12` are left alone
+pub fn abbreviate_hex(text: &str, max_len: usize) -> Cow<'_, str> {
+    let bytes = text.as_bytes();
+    let mut output = String::new();
+    let mut copied = 0;
+    let mut index = 0;
+    
+    while index + 1 < bytes.len() {
+        let at_boundary = index == 0 || !bytes[index - 1].is_ascii_alphanumeric();
+        if !(at_boundary && bytes[index] == b'0' && bytes[index + 1] == b'x') {
+            index += 1;
+            continue;
+        }
+        
+        let digits = bytes[index + 2..].iter().take_while(|b| b.is_ascii_hexdigit()).count();
+        let end = index + 2 + digits;
+        if end - index > max_len && digits > 2 * TRIM_HEX_KEEP {
+            output.push_str(&text[copied..index + 2 + TRIM_HEX_KEEP]);
+            output.push('…');
+            output.push_str(&text[end - TRIM_HEX_KEEP..end]);
+            copied = end;
+        }
+        index = end;
+    }
+    
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    output.push_str(&text[copied..]);
+    Cow::Owned(output)
+}
+
+/// Convert base/head stacks to inferno's differential format
+///
+/// **Private** - internal conversion
+///
+/// Format: one line per stack present in either side
+/// "stack_trace base_weight head_weight\n"
+fn diff_stacks_to_collapsed_format(
+    base: &[CollapsedStack],
+    head: &[CollapsedStack],
+    scale: WeightScale,
+    trim_hex: Option<usize>,
+) -> String {
+    let mut merged: BTreeMap<Cow<str>, (u64, u64)> = BTreeMap::new();
+    for stack in base {
+        merged.entry(display_stack(&stack.stack, trim_hex)).or_default().0 += stack.weight;
+    }
+    for stack in head {
+        merged.entry(display_stack(&stack.stack, trim_hex)).or_default().1 += stack.weight;
+    }
+    
+    merged
+        .into_iter()
+        .map(|(stack, (before, after))| format!("{} {} {}", stack, scale.scale(before), scale.scale(after)))
         .collect::<Vec<_>>()
         .join("\n")
 }
 
+/// Build a palette map pinning the merged "other" frame to its color
+///
+/// **Private** - other frames still get palette colors
+fn other_frame_palette(config: &FlamegraphConfig) -> PaletteMap {
+    let (r, g, b) = config.other_frame_color;
+    let mut palette_map = PaletteMap::default();
+    palette_map.insert(OTHER_FRAME, Color { r, g, b });
+    palette_map
+}
+
 /// Create inferno Options from our config
 ///
 /// **Private** - internal conversion
-fn create_inferno_options(config: &FlamegraphConfig) -> Options<'static> {
+fn create_inferno_options<'a>(
+    config: &FlamegraphConfig,
+    palette_map: &'a mut PaletteMap,
+) -> Options<'a> {
     let mut options = Options::default();
     
     // Set title
@@ -190,12 +656,17 @@ fn create_inferno_options(config: &FlamegraphConfig) -> Options<'static> {
     // FIX: Inferno 0.11 uses `colors` field, not `palette`
     // Set color scheme using the `colors` field
     options.colors = match config.palette {
-        FlamegraphPalette::Hot => Palette::from_str("hot").unwrap_or_default(),
+        FlamegraphPalette::Hot | FlamegraphPalette::Auto => Palette::from_str("hot").unwrap_or_default(),
         FlamegraphPalette::Mem => Palette::from_str("mem").unwrap_or_default(),
         FlamegraphPalette::Io => Palette::from_str("io").unwrap_or_default(),
         FlamegraphPalette::Java => Palette::from_str("java").unwrap_or_default(),
         FlamegraphPalette::Consistent => Palette::from_str("aqua").unwrap_or_default(),
     };
+    options.palette_map = Some(palette_map);
+    
+    // Hash frame names for colors instead of picking them at random
+    options.deterministic = config.deterministic;
+    
     // Set minimum width
     options.min_width = config.min_width;
     
@@ -233,9 +704,9 @@ pub fn generate_text_summary(stacks: &[CollapsedStack], max_lines: usize) -> Str
     
     for (i, stack) in stacks.iter().take(max_lines).enumerate() {
         let line = format!(
-            "{:>3}. {:>10} gas | {}",
+            "{:>3}. {:>13} gas | {}",
             i + 1,
-            stack.weight,
+            format_gas(stack.weight),
             stack.stack
         );
         lines.push(line);
@@ -248,6 +719,47 @@ pub fn generate_text_summary(stacks: &[CollapsedStack], max_lines: usize) -> Str
     lines.join("\n")
 }
 
+/// Text summary of the HostIO types that cost the most gas
+///
+/// **Public** - IO-centric companion to `generate_text_summary`
+///
+/// # Arguments
+/// * `stats` - HostIO statistics from the parser
+/// * `max_lines` - Maximum number of types to list
+///
+/// # Returns
+/// One line per type, heaviest first, with call count and gas
+pub fn generate_hostio_summary(stats: &HostIoStats, max_lines: usize) -> String {
+    let mut rows: Vec<(HostIoType, u64)> = stats.gas_by_type().into_iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0))));
+    
+    let mut lines = Vec::new();
+    
+    lines.push("Top HostIO by Gas:".to_string());
+    lines.push("─".repeat(80));
+    
+    if rows.is_empty() {
+        lines.push("  (no HostIO events)".to_string());
+    }
+    
+    for (i, (io_type, gas)) in rows.iter().take(max_lines).enumerate() {
+        let line = format!(
+            "{:>3}. {:>13} gas | {:>6} calls | {:?}",
+            i + 1,
+            format_gas(*gas),
+            stats.count_for_type(*io_type),
+            io_type
+        );
+        lines.push(line);
+    }
+    
+    if rows.len() > max_lines {
+        lines.push(format!("... and {} more HostIO types", rows.len() - max_lines));
+    }
+    
+    lines.join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -259,11 +771,156 @@ mod tests {
             CollapsedStack::new("main;storage".to_string(), 3000),
         ];
         
-        let collapsed = stacks_to_collapsed_format(&stacks);
+        let collapsed = stacks_to_collapsed_format(&stacks, WeightScale::Gas, false, None);
         
         assert_eq!(collapsed, "main;execute 5000\nmain;storage 3000");
     }
 
+    #[test]
+    fn test_stacks_to_collapsed_format_scaled() {
+        let stacks = vec![
+            CollapsedStack::new("main;execute".to_string(), 2_500_000),
+            CollapsedStack::new("main;storage".to_string(), 1_499),
+            CollapsedStack::new("main;tiny".to_string(), 400),
+        ];
+        
+        let collapsed = stacks_to_collapsed_format(&stacks, WeightScale::KGas, false, None);
+        
+        assert_eq!(collapsed, "main;execute 2500\nmain;storage 1");
+    }
+
+    #[test]
+    fn test_generate_hostio_summary() {
+        let trace = serde_json::json!({
+            "hostio": [
+                { "type": "storage_load", "gas": 2100 },
+                { "type": "storage_load", "gas": 2100 },
+                { "type": "storage_store", "gas": 20000 },
+                { "type": "log", "gas": 375 }
+            ]
+        });
+        let stats = crate::parser::extract_hostio_events(&trace);
+        
+        let summary = generate_hostio_summary(&stats, 2);
+        let lines: Vec<&str> = summary.lines().collect();
+        
+        assert_eq!(lines.len(), 5);
+        assert!(lines[2].contains("20,000 gas") && lines[2].ends_with("StorageStore"));
+        assert!(lines[3].contains("2 calls") && lines[3].ends_with("StorageLoad"));
+        assert_eq!(lines[4], "... and 1 more HostIO types");
+    }
+
+    #[test]
+    fn test_trim_hex_display() {
+        let address = "0xc0ffee254729296a45a3885639ac7e10f9d54979";
+        let stacks = vec![
+            CollapsedStack::new(format!("main;call {};SLOAD", address), 2100),
+            CollapsedStack::new("main;0x1234;f0xabcdefabcdefabcdef".to_string(), 5),
+        ];
+        
+        let collapsed = stacks_to_collapsed_format(&stacks, WeightScale::Gas, false, Some(12));
+        
+        assert_eq!(
+            collapsed,
+            "main;call 0xc0ff…4979;SLOAD 2100\nmain;0x1234;f0xabcdefabcdefabcdef 5"
+        );
+        assert_eq!(stacks[0].stack, format!("main;call {};SLOAD", address));
+        assert_eq!(abbreviate_hex(address, 42), address);
+    }
+
+    #[test]
+    fn test_deterministic_svg_is_reproducible() {
+        let stacks = vec![
+            CollapsedStack::new("main;storage;SLOAD".to_string(), 2100),
+            CollapsedStack::new("main;execute".to_string(), 5000),
+            CollapsedStack::new("main;hash;KECCAK".to_string(), 300),
+        ];
+        let mut shuffled = stacks.clone();
+        shuffled.reverse();
+        let config = FlamegraphConfig::new().with_deterministic(true);
+        
+        assert_eq!(
+            stacks_to_collapsed_format(&shuffled, WeightScale::Gas, true, None),
+            "main;execute 5000\nmain;hash;KECCAK 300\nmain;storage;SLOAD 2100"
+        );
+        
+        let first = generate_flamegraph(&stacks, Some(&config)).unwrap();
+        let second = generate_flamegraph(&shuffled, Some(&config)).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_weight_scale_rounding() {
+        assert_eq!(WeightScale::Gas.scale(1_499), 1_499);
+        assert_eq!(WeightScale::KGas.scale(1_499), 1);
+        assert_eq!(WeightScale::KGas.scale(1_500), 2);
+        assert_eq!(WeightScale::MGas.scale(3_000_000_000), 3_000);
+    }
+
+    #[test]
+    fn test_with_weight_scale_updates_count_name() {
+        let config = FlamegraphConfig::new().with_weight_scale(WeightScale::MGas);
+        assert_eq!(config.weight_scale, WeightScale::MGas);
+        assert_eq!(config.count_name, "Mgas");
+    }
+
+    #[test]
+    fn test_with_count_name_overrides_scale_unit() {
+        let config = FlamegraphConfig::new()
+            .with_weight_scale(WeightScale::KGas)
+            .with_count_name("ink");
+        assert_eq!(config.count_name, "ink");
+        assert_eq!(FlamegraphConfig::new().count_name, "gas");
+    }
+
+    #[test]
+    fn test_other_frame_rendered_in_configured_color() {
+        let stacks = vec![
+            CollapsedStack::new("main;execute".to_string(), 900),
+            CollapsedStack::new(OTHER_FRAME.to_string(), 100),
+        ];
+        let config = FlamegraphConfig::new().with_other_frame_color(1, 2, 3);
+        
+        let svg = generate_flamegraph(&stacks, Some(&config)).unwrap();
+        assert!(svg.contains("rgb(1,2,3)"));
+    }
+
+    #[test]
+    fn test_diff_flamegraph_marks_regressions() {
+        let base = vec![CollapsedStack::new("main;SLOAD".to_string(), 100)];
+        let head = vec![
+            CollapsedStack::new("main;SLOAD".to_string(), 300),
+            CollapsedStack::new("main;SSTORE".to_string(), 50),
+        ];
+        
+        assert_eq!(
+            diff_stacks_to_collapsed_format(&base, &head, WeightScale::Gas, None),
+            "main;SLOAD 100 300\nmain;SSTORE 0 50"
+        );
+        
+        let svg = generate_diff_flamegraph(&base, &head, None).unwrap();
+        assert!(svg.contains("SLOAD"));
+        assert!(svg.contains("+57.14%"));
+        assert!(generate_diff_flamegraph(&base, &[], None).is_err());
+    }
+
+    #[test]
+    fn test_truncate_stacks_folds_deep_frames() {
+        let stacks = vec![
+            CollapsedStack::new("main;a;b;c".to_string(), 10),
+            CollapsedStack::new("main;a;b;d".to_string(), 5),
+            CollapsedStack::new("main;x".to_string(), 7),
+        ];
+        
+        let truncated = truncate_stacks(&stacks, 2);
+        
+        assert_eq!(truncated.len(), 2);
+        assert_eq!(truncated[0].stack, "main;a");
+        assert_eq!(truncated[0].weight, 15);
+        assert_eq!(truncated[1].stack, "main;x");
+        assert_eq!(truncated[1].weight, 7);
+    }
+
     #[test]
     fn test_generate_flamegraph_empty_stacks() {
         let stacks: Vec<CollapsedStack> = vec![];
@@ -290,6 +947,34 @@ mod tests {
         assert!(svg.contains("main"));
     }
 
+    #[test]
+    fn test_flamegraph_stats_counts_narrow_stacks() {
+        let mut stacks = vec![CollapsedStack::new("main;big".to_string(), 1_000_000)];
+        for i in 0..50 {
+            stacks.push(CollapsedStack::new(format!("main;tiny_{}", i), 1));
+        }
+        
+        let (svg, stats) = generate_flamegraph_with_stats(&stacks, None).unwrap();
+        
+        assert!(svg.contains("<svg"));
+        assert_eq!(stats.total_stacks, 51);
+        assert_eq!(stats.hidden_stacks, 50);
+        assert_eq!(stats.hidden_gas, 50);
+    }
+
+    #[test]
+    fn test_flamegraph_stats_nothing_hidden() {
+        let stacks = vec![
+            CollapsedStack::new("main;a".to_string(), 500),
+            CollapsedStack::new("main;b".to_string(), 500),
+        ];
+        
+        let stats = FlamegraphStats::compute(&stacks, &FlamegraphConfig::default());
+        
+        assert_eq!(stats.hidden_stacks, 0);
+        assert_eq!(stats.hidden_percentage(), 0.0);
+    }
+
     #[test]
     fn test_flamegraph_config_builder() {
         let config = FlamegraphConfig::new()
@@ -298,6 +983,8 @@ mod tests {
             .with_width(1600);
         
         assert_eq!(config.title, "Custom Title");
+        assert!(!config.has_default_title());
+        assert!(!FlamegraphConfig::new().with_title(DEFAULT_FLAMEGRAPH_TITLE).has_default_title());
         assert!(matches!(config.palette, FlamegraphPalette::Mem));
         assert_eq!(config.image_width, Some(1600));
     }
@@ -312,7 +999,7 @@ mod tests {
         
         let summary = generate_text_summary(&stacks, 2);
         
-        assert!(summary.contains("5000"));
+        assert!(summary.contains("5,000"));
         assert!(summary.contains("main;execute"));
         assert!(summary.contains("and 1 more stacks"));
     }
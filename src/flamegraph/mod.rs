@@ -7,8 +7,16 @@ pub mod generator;
 
 // Re-export main types
 pub use generator::{
+    abbreviate_hex,
+    generate_diff_flamegraph,
     generate_flamegraph,
+    generate_flamegraph_with_stats,
+    generate_hostio_summary,
     generate_text_summary,
     FlamegraphConfig,
+    DEFAULT_FLAMEGRAPH_TITLE, DEFAULT_OTHER_FRAME_COLOR,
+    MIN_TRIM_HEX_LEN,
     FlamegraphPalette,
+    FlamegraphStats,
+    WeightScale,
 };
\ No newline at end of file
@@ -15,13 +15,29 @@
 //! stylus-trace --help
 //! ```
 //!
+//! ## Library Usage
+//!
+//! The [`Pipeline`] builder runs the whole flow and returns data:
+//!
+//! ```ignore
+//! let (profile, svg) = Pipeline::new()
+//!     .rpc("http://localhost:8547")
+//!     .tx("0xabc123...")
+//!     .flamegraph(FlamegraphConfig::default())
+//!     .run()?;
+//! ```
+//!
 //! For full documentation and examples, see:
 //! https://github.com/CreativesOnchain/Stylus-Trace
 
-mod aggregator;
-mod commands;
-mod flamegraph;
-mod output;
-mod parser;
-mod rpc;
-mod utils;
+pub mod aggregator;
+pub mod commands;
+pub mod diff;
+pub mod flamegraph;
+pub mod output;
+pub mod parser;
+pub mod pipeline;
+pub mod rpc;
+pub mod utils;
+
+pub use pipeline::Pipeline;
@@ -3,23 +3,29 @@
 //! A performance profiling tool for Arbitrum Stylus transactions.
 //! Generates flamegraphs and detailed profiles from transaction traces.
 
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{Context, Result};
+use log::warn;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use env_logger::Env;
-use log::error;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-mod aggregator;
-mod commands;
-mod flamegraph;
-mod output;
-mod parser;
-mod rpc;
-mod utils;
-
-use commands::{execute_capture, validate_args, CaptureArgs};
-use flamegraph::{FlamegraphConfig, FlamegraphPalette};
-use utils::config::SCHEMA_VERSION;
+use stylus_trace_studio::aggregator::{AggregateBy, GroupRule, StackBuildOptions, StackWeight, DEFAULT_MAX_DEPTH};
+use stylus_trace_studio::commands::{
+    execute_capture, execute_compare, execute_diff, execute_hostio, execute_lint, execute_range, execute_render,
+    execute_watch, resolve_tx_hash, validate_args, validate_range_args, validate_watch_args, CaptureArgs,
+    CompareArgs, DiffArgs, HostioArgs, LintArgs, RangeArgs, RenderArgs, WatchArgs,
+};
+#[cfg(feature = "server")]
+use stylus_trace_studio::commands::{execute_serve, ServeArgs};
+use stylus_trace_studio::flamegraph::{FlamegraphConfig, FlamegraphPalette, WeightScale};
+use stylus_trace_studio::output::{FoldedSort, OutputFormat};
+use stylus_trace_studio::parser::{FieldDescription, HostIoRules, SourceMap, TraceFormat, PROFILE_FIELDS};
+use stylus_trace_studio::rpc::TraceConfig;
+use stylus_trace_studio::utils::config::{FileConfig, RPC_ENV_VAR, SCHEMA_VERSION};
+use stylus_trace_studio::utils::{format_gas, AbiSelectors, SymbolTable};
 
 /// Stylus Trace Studio - Performance profiling for Arbitrum Stylus
 #[derive(Parser, Debug)]
@@ -33,20 +39,53 @@ struct Cli {
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     verbose: bool,
+    
+    /// Config file with option defaults (default: ./stylus-trace.toml or
+    /// ~/.config/stylus-trace.toml)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
 }
 
 /// Available commands
+///
+/// `Capture` carries every capture flag inline, so it is much larger than
+/// the other variants; it is parsed once per run, so boxing buys nothing.
 #[derive(Subcommand, Debug)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Capture and profile a transaction
     Capture {
         /// RPC endpoint URL
-        #[arg(short, long, default_value = "http://localhost:8547")]
+        #[arg(short, long, env = RPC_ENV_VAR, hide_env_values = true, default_value = "http://localhost:8547")]
         rpc: String,
         
         /// Transaction hash to profile
-        #[arg(short, long)]
-        tx: String,
+        #[arg(short, long, required_unless_present = "block", conflicts_with = "block")]
+        tx: Option<String>,
+        
+        /// Block containing the transaction to profile (use with --index)
+        #[arg(long, requires = "index")]
+        block: Option<u64>,
+        
+        /// Position of the transaction within --block (zero-based)
+        #[arg(long, requires = "block")]
+        index: Option<usize>,
+        
+        /// Read the raw trace JSON from a file instead of the RPC ("-" for stdin)
+        #[arg(long)]
+        trace_file: Option<PathBuf>,
+        
+        /// Wait until the transaction has N confirmations before tracing
+        #[arg(long, conflicts_with = "trace_file")]
+        confirmations: Option<u64>,
+        
+        /// Seconds to wait for --confirmations before failing (0 = fail at once)
+        #[arg(long, default_value = "0", requires = "confirmations")]
+        confirmation_timeout: u64,
+        
+        /// Trace format: auto (detect by shape), stylus or cast (`cast run --json`)
+        #[arg(long, default_value = "auto", value_parser = parse_trace_format)]
+        trace_format: TraceFormat,
         
         /// Output path for JSON profile
         #[arg(short, long, default_value = "profile.json")]
@@ -56,15 +95,28 @@ enum Commands {
         #[arg(short, long)]
         flamegraph: Option<PathBuf>,
         
-        /// Number of top hot paths to include
+        /// Zoom the flamegraph to the subtree under this frame
+        #[arg(long)]
+        focus: Option<String>,
+        
+        /// Artifacts to write, named from --output as base (json,svg,folded,speedscope,dot);
+        /// --flamegraph still adds the SVG at its own path
+        #[arg(long, value_delimiter = ',', value_parser = parse_output_format)]
+        formats: Vec<OutputFormat>,
+        
+        /// Number of top hot paths to include (0 = all paths)
         #[arg(long, default_value = "20")]
         top_paths: usize,
         
-        /// Flamegraph title
+        /// Decimal places for hot path percentages (tiny paths keep two significant digits)
+        #[arg(long, default_value = "2")]
+        percent_precision: u32,
+        
+        /// Flamegraph title (default: abbreviated tx hash and total gas)
         #[arg(long)]
         title: Option<String>,
         
-        /// Flamegraph color palette (hot, mem, io, java, consistent)
+        /// Flamegraph color palette (hot, mem, io, java, consistent, auto)
         #[arg(long, default_value = "hot")]
         palette: String,
         
@@ -72,9 +124,424 @@ enum Commands {
         #[arg(long, default_value = "1200")]
         width: usize,
         
+        /// Unit for flamegraph weights (gas, kgas, mgas)
+        #[arg(long, default_value = "gas")]
+        weight_scale: String,
+        
+        /// Render at most this many frames per stack (deeper frames are folded)
+        #[arg(long)]
+        max_render_depth: Option<usize>,
+        
+        /// Leave stacks under this much gas out of the flamegraph
+        #[arg(long)]
+        min_gas: Option<u64>,
+        
+        /// Abbreviate 0x hex strings longer than N chars in flamegraph frames
+        /// (0x1234…cdef); the JSON keeps full values
+        #[arg(long)]
+        trim_hex: Option<usize>,
+        
+        /// Merge small stacks until the flamegraph and folded/speedscope/dot
+        /// outputs have at most N (reports the threshold used)
+        #[arg(long)]
+        max_frames: Option<usize>,
+        
+        /// Unit label for flamegraph tooltips (defaults to the weight scale unit)
+        #[arg(long)]
+        count_name: Option<String>,
+        
         /// Print text summary to stdout
         #[arg(long)]
         summary: bool,
+        
+        /// Add the N costliest HostIO types (calls and gas) to the summary
+        #[arg(long, requires = "summary")]
+        top_hostio: Option<usize>,
+        
+        /// Add a short explanation of each hot path to the summary
+        #[arg(long, requires = "summary")]
+        explain: bool,
+        
+        /// Write minified JSON instead of pretty-printed
+        #[arg(long)]
+        compact_json: bool,
+        
+        /// Fold consecutive identical opcodes into `op (×N)` frames
+        #[arg(long)]
+        coalesce_runs: bool,
+        
+        /// Record the raw trace's SHA-256 (and file path) in the profile
+        #[arg(long)]
+        embed_raw: bool,
+        
+        /// Embed the full raw trace in the profile (can be very large)
+        #[arg(long)]
+        embed_raw_full: bool,
+        
+        /// Store every collapsed stack in the profile (for render/diff without RPC)
+        #[arg(long)]
+        full_stacks: bool,
+        
+        /// Store a nested {name, gas, children} call tree in the profile
+        #[arg(long)]
+        call_tree: bool,
+        
+        /// Keep geth stack/memory/storage per step and record them for hot steps
+        /// (not allowed with --redact)
+        #[arg(long)]
+        capture_state: bool,
+        
+        /// Ask the node to leave storage out of the trace (smaller, gas-only)
+        #[arg(long)]
+        disable_storage: bool,
+        
+        /// Ask the node to leave memory out of the trace (smaller, gas-only)
+        #[arg(long)]
+        disable_memory: bool,
+        
+        /// Ask the node to leave the stack out of the trace (smaller, gas-only)
+        #[arg(long)]
+        disable_stack: bool,
+        
+        /// Fetch the receipt and record L1 data vs L2 execution gas
+        #[arg(long)]
+        gas_breakdown: bool,
+        
+        /// Fetch the transaction and record its calldata and entry selector
+        #[arg(long)]
+        calldata: bool,
+        
+        /// Solidity JSON ABI (or build artifact) naming the calldata's entry function
+        #[arg(long, requires = "calldata")]
+        abi: Option<PathBuf>,
+        
+        /// Leave synthetic HostIO stacks out of the flamegraph (summary only)
+        #[arg(long)]
+        no_hostio_frames: bool,
+        
+        /// Fold all HostIO gas into a single `hostio` frame
+        #[arg(long)]
+        collapse_hostio: bool,
+        
+        /// Drop zero-gas frames (e.g. JUMPDEST) instead of keeping them as zero-weight stacks
+        #[arg(long)]
+        drop_zero_gas: bool,
+        
+        /// Name steps without function/op as raw `op@0xpc` frames instead of "unknown"
+        #[arg(long)]
+        keep_raw_ops: bool,
+        
+        /// Fold the lightest stacks into "other" beyond this many unique stacks
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        max_unique_stacks: Option<u64>,
+        
+        /// Clamp call depths claimed by the trace to this many levels
+        #[arg(long, default_value_t = DEFAULT_MAX_DEPTH as u64, value_parser = clap::value_parser!(u64).range(1..))]
+        max_call_depth: u64,
+        
+        /// Separator between frames in folded output (names containing it are escaped)
+        #[arg(long, default_value = ";", value_parser = parse_frame_separator)]
+        frame_separator: String,
+        
+        /// Folded output line order: weight (heaviest first) or name (stable diffs)
+        #[arg(long, default_value = "weight", value_parser = parse_folded_sort)]
+        folded_sort: FoldedSort,
+        
+        /// What becomes a frame: auto (symbol/function/op), pc, op, function or category
+        #[arg(long, default_value = "auto", value_parser = parse_aggregate_by)]
+        aggregate_by: AggregateBy,
+        
+        /// Rendered/folded stack weight: gas (default) or steps (instruction count); the profile stays in gas
+        #[arg(long, default_value = "gas", value_parser = parse_stack_weight)]
+        weight: StackWeight,
+        
+        /// Demangle Rust symbols in function names (`--demangle false` to disable)
+        #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+        demangle: bool,
+        
+        /// Source map JSON (pc ranges → file/line/function) for frame labels
+        #[arg(long)]
+        source_map: Option<PathBuf>,
+        
+        /// Symbols JSON naming frames by pc range or 4-byte selector
+        #[arg(long)]
+        symbols: Option<PathBuf>,
+        
+        /// Rewrite frame names before aggregation ('pattern=>replacement', repeatable)
+        #[arg(long = "group-regex", value_parser = parse_group_rule)]
+        group_regex: Vec<GroupRule>,
+        
+        /// Baseline profile JSON to compare against
+        #[arg(long)]
+        baseline: Option<PathBuf>,
+        
+        /// Fail if total gas rose more than this over the baseline (e.g. "5%")
+        #[arg(long, value_parser = parse_percentage)]
+        fail_on_regression: Option<f64>,
+        
+        /// Overwrite the baseline with the new profile if the check passes
+        #[arg(long)]
+        update_baseline: bool,
+        
+        /// Write a differential flamegraph (baseline vs this capture) to this path
+        #[arg(long, alias = "compare-baseline-svg", requires = "baseline")]
+        diff_svg: Option<PathBuf>,
+        
+        /// Replace addresses and hashes with stable pseudonyms in all outputs
+        #[arg(long)]
+        redact: bool,
+        
+        /// Warn about HostIO events priced outside their known gas range
+        #[arg(long)]
+        check_hostio_costs: bool,
+        
+        /// JSON rules mapping HostIO name regexes to types
+        #[arg(long)]
+        hostio_rules: Option<PathBuf>,
+        
+        /// Reject traces whose gas remaining rises within a call frame
+        #[arg(long)]
+        strict_validate: bool,
+        
+        /// Fail if the trace has no execution steps (tracer didn't run?)
+        #[arg(long)]
+        require_steps: bool,
+        
+        /// Error if the RPC returns a trace for a different transaction hash
+        #[arg(long)]
+        strict_hash_check: bool,
+        
+        /// Print how long each capture phase took (to stderr)
+        #[arg(long)]
+        timings: bool,
+        
+        /// Scenario name stored in the profile (e.g. "cold cache"), used by compare
+        #[arg(long)]
+        label: Option<String>,
+        
+        /// Write a JSON index of the files produced (paths, sizes, hashes)
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+        
+        /// Write one CSV row per leaf frame: frame,total_gas,self_gas,count,percentage
+        #[arg(long)]
+        frames_csv: Option<PathBuf>,
+    },
+    
+    /// Print the HostIO breakdown only (fast: no stacks or flamegraph)
+    Hostio {
+        /// RPC endpoint URL
+        #[arg(short, long, env = RPC_ENV_VAR, hide_env_values = true, default_value = "http://localhost:8547")]
+        rpc: String,
+        
+        /// Transaction hash to inspect
+        #[arg(short, long)]
+        tx: String,
+        
+        /// Read the raw trace JSON from a file instead of the RPC ("-" for stdin)
+        #[arg(long)]
+        trace_file: Option<PathBuf>,
+        
+        /// JSON rules mapping HostIO name regexes to types
+        #[arg(long)]
+        hostio_rules: Option<PathBuf>,
+    },
+    
+    /// Flag gas anti-patterns: repeated storage loads, no-op stores, calls in loops
+    Lint {
+        /// Raw trace JSON, or a profile captured with --embed-raw-full ("-" for stdin)
+        #[arg(short, long)]
+        file: PathBuf,
+    },
+    
+    /// Profile new transactions to a contract as they land (Ctrl-C to stop)
+    Watch {
+        /// RPC endpoint URL
+        #[arg(short, long, env = RPC_ENV_VAR, hide_env_values = true, default_value = "http://localhost:8547")]
+        rpc: String,
+        
+        /// Contract address to watch
+        #[arg(short, long)]
+        address: String,
+        
+        /// Directory for per-transaction profiles (<tx_hash>.json/.svg)
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+        
+        /// Seconds between polls for new blocks
+        #[arg(long, default_value = "2")]
+        poll_interval: u64,
+        
+        /// First block to scan (defaults to the next new block)
+        #[arg(long)]
+        from_block: Option<u64>,
+        
+        /// Also write a flamegraph SVG per transaction
+        #[arg(long)]
+        flamegraph: bool,
+        
+        /// Number of top hot paths to include (0 = all paths)
+        #[arg(long, default_value = "20")]
+        top_paths: usize,
+        
+        /// Keep a cumulative.folded (and .svg) aggregate across all transactions
+        #[arg(long)]
+        cumulative: bool,
+        
+        /// Also write cumulative.sources.json: top transactions behind each stack
+        #[arg(long, requires = "cumulative")]
+        track_sources: bool,
+    },
+    
+    /// Profile a contract's transactions in a block range (found via eth_getLogs)
+    Range {
+        /// RPC endpoint URL
+        #[arg(short, long, env = RPC_ENV_VAR, hide_env_values = true, default_value = "http://localhost:8547")]
+        rpc: String,
+        
+        /// Contract address whose transactions are profiled
+        #[arg(short, long)]
+        address: String,
+        
+        /// First block of the range (inclusive)
+        #[arg(long)]
+        from_block: u64,
+        
+        /// Last block of the range (inclusive)
+        #[arg(long)]
+        to_block: u64,
+        
+        /// Directory for per-transaction profiles and the aggregate
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+        
+        /// Also write flamegraph SVGs (per transaction and aggregate)
+        #[arg(long)]
+        flamegraph: bool,
+        
+        /// Number of top hot paths to include (0 = all paths)
+        #[arg(long, default_value = "20")]
+        top_paths: usize,
+    },
+    
+    /// Serve profiles and flamegraphs over HTTP (requires the `server` feature)
+    #[cfg(feature = "server")]
+    Serve {
+        /// Port to listen on
+        #[arg(short, long, default_value = "8080")]
+        port: u16,
+        
+        /// Interface to bind (use 0.0.0.0 to accept remote connections)
+        #[arg(long, default_value = "127.0.0.1")]
+        host: std::net::IpAddr,
+        
+        /// RPC endpoint used when a request does not name one
+        #[arg(short, long, env = RPC_ENV_VAR, hide_env_values = true, default_value = "http://localhost:8547")]
+        rpc: String,
+        
+        /// Another RPC endpoint requests may name with `rpc` (repeatable)
+        #[arg(long = "allow-rpc")]
+        allow_rpc: Vec<String>,
+        
+        /// Number of top hot paths to include (0 = all paths)
+        #[arg(long, default_value = "20")]
+        top_paths: usize,
+        
+        /// Maximum number of profiles computed at once
+        #[arg(long, default_value = "4")]
+        max_concurrent: usize,
+        
+        /// Per-request timeout in seconds
+        #[arg(long, default_value = "60")]
+        timeout: u64,
+    },
+    
+    /// Compare two profile JSON files
+    Diff {
+        /// Reference profile
+        base: PathBuf,
+        
+        /// Profile to compare against the reference
+        head: PathBuf,
+        
+        /// Compare each path's share of its own total gas instead of absolute gas
+        #[arg(long)]
+        normalize: bool,
+        
+        /// Compare per instruction (profiles captured with --aggregate-by pc)
+        #[arg(long)]
+        by_pc: bool,
+        
+        /// Maximum number of changed paths to print
+        #[arg(long, default_value = "20")]
+        top: usize,
+        
+        /// Only show hot paths present in head but absent from base
+        #[arg(long)]
+        only_new: bool,
+        
+        /// With --only-new, skip new paths under this much gas
+        #[arg(long, default_value = "0", requires = "only_new")]
+        min_gas: u64,
+    },
+    
+    /// Compare several profiles side by side, keyed by their --label
+    Compare {
+        /// Profiles to compare (the first is the reference)
+        #[arg(long, num_args = 2.., required = true)]
+        inputs: Vec<PathBuf>,
+        
+        /// Maximum number of hot paths to print
+        #[arg(long, default_value = "20")]
+        top: usize,
+    },
+    
+    /// Render a flamegraph from a stored profile or folded stacks file (no RPC)
+    Render {
+        /// Profile JSON captured with --full-stacks, or a folded stacks file
+        #[arg(short, long)]
+        file: PathBuf,
+        
+        /// Output path for flamegraph SVG
+        #[arg(long, default_value = "flamegraph.svg")]
+        flamegraph: PathBuf,
+        
+        /// Flamegraph title
+        #[arg(long)]
+        title: Option<String>,
+        
+        /// Flamegraph color palette (hot, mem, io, java, consistent, auto)
+        #[arg(long, default_value = "hot")]
+        palette: String,
+        
+        /// Flamegraph width in pixels
+        #[arg(long, default_value = "1200")]
+        width: usize,
+        
+        /// Unit for flamegraph weights (gas, kgas, mgas)
+        #[arg(long, default_value = "gas")]
+        weight_scale: String,
+        
+        /// Render at most this many frames per stack (deeper frames are folded)
+        #[arg(long)]
+        max_render_depth: Option<usize>,
+        
+        /// Leave stacks under this much gas out of the flamegraph
+        #[arg(long)]
+        min_gas: Option<u64>,
+        
+        /// Abbreviate 0x hex strings longer than N chars in flamegraph frames
+        /// (0x1234…cdef); the JSON keeps full values
+        #[arg(long)]
+        trim_hex: Option<usize>,
+        
+        /// Unit label for flamegraph tooltips (defaults to the weight scale unit)
+        #[arg(long)]
+        count_name: Option<String>,
+        
+        /// Render only the subtree rooted at this frame
+        #[arg(long)]
+        focus: Option<String>,
     },
     
     /// Validate a profile JSON file
@@ -89,6 +556,10 @@ enum Commands {
         /// Show full schema details
         #[arg(long)]
         show: bool,
+        
+        /// Print the field descriptions as JSON
+        #[arg(long)]
+        json: bool,
     },
     
     /// Display version information
@@ -96,44 +567,151 @@ enum Commands {
 }
 
 fn main() -> Result<()> {
-    // Parse CLI arguments
-    let cli = Cli::parse();
+    // Parse CLI arguments, with config file values as defaults
+    let file_config = load_file_config(config_path_from_args(std::env::args()))?;
+    let matches = apply_file_defaults(Cli::command(), &file_config).get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
     
     // Setup logging
     let log_level = if cli.verbose { "debug" } else { "info" };
     env_logger::Builder::from_env(Env::default().default_filter_or(log_level)).init();
+    check_file_config(&file_config, &matches)?;
     
     // Execute command
     match cli.command {
         Commands::Capture {
             rpc,
             tx,
+            block,
+            index,
+            trace_file,
+            confirmations,
+            confirmation_timeout,
+            trace_format,
             output,
             flamegraph,
+            focus,
+            formats,
             top_paths,
+            percent_precision,
             title,
             palette,
             width,
+            weight_scale,
+            count_name,
+            max_render_depth,
+            min_gas,
+            trim_hex,
+            max_frames,
             summary,
+            top_hostio,
+            explain,
+            compact_json,
+            coalesce_runs,
+            embed_raw,
+            embed_raw_full,
+            full_stacks,
+            call_tree,
+            capture_state,
+            disable_storage,
+            disable_memory,
+            disable_stack,
+            gas_breakdown,
+            calldata,
+            abi,
+            no_hostio_frames,
+            collapse_hostio,
+            drop_zero_gas,
+            keep_raw_ops,
+            max_unique_stacks,
+            max_call_depth,
+            aggregate_by,
+            weight,
+            frame_separator,
+            folded_sort,
+            demangle,
+            source_map,
+            symbols,
+            group_regex,
+            baseline,
+            fail_on_regression,
+            update_baseline,
+            diff_svg,
+            redact,
+            check_hostio_costs,
+            hostio_rules,
+            strict_validate,
+            require_steps,
+            strict_hash_check,
+            timings,
+            label,
+            manifest,
+            frames_csv,
         } => {
             // Parse palette
             let palette_enum = parse_palette(&palette);
             
             // Create flamegraph config
-            let fg_config = if flamegraph.is_some() {
-                let mut config = FlamegraphConfig::new();
-                
-                if let Some(title_str) = title {
-                    config = config.with_title(title_str);
+            let wants_svg = flamegraph.is_some() || formats.contains(&OutputFormat::Svg);
+            let fg_config = if wants_svg {
+                let mut config = build_flamegraph_config(
+                    title,
+                    palette_enum,
+                    width,
+                    &weight_scale,
+                    count_name.or_else(|| {
+                        (weight != StackWeight::Gas).then(|| weight.unit_name().to_string())
+                    }),
+                    max_render_depth,
+                    min_gas,
+                );
+                if let Some(max_len) = trim_hex {
+                    config = config.with_trim_hex(max_len);
                 }
-                
-                config = config.with_palette(palette_enum).with_width(width);
-                
                 Some(config)
             } else {
                 None
             };
             
+            let mut stack_options = StackBuildOptions::new()
+                .with_coalesce_runs(coalesce_runs)
+                .with_collapse_hostio(collapse_hostio)
+                .with_hostio_frames(!no_hostio_frames)
+                .with_include_zero_gas(!drop_zero_gas)
+                .with_keep_raw_ops(keep_raw_ops)
+                .with_max_depth(max_call_depth as usize)
+                .with_aggregate_by(aggregate_by)
+                .with_weight(weight)
+                .with_frame_separator(frame_separator)
+                .with_demangle(demangle)
+                .with_group_rules(group_regex);
+            if let Some(max) = max_unique_stacks {
+                stack_options = stack_options.with_max_unique_stacks(max as usize);
+            }
+            if let Some(path) = source_map {
+                let map = SourceMap::from_file(&path)
+                    .with_context(|| format!("Failed to load source map {}", path.display()))?;
+                stack_options = stack_options.with_source_map(map);
+            }
+            let abi = abi
+                .map(|path| {
+                    AbiSelectors::from_file(&path)
+                        .with_context(|| format!("Failed to load ABI {}", path.display()))
+                })
+                .transpose()?;
+            if let Some(path) = symbols {
+                let table = SymbolTable::from_file(&path)
+                    .with_context(|| format!("Failed to load symbols {}", path.display()))?;
+                stack_options = stack_options.with_symbols(table);
+            }
+            
+            // Resolve --block/--index to a hash (clap guarantees one form)
+            let tx = match (tx, block, index) {
+                (Some(tx), _, _) => tx,
+                (None, Some(block), Some(index)) => resolve_tx_hash(&rpc, block, index)?,
+                _ => anyhow::bail!("Either --tx or --block with --index is required"),
+            };
+            
             // Create capture args
             let args = CaptureArgs {
                 rpc_url: rpc,
@@ -141,9 +719,49 @@ fn main() -> Result<()> {
                 output_json: output,
                 output_svg: flamegraph,
                 top_paths,
+                percent_precision,
                 flamegraph_config: fg_config,
+                stack_options,
+                max_frames,
+                folded_sort,
                 print_summary: summary,
+                top_hostio,
+                explain,
+                compact_json,
                 tracer: None,  // FIXED: Use default opcode tracer
+                trace_config: TraceConfig {
+                    disable_storage,
+                    disable_memory,
+                    disable_stack,
+                },
+                baseline,
+                fail_on_regression,
+                update_baseline,
+                diff_svg,
+                redact,
+                formats,
+                check_hostio_costs,
+                trace_file,
+                confirmations,
+                confirmation_timeout: Duration::from_secs(confirmation_timeout),
+                trace_format,
+                gas_breakdown,
+                calldata,
+                abi,
+                capture_state,
+                embed_raw,
+                embed_raw_full,
+                focus,
+                full_stacks,
+                call_tree,
+                hostio_rules: hostio_rules.map(load_hostio_rules).transpose()?,
+                strict_validate,
+                require_steps,
+                strict_hash_check,
+                timings,
+                label,
+                manifest,
+                frames_csv,
             };
             
             // Validate args first
@@ -153,12 +771,130 @@ fn main() -> Result<()> {
             execute_capture(args)?;
         }
         
+        Commands::Hostio { rpc, tx, trace_file, hostio_rules } => {
+            execute_hostio(HostioArgs {
+                rpc_url: rpc,
+                transaction_hash: tx,
+                trace_file,
+                tracer: None,
+                hostio_rules: hostio_rules.map(load_hostio_rules).transpose()?,
+            })?;
+        }
+        
+        Commands::Lint { file } => {
+            execute_lint(LintArgs { file })?;
+        }
+        
+        Commands::Watch {
+            rpc,
+            address,
+            out_dir,
+            poll_interval,
+            from_block,
+            flamegraph,
+            top_paths,
+            cumulative,
+            track_sources,
+        } => {
+            let args = WatchArgs {
+                rpc_url: rpc,
+                address,
+                out_dir,
+                poll_interval: Duration::from_secs(poll_interval),
+                from_block,
+                top_paths,
+                flamegraph_config: flamegraph.then(FlamegraphConfig::new),
+                cumulative,
+                track_sources,
+                ..Default::default()
+            };
+            
+            validate_watch_args(&args)?;
+            
+            // Finish the transaction in progress on Ctrl-C, then exit
+            let stop = Arc::new(AtomicBool::new(false));
+            let handler_stop = Arc::clone(&stop);
+            ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst))
+                .context("Failed to install Ctrl-C handler")?;
+            
+            execute_watch(args, &stop)?;
+        }
+        
+        Commands::Range { rpc, address, from_block, to_block, out_dir, flamegraph, top_paths } => {
+            let args = RangeArgs {
+                rpc_url: rpc,
+                address,
+                from_block,
+                to_block,
+                out_dir,
+                top_paths,
+                flamegraph_config: flamegraph.then(FlamegraphConfig::new),
+                ..Default::default()
+            };
+            
+            validate_range_args(&args)?;
+            execute_range(args)?;
+        }
+        
+        #[cfg(feature = "server")]
+        Commands::Serve { port, host, rpc, allow_rpc, top_paths, max_concurrent, timeout } => {
+            execute_serve(ServeArgs {
+                listen: std::net::SocketAddr::new(host, port),
+                rpc_url: rpc,
+                allowed_rpcs: allow_rpc,
+                top_paths,
+                max_concurrent,
+                request_timeout: Duration::from_secs(timeout),
+            })?;
+        }
+        
+        Commands::Diff { base, head, normalize, by_pc, top, only_new, min_gas } => {
+            execute_diff(DiffArgs { base, head, normalize, by_pc, top, only_new, min_gas })?;
+        }
+        
+        Commands::Compare { inputs, top } => {
+            execute_compare(CompareArgs { inputs, top })?;
+        }
+        
+        Commands::Render {
+            file,
+            flamegraph,
+            title,
+            palette,
+            width,
+            weight_scale,
+            max_render_depth,
+            min_gas,
+            trim_hex,
+            count_name,
+            focus,
+        } => {
+            let mut config = build_flamegraph_config(
+                title,
+                parse_palette(&palette),
+                width,
+                &weight_scale,
+                count_name,
+                max_render_depth,
+                min_gas,
+            );
+            if let Some(max_len) = trim_hex {
+                config = config.with_trim_hex(max_len);
+            }
+            execute_render(RenderArgs {
+                input: file,
+                output_svg: flamegraph,
+                flamegraph_config: Some(config),
+                focus,
+            })?;
+        }
+        
         Commands::Validate { file } => {
             validate_profile_file(file)?;
         }
         
-        Commands::Schema { show } => {
-            display_schema(show);
+        Commands::Schema { show, json } => {
+            display_schema(show, json)?;
         }
         
         Commands::Version => {
@@ -169,6 +905,115 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Find the `--config` value before clap runs
+///
+/// **Private** - the file must be read before clap applies defaults
+fn config_path_from_args(args: impl IntoIterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// Load the explicit or discovered config file (empty if there is none)
+///
+/// **Private** - internal helper
+fn load_file_config(explicit: Option<PathBuf>) -> Result<FileConfig> {
+    match explicit.or_else(FileConfig::discover) {
+        Some(path) => FileConfig::from_file(&path)
+            .with_context(|| format!("Failed to load config file {}", path.display())),
+        None => Ok(FileConfig::default()),
+    }
+}
+
+/// Install config file values as defaults on every subcommand that has the option
+///
+/// **Private** - explicit flags still win because clap only falls back to
+/// defaults for options that were not given
+fn apply_file_defaults(mut command: clap::Command, file_config: &FileConfig) -> clap::Command {
+    for (id, value) in file_config.defaults() {
+        let names: Vec<String> = command
+            .get_subcommands()
+            .filter(|sub| sub.get_arguments().any(|arg| arg.get_id() == id))
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+        for name in names {
+            command = command.mut_subcommand(name, |sub| sub.mut_arg(id, |arg| arg.default_value(value.clone())));
+        }
+    }
+    command
+}
+
+/// Fail on config file keys the chosen subcommand reads but that could
+/// not be used; warn about the rest
+///
+/// **Private** - a bad `width` should not break `validate`
+fn check_file_config(file_config: &FileConfig, matches: &clap::ArgMatches) -> Result<()> {
+    let sub_matches = matches.subcommand().map(|(_, sub)| sub);
+    
+    for (key, reason) in &file_config.invalid {
+        let read_by_command = sub_matches.is_some_and(|sub| sub.try_get_raw(key).is_ok());
+        if read_by_command {
+            anyhow::bail!("Config file key `{}` is invalid: {}", key, reason);
+        }
+        warn!("Ignoring config file key `{}`: {}", key, reason);
+    }
+    
+    Ok(())
+}
+
+/// Load a `--hostio-rules` file
+///
+/// **Private** - internal helper
+fn load_hostio_rules(path: PathBuf) -> Result<HostIoRules> {
+    HostIoRules::from_file(&path)
+        .with_context(|| format!("Failed to load HostIO rules {}", path.display()))
+}
+
+/// Build the flamegraph config shared by capture and render
+///
+/// **Private** - internal helper
+fn build_flamegraph_config(
+    title: Option<String>,
+    palette: FlamegraphPalette,
+    width: usize,
+    weight_scale: &str,
+    count_name: Option<String>,
+    max_render_depth: Option<usize>,
+    min_gas: Option<u64>,
+) -> FlamegraphConfig {
+    let mut config = FlamegraphConfig::new();
+    
+    if let Some(title_str) = title {
+        config = config.with_title(title_str);
+    }
+    
+    config = config
+        .with_palette(palette)
+        .with_width(width)
+        .with_weight_scale(parse_weight_scale(weight_scale));
+    
+    if let Some(name) = count_name {
+        config = config.with_count_name(name);
+    }
+    
+    if let Some(depth) = max_render_depth {
+        config = config.with_max_depth(depth);
+    }
+    
+    if let Some(min_gas) = min_gas {
+        config = config.with_min_gas(min_gas);
+    }
+    
+    config
+}
+
 /// Parse palette string to enum
 ///
 /// **Private** - internal helper
@@ -179,6 +1024,7 @@ fn parse_palette(palette_str: &str) -> FlamegraphPalette {
         "io" => FlamegraphPalette::Io,
         "java" => FlamegraphPalette::Java,
         "consistent" => FlamegraphPalette::Consistent,
+        "auto" => FlamegraphPalette::Auto,
         _ => {
             eprintln!("Warning: Unknown palette '{}', using 'hot'", palette_str);
             FlamegraphPalette::Hot
@@ -186,11 +1032,98 @@ fn parse_palette(palette_str: &str) -> FlamegraphPalette {
     }
 }
 
+/// Parse a percentage like "5%" or "5"
+///
+/// **Private** - clap value parser
+fn parse_percentage(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .parse::<f64>()
+        .map_err(|e| format!("invalid percentage '{}': {}", value, e))
+}
+
+/// Parse a `--group-regex` rule
+///
+/// **Private** - clap value parser
+fn parse_group_rule(value: &str) -> Result<GroupRule, String> {
+    GroupRule::parse(value).map_err(|e| e.to_string())
+}
+
+/// Parse a `--formats` entry
+///
+/// **Private** - clap value parser
+fn parse_output_format(value: &str) -> Result<OutputFormat, String> {
+    OutputFormat::parse(value).ok_or_else(|| {
+        format!("unknown format '{}' (expected json, svg, folded, speedscope, dot)", value)
+    })
+}
+
+/// Parse an `--aggregate-by` key
+///
+/// **Private** - clap value parser
+fn parse_aggregate_by(value: &str) -> Result<AggregateBy, String> {
+    AggregateBy::parse(value).ok_or_else(|| {
+        format!("unknown aggregation key '{}' (expected auto, pc, op, function, category)", value)
+    })
+}
+
+/// Parse a `--weight` name
+///
+/// **Private** - clap value parser
+fn parse_stack_weight(value: &str) -> Result<StackWeight, String> {
+    StackWeight::parse(value).ok_or_else(|| format!("unknown weight '{}' (expected gas, steps)", value))
+}
+
+/// Parse a `--trace-format` name
+///
+/// **Private** - clap value parser
+fn parse_trace_format(value: &str) -> Result<TraceFormat, String> {
+    TraceFormat::parse(value).ok_or_else(|| {
+        format!("unknown trace format '{}' (expected auto, stylus, cast)", value)
+    })
+}
+
+/// Parse a `--folded-sort` name
+///
+/// **Private** - clap value parser
+fn parse_folded_sort(value: &str) -> Result<FoldedSort, String> {
+    FoldedSort::parse(value).ok_or_else(|| {
+        format!("unknown folded sort '{}' (expected weight, name)", value)
+    })
+}
+
+/// Parse a `--frame-separator` value
+///
+/// **Private** - clap value parser; whitespace would clash with the weight
+/// column and `%` with the escape syntax
+fn parse_frame_separator(value: &str) -> Result<String, String> {
+    if value.is_empty() || value.contains(char::is_whitespace) || value.contains('%') {
+        return Err(format!("invalid frame separator '{}' (must be non-empty, without spaces or '%')", value));
+    }
+    Ok(value.to_string())
+}
+
+/// Parse weight scale string to enum
+///
+/// **Private** - internal helper
+fn parse_weight_scale(scale_str: &str) -> WeightScale {
+    match scale_str.to_lowercase().as_str() {
+        "gas" => WeightScale::Gas,
+        "kgas" => WeightScale::KGas,
+        "mgas" => WeightScale::MGas,
+        _ => {
+            eprintln!("Warning: Unknown weight scale '{}', using 'gas'", scale_str);
+            WeightScale::Gas
+        }
+    }
+}
+
 /// Validate a profile JSON file
 ///
 /// **Private** - internal command implementation
 fn validate_profile_file(file_path: PathBuf) -> Result<()> {
-    use output::read_profile;
+    use stylus_trace_studio::output::read_profile;
     
     println!("Validating profile: {}", file_path.display());
     
@@ -199,9 +1132,47 @@ fn validate_profile_file(file_path: PathBuf) -> Result<()> {
     println!("✓ Valid profile JSON");
     println!("  Version: {}", profile.version);
     println!("  Transaction: {}", profile.transaction_hash);
-    println!("  Total Gas: {}", profile.total_gas);
+    if !profile.fingerprint.is_empty() {
+        println!("  Fingerprint: {}", profile.fingerprint);
+    }
+    println!("  Total Gas: {}", format_gas(profile.total_gas));
     println!("  HostIO Calls: {}", profile.hostio_summary.total_calls);
+    if let Some(ratio) = profile.hostio_gas_excess() {
+        println!(
+            "  ⚠ HostIO gas ({}) exceeds total gas by {:.2}x - likely an ink/gas unit mistake",
+            format_gas(profile.hostio_summary.total_hostio_gas),
+            ratio
+        );
+    }
     println!("  Hot Paths: {}", profile.hot_paths.len());
+    println!("  Gas/Step: {:.2}", profile.metrics.gas_per_step);
+    println!("  Gas/HostIO Call: {:.2}", profile.metrics.gas_per_hostio_call);
+    println!(
+        "  Unattributed Gas: {} ({:.1}%)",
+        format_gas(profile.unattributed.total_gas), profile.unattributed.percentage
+    );
+    for (op, gas) in &profile.unattributed.ops {
+        println!("    {}: {}", op, format_gas(*gas));
+    }
+    if let Some(breakdown) = &profile.gas_breakdown {
+        println!("  L1 Gas: {}", format_gas(breakdown.l1_gas));
+        println!("  L2 Gas: {}", format_gas(breakdown.l2_gas));
+    }
+    if let Some(calldata) = &profile.calldata {
+        println!(
+            "  Entry: {} {}",
+            calldata.selector.as_deref().unwrap_or("(no selector)"),
+            calldata.function.as_deref().unwrap_or("")
+        );
+    }
+    if let Some(accounting) = &profile.gas_accounting {
+        println!(
+            "  Gas Refund: {} (gross {}, net {})",
+            format_gas(accounting.refund),
+            format_gas(accounting.gross_gas),
+            format_gas(accounting.net_gas)
+        );
+    }
     
     Ok(())
 }
@@ -209,29 +1180,51 @@ fn validate_profile_file(file_path: PathBuf) -> Result<()> {
 /// Display schema information
 ///
 /// **Private** - internal command implementation
-fn display_schema(show_details: bool) {
+///
+/// With `json`, prints the field table as data for editors and UIs.
+fn display_schema(show_details: bool, json: bool) -> Result<()> {
+    if json {
+        #[derive(serde::Serialize)]
+        struct SchemaDescription {
+            version: &'static str,
+            fields: &'static [FieldDescription],
+        }
+        
+        let schema = SchemaDescription { version: SCHEMA_VERSION, fields: PROFILE_FIELDS };
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        return Ok(());
+    }
+    
     println!("Stylus Trace Studio Profile Schema");
     println!("Current Version: {}", SCHEMA_VERSION);
     println!();
     
     if show_details {
         println!("Schema Structure:");
-        println!("  version: string          - Schema version (e.g., '1.0.0')");
-        println!("  transaction_hash: string - Transaction hash");
-        println!("  total_gas: number        - Total gas used");
-        println!("  hostio_summary: object   - HostIO event statistics");
-        println!("    total_calls: number    - Total HostIO calls");
-        println!("    by_type: object        - Breakdown by HostIO type");
-        println!("    total_hostio_gas: number - Gas consumed by HostIO");
-        println!("  hot_paths: array         - Top gas-consuming execution paths");
-        println!("    stack: string          - Stack trace");
-        println!("    gas: number            - Gas consumed");
-        println!("    percentage: number     - Percentage of total gas");
-        println!("    source_hint: object?   - Source location (if available)");
-        println!("  generated_at: string     - ISO 8601 timestamp");
+        print_fields(PROFILE_FIELDS, 1);
     } else {
         println!("Use --show for detailed schema information");
     }
+    
+    Ok(())
+}
+
+/// Print a field table as an indented `name: type - description` tree
+///
+/// **Private** - internal helper for display_schema
+fn print_fields(fields: &[FieldDescription], level: usize) {
+    for field in fields {
+        let optional = if field.optional { "?" } else { "" };
+        let label = format!(
+            "{}{}: {}{}",
+            "  ".repeat(level),
+            field.name,
+            field.field_type,
+            optional
+        );
+        println!("{:<26} - {}", label, field.description);
+        print_fields(field.fields, level + 1);
+    }
 }
 
 /// Display version information
@@ -0,0 +1,171 @@
+//! Per-frame gas as a flat CSV table.
+//!
+//! Collapses stacks into one row per distinct leaf frame name so the numbers
+//! can be sorted and charted in a spreadsheet. Frames that only ever appear
+//! as callers get no row, so `self_gas` sums to the stack total. `total_gas`
+//! is inclusive (every stack passing through the frame, counted once per
+//! stack so recursion does not double-count) and `self_gas` is the gas of
+//! stacks ending in it.
+
+use super::folded::create_parent_dirs;
+use crate::aggregator::{CollapsedStack, DEFAULT_FRAME_SEPARATOR};
+use crate::utils::error::OutputError;
+use log::info;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// CSV header row
+const FRAMES_CSV_HEADER: &str = "frame,total_gas,self_gas,count,percentage";
+
+/// Gas attributed to one frame across all stacks
+///
+/// **Public** - returned by `frame_gas`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameGas {
+    /// Frame name
+    pub frame: String,
+
+    /// Gas of every stack containing the frame
+    pub total_gas: u64,
+
+    /// Gas of stacks where the frame is the leaf
+    pub self_gas: u64,
+
+    /// Number of stacks containing the frame
+    pub count: u64,
+
+    /// `total_gas` as a percentage of all stack gas
+    pub percentage: f64,
+}
+
+/// Aggregate collapsed stacks into per-frame totals
+///
+/// **Public** - useful for in-memory use and tests
+///
+/// # Returns
+/// One entry per distinct leaf frame, sorted by self gas, then total gas
+/// (both descending), then name
+pub fn frame_gas(stacks: &[CollapsedStack]) -> Vec<FrameGas> {
+    let mut frames: HashMap<&str, FrameGas> = HashMap::new();
+    let mut leaves: BTreeSet<&str> = BTreeSet::new();
+    let total: u64 = stacks.iter().map(|stack| stack.weight).sum();
+
+    for stack in stacks {
+        let path: Vec<&str> = stack.stack.split(DEFAULT_FRAME_SEPARATOR).collect();
+        let unique: BTreeSet<&str> = path.iter().copied().collect();
+
+        for name in unique {
+            let entry = frames.entry(name).or_insert_with(|| FrameGas {
+                frame: name.to_string(),
+                total_gas: 0,
+                self_gas: 0,
+                count: 0,
+                percentage: 0.0,
+            });
+            entry.total_gas += stack.weight;
+            entry.count += 1;
+        }
+
+        if let Some(&leaf) = path.last() {
+            leaves.insert(leaf);
+            if let Some(row) = frames.get_mut(leaf) {
+                row.self_gas += stack.weight;
+            }
+        }
+    }
+
+    let mut rows: Vec<FrameGas> = frames
+        .into_values()
+        .filter(|row| leaves.contains(row.frame.as_str()))
+        .map(|mut row| {
+            if total > 0 {
+                row.percentage = row.total_gas as f64 / total as f64 * 100.0;
+            }
+            row
+        })
+        .collect();
+
+    rows.sort_by(|a, b| {
+        b.self_gas
+            .cmp(&a.self_gas)
+            .then_with(|| b.total_gas.cmp(&a.total_gas))
+            .then_with(|| a.frame.cmp(&b.frame))
+    });
+
+    rows
+}
+
+/// Write per-frame gas as CSV
+///
+/// **Public** - main entry point for `--frames-csv`
+///
+/// # Arguments
+/// * `stacks` - Collapsed stacks from aggregator
+/// * `output_path` - Where to write the CSV file
+///
+/// # Errors
+/// * `OutputError::Io` - Directory creation or write failure
+pub fn write_frames_csv(
+    stacks: &[CollapsedStack],
+    output_path: impl AsRef<Path>,
+) -> Result<(), OutputError> {
+    let output_path = output_path.as_ref();
+
+    info!("Writing frame CSV to: {}", output_path.display());
+
+    create_parent_dirs(output_path)?;
+
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    writeln!(writer, "{}", FRAMES_CSV_HEADER)?;
+    for row in frame_gas(stacks) {
+        writeln!(
+            writer,
+            "{},{},{},{},{:.2}",
+            csv_field(&row.frame),
+            row.total_gas,
+            row.self_gas,
+            row.count,
+            row.percentage
+        )?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Quote a field containing commas, quotes or newlines (RFC 4180)
+///
+/// **Private** - frame names are often `fn(a, b)` signatures
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_gas_self_and_total() {
+        let stacks = vec![
+            CollapsedStack::new("main;execute".to_string(), 5000),
+            CollapsedStack::new("main;execute;execute".to_string(), 2000),
+            CollapsedStack::new("main;storage".to_string(), 3000),
+        ];
+
+        let rows = frame_gas(&stacks);
+
+        assert_eq!(rows[0].frame, "execute");
+        assert_eq!((rows[0].total_gas, rows[0].self_gas, rows[0].count), (7000, 7000, 2));
+        assert_eq!(rows[1].frame, "storage");
+        assert_eq!(rows[1].percentage, 30.0);
+        // `main` is never a leaf, so it gets no row
+        assert_eq!(rows.len(), 2);
+        assert_eq!(csv_field("f(a, \"b\")"), "\"f(a, \"\"b\"\")\"");
+    }
+}
@@ -0,0 +1,84 @@
+//! Graphviz DOT output for call graphs.
+//!
+//! Edges are labelled with gas and their pen width scales with the share
+//! of the heaviest edge. Self-edges (direct recursion) are drawn dashed.
+
+use crate::aggregator::CallEdge;
+use crate::utils::error::OutputError;
+use super::folded::create_parent_dirs;
+use log::info;
+use std::path::Path;
+
+/// Pen width for the heaviest edge; lighter edges scale down to 1.0
+const MAX_PENWIDTH: f64 = 8.0;
+
+/// Render a call graph as a DOT document
+///
+/// **Public** - usable without touching the filesystem
+pub fn to_dot(graph: &[CallEdge]) -> String {
+    let max_gas = graph.iter().map(|edge| edge.gas).max().unwrap_or(0).max(1);
+
+    let mut dot = String::from("digraph calls {\n    node [shape=box];\n");
+    for edge in graph {
+        let penwidth = 1.0 + (MAX_PENWIDTH - 1.0) * edge.gas as f64 / max_gas as f64;
+        let style = if edge.is_self_edge() { ", style=dashed" } else { "" };
+        dot.push_str(&format!(
+            "    {} -> {} [label=\"{}\", penwidth={:.2}{}];\n",
+            quote(&edge.caller),
+            quote(&edge.callee),
+            edge.gas,
+            penwidth,
+            style
+        ));
+    }
+    dot.push_str("}\n");
+
+    dot
+}
+
+/// Write a call graph as a Graphviz DOT file
+///
+/// **Public** - main entry point for DOT output
+///
+/// # Arguments
+/// * `graph` - Edges from `aggregator::build_call_graph`
+/// * `output_path` - Path to output file
+///
+/// # Returns
+/// Ok if file written successfully
+pub fn write_dot(graph: &[CallEdge], output_path: impl AsRef<Path>) -> Result<(), OutputError> {
+    let output_path = output_path.as_ref();
+
+    info!("Writing call graph to: {}", output_path.display());
+
+    create_parent_dirs(output_path)?;
+    std::fs::write(output_path, to_dot(graph))?;
+
+    info!("Call graph written successfully ({} edges)", graph.len());
+
+    Ok(())
+}
+
+/// Quote a frame name as a DOT identifier
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let graph = vec![
+            CallEdge { caller: "main".to_string(), callee: "transfer".to_string(), gas: 100 },
+            CallEdge { caller: "f\"x".to_string(), callee: "f\"x".to_string(), gas: 50 },
+        ];
+
+        let dot = to_dot(&graph);
+
+        assert!(dot.starts_with("digraph calls {"));
+        assert!(dot.contains("\"main\" -> \"transfer\" [label=\"100\", penwidth=8.00];"));
+        assert!(dot.contains("\"f\\\"x\" -> \"f\\\"x\" [label=\"50\", penwidth=4.50, style=dashed];"));
+    }
+}
@@ -0,0 +1,151 @@
+//! Folded (collapsed stack) output writer.
+//!
+//! Writes one `stack weight` line per collapsed stack, the format
+//! consumed by inferno, flamegraph.pl, and most flamegraph tooling.
+//! Lines are heaviest first by default; sorting by stack name instead
+//! keeps line positions stable, so committed folded files diff cleanly.
+
+use crate::aggregator::{CollapsedStack, DEFAULT_FRAME_SEPARATOR};
+use crate::utils::error::OutputError;
+use log::info;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Line order of a folded file
+///
+/// **Public** - selected with `--folded-sort`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FoldedSort {
+    /// Heaviest stack first (the order stacks are built in)
+    #[default]
+    Weight,
+    
+    /// Lexicographic by stack string, stable across captures
+    Name,
+}
+
+impl FoldedSort {
+    /// Parse a CLI name (`weight`, `name`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "weight" | "gas" => Some(Self::Weight),
+            "name" | "stack" => Some(Self::Name),
+            _ => None,
+        }
+    }
+}
+
+/// Write collapsed stacks to a folded file
+///
+/// **Public** - main entry point for folded output
+///
+/// # Arguments
+/// * `stacks` - Collapsed stacks from aggregator
+/// * `output_path` - Path to output file
+///
+/// # Returns
+/// Ok if file written successfully
+pub fn write_folded(stacks: &[CollapsedStack], output_path: impl AsRef<Path>) -> Result<(), OutputError> {
+    write_folded_with_separator(stacks, output_path, DEFAULT_FRAME_SEPARATOR)
+}
+
+/// Write collapsed stacks to a folded file, joining frames with `separator`
+///
+/// **Public** - for downstream tools expecting a delimiter other than `;`
+pub fn write_folded_with_separator(
+    stacks: &[CollapsedStack],
+    output_path: impl AsRef<Path>,
+    separator: &str,
+) -> Result<(), OutputError> {
+    write_folded_sorted(stacks, output_path, separator, FoldedSort::Weight)
+}
+
+/// Write collapsed stacks to a folded file in the given line order
+///
+/// **Public** - `FoldedSort::Weight` keeps `stacks` as given (they come
+/// weight-sorted from the stack builder)
+pub fn write_folded_sorted(
+    stacks: &[CollapsedStack],
+    output_path: impl AsRef<Path>,
+    separator: &str,
+    sort: FoldedSort,
+) -> Result<(), OutputError> {
+    let output_path = output_path.as_ref();
+    
+    info!("Writing folded stacks to: {}", output_path.display());
+    
+    create_parent_dirs(output_path)?;
+    
+    let mut ordered: Vec<&CollapsedStack> = stacks.iter().collect();
+    if sort == FoldedSort::Name {
+        ordered.sort_by(|a, b| a.stack.cmp(&b.stack));
+    }
+    
+    let mut writer = BufWriter::new(File::create(output_path)?);
+    for stack in ordered {
+        writeln!(writer, "{}", stack.to_line_with_separator(separator))?;
+    }
+    writer.flush()?;
+    
+    info!("Folded stacks written successfully ({} lines)", stacks.len());
+    
+    Ok(())
+}
+
+/// Create parent directories for an output path if needed
+///
+/// **Private** - shared by the folded and speedscope writers
+pub(crate) fn create_parent_dirs(output_path: &Path) -> Result<(), OutputError> {
+    if output_path.as_os_str().is_empty() {
+        return Err(OutputError::InvalidPath("Path is empty".to_string()));
+    }
+    
+    if let Some(parent) = output_path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| OutputError::InvalidPath(format!(
+                    "Cannot create directory {}: {}",
+                    parent.display(),
+                    e
+                )))?;
+        }
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_write_folded() {
+        let stacks = vec![
+            CollapsedStack::new("main;execute".to_string(), 5000),
+            CollapsedStack::new("main;storage".to_string(), 3000),
+        ];
+        let temp_file = NamedTempFile::new().unwrap();
+        
+        write_folded(&stacks, temp_file.path()).unwrap();
+        
+        let content = std::fs::read_to_string(temp_file.path()).unwrap();
+        assert_eq!(content, "main;execute 5000\nmain;storage 3000\n");
+    }
+
+    #[test]
+    fn test_write_folded_sorted_by_name() {
+        let stacks = vec![
+            CollapsedStack::new("main;storage".to_string(), 5000),
+            CollapsedStack::new("main;execute".to_string(), 3000),
+        ];
+        let temp_file = NamedTempFile::new().unwrap();
+        
+        write_folded_sorted(&stacks, temp_file.path(), ";", FoldedSort::Name).unwrap();
+        
+        let content = std::fs::read_to_string(temp_file.path()).unwrap();
+        assert_eq!(content, "main;execute 3000\nmain;storage 5000\n");
+        assert_eq!(FoldedSort::parse("Name"), Some(FoldedSort::Name));
+    }
+}
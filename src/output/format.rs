@@ -0,0 +1,93 @@
+//! Output artifact formats selectable with `--formats`.
+
+use std::path::{Path, PathBuf};
+
+/// An artifact the capture command can produce
+///
+/// **Public** - parsed from the `--formats` CLI list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// JSON profile (`.json`)
+    Json,
+    
+    /// SVG flamegraph (`.svg`)
+    Svg,
+    
+    /// Collapsed/folded stacks, one `stack weight` line each (`.folded`)
+    Folded,
+    
+    /// Speedscope sampled profile (`.speedscope.json`)
+    Speedscope,
+    
+    /// Graphviz DOT call graph (`.dot`)
+    Dot,
+}
+
+impl OutputFormat {
+    /// Parse a format name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "svg" | "flamegraph" => Some(Self::Svg),
+            "folded" | "collapsed" => Some(Self::Folded),
+            "speedscope" => Some(Self::Speedscope),
+            "dot" | "callgraph" => Some(Self::Dot),
+            _ => None,
+        }
+    }
+    
+    /// Canonical lowercase name, as accepted by `parse`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Svg => "svg",
+            Self::Folded => "folded",
+            Self::Speedscope => "speedscope",
+            Self::Dot => "dot",
+        }
+    }
+    
+    /// File extension (without leading dot) for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Svg => "svg",
+            Self::Folded => "folded",
+            Self::Speedscope => "speedscope.json",
+            Self::Dot => "dot",
+        }
+    }
+    
+    /// Derive this format's output path from a base path
+    ///
+    /// The base path's extension (if any) is replaced, so `out/profile.json`
+    /// becomes `out/profile.svg`, `out/profile.folded`, and so on.
+    pub fn path_from_base(&self, base: &Path) -> PathBuf {
+        base.with_extension(self.extension())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formats() {
+        assert_eq!(OutputFormat::parse("JSON"), Some(OutputFormat::Json));
+        assert_eq!(OutputFormat::parse(" svg "), Some(OutputFormat::Svg));
+        assert_eq!(OutputFormat::parse("folded"), Some(OutputFormat::Folded));
+        assert_eq!(OutputFormat::parse("speedscope"), Some(OutputFormat::Speedscope));
+        assert_eq!(OutputFormat::parse("dot"), Some(OutputFormat::Dot));
+        assert_eq!(OutputFormat::parse("pdf"), None);
+    }
+
+    #[test]
+    fn test_path_from_base() {
+        let base = Path::new("out/profile.json");
+        assert_eq!(OutputFormat::Svg.path_from_base(base), PathBuf::from("out/profile.svg"));
+        assert_eq!(
+            OutputFormat::Speedscope.path_from_base(base),
+            PathBuf::from("out/profile.speedscope.json")
+        );
+    }
+}
@@ -3,12 +3,37 @@
 //! Writes Profile structs to JSON files with proper formatting.
 
 use crate::parser::schema::Profile;
+use crate::utils::config::SCHEMA_VERSION;
 use crate::utils::error::OutputError;
-use log::{debug, info};
+use log::{debug, info, warn};
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::Path;
 
+/// Options controlling how profiles are serialized to JSON
+///
+/// **Public** - passed to `write_profile_with_opts`
+#[derive(Debug, Clone, Copy)]
+pub struct JsonWriteOptions {
+    /// Pretty-print with indentation (true) or emit minified JSON (false)
+    pub pretty: bool,
+}
+
+impl Default for JsonWriteOptions {
+    fn default() -> Self {
+        Self { pretty: true }
+    }
+}
+
+impl JsonWriteOptions {
+    /// Options for minified output (archival, CI artifacts)
+    ///
+    /// **Public** - convenience constructor
+    pub fn compact() -> Self {
+        Self { pretty: false }
+    }
+}
+
 /// Write a profile to a JSON file
 ///
 /// **Public** - main entry point for JSON output
@@ -31,40 +56,7 @@ use std::path::Path;
 /// write_profile(&profile, "profile.json")?;
 /// ```
 pub fn write_profile(profile: &Profile, output_path: impl AsRef<Path>) -> Result<(), OutputError> {
-    let output_path = output_path.as_ref();
-    
-    info!("Writing profile to: {}", output_path.display());
-    
-    // Validate path
-    validate_output_path(output_path)?;
-    
-    // Create parent directories if needed
-    if let Some(parent) = output_path.parent() {
-        if !parent.exists() {
-            debug!("Creating parent directories: {}", parent.display());
-            std::fs::create_dir_all(parent)
-                .map_err(|e| OutputError::InvalidPath(format!(
-                    "Cannot create directory {}: {}",
-                    parent.display(),
-                    e
-                )))?;
-        }
-    }
-    
-    // Open file for writing
-    let file = File::create(output_path)
-        .map_err(|e| OutputError::WriteFailed(e))?;
-    
-    let writer = BufWriter::new(file);
-    
-    // Serialize to JSON with pretty printing
-    serde_json::to_writer_pretty(writer, profile)
-        .map_err(|e| OutputError::SerializationFailed(e))?;
-    
-    info!("Profile written successfully ({} bytes)", 
-          calculate_file_size(output_path));
-    
-    Ok(())
+    write_profile_with_opts(profile, output_path, JsonWriteOptions::default())
 }
 
 /// Write profile as compact JSON (no formatting)
@@ -80,31 +72,61 @@ pub fn write_profile(profile: &Profile, output_path: impl AsRef<Path>) -> Result
 pub fn write_profile_compact(
     profile: &Profile,
     output_path: impl AsRef<Path>,
+) -> Result<(), OutputError> {
+    write_profile_with_opts(profile, output_path, JsonWriteOptions::compact())
+}
+
+/// Write a profile to a JSON file with explicit formatting options
+///
+/// **Public** - used by the capture command to honor `--compact-json`
+///
+/// # Arguments
+/// * `profile` - Profile data to write
+/// * `output_path` - Path to output JSON file
+/// * `opts` - Serialization options (pretty vs compact)
+///
+/// # Returns
+/// Ok if file written successfully
+pub fn write_profile_with_opts(
+    profile: &Profile,
+    output_path: impl AsRef<Path>,
+    opts: JsonWriteOptions,
 ) -> Result<(), OutputError> {
     let output_path = output_path.as_ref();
     
-    info!("Writing compact profile to: {}", output_path.display());
+    info!("Writing {} profile to: {}",
+          if opts.pretty { "pretty" } else { "compact" },
+          output_path.display());
     
+    // Validate path
     validate_output_path(output_path)?;
     
     // Create parent directories if needed
     if let Some(parent) = output_path.parent() {
         if !parent.exists() {
+            debug!("Creating parent directories: {}", parent.display());
             std::fs::create_dir_all(parent)
                 .map_err(|e| OutputError::InvalidPath(format!(
-                    "Cannot create directory: {}",
+                    "Cannot create directory {}: {}",
+                    parent.display(),
                     e
                 )))?;
         }
     }
     
-    let file = File::create(output_path)?;
+    // Open file for writing
+    let file = File::create(output_path)
+        .map_err(OutputError::WriteFailed)?;
+    
     let writer = BufWriter::new(file);
     
-    // Serialize without pretty printing (more compact)
-    serde_json::to_writer(writer, profile)?;
+    if opts.pretty {
+        serde_json::to_writer_pretty(writer, profile)?;
+    } else {
+        serde_json::to_writer(writer, profile)?;
+    }
     
-    info!("Compact profile written successfully ({} bytes)",
+    info!("Profile written successfully ({} bytes)", 
           calculate_file_size(output_path));
     
     Ok(())
@@ -174,6 +196,7 @@ fn calculate_file_size(path: &Path) -> u64 {
 /// # Errors
 /// * `OutputError::WriteFailed` - File read error (reusing WriteFailed for I/O)
 /// * `OutputError::SerializationFailed` - JSON parse error
+/// * `OutputError::UnsupportedVersion` - See `check_schema_version`
 pub fn read_profile(input_path: impl AsRef<Path>) -> Result<Profile, OutputError> {
     let input_path = input_path.as_ref();
     
@@ -189,20 +212,70 @@ pub fn read_profile(input_path: impl AsRef<Path>) -> Result<Profile, OutputError
            profile.version, 
            profile.transaction_hash);
     
+    check_schema_version(&profile.version)?;
+    
     Ok(profile)
 }
 
+/// Check a profile's schema version against `SCHEMA_VERSION`
+///
+/// **Public** - applied by `read_profile`
+///
+/// Same major version with a different minor/patch loads with a warning,
+/// since fields may have been added or defaulted.
+///
+/// # Errors
+/// * `OutputError::UnsupportedVersion` - Different major version (fields
+///   may be renamed) or a version that is not `major.minor.patch`
+pub fn check_schema_version(version: &str) -> Result<(), OutputError> {
+    let current = parse_schema_version(SCHEMA_VERSION)
+        .expect("SCHEMA_VERSION is major.minor.patch");
+    let found = parse_schema_version(version).ok_or_else(|| {
+        OutputError::UnsupportedVersion(format!("'{}' is not a major.minor.patch version", version))
+    })?;
+    
+    if found.0 != current.0 {
+        return Err(OutputError::UnsupportedVersion(format!(
+            "{} (this build reads {}.x.x)",
+            version, current.0
+        )));
+    }
+    
+    if found != current {
+        let relation = if found < current { "older" } else { "newer" };
+        warn!(
+            "Profile schema {} is {} than {}; missing fields use defaults",
+            version, relation, SCHEMA_VERSION
+        );
+    }
+    
+    Ok(())
+}
+
+/// Parse `major.minor.patch`
+///
+/// **Private** - internal helper
+fn parse_schema_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.trim().split('.').map(|part| part.parse::<u64>().ok());
+    let parsed = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(parsed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::schema::{Profile, HostIoSummary, HotPath};
+    use crate::aggregator::CollapsedStack;
     use std::collections::HashMap;
     use tempfile::NamedTempFile;
 
     fn create_test_profile() -> Profile {
         Profile {
             version: "1.0.0".to_string(),
+            fingerprint: String::new(),
             transaction_hash: "0xtest123".to_string(),
+            contract_address: None,
+            label: None,
             total_gas: 100000,
             hostio_summary: HostIoSummary {
                 total_calls: 10,
@@ -218,6 +291,15 @@ mod tests {
                 }
             ],
             generated_at: "2024-01-01T00:00:00Z".to_string(),
+            metrics: Default::default(),
+            unattributed: Default::default(),
+            gas_breakdown: None,
+            hot_steps: Vec::new(),
+            source: None,
+            collapsed_stacks: Vec::new(),
+            call_tree: None,
+            gas_accounting: None,
+            calldata: None,
         }
     }
 
@@ -257,6 +339,38 @@ mod tests {
         assert!(compact_size < pretty_size);
     }
 
+    #[test]
+    fn test_pretty_and_compact_round_trip() {
+        let profile = create_test_profile();
+        
+        for opts in [JsonWriteOptions::default(), JsonWriteOptions::compact()] {
+            let temp_file = NamedTempFile::new().unwrap();
+            write_profile_with_opts(&profile, temp_file.path(), opts).unwrap();
+            
+            let contents = std::fs::read_to_string(temp_file.path()).unwrap();
+            assert_eq!(contents.contains('\n'), opts.pretty);
+            
+            let loaded = read_profile(temp_file.path()).unwrap();
+            assert_eq!(loaded.transaction_hash, profile.transaction_hash);
+            assert_eq!(loaded.total_gas, profile.total_gas);
+            assert_eq!(loaded.hot_paths.len(), profile.hot_paths.len());
+        }
+    }
+
+    #[test]
+    fn test_collapsed_stacks_optional() {
+        let mut profile = create_test_profile();
+        assert!(!profile_to_string(&profile).unwrap().contains("collapsed_stacks"));
+        
+        profile.collapsed_stacks = vec![CollapsedStack::new("main;execute".to_string(), 50000)];
+        let temp_file = NamedTempFile::new().unwrap();
+        write_profile(&profile, temp_file.path()).unwrap();
+        
+        let loaded = read_profile(temp_file.path()).unwrap();
+        assert_eq!(loaded.collapsed_stacks.len(), 1);
+        assert_eq!(loaded.collapsed_stacks[0].weight, 50000);
+    }
+
     #[test]
     fn test_profile_to_string() {
         let profile = create_test_profile();
@@ -290,4 +404,42 @@ mod tests {
         
         assert!(nested_path.exists());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_check_schema_version() {
+        // Same
+        assert!(check_schema_version(SCHEMA_VERSION).is_ok());
+        // Older / newer within the same major load with a warning
+        assert!(check_schema_version("1.0.0").is_ok());
+        assert!(check_schema_version("1.7.3").is_ok());
+        // Different major
+        assert!(matches!(check_schema_version("2.0.0"), Err(OutputError::UnsupportedVersion(_))));
+        assert!(check_schema_version("0.9.0").is_err());
+        // Unknown
+        assert!(check_schema_version("").is_err());
+        assert!(check_schema_version("v1").is_err());
+        assert!(check_schema_version("1.0.0.1").is_err());
+    }
+
+    #[test]
+    fn test_read_profile_rejects_future_major() {
+        let mut profile = create_test_profile();
+        profile.version = "2.0.0".to_string();
+        let temp_file = NamedTempFile::new().unwrap();
+        write_profile(&profile, temp_file.path()).unwrap();
+        
+        assert!(matches!(read_profile(temp_file.path()), Err(OutputError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    fn test_read_profile_accepts_older_minor() {
+        let mut profile = create_test_profile();
+        profile.version = "1.0.0".to_string();
+        let temp_file = NamedTempFile::new().unwrap();
+        write_profile(&profile, temp_file.path()).unwrap();
+        
+        let loaded = read_profile(temp_file.path()).unwrap();
+        assert_eq!(loaded.version, "1.0.0");
+        assert_ne!(loaded.version, SCHEMA_VERSION);
+    }
+}
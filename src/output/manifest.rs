@@ -0,0 +1,136 @@
+//! Capture manifest writer.
+//!
+//! A `manifest.json` indexes every artifact one capture run wrote (path,
+//! format, size, content hash) alongside the transaction it profiles, so
+//! automation can read one file instead of globbing an output directory.
+
+use super::folded::create_parent_dirs;
+use super::format::OutputFormat;
+use crate::parser::Profile;
+use crate::utils::error::OutputError;
+use crate::utils::hash::sha256_hex;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Index of the artifacts written by one capture
+///
+/// **Public** - built by capture, written with `write_manifest`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Transaction that was profiled
+    pub transaction_hash: String,
+
+    /// Total gas of the transaction
+    pub total_gas: u64,
+
+    /// Profile fingerprint (see `compute_fingerprint`)
+    pub fingerprint: String,
+
+    /// Scenario name, if the capture was labelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// When the profile was generated (ISO 8601)
+    pub generated_at: String,
+
+    /// Files written, in write order
+    pub artifacts: Vec<ManifestArtifact>,
+}
+
+/// One file listed in a manifest
+///
+/// **Public** - entry of `Manifest::artifacts`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestArtifact {
+    /// Format name (`json`, `svg`, `folded`, `speedscope`, `dot`)
+    pub format: String,
+
+    /// Path as given on the command line
+    pub path: String,
+
+    /// File size in bytes
+    pub bytes: u64,
+
+    /// SHA-256 of the file contents
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Start an empty manifest describing `profile`
+    pub fn new(profile: &Profile) -> Self {
+        Self {
+            transaction_hash: profile.transaction_hash.clone(),
+            total_gas: profile.total_gas,
+            fingerprint: profile.fingerprint.clone(),
+            label: profile.label.clone(),
+            generated_at: profile.generated_at.clone(),
+            artifacts: Vec::new(),
+        }
+    }
+
+    /// Record a written file, reading back its size and hash
+    ///
+    /// # Errors
+    /// * `OutputError::WriteFailed` - The file cannot be read
+    pub fn add_artifact(&mut self, format: OutputFormat, path: &Path) -> Result<(), OutputError> {
+        let contents = std::fs::read(path)?;
+        self.artifacts.push(ManifestArtifact {
+            format: format.name().to_string(),
+            path: path.display().to_string(),
+            bytes: contents.len() as u64,
+            sha256: sha256_hex(&contents),
+        });
+        Ok(())
+    }
+}
+
+/// Write a manifest as pretty-printed JSON
+///
+/// **Public** - main entry point for manifest output
+///
+/// # Errors
+/// * `OutputError::InvalidPath` - Parent directory cannot be created
+/// * `OutputError::WriteFailed` / `SerializationFailed` - Write errors
+pub fn write_manifest(manifest: &Manifest, output_path: impl AsRef<Path>) -> Result<(), OutputError> {
+    let output_path = output_path.as_ref();
+
+    info!("Writing manifest to: {}", output_path.display());
+
+    create_parent_dirs(output_path)?;
+
+    let writer = BufWriter::new(File::create(output_path)?);
+    serde_json::to_writer_pretty(writer, manifest)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_lists_artifacts() {
+        let dir = tempfile::tempdir().unwrap();
+        let svg = dir.path().join("out.svg");
+        std::fs::write(&svg, "<svg/>").unwrap();
+
+        let raw = serde_json::json!({ "gasUsed": 100, "structLogs": [] });
+        let parsed = crate::parser::parse_trace("0xabc", &raw).unwrap();
+        let mut manifest = Manifest::new(&crate::parser::to_profile(&parsed, Vec::new()));
+        manifest.add_artifact(OutputFormat::Svg, &svg).unwrap();
+
+        let path = dir.path().join("nested/manifest.json");
+        write_manifest(&manifest, &path).unwrap();
+        let read: Manifest = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        assert_eq!(read.transaction_hash, "0xabc");
+        assert_eq!(read.total_gas, 100);
+        assert_eq!(read.artifacts[0].format, "svg");
+        assert_eq!(read.artifacts[0].bytes, 6);
+        assert_eq!(read.artifacts[0].sha256, sha256_hex(b"<svg/>"));
+        assert!(manifest.add_artifact(OutputFormat::Json, &dir.path().join("missing.json")).is_err());
+    }
+}
@@ -3,11 +3,32 @@
 //! This module handles writing data to disk in various formats:
 //! - JSON profiles (pretty and compact)
 //! - SVG flamegraphs
+//! - Folded stacks and speedscope profiles
+//! - Graphviz DOT call graphs
+//! - Per-frame gas CSV tables
+//! - Capture manifests indexing the files written
 //! - Text summaries
 
+pub mod csv;
+pub mod dot;
+pub mod folded;
+pub mod format;
 pub mod json;
+pub mod manifest;
+pub mod redact;
+pub mod speedscope;
 pub mod svg;
 
 // Re-export main functions
-pub use json::{write_profile, write_profile_compact, read_profile, profile_to_string};
+pub use csv::{frame_gas, write_frames_csv, FrameGas};
+pub use dot::{to_dot, write_dot};
+pub use folded::{write_folded, write_folded_sorted, write_folded_with_separator, FoldedSort};
+pub use format::OutputFormat;
+pub use json::{
+    write_profile, write_profile_compact, write_profile_with_opts, read_profile,
+    check_schema_version, profile_to_string, JsonWriteOptions,
+};
+pub use manifest::{write_manifest, Manifest, ManifestArtifact};
+pub use redact::Redactor;
+pub use speedscope::{to_speedscope, write_speedscope};
 pub use svg::{write_svg, write_svg_validated, read_svg, get_svg_info, SvgInfo};
\ No newline at end of file
@@ -0,0 +1,179 @@
+//! Redaction of addresses and hashes before sharing profiles.
+//!
+//! Replaces 20-byte addresses and 32-byte hashes with stable pseudonyms
+//! (`contract_1`, `hash_1`, `tx_1`). The mapping is built from the sorted
+//! set of values found, so it is deterministic for a given profile.
+
+use crate::aggregator::CollapsedStack;
+use crate::parser::schema::Profile;
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+/// Pseudonym used for the profiled transaction's own hash
+const TX_PSEUDONYM: &str = "tx_1";
+
+/// Pseudonym for a profiled contract that never appears in a frame name
+const TARGET_PSEUDONYM: &str = "contract_0";
+
+/// Matches 0x-prefixed hex runs (filtered to address/hash lengths by `is_redactable`)
+fn hex_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"0x[0-9a-fA-F]+").expect("valid regex"))
+}
+
+/// Whether a hex run is a 20-byte address or 32-byte hash
+fn is_redactable(hex: &str) -> bool {
+    matches!(hex.len(), 42 | 66)
+}
+
+/// Consistent address/hash → pseudonym mapping
+///
+/// **Public** - used by the capture command for `--redact`
+#[derive(Debug, Clone, Default)]
+pub struct Redactor {
+    mapping: HashMap<String, String>,
+}
+
+impl Redactor {
+    /// Build a mapping from every address/hash in the stacks plus the tx hash
+    ///
+    /// **Public** - constructor
+    pub fn from_stacks(stacks: &[CollapsedStack], transaction_hash: &str) -> Self {
+        let tx_key = normalize(transaction_hash);
+        
+        let found: BTreeSet<String> = stacks
+            .iter()
+            .flat_map(|s| hex_pattern().find_iter(&s.stack))
+            .filter(|m| is_redactable(m.as_str()))
+            .map(|m| normalize(m.as_str()))
+            .filter(|value| *value != tx_key)
+            .collect();
+        
+        let mut mapping = HashMap::new();
+        mapping.insert(tx_key, TX_PSEUDONYM.to_string());
+        
+        let (mut contracts, mut hashes) = (0, 0);
+        for value in found {
+            let pseudonym = if value.len() == 42 {
+                contracts += 1;
+                format!("contract_{}", contracts)
+            } else {
+                hashes += 1;
+                format!("hash_{}", hashes)
+            };
+            mapping.insert(value, pseudonym);
+        }
+        
+        Self { mapping }
+    }
+    
+    /// Replace every known address/hash in a string
+    ///
+    /// **Public** - applies the mapping to arbitrary text
+    pub fn redact_str(&self, text: &str) -> String {
+        hex_pattern()
+            .replace_all(text, |caps: &regex::Captures| {
+                let original = &caps[0];
+                if !is_redactable(original) {
+                    return original.to_string();
+                }
+                let value = normalize(original);
+                self.mapping.get(&value).cloned().unwrap_or(value)
+            })
+            .into_owned()
+    }
+    
+    /// Pseudonym of a transaction hash (`tx_1` for the profiled one)
+    ///
+    /// **Public** - used for the profile and the default flamegraph title
+    pub fn redact_tx_hash(&self, transaction_hash: &str) -> String {
+        self.mapping
+            .get(&normalize(transaction_hash))
+            .cloned()
+            .unwrap_or_else(|| TX_PSEUDONYM.to_string())
+    }
+    
+    /// Redact frame names in collapsed stacks
+    ///
+    /// **Public** - applied before flamegraph generation
+    pub fn redact_stacks(&self, stacks: &[CollapsedStack]) -> Vec<CollapsedStack> {
+        stacks
+            .iter()
+            .map(|s| CollapsedStack::new(self.redact_str(&s.stack), s.weight))
+            .collect()
+    }
+    
+    /// Redact the transaction hash, contract address and hot path stacks
+    /// of a profile
+    ///
+    /// **Public** - applied before writing the profile. Calldata arguments
+    /// embed addresses without a `0x` prefix, so only the selector of
+    /// captured calldata is kept.
+    pub fn redact_profile(&self, profile: &mut Profile) {
+        profile.transaction_hash = self.redact_tx_hash(&profile.transaction_hash);
+        
+        if let Some(address) = &mut profile.contract_address {
+            *address = self.mapping
+                .get(&normalize(address))
+                .cloned()
+                .unwrap_or_else(|| TARGET_PSEUDONYM.to_string());
+        }
+        
+        for path in &mut profile.hot_paths {
+            path.stack = self.redact_str(&path.stack);
+        }
+        
+        if let Some(calldata) = &mut profile.calldata {
+            calldata.input = calldata.selector.clone().unwrap_or_else(|| "0x".to_string());
+        }
+    }
+}
+
+/// Lowercase with 0x prefix so differently-cased inputs map the same
+fn normalize(value: &str) -> String {
+    let lower = value.to_lowercase();
+    if lower.starts_with("0x") {
+        lower
+    } else {
+        format!("0x{}", lower)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR_A: &str = "0x1111111111111111111111111111111111111111";
+    const ADDR_B: &str = "0x2222222222222222222222222222222222222222";
+    const TX: &str = "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+    #[test]
+    fn test_redactor_is_consistent() {
+        let stacks = vec![
+            CollapsedStack::new(format!("call_{};call_{}", ADDR_B, ADDR_A), 10),
+            CollapsedStack::new(format!("call_{}", ADDR_A.to_uppercase().replace("0X", "0x")), 5),
+        ];
+        
+        let redactor = Redactor::from_stacks(&stacks, TX);
+        let redacted = redactor.redact_stacks(&stacks);
+        
+        assert_eq!(redacted[0].stack, "call_contract_2;call_contract_1");
+        assert_eq!(redacted[1].stack, "call_contract_1");
+        assert_eq!(redacted[0].weight, 10);
+    }
+
+    #[test]
+    fn test_redact_tx_hash_everywhere() {
+        let stacks = vec![CollapsedStack::new(format!("tx;{}", TX), 1)];
+        let redactor = Redactor::from_stacks(&stacks, &TX[2..]);
+        
+        assert_eq!(redactor.redact_str(&stacks[0].stack), "tx;tx_1");
+    }
+
+    #[test]
+    fn test_non_hex_text_untouched() {
+        let redactor = Redactor::from_stacks(&[], TX);
+        assert_eq!(redactor.redact_str("main;SLOAD;0x1234"), "main;SLOAD;0x1234");
+    }
+}
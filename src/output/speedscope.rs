@@ -0,0 +1,105 @@
+//! Speedscope output writer.
+//!
+//! Converts collapsed stacks into a speedscope "sampled" profile
+//! (https://www.speedscope.app/file-format-schema.json), where each
+//! stack is one sample weighted by its gas.
+
+use super::folded::create_parent_dirs;
+use crate::aggregator::CollapsedStack;
+use crate::utils::error::OutputError;
+use log::info;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Speedscope file format schema URL
+const SPEEDSCOPE_SCHEMA: &str = "https://www.speedscope.app/file-format-schema.json";
+
+/// Build a speedscope document from collapsed stacks
+///
+/// **Public** - useful for in-memory use and tests
+///
+/// # Arguments
+/// * `stacks` - Collapsed stacks from aggregator
+/// * `name` - Profile name shown in speedscope
+pub fn to_speedscope(stacks: &[CollapsedStack], name: &str) -> serde_json::Value {
+    let mut frame_index: HashMap<&str, usize> = HashMap::new();
+    let mut frames = Vec::new();
+    let mut samples = Vec::with_capacity(stacks.len());
+    let mut weights = Vec::with_capacity(stacks.len());
+    
+    for stack in stacks {
+        let sample: Vec<usize> = stack
+            .stack
+            .split(';')
+            .map(|frame| {
+                *frame_index.entry(frame).or_insert_with(|| {
+                    frames.push(json!({ "name": frame }));
+                    frames.len() - 1
+                })
+            })
+            .collect();
+        samples.push(sample);
+        weights.push(stack.weight);
+    }
+    
+    let total: u64 = weights.iter().sum();
+    
+    json!({
+        "$schema": SPEEDSCOPE_SCHEMA,
+        "name": name,
+        "exporter": format!("stylus-trace-studio@{}", env!("CARGO_PKG_VERSION")),
+        "shared": { "frames": frames },
+        "profiles": [{
+            "type": "sampled",
+            "name": name,
+            "unit": "none",
+            "startValue": 0,
+            "endValue": total,
+            "samples": samples,
+            "weights": weights,
+        }],
+    })
+}
+
+/// Write collapsed stacks as a speedscope JSON file
+///
+/// **Public** - main entry point for speedscope output
+pub fn write_speedscope(
+    stacks: &[CollapsedStack],
+    name: &str,
+    output_path: impl AsRef<Path>,
+) -> Result<(), OutputError> {
+    let output_path = output_path.as_ref();
+    
+    info!("Writing speedscope profile to: {}", output_path.display());
+    
+    create_parent_dirs(output_path)?;
+    
+    let writer = BufWriter::new(File::create(output_path)?);
+    serde_json::to_writer(writer, &to_speedscope(stacks, name))?;
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_speedscope_shares_frames() {
+        let stacks = vec![
+            CollapsedStack::new("main;execute".to_string(), 5000),
+            CollapsedStack::new("main;storage".to_string(), 3000),
+        ];
+        
+        let doc = to_speedscope(&stacks, "0xtest");
+        
+        assert_eq!(doc["shared"]["frames"].as_array().unwrap().len(), 3);
+        assert_eq!(doc["profiles"][0]["samples"], json!([[0, 1], [0, 2]]));
+        assert_eq!(doc["profiles"][0]["weights"], json!([5000, 3000]));
+        assert_eq!(doc["profiles"][0]["endValue"], 8000);
+    }
+}
@@ -0,0 +1,392 @@
+//! Parsing of Foundry `cast run --json` call traces.
+//!
+//! cast records one node per call (an "arena" of nodes linked by child
+//! indices) rather than one step per opcode. Each call node becomes a
+//! single execution step at its call depth, weighted by its own gas
+//! (`gas_used` minus that of its direct children), so the stack builder
+//! produces one frame per call. Calls, creates and logs also feed the
+//! HostIO summary.
+//!
+//! Accepted shapes: `{"arena": [...]}`, `{"traces": [["Execution",
+//! {"arena": [...]}], ...]}` (one entry per trace kind) and a bare array
+//! of either.
+
+use super::hostio::{HostIoEvent, HostIoStats, HostIoType};
+use super::stylus_trace::{ExecutionStep, ParseOptions, ParsedTrace, UNKNOWN_CREATE_ADDRESS};
+use crate::aggregator::CREATE_FRAME_PREFIX;
+use crate::rpc::RawTraceData;
+use crate::utils::error::ParseError;
+use log::{debug, warn};
+use serde::Deserialize;
+
+/// Which parser a raw trace is handed to
+///
+/// **Public** - selected with `--trace-format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceFormat {
+    /// Detect from the JSON shape (default)
+    #[default]
+    Auto,
+
+    /// Nitro stylusTracer / geth structLogs
+    Stylus,
+
+    /// Foundry `cast run --json`
+    Cast,
+}
+
+impl TraceFormat {
+    /// Parse a CLI name (`auto`, `stylus`, `cast`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "stylus" | "geth" => Some(Self::Stylus),
+            "cast" | "foundry" => Some(Self::Cast),
+            _ => None,
+        }
+    }
+
+    /// Resolve `Auto` against a raw trace
+    pub fn resolve(self, raw_trace: &RawTraceData) -> Self {
+        match self {
+            Self::Auto if is_cast_trace_data(raw_trace) => Self::Cast,
+            Self::Auto => Self::Stylus,
+            other => other,
+        }
+    }
+}
+
+/// One call node in a cast trace arena
+///
+/// **Private** - only the fields mapped into frames are kept
+#[derive(Debug, Deserialize)]
+struct CastNode {
+    #[serde(default)]
+    children: Vec<usize>,
+
+    trace: CastCall,
+
+    #[serde(default)]
+    logs: Vec<serde_json::Value>,
+}
+
+/// The call itself
+///
+/// **Private** - `kind` is CALL, STATICCALL, DELEGATECALL, CREATE, ...
+#[derive(Debug, Deserialize)]
+struct CastCall {
+    #[serde(default)]
+    depth: u32,
+
+    #[serde(default)]
+    address: Option<String>,
+
+    #[serde(default)]
+    kind: Option<String>,
+
+    #[serde(default)]
+    gas_used: u64,
+
+    #[serde(default)]
+    decoded: Option<CastDecoded>,
+}
+
+/// Names cast resolved for the call (from its signature database)
+#[derive(Debug, Deserialize)]
+struct CastDecoded {
+    #[serde(default)]
+    label: Option<String>,
+
+    #[serde(default)]
+    call_data: Option<CastCallData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CastCallData {
+    #[serde(default)]
+    signature: Option<String>,
+}
+
+/// Whether a raw trace has the shape of `cast run --json` output
+///
+/// **Public** - used by `TraceFormat::Auto`
+pub fn is_cast_trace(raw_trace: &serde_json::Value) -> bool {
+    !arena_values(raw_trace).is_empty()
+}
+
+/// Whether a typed trace looks like `cast run --json` output
+///
+/// **Private** - `is_cast_trace` without converting back to JSON
+fn is_cast_trace_data(raw_trace: &RawTraceData) -> bool {
+    match raw_trace {
+        RawTraceData::Other(fields) => !object_arena_values(fields).is_empty(),
+        RawTraceData::Stylus(items) => items.iter().filter(|item| item.is_object()).any(is_cast_trace),
+        RawTraceData::StructLogs { .. } | RawTraceData::CallTree { .. } => false,
+    }
+}
+
+/// Parse a `cast run --json` trace
+///
+/// **Public** - counterpart of `parse_trace` for Foundry traces
+///
+/// # Errors
+/// * `ParseError::InvalidFormat` - No arena, or a node is malformed
+pub fn parse_cast_trace(tx_hash: &str, raw_trace: &serde_json::Value) -> Result<ParsedTrace, ParseError> {
+    parse_cast_trace_with_options(tx_hash, raw_trace, &ParseOptions::default())
+}
+
+/// Parse a `cast run --json` trace with explicit parse options
+///
+/// **Public** - like `parse_cast_trace`. HostIO rules classify call kinds
+/// (`CALL`, `STATICCALL`, ...) and `strict_validate` rejects calls whose
+/// children used more gas than they did. Cast traces carry no per-step
+/// state, so `capture_state` only logs a warning.
+///
+/// # Errors
+/// * `ParseError::InvalidFormat` - No arena, a node is malformed, or
+///   (strict) a call's children outspend it
+pub fn parse_cast_trace_with_options(
+    tx_hash: &str,
+    raw_trace: &serde_json::Value,
+    options: &ParseOptions,
+) -> Result<ParsedTrace, ParseError> {
+    if options.capture_state {
+        warn!("Cast traces carry no stack/memory/storage; --capture-state has no effect");
+    }
+
+    let arenas = arena_values(raw_trace);
+    if arenas.is_empty() {
+        return Err(ParseError::InvalidFormat(
+            "Not a cast trace: no `arena` of call nodes found".to_string()
+        ));
+    }
+
+    let mut execution_steps = Vec::new();
+    let mut hostio_stats = HostIoStats::new();
+    let mut total_gas_used = 0;
+    let mut contract_address = None;
+
+    for arena in arenas {
+        let nodes = Vec::<CastNode>::deserialize(arena)
+            .map_err(|e| ParseError::InvalidFormat(format!("Malformed cast arena: {}", e)))?;
+        let Some(root) = nodes.first() else {
+            continue;
+        };
+
+        total_gas_used += root.trace.gas_used;
+        if contract_address.is_none() {
+            contract_address = root.trace.address.as_deref().map(str::to_lowercase);
+        }
+        walk(&nodes, 0, options, &mut execution_steps, &mut hostio_stats)?;
+    }
+
+    debug!("Parsed cast trace: {} calls, {} gas", execution_steps.len(), total_gas_used);
+
+    Ok(ParsedTrace {
+        transaction_hash: tx_hash.to_string(),
+        total_gas_used,
+        execution_steps,
+        hostio_stats,
+        entry_selector: None,
+        contract_address,
+        gas_refund: None,
+    })
+}
+
+/// Emit a step for `index` and its subtree, in call order
+///
+/// **Private** - the root call is the transaction itself, so only nested
+/// calls count as HostIO
+fn walk(
+    nodes: &[CastNode],
+    index: usize,
+    options: &ParseOptions,
+    steps: &mut Vec<ExecutionStep>,
+    hostio: &mut HostIoStats,
+) -> Result<(), ParseError> {
+    let node = &nodes[index];
+    let children_gas: u64 = node
+        .children
+        .iter()
+        .map(|&child| nodes.get(child).map_or(0, |child| child.trace.gas_used))
+        .sum();
+    if options.strict_validate && children_gas > node.trace.gas_used {
+        return Err(ParseError::InvalidFormat(format!(
+            "cast node {} used {} gas but its calls used {}", index, node.trace.gas_used, children_gas
+        )));
+    }
+
+    let kind = node.trace.kind.as_deref().unwrap_or("CALL").to_uppercase();
+    steps.push(ExecutionStep {
+        gas_cost: node.trace.gas_used.saturating_sub(children_gas),
+        op: Some(kind.clone()),
+        depth: node.trace.depth + 1,
+        function: Some(frame_name(&node.trace, &kind)),
+        ..Default::default()
+    });
+
+    if index != 0 {
+        let io_type = options
+            .hostio_rules
+            .as_ref()
+            .and_then(|rules| rules.classify(&kind))
+            .unwrap_or_else(|| call_hostio_type(&kind));
+        hostio.add_event(HostIoEvent::new(io_type, node.trace.gas_used));
+    }
+    for _ in &node.logs {
+        hostio.add_event(HostIoEvent::new(HostIoType::Log, 0));
+    }
+
+    for &child in &node.children {
+        if child <= index || child >= nodes.len() {
+            return Err(ParseError::InvalidFormat(format!(
+                "cast node {} has invalid child index {}", index, child
+            )));
+        }
+        walk(nodes, child, options, steps, hostio)?;
+    }
+
+    Ok(())
+}
+
+/// Best name cast gives a call: signature, decoded label, then address
+///
+/// **Private** - creates are named `create:<address>` like constructor
+/// frames in opcode traces
+fn frame_name(call: &CastCall, kind: &str) -> String {
+    if kind.starts_with("CREATE") {
+        let address = call.address.as_deref().unwrap_or(UNKNOWN_CREATE_ADDRESS);
+        return format!("{}:{}", CREATE_FRAME_PREFIX, address.to_lowercase());
+    }
+
+    let decoded = call.decoded.as_ref();
+    decoded
+        .and_then(|decoded| decoded.call_data.as_ref()?.signature.clone())
+        .or_else(|| decoded?.label.clone())
+        .or_else(|| call.address.clone())
+        .unwrap_or_else(|| "call".to_string())
+}
+
+/// HostIO type of a nested call kind
+///
+/// **Private** - internal helper
+fn call_hostio_type(kind: &str) -> HostIoType {
+    match kind {
+        "STATICCALL" => HostIoType::StaticCall,
+        "DELEGATECALL" | "CALLCODE" => HostIoType::DelegateCall,
+        "CREATE" | "CREATE2" => HostIoType::Create,
+        _ => HostIoType::Call,
+    }
+}
+
+/// Every `arena` array in the accepted shapes
+///
+/// **Private** - empty if the value is not a cast trace
+fn arena_values(value: &serde_json::Value) -> Vec<&serde_json::Value> {
+    match value {
+        serde_json::Value::Object(obj) => object_arena_values(obj),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter(|item| item.is_object())
+            .flat_map(arena_values)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `arena_values` of a single object
+///
+/// **Private** - its own `arena`, else the arenas under `traces`
+fn object_arena_values(obj: &serde_json::Map<String, serde_json::Value>) -> Vec<&serde_json::Value> {
+    if let Some(arena) = obj.get("arena").filter(|arena| arena.is_array()) {
+        return vec![arena];
+    }
+    obj.get("traces")
+        .and_then(|traces| traces.as_array())
+        .map(|traces| {
+            traces
+                .iter()
+                .flat_map(|entry| match entry {
+                    // ["Execution", {"arena": [...]}]
+                    serde_json::Value::Array(pair) => pair.iter().flat_map(arena_values).collect(),
+                    other => arena_values(other),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cast_trace() -> serde_json::Value {
+        json!({
+            "traces": [["Execution", { "arena": [
+                {
+                    "parent": null, "children": [1, 2], "idx": 0,
+                    "trace": {
+                        "depth": 0, "address": "0xAAAA", "kind": "CALL", "gas_used": 50000,
+                        "decoded": { "label": "Vault", "call_data": { "signature": "deposit()" } }
+                    },
+                    "logs": [{ "raw_log": {} }]
+                },
+                {
+                    "parent": 0, "children": [], "idx": 1,
+                    "trace": { "depth": 1, "address": "0xbbbb", "kind": "STATICCALL", "gas_used": 8000 }
+                },
+                {
+                    "parent": 0, "children": [], "idx": 2,
+                    "trace": {
+                        "depth": 1, "address": "0xcccc", "kind": "CALL", "gas_used": 12000,
+                        "decoded": { "call_data": { "signature": "transfer(address,uint256)" } }
+                    }
+                }
+            ]}]]
+        })
+    }
+
+    #[test]
+    fn test_parse_cast_trace() {
+        let raw = cast_trace();
+        let typed = |value: serde_json::Value| RawTraceData::deserialize(value).unwrap();
+        assert_eq!(TraceFormat::Auto.resolve(&typed(raw.clone())), TraceFormat::Cast);
+        assert_eq!(TraceFormat::Auto.resolve(&typed(json!({ "structLogs": [] }))), TraceFormat::Stylus);
+
+        let parsed = parse_cast_trace("0xtx", &raw).unwrap();
+
+        assert_eq!(parsed.total_gas_used, 50000);
+        assert_eq!(parsed.contract_address.as_deref(), Some("0xaaaa"));
+        let names: Vec<(&str, u32, u64)> = parsed
+            .execution_steps
+            .iter()
+            .map(|step| (step.function.as_deref().unwrap(), step.depth, step.gas_cost))
+            .collect();
+        assert_eq!(names, vec![
+            ("deposit()", 1, 30000),
+            ("0xbbbb", 2, 8000),
+            ("transfer(address,uint256)", 2, 12000),
+        ]);
+        assert_eq!(parsed.hostio_stats.count_for_type(HostIoType::StaticCall), 1);
+        assert_eq!(parsed.hostio_stats.count_for_type(HostIoType::Call), 1);
+        assert_eq!(parsed.hostio_stats.count_for_type(HostIoType::Log), 1);
+
+        assert!(parse_cast_trace("0xtx", &json!({ "structLogs": [] })).is_err());
+    }
+
+    #[test]
+    fn test_parse_cast_trace_with_options() {
+        let mut raw = cast_trace();
+        let rules = crate::parser::HostIoRules::from_json(r#"[{ "pattern": "^STATICCALL$", "type": "storage_load" }]"#).unwrap();
+        let options = ParseOptions { hostio_rules: Some(rules), strict_validate: true, ..Default::default() };
+
+        let parsed = parse_cast_trace_with_options("0xtx", &raw, &options).unwrap();
+        assert_eq!(parsed.hostio_stats.count_for_type(HostIoType::StorageLoad), 1);
+        assert_eq!(parsed.hostio_stats.count_for_type(HostIoType::StaticCall), 0);
+
+        raw["traces"][0][1]["arena"][1]["trace"]["gas_used"] = json!(60000);
+        assert!(parse_cast_trace("0xtx", &raw).is_ok());
+        assert!(parse_cast_trace_with_options("0xtx", &raw, &options).is_err());
+    }
+}
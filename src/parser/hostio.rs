@@ -2,9 +2,26 @@
 //!
 //! HostIO events represent calls from WASM to the Stylus VM runtime.
 //! Common types: storage_read, storage_write, call, log, etc.
+//!
+//! Names the built-in table doesn't know (vendor-specific or newly added
+//! HostIOs) can be classified with `HostIoRules`, a JSON list of regexes:
+//!
+//! ```json
+//! [
+//!   { "pattern": "^vendor_sload", "type": "storage_load" },
+//!   { "pattern": "emit_event", "type": "log" }
+//! ]
+//! ```
+//!
+//! Rules are tried in order before the built-in table.
 
+use crate::rpc::RawTraceData;
+use crate::utils::error::ConfigError;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::Path;
 
 /// Type of HostIO operation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -44,11 +61,80 @@ impl HostIoType {
     }
 }
 
+/// One classification rule as written in a rules file
+///
+/// **Private** - deserialization shape for `HostIoRules`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawHostIoRule {
+    pattern: String,
+    #[serde(rename = "type")]
+    io_type: HostIoType,
+}
+
+/// User-supplied regex rules mapping raw HostIO names to types
+///
+/// **Public** - loaded from `--hostio-rules` and passed via `ParseOptions`
+#[derive(Debug, Clone, Default)]
+pub struct HostIoRules {
+    rules: Vec<(Regex, HostIoType)>,
+}
+
+impl HostIoRules {
+    /// Parse rules from a JSON array of `{ "pattern", "type" }` objects
+    ///
+    /// **Public** - constructor
+    ///
+    /// # Errors
+    /// * `ConfigError::Json` - Not an array of rules, or an unknown type
+    /// * `ConfigError::InvalidRegex` - A pattern does not compile
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        let raw: Vec<RawHostIoRule> = serde_json::from_str(json)?;
+        let rules = raw
+            .into_iter()
+            .map(|rule| Ok((Regex::new(&rule.pattern)?, rule.io_type)))
+            .collect::<Result<Vec<_>, ConfigError>>()?;
+        
+        Ok(Self { rules })
+    }
+    
+    /// Load a rules file
+    ///
+    /// **Public** - used for `--hostio-rules`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+    
+    /// Type of the first rule whose pattern matches `name`
+    pub fn classify(&self, name: &str) -> Option<HostIoType> {
+        self.rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(name))
+            .map(|(_, io_type)| *io_type)
+    }
+}
+
 /// A single HostIO event from the trace
 #[derive(Debug, Clone)]
 pub struct HostIoEvent {
     pub io_type: HostIoType,
     pub gas_cost: u64,
+    
+    /// Hex-encoded arguments (e.g. storage key and value, call target),
+    /// when the tracer reports them
+    pub args: Option<String>,
+    
+    /// Hex-encoded outputs (e.g. the loaded storage value)
+    pub outs: Option<String>,
+}
+
+impl HostIoEvent {
+    /// Event without arguments or outputs
+    ///
+    /// **Public** - constructor
+    pub fn new(io_type: HostIoType, gas_cost: u64) -> Self {
+        Self { io_type, gas_cost, args: None, outs: None }
+    }
 }
 
 /// Aggregated HostIO statistics
@@ -56,6 +142,12 @@ pub struct HostIoEvent {
 pub struct HostIoStats {
     counts: HashMap<HostIoType, u64>,
     total_gas: u64,
+    
+    /// Individual events in trace order
+    events: Vec<HostIoEvent>,
+    
+    /// Raw names that fell through to `Other`, with their call counts
+    unclassified: BTreeMap<String, u64>,
 }
 
 impl HostIoStats {
@@ -64,6 +156,8 @@ impl HostIoStats {
         Self {
             counts: HashMap::new(),
             total_gas: 0,
+            events: Vec::new(),
+            unclassified: BTreeMap::new(),
         }
     }
 
@@ -71,6 +165,24 @@ impl HostIoStats {
     pub fn add_event(&mut self, event: HostIoEvent) {
         *self.counts.entry(event.io_type).or_insert(0) += 1;
         self.total_gas += event.gas_cost;
+        self.events.push(event);
+    }
+
+    /// Record a raw HostIO name that could not be classified
+    pub fn add_unclassified(&mut self, name: &str) {
+        *self.unclassified.entry(name.to_string()).or_insert(0) += 1;
+    }
+
+    /// Raw names classified as `Other`, with call counts (sorted by name)
+    ///
+    /// Candidates for new `--hostio-rules` entries.
+    pub fn unclassified(&self) -> &BTreeMap<String, u64> {
+        &self.unclassified
+    }
+
+    /// Get the individual events in trace order
+    pub fn events(&self) -> &[HostIoEvent] {
+        &self.events
     }
 
     /// Get total number of HostIO calls
@@ -88,6 +200,15 @@ impl HostIoStats {
         self.total_gas
     }
 
+    /// Get gas consumed per HostIO type
+    pub fn gas_by_type(&self) -> HashMap<HostIoType, u64> {
+        let mut gas = HashMap::new();
+        for event in &self.events {
+            *gas.entry(event.io_type).or_insert(0) += event.gas_cost;
+        }
+        gas
+    }
+
     /// Convert to a map for JSON serialization
     pub fn to_map(&self) -> HashMap<String, u64> {
         self.counts
@@ -103,6 +224,77 @@ impl Default for HostIoStats {
     }
 }
 
+/// Expected gas range for a single HostIO event of the given type
+///
+/// **Public** - used by `validate_hostio_costs`
+///
+/// Ranges are deliberately generous (cold/warm access, dynamic data
+/// costs). Types whose cost depends on forwarded gas or deployed code
+/// size (calls, creates, `Other`) have no fixed range and return `None`.
+pub fn expected_cost(io_type: HostIoType) -> Option<Range<u64>> {
+    match io_type {
+        HostIoType::StorageLoad => Some(50..3_000),
+        HostIoType::StorageStore => Some(100..25_000),
+        HostIoType::Log => Some(375..1_000_000),
+        HostIoType::SelfDestruct => Some(5_000..35_000),
+        HostIoType::AccountBalance => Some(100..3_000),
+        HostIoType::BlockHash => Some(20..200),
+        HostIoType::Call
+        | HostIoType::StaticCall
+        | HostIoType::DelegateCall
+        | HostIoType::Create
+        | HostIoType::Other => None,
+    }
+}
+
+/// A HostIO event whose gas falls outside its expected range
+///
+/// **Public** - returned from `validate_hostio_costs`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostIoCostAnomaly {
+    /// Position of the event in the trace's HostIO list
+    pub index: usize,
+    pub io_type: HostIoType,
+    pub gas_cost: u64,
+    pub expected: Range<u64>,
+}
+
+impl std::fmt::Display for HostIoCostAnomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "HostIO #{} {:?} cost {} gas, expected {}..{}",
+            self.index, self.io_type, self.gas_cost, self.expected.start, self.expected.end
+        )
+    }
+}
+
+/// Compare observed per-event HostIO gas against known pricing
+///
+/// **Public** - optional sanity pass after parsing
+///
+/// Anomalies usually mean an ink/gas unit mix-up or a tracer quirk
+/// (e.g., a storage load priced at 1 gas).
+pub fn validate_hostio_costs(stats: &HostIoStats) -> Vec<HostIoCostAnomaly> {
+    stats
+        .events()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, event)| {
+            let expected = expected_cost(event.io_type)?;
+            if expected.contains(&event.gas_cost) {
+                return None;
+            }
+            Some(HostIoCostAnomaly {
+                index,
+                io_type: event.io_type,
+                gas_cost: event.gas_cost,
+                expected,
+            })
+        })
+        .collect()
+}
+
 /// Extract HostIO events from raw trace data
 ///
 /// **Public** - used by the main parser to build statistics
@@ -113,14 +305,43 @@ impl Default for HostIoStats {
 /// # Returns
 /// Parsed HostIO statistics
 pub fn extract_hostio_events(trace_data: &serde_json::Value) -> HostIoStats {
+    extract_hostio_events_with_rules(trace_data, None)
+}
+
+/// Extract HostIO events, classifying names with user rules first
+///
+/// **Public** - `extract_hostio_events` with `--hostio-rules` applied
+///
+/// Names matched by neither the rules nor the built-in table are counted
+/// as `Other` and listed in `HostIoStats::unclassified`.
+pub fn extract_hostio_events_with_rules(
+    trace_data: &serde_json::Value,
+    rules: Option<&HostIoRules>,
+) -> HostIoStats {
+    collect_hostio_events(trace_data.get("hostio"), rules)
+}
+
+/// Extract HostIO events from a typed RPC trace
+///
+/// **Public** - like `extract_hostio_events_with_rules`, reading the
+/// `hostio` field of the trace's top-level fields
+pub fn extract_trace_hostio_events(trace: &RawTraceData, rules: Option<&HostIoRules>) -> HostIoStats {
+    collect_hostio_events(trace.fields().and_then(|fields| fields.get("hostio")), rules)
+}
+
+/// Classify every event of a `hostio` array
+///
+/// **Private** - shared by the JSON and typed extractors
+fn collect_hostio_events(hostio: Option<&serde_json::Value>, rules: Option<&HostIoRules>) -> HostIoStats {
     let mut stats = HostIoStats::new();
     
-    // Try to extract HostIO array from trace
     // Actual field name depends on stylusTracer output format
-    // This is a placeholder - adjust based on real trace format
-    if let Some(hostio_array) = trace_data.get("hostio").and_then(|v| v.as_array()) {
+    if let Some(hostio_array) = hostio.and_then(|v| v.as_array()) {
         for event_json in hostio_array {
-            if let Some(event) = parse_hostio_event(event_json) {
+            if let Some((name, event)) = parse_hostio_event(event_json, rules) {
+                if event.io_type == HostIoType::Other {
+                    stats.add_unclassified(name);
+                }
                 stats.add_event(event);
             }
         }
@@ -131,15 +352,20 @@ pub fn extract_hostio_events(trace_data: &serde_json::Value) -> HostIoStats {
 
 /// Parse a single HostIO event from JSON
 ///
-/// **Private** - internal parsing logic
-fn parse_hostio_event(event_json: &serde_json::Value) -> Option<HostIoEvent> {
+/// **Private** - internal parsing logic; returns the raw name with the event
+fn parse_hostio_event<'a>(
+    event_json: &'a serde_json::Value,
+    rules: Option<&HostIoRules>,
+) -> Option<(&'a str, HostIoEvent)> {
     let io_type_str = event_json.get("type")?.as_str()?;
     let gas_cost = event_json.get("gas")?.as_u64()?;
     
-    Some(HostIoEvent {
-        io_type: HostIoType::from_str(io_type_str),
-        gas_cost,
-    })
+    let io_type = rules
+        .and_then(|rules| rules.classify(io_type_str))
+        .unwrap_or_else(|| HostIoType::from_str(io_type_str));
+    
+    let hex = |key: &str| event_json.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    Some((io_type_str, HostIoEvent { io_type, gas_cost, args: hex("args"), outs: hex("outs") }))
 }
 
 #[cfg(test)]
@@ -153,22 +379,64 @@ mod tests {
         assert_eq!(HostIoType::from_str("unknown"), HostIoType::Other);
     }
 
+    #[test]
+    fn test_hostio_rules_classify_before_builtin() {
+        let rules = HostIoRules::from_json(
+            r#"[{ "pattern": "^vendor_sload$", "type": "storage_load" }, { "pattern": "^call$", "type": "static_call" }]"#,
+        )
+        .unwrap();
+        let trace = serde_json::json!({
+            "hostio": [
+                { "type": "vendor_sload", "gas": 100 },
+                { "type": "call", "gas": 700 },
+                { "type": "mystery", "gas": 5 },
+                { "type": "mystery", "gas": 5 }
+            ]
+        });
+        
+        let stats = extract_hostio_events_with_rules(&trace, Some(&rules));
+        
+        assert_eq!(stats.count_for_type(HostIoType::StorageLoad), 1);
+        assert_eq!(stats.count_for_type(HostIoType::StaticCall), 1);
+        assert_eq!(stats.count_for_type(HostIoType::Other), 2);
+        assert_eq!(stats.unclassified().get("mystery"), Some(&2));
+        
+        assert!(HostIoRules::from_json(r#"[{ "pattern": "(", "type": "log" }]"#).is_err());
+        assert!(HostIoRules::from_json(r#"[{ "pattern": "x", "type": "nope" }]"#).is_err());
+    }
+
     #[test]
     fn test_hostio_stats() {
         let mut stats = HostIoStats::new();
         
-        stats.add_event(HostIoEvent {
-            io_type: HostIoType::StorageLoad,
-            gas_cost: 100,
-        });
+        stats.add_event(HostIoEvent::new(HostIoType::StorageLoad, 100));
         
-        stats.add_event(HostIoEvent {
-            io_type: HostIoType::StorageLoad,
-            gas_cost: 200,
-        });
+        stats.add_event(HostIoEvent::new(HostIoType::StorageLoad, 200));
         
         assert_eq!(stats.count_for_type(HostIoType::StorageLoad), 2);
         assert_eq!(stats.total_gas(), 300);
         assert_eq!(stats.total_calls(), 2);
+        assert_eq!(stats.events().len(), 2);
+    }
+
+    #[test]
+    fn test_validate_hostio_costs() {
+        let mut stats = HostIoStats::new();
+        for (io_type, gas_cost) in [
+            (HostIoType::StorageLoad, 2100),
+            (HostIoType::StorageLoad, 1),
+            (HostIoType::Call, 1),
+            (HostIoType::StorageStore, 1_000_000),
+        ] {
+            stats.add_event(HostIoEvent::new(io_type, gas_cost));
+        }
+        
+        let anomalies = validate_hostio_costs(&stats);
+        
+        assert_eq!(anomalies.len(), 2);
+        assert_eq!(anomalies[0].index, 1);
+        assert_eq!(anomalies[0].io_type, HostIoType::StorageLoad);
+        assert_eq!(anomalies[1].index, 3);
+        assert!(expected_cost(HostIoType::Call).is_none());
     }
 }
\ No newline at end of file
@@ -0,0 +1,306 @@
+//! Loading raw trace JSON from files or stdin.
+//!
+//! Lets the capture command work offline (`--trace-file trace.json`)
+//! and in shell pipelines (`--trace-file -` reads stdin).
+//!
+//! Newline-delimited JSON (one structLogs step per line, as streamed by
+//! some capture tooling) is detected from the shape of its first object
+//! and deserialized line by line straight into steps, so no JSON tree of
+//! the whole trace is ever built.
+
+use super::stylus_trace::{parse_step_line, ExecutionStep, ParseOptions};
+use crate::rpc::types::STEP_ARRAY_KEYS;
+use crate::utils::error::ParseError;
+use crate::utils::hash::Sha256Stream;
+use log::{debug, warn};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Path value meaning "read from stdin"
+pub const STDIN_PATH: &str = "-";
+
+/// Set once stdin has been consumed so a second read fails loudly
+static STDIN_CONSUMED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a path refers to stdin
+pub fn is_stdin(path: &Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+/// Fields only a step has, used to tell an NDJSON step from a trace object
+const STEP_FIELDS: [&str; 4] = ["op", "pc", "gasCost", "gas_cost"];
+
+/// Trace input as read from a file or stdin
+///
+/// **Public** - returned by `read_trace_file`
+#[derive(Debug)]
+pub enum TraceInput {
+    /// A JSON document (trace object, step array or cast output)
+    Json(serde_json::Value),
+    
+    /// NDJSON steps, already parsed
+    Steps(StepStream),
+}
+
+/// Steps read from an NDJSON trace
+///
+/// **Public** - parsed further with `parse_step_stream`
+#[derive(Debug, Clone, Default)]
+pub struct StepStream {
+    /// Parsed steps, in order
+    pub steps: Vec<ExecutionStep>,
+    
+    /// Refund counter reported by the last step that carries one
+    pub refund: Option<u64>,
+    
+    /// SHA-256 of the trimmed non-empty lines, each followed by `\n`
+    pub sha256: String,
+}
+
+/// Read a trace from a file, or from stdin if the path is `-`
+///
+/// **Public** - entry point for `--trace-file`
+///
+/// # Errors
+/// * `ParseError::Io` - File could not be read
+/// * `ParseError::JsonError` - Content is not valid JSON
+/// * `ParseError::InvalidFormat` - Stdin was already consumed, or an
+///   NDJSON line is not valid JSON
+pub fn read_trace_file(path: &Path, options: &ParseOptions) -> Result<TraceInput, ParseError> {
+    if is_stdin(path) {
+        if STDIN_CONSUMED.swap(true, Ordering::SeqCst) {
+            return Err(ParseError::InvalidFormat(
+                "stdin has already been read; it can only be used once".to_string()
+            ));
+        }
+        debug!("Reading trace from stdin");
+        return read_trace_reader(std::io::stdin().lock(), options);
+    }
+    
+    debug!("Reading trace from: {}", path.display());
+    let file = std::fs::File::open(path)?;
+    read_trace_reader(file, options)
+}
+
+/// Read a trace (JSON document or NDJSON steps) from any reader
+///
+/// **Public** - useful for tests and embedding
+///
+/// Only an object can start NDJSON, so the first non-whitespace byte
+/// decides whether to look further: if the first line is a complete JSON
+/// object shaped like a step, every line is a step.
+pub fn read_trace_reader(reader: impl Read, options: &ParseOptions) -> Result<TraceInput, ParseError> {
+    let mut reader = BufReader::new(reader);
+    
+    let first_byte = loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break None;
+        }
+        match buf.iter().position(|byte| !byte.is_ascii_whitespace()) {
+            Some(pos) => {
+                let byte = buf[pos];
+                reader.consume(pos);
+                break Some(byte);
+            }
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+            }
+        }
+    };
+    
+    match first_byte {
+        None => Err(ParseError::InvalidFormat("Trace input is empty".to_string())),
+        Some(b'{') => read_object_or_ndjson(reader, options),
+        Some(_) => {
+            let mut content = String::new();
+            reader.read_to_string(&mut content)?;
+            Ok(TraceInput::Json(serde_json::from_str(&content)?))
+        }
+    }
+}
+
+/// Read a JSON object, or NDJSON steps
+///
+/// **Private** - a first line that is not a complete object means a
+/// multi-line JSON document. A complete one is a step if it has step
+/// fields and no trace-level ones (`structLogs`, `gasUsed`, ...), even
+/// when it is the only line; otherwise it is a single-line document.
+fn read_object_or_ndjson(mut reader: impl BufRead, options: &ParseOptions) -> Result<TraceInput, ParseError> {
+    let mut content = String::new();
+    reader.read_line(&mut content)?;
+    
+    let Ok(first) = serde_json::from_str::<serde_json::Value>(&content) else {
+        reader.read_to_string(&mut content)?;
+        return Ok(TraceInput::Json(serde_json::from_str(&content)?));
+    };
+    
+    if !is_step_object(&first) {
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest)?;
+        if rest.trim().is_empty() {
+            return Ok(TraceInput::Json(first));
+        }
+        // Anything after a one-line document is a JSON error, not more steps
+        content.push_str(&rest);
+        return Ok(TraceInput::Json(serde_json::from_str(&content)?));
+    }
+    drop(first);
+    
+    let mut stream = StepStream::default();
+    let mut hasher = Sha256Stream::default();
+    let mut line_number = 0usize;
+    
+    let mut read_step = |line: &str| -> Result<(), ParseError> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+        line_number += 1;
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+        
+        match parse_step_line(line, options) {
+            Ok((step, refund)) => {
+                stream.steps.push(step);
+                stream.refund = refund.or(stream.refund);
+                Ok(())
+            }
+            Err(e) if e.is_data() => {
+                // Log but don't fail, as for JSON step arrays
+                warn!("Failed to parse NDJSON step {}: {}", line_number, e);
+                Ok(())
+            }
+            Err(e) => Err(ParseError::InvalidFormat(format!("Invalid NDJSON step {}: {}", line_number, e))),
+        }
+    };
+    
+    read_step(&content)?;
+    for line in reader.lines() {
+        read_step(&line?)?;
+    }
+    
+    if stream.steps.is_empty() {
+        return Err(ParseError::InvalidFormat(
+            "All execution steps failed to parse".to_string()
+        ));
+    }
+    
+    stream.sha256 = hasher.finish();
+    debug!("Read {} NDJSON steps", stream.steps.len());
+    Ok(TraceInput::Steps(stream))
+}
+
+/// Whether a JSON value looks like a single step rather than a trace
+///
+/// **Private** - decides between NDJSON and a one-line document
+fn is_step_object(value: &serde_json::Value) -> bool {
+    let Some(obj) = value.as_object() else {
+        return false;
+    };
+    let has_trace_fields = obj.contains_key("gasUsed")
+        || STEP_ARRAY_KEYS.iter().any(|key| obj.contains_key(*key));
+    !has_trace_fields && STEP_FIELDS.iter().any(|key| obj.contains_key(*key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash::sha256_hex;
+    use std::io::Cursor;
+
+    fn read(input: &str) -> Result<TraceInput, ParseError> {
+        read_trace_reader(Cursor::new(input.to_string()), &ParseOptions::default())
+    }
+    
+    fn json(input: &str) -> serde_json::Value {
+        match read(input).unwrap() {
+            TraceInput::Json(value) => value,
+            TraceInput::Steps(_) => panic!("expected a JSON document"),
+        }
+    }
+    
+    fn steps(input: &str) -> StepStream {
+        match read(input).unwrap() {
+            TraceInput::Steps(stream) => stream,
+            TraceInput::Json(_) => panic!("expected NDJSON steps"),
+        }
+    }
+
+    #[test]
+    fn test_read_trace_reader() {
+        assert_eq!(json(r#"{"gasUsed": 10, "structLogs": []}"#)["gasUsed"], 10);
+        assert_eq!(json(r#"{"gasUsed": 10, "structLogs": [{"op": "ADD", "pc": 0}]}"#)["gasUsed"], 10);
+    }
+
+    #[test]
+    fn test_read_trace_reader_ndjson() {
+        let ndjson = "\n{\"op\": \"ADD\", \"gasCost\": 3}\n{\"op\": \"SLOAD\", \"gasCost\": 100, \"refund\": \"0x10\"}\n\n";
+        let stream = steps(ndjson);
+        assert_eq!(stream.steps.len(), 2);
+        assert_eq!(stream.steps[1].op.as_deref(), Some("SLOAD"));
+        assert_eq!(stream.steps[1].gas_cost, 100);
+        assert_eq!(stream.refund, Some(16));
+        assert_eq!(
+            stream.sha256,
+            sha256_hex(b"{\"op\": \"ADD\", \"gasCost\": 3}\n{\"op\": \"SLOAD\", \"gasCost\": 100, \"refund\": \"0x10\"}\n")
+        );
+        
+        let pretty = "{\n  \"gasUsed\": 10,\n  \"structLogs\": []\n}\n";
+        assert_eq!(json(pretty)["gasUsed"], 10);
+        
+        assert!(read("{\"op\": \"ADD\"}\nnot json\n").is_err());
+        // A trace object followed by more lines is not NDJSON
+        assert!(read("{\"gasUsed\": 10, \"structLogs\": []}\n{\"op\": \"ADD\"}\n").is_err());
+    }
+
+    #[test]
+    fn test_read_trace_reader_single_step_ndjson() {
+        let stream = steps("{\"pc\": 4, \"op\": \"SSTORE\", \"gasCost\": 20000, \"depth\": 1}\n");
+        assert_eq!(stream.steps.len(), 1);
+        assert_eq!(stream.steps[0].pc, 4);
+    }
+
+    #[test]
+    fn test_read_trace_reader_ndjson_state() {
+        let line = r#"{"op": "SLOAD", "gasCost": 100, "stack": ["0x1"], "storage": {"0x1": "0x2"}}"#;
+        assert!(steps(line).steps[0].stack.is_none());
+        
+        let options = ParseOptions { capture_state: true, ..Default::default() };
+        let TraceInput::Steps(stream) = read_trace_reader(Cursor::new(line), &options).unwrap() else {
+            panic!("expected NDJSON steps");
+        };
+        assert_eq!(stream.steps[0].stack.as_deref(), Some(&["0x1".to_string()][..]));
+        assert_eq!(stream.steps[0].storage.as_ref().unwrap()["0x1"], "0x2");
+    }
+
+    #[test]
+    fn test_read_trace_reader_ndjson_bad_steps() {
+        // Wrongly typed steps are skipped; a stream of only those fails
+        let stream = steps("{\"op\": \"ADD\", \"gasCost\": 3}\n{\"op\": 7, \"gasCost\": 3}\n");
+        assert_eq!(stream.steps.len(), 1);
+        assert!(read("{\"op\": 7, \"gasCost\": 3}\n").is_err());
+    }
+
+    #[test]
+    fn test_read_trace_reader_empty() {
+        assert!(read("  \n").is_err());
+    }
+
+    #[test]
+    fn test_read_trace_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), r#"[{"op": "ADD", "gasCost": 3}]"#).unwrap();
+        
+        let trace = read_trace_file(file.path(), &ParseOptions::default()).unwrap();
+        assert!(matches!(trace, TraceInput::Json(value) if value.is_array()));
+    }
+
+    #[test]
+    fn test_is_stdin() {
+        assert!(is_stdin(Path::new("-")));
+        assert!(!is_stdin(Path::new("trace.json")));
+    }
+}
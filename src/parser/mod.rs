@@ -2,15 +2,32 @@
 //!
 //! This module handles:
 //! - Parsing raw JSON from stylusTracer
+//! - Parsing Foundry `cast run --json` call traces
+//! - Loading raw traces from files or stdin
 //! - Extracting HostIO events
 //! - Validating trace format
+//! - Resolving pcs to source locations
 //! - Defining output schema
 
+pub mod cast;
 pub mod hostio;
+pub mod input;
 pub mod schema;
+pub mod source_map;
 pub mod stylus_trace;
 
 // Re-export main types
-pub use hostio::{HostIoEvent, HostIoStats, HostIoType};
-pub use schema::{Profile, HotPath, HostIoSummary, SourceHint};
-pub use stylus_trace::{parse_trace, to_profile, validate_trace_format, ParsedTrace};
\ No newline at end of file
+pub use cast::{is_cast_trace, parse_cast_trace, parse_cast_trace_with_options, TraceFormat};
+pub use hostio::{
+    expected_cost, extract_hostio_events, extract_hostio_events_with_rules, extract_trace_hostio_events,
+    validate_hostio_costs,
+    HostIoCostAnomaly, HostIoEvent, HostIoRules, HostIoStats, HostIoType,
+};
+pub use input::{is_stdin, read_trace_file, read_trace_reader, StepStream, TraceInput};
+pub use source_map::{annotate_frame, SourceMap};
+pub use schema::{FieldDescription, PROFILE_FIELDS, GasAccounting, GasBreakdown, HotStep, Profile, TraceSource, ProfileMetrics, HotPath, HostIoSummary, SourceHint, TransactionInput, UnattributedGas};
+pub use stylus_trace::{
+    collect_hot_steps, parse_raw_trace, parse_step_stream, parse_trace, parse_trace_with_options, to_profile, validate_gas_monotonic, validate_trace_format,
+    ParseOptions,
+    ParsedTrace,
+};
\ No newline at end of file
@@ -3,8 +3,11 @@
 //! This module defines the structure of JSON files we write to disk.
 //! Schema is versioned to allow future evolution.
 
+use crate::aggregator::{CallTreeNode, CollapsedStack};
+use crate::utils::hash::sha256_hex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
 /// Top-level profile structure written to JSON
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,9 +15,23 @@ pub struct Profile {
     /// Schema version for compatibility checking
     pub version: String,
     
+    /// Deterministic hash of the collapsed stacks and total gas; equal for
+    /// profiles derived from the same trace (absent in older profiles)
+    #[serde(default)]
+    pub fingerprint: String,
+    
     /// Transaction hash that was profiled
     pub transaction_hash: String,
     
+    /// Contract that ran: the `to` address, or the deployed address for
+    /// contract creations (absent if neither trace nor receipt had it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<String>,
+    
+    /// Scenario name given with `--label` (e.g. "cold cache")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    
     /// Total gas used by the transaction
     pub total_gas: u64,
     
@@ -26,6 +43,256 @@ pub struct Profile {
     
     /// Timestamp when profile was generated
     pub generated_at: String,
+    
+    /// Normalized efficiency metrics (absent in older profiles)
+    #[serde(default)]
+    pub metrics: ProfileMetrics,
+    
+    /// Gas that could not be attributed to a named op (absent in older profiles)
+    #[serde(default)]
+    pub unattributed: UnattributedGas,
+    
+    /// L1 data vs L2 execution gas, from the receipt (if fetched)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_breakdown: Option<GasBreakdown>,
+    
+    /// Transaction calldata and the entry function it selects (only with
+    /// `--calldata`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calldata: Option<TransactionInput>,
+    
+    /// Most expensive steps with their EVM state (only with `--capture-state`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hot_steps: Vec<HotStep>,
+    
+    /// Raw trace this profile was derived from (only with `--embed-raw`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<TraceSource>,
+    
+    /// Every collapsed stack, for re-rendering or diffing without the
+    /// trace (only with `--full-stacks`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub collapsed_stacks: Vec<CollapsedStack>,
+    
+    /// Nested `{name, gas, children}` tree with inclusive gas, built from
+    /// the collapsed stacks (only with `--call-tree`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub call_tree: Option<CallTreeNode>,
+    
+    /// Step-summed gas reconciled with the gas refund (only when the
+    /// trace reports a refund)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_accounting: Option<GasAccounting>,
+}
+
+impl Profile {
+    /// HostIO gas as a multiple of total gas, if it exceeds total gas
+    ///
+    /// **Public** - sanity check; HostIO gas is part of total gas, so a
+    /// result here almost always means HostIO costs are in ink (or another
+    /// unit). The ratio hints at the missing conversion factor.
+    pub fn hostio_gas_excess(&self) -> Option<f64> {
+        let hostio_gas = self.hostio_summary.total_hostio_gas;
+        (hostio_gas > self.total_gas)
+            .then(|| hostio_gas as f64 / self.total_gas.max(1) as f64)
+    }
+}
+
+/// Reference to (or copy of) the raw trace behind a profile
+///
+/// Lets a profile be re-analyzed by a newer tool version from the
+/// artifact alone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceSource {
+    /// SHA-256 of the compact JSON serialization of the raw trace (of
+    /// its trimmed lines, for NDJSON step traces)
+    pub sha256: String,
+    
+    /// Trace file the profile was built from (None for RPC/stdin)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    
+    /// Full raw trace (only with `--embed-raw-full`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<serde_json::Value>,
+}
+
+impl TraceSource {
+    /// Describe a raw trace by hash, optionally embedding it in full
+    ///
+    /// # Arguments
+    /// * `raw_trace` - Raw trace JSON
+    /// * `path` - File it was read from, if any
+    /// * `embed_full` - Copy the whole trace into the profile
+    pub fn from_raw(raw_trace: &serde_json::Value, path: Option<&Path>, embed_full: bool) -> Self {
+        Self {
+            sha256: sha256_hex(raw_trace.to_string().as_bytes()),
+            path: path.map(|p| p.display().to_string()),
+            raw: embed_full.then(|| raw_trace.clone()),
+        }
+    }
+}
+
+/// A single expensive step with the EVM state captured at it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotStep {
+    /// Program counter
+    pub pc: u64,
+    
+    /// Operation name (if available)
+    pub op: Option<String>,
+    
+    /// Call depth
+    pub depth: u32,
+    
+    /// Gas cost of this step
+    pub gas_cost: u64,
+    
+    /// Stack words at this step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<String>>,
+    
+    /// Memory words at this step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+    
+    /// Storage slots at this step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+}
+
+/// Split of `total_gas` between L1 data posting and L2 execution
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GasBreakdown {
+    /// Gas paying for L1 calldata posting (`gasUsedForL1`)
+    pub l1_gas: u64,
+    
+    /// Remaining gas spent on L2 execution
+    pub l2_gas: u64,
+}
+
+/// Calldata the transaction was sent with (`eth_getTransactionByHash`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransactionInput {
+    /// Full `0x`-prefixed calldata
+    pub input: String,
+    
+    /// First 4 bytes of `input` as `0x` hex (absent for plain transfers)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    
+    /// Signature of the selected function, from `--abi` or `--symbols`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+}
+
+impl TransactionInput {
+    /// Wrap raw calldata, splitting off its selector
+    ///
+    /// **Public** - constructor; `function` is left for the caller to
+    /// resolve
+    pub fn new(input: &str) -> Self {
+        let hex = input.strip_prefix("0x").unwrap_or(input).to_lowercase();
+        let selector = hex
+            .get(..8)
+            .filter(|selector| selector.chars().all(|c| c.is_ascii_hexdigit()))
+            .map(|selector| format!("0x{}", selector));
+        Self {
+            input: format!("0x{}", hex),
+            selector,
+            function: None,
+        }
+    }
+    
+    /// The selector as a number, for ABI / symbol lookups
+    pub fn selector_value(&self) -> Option<u32> {
+        let selector = self.selector.as_deref()?;
+        u32::from_str_radix(selector.strip_prefix("0x").unwrap_or(selector), 16).ok()
+    }
+}
+
+/// Gross step gas, the refund applied to it, and the resulting net gas
+///
+/// Storage clears earn refunds, so `gasUsed` is lower than the gas spent
+/// before the refund; `net_gas` is the execution share of `gasUsed`
+/// (intrinsic gas is not part of any step).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GasAccounting {
+    /// Sum of all step gas costs
+    pub gross_gas: u64,
+    
+    /// Refund applied (capped at a fifth of the transaction's gas before
+    /// the refund, `gasUsed + refund`, per EIP-3529)
+    pub refund: u64,
+    
+    /// `gross_gas - refund`, saturating
+    pub net_gas: u64,
+}
+
+impl GasAccounting {
+    /// Apply a raw refund counter to gross step gas
+    ///
+    /// **Public** - constructor; `gas_used` is the post-refund `gasUsed`.
+    /// The EVM caps the refund at a fifth of the gas used before it
+    /// (`gas_used + refund`); solved for the refund, a counter above
+    /// `gas_used / 4` was capped to `gas_used / 4`.
+    pub fn new(gross_gas: u64, gas_used: u64, refund_counter: u64) -> Self {
+        let refund = refund_counter.min(gas_used / 4);
+        Self {
+            gross_gas,
+            refund,
+            net_gas: gross_gas.saturating_sub(refund),
+        }
+    }
+}
+
+/// Derived efficiency metrics for comparing transactions
+///
+/// Normalized numbers are easier to track over time than raw gas.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileMetrics {
+    /// Number of execution steps in the trace
+    pub total_steps: u64,
+    
+    /// Average gas per execution step (total_gas / total_steps)
+    pub gas_per_step: f64,
+    
+    /// Average gas per HostIO call (total_hostio_gas / total_calls)
+    pub gas_per_hostio_call: f64,
+    
+    /// Share of total gas (0.0-1.0) landing in named frames rather than
+    /// `unknown` placeholders; set by capture from the collapsed stacks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<f64>,
+}
+
+impl ProfileMetrics {
+    /// Compute metrics from raw totals (zero denominators give 0.0)
+    pub fn compute(total_gas: u64, total_steps: u64, hostio_gas: u64, hostio_calls: u64) -> Self {
+        let ratio = |num: u64, den: u64| if den == 0 { 0.0 } else { num as f64 / den as f64 };
+        
+        Self {
+            total_steps,
+            gas_per_step: ratio(total_gas, total_steps),
+            gas_per_hostio_call: ratio(hostio_gas, hostio_calls),
+            coverage: None,
+        }
+    }
+}
+
+/// Gas from steps whose op fell through to the `unknown` frame
+///
+/// A large share here means the profile's attribution is unreliable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UnattributedGas {
+    /// Total gas of unattributed steps
+    pub total_gas: u64,
+    
+    /// Share of all step gas that is unattributed (0-100)
+    pub percentage: f64,
+    
+    /// Gas by raw op name (`<missing>` when the step had no op at all)
+    pub ops: BTreeMap<String, u64>,
 }
 
 /// Summary statistics for HostIO events
@@ -64,4 +331,151 @@ pub struct SourceHint {
     pub file: String,
     pub line: Option<u32>,
     pub function: Option<String>,
-}
\ No newline at end of file
+}
+/// Curated description of one profile field, for docs and tooling
+///
+/// **Public** - rendered by the `schema` command as text or JSON
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDescription {
+    /// JSON field name
+    pub name: &'static str,
+    
+    /// JSON type: string, number, object or array
+    #[serde(rename = "type")]
+    pub field_type: &'static str,
+    
+    /// Whether the field may be absent
+    pub optional: bool,
+    
+    /// One-line description
+    pub description: &'static str,
+    
+    /// Nested fields (of the object, or of each array element)
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub fields: &'static [FieldDescription],
+}
+
+/// Shorthand for building `PROFILE_FIELDS`
+const fn field(
+    name: &'static str,
+    field_type: &'static str,
+    optional: bool,
+    description: &'static str,
+    fields: &'static [FieldDescription],
+) -> FieldDescription {
+    FieldDescription { name, field_type, optional, description, fields }
+}
+
+/// Description of every `Profile` field, in serialization order
+///
+/// **Public** - keep in sync with `Profile` when adding fields
+pub const PROFILE_FIELDS: &[FieldDescription] = &[
+    field("version", "string", false, "Schema version (e.g., '1.0.0')", &[]),
+    field("fingerprint", "string", false, "SHA-256 of sorted collapsed stacks + total gas", &[]),
+    field("transaction_hash", "string", false, "Transaction hash", &[]),
+    field("contract_address", "string", true, "Contract that ran (deployed address for creates)", &[]),
+    field("label", "string", true, "Scenario name (--label)", &[]),
+    field("total_gas", "number", false, "Total gas used", &[]),
+    field("hostio_summary", "object", false, "HostIO event statistics", &[
+        field("total_calls", "number", false, "Total HostIO calls", &[]),
+        field("by_type", "object", false, "Breakdown by HostIO type", &[]),
+        field("total_hostio_gas", "number", false, "Gas consumed by HostIO", &[]),
+    ]),
+    field("hot_paths", "array", false, "Top gas-consuming execution paths", &[
+        field("stack", "string", false, "Stack trace", &[]),
+        field("gas", "number", false, "Gas consumed", &[]),
+        field("percentage", "number", false, "Percentage of total gas", &[]),
+        field("source_hint", "object", true, "Source location (if available)", &[]),
+    ]),
+    field("generated_at", "string", false, "ISO 8601 timestamp", &[]),
+    field("metrics", "object", false, "Normalized efficiency metrics", &[
+        field("total_steps", "number", false, "Execution steps in the trace", &[]),
+        field("gas_per_step", "number", false, "total_gas / total_steps", &[]),
+        field("gas_per_hostio_call", "number", false, "total_hostio_gas / total_calls", &[]),
+        field("coverage", "number", true, "Share of total gas in named frames (0-1)", &[]),
+    ]),
+    field("unattributed", "object", false, "Gas from steps labelled 'unknown'", &[
+        field("total_gas", "number", false, "Unattributed gas", &[]),
+        field("percentage", "number", false, "Share of all step gas", &[]),
+        field("ops", "object", false, "Gas by raw op name", &[]),
+    ]),
+    field("gas_breakdown", "object", true, "L1/L2 split from the receipt (if fetched)", &[
+        field("l1_gas", "number", false, "Gas for L1 calldata posting", &[]),
+        field("l2_gas", "number", false, "Gas for L2 execution", &[]),
+    ]),
+    field("calldata", "object", true, "Transaction input (--calldata)", &[
+        field("input", "string", false, "0x calldata (selector only when redacted)", &[]),
+        field("selector", "string", true, "First 4 bytes of the calldata", &[]),
+        field("function", "string", true, "Function signature from --abi or --symbols", &[]),
+    ]),
+    field("hot_steps", "array", true, "Costliest steps with EVM state (--capture-state)", &[]),
+    field("source", "object", true, "Raw trace reference (--embed-raw)", &[
+        field("sha256", "string", false, "Hash of the compact raw trace JSON (of its trimmed lines, for NDJSON)", &[]),
+        field("path", "string", true, "Trace file path", &[]),
+        field("raw", "object", true, "Full raw trace (--embed-raw-full)", &[]),
+    ]),
+    field("collapsed_stacks", "array", true, "Every collapsed stack (--full-stacks)", &[
+        field("stack", "string", false, "Stack trace", &[]),
+        field("weight", "number", false, "Gas consumed", &[]),
+    ]),
+    field("gas_accounting", "object", true, "Refund reconciliation (if the trace reports refunds)", &[
+        field("gross_gas", "number", false, "Sum of step gas costs", &[]),
+        field("refund", "number", false, "Refund applied (capped at (gasUsed + refund) / 5)", &[]),
+        field("net_gas", "number", false, "gross_gas - refund", &[]),
+    ]),
+    field("call_tree", "object", true, "Nested call tree (--call-tree)", &[
+        field("name", "string", false, "Frame name ('root' at the top)", &[]),
+        field("gas", "number", false, "Inclusive gas of the frame and its callees", &[]),
+        field("children", "array", true, "Callee nodes, heaviest first", &[]),
+    ]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hostio_gas_excess() {
+        let raw = serde_json::json!({ "gasUsed": 1000, "structLogs": [] });
+        let parsed = crate::parser::parse_trace("0xabc", &raw).unwrap();
+        let mut profile = crate::parser::to_profile(&parsed, Vec::new());
+        
+        profile.hostio_summary.total_hostio_gas = 400;
+        assert_eq!(profile.hostio_gas_excess(), None);
+        
+        profile.hostio_summary.total_hostio_gas = 10_000_000;
+        assert_eq!(profile.hostio_gas_excess(), Some(10_000.0));
+    }
+
+    #[test]
+    fn test_gas_accounting_caps_refund() {
+        // 21000 intrinsic + 27106 step gas = 48106 before the refund
+        let uncapped = GasAccounting::new(27106, 43306, 4800);
+        assert_eq!(uncapped.refund, 4800);
+        assert_eq!(uncapped.net_gas, 22306);
+        
+        // 48106 before the refund caps it at 9621, leaving gasUsed 38485
+        let capped = GasAccounting::new(27106, 38485, 20000);
+        assert_eq!(capped.refund, 9621);
+    }
+
+    #[test]
+    fn test_transaction_input_selector() {
+        let input = TransactionInput::new("0xA9059CBB000000000000000000000000000000000000000000000000000000000000beef");
+        assert_eq!(input.selector.as_deref(), Some("0xa9059cbb"));
+        assert_eq!(input.selector_value(), Some(0xa9059cbb));
+        
+        assert_eq!(TransactionInput::new("0x").selector, None);
+    }
+
+    #[test]
+    fn test_profile_fields_cover_serialized_profile() {
+        let raw = serde_json::json!({ "gasUsed": 1, "structLogs": [] });
+        let parsed = crate::parser::parse_trace("0xabc", &raw).unwrap();
+        let profile = serde_json::to_value(crate::parser::to_profile(&parsed, Vec::new())).unwrap();
+        
+        for key in profile.as_object().unwrap().keys() {
+            assert!(PROFILE_FIELDS.iter().any(|field| field.name == key), "undocumented field {}", key);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Program-counter to source-location mapping.
+//!
+//! A source map is a JSON array of entries, each covering a pc range:
+//!
+//! ```json
+//! [
+//!   { "pc": 0,  "end_pc": 40, "file": "lib.rs", "line": 12, "function": "entrypoint" },
+//!   { "pc": 40, "file": "lib.rs", "line": 42, "function": "transfer" }
+//! ]
+//! ```
+//!
+//! `end_pc` is exclusive and optional; without it an entry extends to the
+//! next entry's `pc`.
+
+use super::schema::SourceHint;
+use crate::utils::error::ConfigError;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// One pc range in a source map file
+#[derive(Debug, Clone, Deserialize)]
+struct SourceMapEntry {
+    pc: u64,
+    #[serde(default)]
+    end_pc: Option<u64>,
+    file: String,
+    #[serde(default)]
+    line: Option<u32>,
+    #[serde(default)]
+    function: Option<String>,
+}
+
+/// Lookup table from pc to source location
+///
+/// **Public** - consulted by the stack builder to name frames
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    /// Entries keyed by starting pc
+    entries: BTreeMap<u64, (Option<u64>, SourceHint)>,
+}
+
+impl SourceMap {
+    /// Parse a source map from JSON
+    ///
+    /// **Public** - constructor
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        let raw: Vec<SourceMapEntry> = serde_json::from_str(json)?;
+        
+        let entries = raw
+            .into_iter()
+            .map(|e| {
+                let hint = SourceHint { file: e.file, line: e.line, function: e.function };
+                (e.pc, (e.end_pc, hint))
+            })
+            .collect();
+        
+        Ok(Self { entries })
+    }
+    
+    /// Load a source map from a JSON file
+    ///
+    /// **Public** - used for `--source-map`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+    
+    /// Number of pc ranges in the map
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    /// Whether the map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    /// Resolve a pc to its source location
+    ///
+    /// **Public** - returns `None` if no range covers the pc
+    pub fn lookup(&self, pc: u64) -> Option<&SourceHint> {
+        let (_, (end_pc, hint)) = self.entries.range(..=pc).next_back()?;
+        match end_pc {
+            Some(end) if pc >= *end => None,
+            _ => Some(hint),
+        }
+    }
+}
+
+/// Format a frame label like `transfer (lib.rs:42)`
+///
+/// **Public** - shared by the stack builder and text output
+///
+/// `fallback` (the raw op/function name) is used when the hint has no
+/// function name.
+pub fn annotate_frame(hint: &SourceHint, fallback: &str) -> String {
+    let name = hint.function.as_deref().unwrap_or(fallback);
+    match hint.line {
+        Some(line) => format!("{} ({}:{})", name, hint.file, line),
+        None => format!("{} ({})", name, hint.file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"[
+        { "pc": 0, "end_pc": 10, "file": "lib.rs", "line": 12, "function": "entrypoint" },
+        { "pc": 20, "file": "lib.rs", "line": 42, "function": "transfer" },
+        { "pc": 30, "file": "math.rs" }
+    ]"#;
+
+    #[test]
+    fn test_lookup_ranges() {
+        let map = SourceMap::from_json(MAP).unwrap();
+        
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.lookup(5).unwrap().function.as_deref(), Some("entrypoint"));
+        assert!(map.lookup(15).is_none());
+        assert_eq!(map.lookup(29).unwrap().line, Some(42));
+        assert_eq!(map.lookup(1000).unwrap().file, "math.rs");
+    }
+
+    #[test]
+    fn test_annotate_frame() {
+        let map = SourceMap::from_json(MAP).unwrap();
+        
+        assert_eq!(annotate_frame(map.lookup(20).unwrap(), "SLOAD"), "transfer (lib.rs:42)");
+        assert_eq!(annotate_frame(map.lookup(30).unwrap(), "ADD"), "ADD (math.rs)");
+    }
+
+    #[test]
+    fn test_invalid_source_map() {
+        assert!(SourceMap::from_json("{}").is_err());
+    }
+}
@@ -3,18 +3,21 @@
 //! Parses raw JSON from debug_traceTransaction into structured data.
 //! Handles schema validation and extraction of execution steps.
 
-use super::hostio::{extract_hostio_events, HostIoStats};
-use super::schema::Profile;
+use super::hostio::{extract_trace_hostio_events, HostIoRules, HostIoStats};
+use super::input::StepStream;
+use super::schema::{HotStep, Profile};
+use crate::rpc::RawTraceData;
 use crate::utils::error::ParseError;
-use crate::utils::config::SCHEMA_VERSION;
+use crate::utils::config::{MAX_HOT_STEPS, SCHEMA_VERSION};
 use log::{debug, warn};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use std::collections::BTreeMap;
 
 /// Raw execution step from stylusTracer
 ///
 /// This represents a single step in the WASM execution.
 /// The exact fields depend on the stylusTracer implementation.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct ExecutionStep {
     /// Program counter / instruction pointer
     #[serde(default)]
@@ -40,6 +43,48 @@ pub struct ExecutionStep {
     /// Function name (if debug symbols present)
     #[serde(default)]
     pub function: Option<String>, 
+    
+    /// EVM stack words (geth structLogs, only with `capture_state`)
+    #[serde(skip)]
+    pub stack: Option<Vec<String>>,
+    
+    /// Memory words (geth structLogs, only with `capture_state`)
+    #[serde(skip)]
+    pub memory: Option<Vec<String>>,
+    
+    /// Storage slots touched so far (geth structLogs, only with `capture_state`)
+    #[serde(skip)]
+    pub storage: Option<BTreeMap<String, String>>,
+    
+    /// First step of a frame entered by delegatecall (callee code running
+    /// in the caller's context); set by the parser, not the tracer
+    #[serde(skip)]
+    pub delegated: bool,
+    
+    /// First step of a constructor frame entered by CREATE/CREATE2, with
+    /// the new contract's address when the trace reveals it; set by the
+    /// parser, not the tracer
+    #[serde(skip)]
+    pub created: Option<String>,
+}
+
+/// Options controlling what the parser keeps from each step
+///
+/// **Public** - passed to `parse_trace_with_options`
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Keep geth `stack`/`memory`/`storage` arrays per step
+    ///
+    /// Off by default: memory snapshots can dwarf the rest of the trace.
+    pub capture_state: bool,
+    
+    /// Extra HostIO classification rules, tried before the built-in names
+    pub hostio_rules: Option<HostIoRules>,
+    
+    /// Reject traces whose gas remaining rises within a call frame
+    ///
+    /// Off by default since some tracers reset gas across calls.
+    pub strict_validate: bool,
 }
 
 /// Parsed trace data (internal representation)
@@ -51,8 +96,38 @@ pub struct ParsedTrace {
     pub total_gas_used: u64,
     pub execution_steps: Vec<ExecutionStep>,
     pub hostio_stats: HostIoStats,
+    
+    /// 4-byte selector of the called method, if it could be detected
+    pub entry_selector: Option<u32>,
+    
+    /// Contract that ran (`to`, or the deployed address for creates),
+    /// if the trace carries it
+    pub contract_address: Option<String>,
+    
+    /// Gas refund counter reported by the trace (None if it has none)
+    pub gas_refund: Option<u64>,
 }
 
+/// Ops (EVM opcodes and Stylus HostIO names) that run callee code in the
+/// caller's context
+const DELEGATE_CALL_OPS: [&str; 3] = ["delegatecall", "callcode", "delegate_call_contract"];
+
+/// Ops (EVM opcodes and Stylus HostIO names) that deploy a contract and
+/// run its constructor one level deeper
+const CREATE_OPS: [&str; 4] = ["create", "create2", "create1", "create_contract"];
+
+/// `created` value when the new contract's address is not in the trace
+pub const UNKNOWN_CREATE_ADDRESS: &str = "unknown";
+
+/// Top-level fields that may carry the transaction calldata
+const CALLDATA_FIELDS: [&str; 3] = ["input", "calldata", "callData"];
+
+/// Top-level fields that may carry the executed contract's address
+const ADDRESS_FIELDS: [&str; 3] = ["to", "contractAddress", "contract_address"];
+
+/// Top-level fields that may carry the transaction's gas refund
+const REFUND_FIELDS: [&str; 3] = ["refund", "gasRefund", "gas_refund"];
+
 /// Parse raw trace JSON from stylusTracer
 ///
 /// **Public** - main entry point for parsing
@@ -71,41 +146,96 @@ pub struct ParsedTrace {
 pub fn parse_trace(
     tx_hash: &str,
     raw_trace: &serde_json::Value,
+) -> Result<ParsedTrace, ParseError> {
+    parse_trace_with_options(tx_hash, raw_trace, &ParseOptions::default())
+}
+
+/// Parse raw trace JSON with explicit parse options
+///
+/// **Public** - like `parse_trace`, optionally keeping per-step state
+///
+/// # Arguments
+/// * `tx_hash` - Transaction hash being profiled
+/// * `raw_trace` - Raw JSON from debug_traceTransaction
+/// * `options` - What to keep from each step
+///
+/// # Errors
+/// Same as `parse_trace`
+pub fn parse_trace_with_options(
+    tx_hash: &str,
+    raw_trace: &serde_json::Value,
+    options: &ParseOptions,
+) -> Result<ParsedTrace, ParseError> {
+    let trace = RawTraceData::deserialize(raw_trace).map_err(|_| {
+        ParseError::InvalidFormat("Trace must be a JSON object or array".to_string())
+    })?;
+    
+    parse_raw_trace(tx_hash, &trace, options)
+}
+
+/// Parse a typed trace as fetched over RPC
+///
+/// **Public** - the core of `parse_trace_with_options`, without going
+/// through JSON again
+///
+/// # Arguments
+/// * `tx_hash` - Transaction hash being profiled
+/// * `trace` - Trace from `RpcClient::debug_trace_transaction`
+/// * `options` - What to keep from each step
+///
+/// # Errors
+/// * `ParseError::InvalidFormat` - Several step arrays (e.g. both
+///   `structLogs` and `trace`), so which one holds the steps is ambiguous
+/// * Otherwise the same as `parse_trace`
+pub fn parse_raw_trace(
+    tx_hash: &str,
+    trace: &RawTraceData,
+    options: &ParseOptions,
 ) -> Result<ParsedTrace, ParseError> {
     debug!("Parsing trace for transaction: {}", tx_hash);
     
-    // Handle different trace formats
-    let trace_obj = match raw_trace {
-        // Format 1: Direct object with structLogs/gasUsed
-        serde_json::Value::Object(obj) => obj.clone(),
-        
-        // Format 2: Array of structLogs (wrap it)
-        serde_json::Value::Array(logs) => {
-            warn!("Trace is array format, wrapping as structLogs");
-            let mut wrapper = serde_json::Map::new();
-            wrapper.insert("structLogs".to_string(), raw_trace.clone());
-            wrapper.insert("gasUsed".to_string(), serde_json::json!(0));
-            wrapper
+    let ambiguous = trace.ambiguous_step_keys();
+    if !ambiguous.is_empty() {
+        return Err(ParseError::InvalidFormat(format!(
+            "Trace has several step arrays ({}); expected exactly one",
+            ambiguous.join(", ")
+        )));
+    }
+    
+    let no_fields = serde_json::Map::new();
+    let (steps_array, trace_obj): (&[serde_json::Value], _) = match trace {
+        RawTraceData::StructLogs { struct_logs, fields } => (struct_logs, fields),
+        RawTraceData::Stylus(steps) => {
+            warn!("Trace is array format, total gas comes from steps");
+            (steps, &no_fields)
         }
-        
-        // Format 3: Invalid
-        _ => {
-            return Err(ParseError::InvalidFormat(
-                "Trace must be a JSON object or array".to_string()
-            ));
+        RawTraceData::CallTree { call_type, calls, fields, .. } => {
+            warn!(
+                "Trace is a {} call tree ({} nested calls) with no execution steps; use the default tracer",
+                call_type,
+                calls.len()
+            );
+            (&[], fields)
+        }
+        RawTraceData::Other(fields) => {
+            // No steps found - this might be valid for very simple transactions
+            warn!("No execution steps found in trace");
+            (&[], fields)
         }
     };
     
-    // Extract total gas used
-    let total_gas_used = extract_total_gas(&trace_obj)?;
+    let mut execution_steps = parse_steps_array(steps_array, options)?;
+    finish_steps(&mut execution_steps, options)?;
     
-    // Extract execution steps
-    let execution_steps = extract_execution_steps(&trace_obj)?;
-    
-    debug!("Parsed {} execution steps", execution_steps.len());
+    // Extract total gas used (bare step arrays carry no total, so sum the steps)
+    let total_gas_used = if let RawTraceData::Stylus(_) = trace {
+        execution_steps.iter().map(|step| step.gas_cost).sum()
+    } else {
+        extract_total_gas(trace_obj)?
+    };
     
     // Extract HostIO statistics
-    let hostio_stats = extract_hostio_events(raw_trace);
+    let hostio_stats = extract_trace_hostio_events(trace, options.hostio_rules.as_ref());
     
     debug!(
         "Found {} HostIO calls consuming {} gas",
@@ -113,14 +243,159 @@ pub fn parse_trace(
         hostio_stats.total_gas()
     );
     
+    let entry_selector = detect_entry_selector(trace_obj);
+    if let Some(selector) = entry_selector {
+        debug!("Detected entry selector 0x{:08x}", selector);
+    }
+    
+    let contract_address = detect_contract_address(trace_obj);
+    let gas_refund = extract_refund(trace_obj, steps_array);
+    
     Ok(ParsedTrace {
         transaction_hash: tx_hash.to_string(),
         total_gas_used,
         execution_steps,
         hostio_stats,
+        entry_selector,
+        contract_address,
+        gas_refund,
     })
 }
 
+/// Parse the steps of an NDJSON trace read by `read_trace_file`
+///
+/// **Public** - counterpart of `parse_raw_trace` for step streams, which
+/// carry no top-level fields: total gas is the sum of the steps and there
+/// are no HostIO events
+///
+/// # Errors
+/// * `ParseError::InvalidFormat` - `strict_validate` found rising gas
+pub fn parse_step_stream(
+    tx_hash: &str,
+    stream: StepStream,
+    options: &ParseOptions,
+) -> Result<ParsedTrace, ParseError> {
+    debug!("Parsing step stream for transaction: {}", tx_hash);
+    
+    let mut execution_steps = stream.steps;
+    finish_steps(&mut execution_steps, options)?;
+    
+    Ok(ParsedTrace {
+        transaction_hash: tx_hash.to_string(),
+        total_gas_used: execution_steps.iter().map(|step| step.gas_cost).sum(),
+        execution_steps,
+        hostio_stats: HostIoStats::new(),
+        entry_selector: None,
+        contract_address: None,
+        gas_refund: stream.refund,
+    })
+}
+
+/// Mark delegated and constructor frames, then validate if asked
+///
+/// **Private** - shared by every step source
+fn finish_steps(execution_steps: &mut [ExecutionStep], options: &ParseOptions) -> Result<(), ParseError> {
+    mark_delegated_frames(execution_steps);
+    mark_created_frames(execution_steps);
+    
+    debug!("Parsed {} execution steps", execution_steps.len());
+    
+    if options.strict_validate {
+        validate_gas_monotonic(execution_steps)?;
+    }
+    
+    Ok(())
+}
+
+/// One NDJSON line: a step plus the fields `ExecutionStep` skips
+///
+/// **Private** - deserialized straight from the line's text
+#[derive(Deserialize)]
+struct StepLine {
+    #[serde(flatten)]
+    step: ExecutionStep,
+    
+    #[serde(default)]
+    stack: Option<Vec<String>>,
+    
+    #[serde(default)]
+    memory: Option<Vec<String>>,
+    
+    #[serde(default)]
+    storage: Option<BTreeMap<String, String>>,
+    
+    #[serde(default)]
+    refund: Option<serde_json::Value>,
+}
+
+/// Parse one NDJSON line into a step and the refund it reports
+///
+/// **Private** - used by `read_trace_reader`; state is kept only with
+/// `capture_state`, as for JSON steps
+pub(crate) fn parse_step_line(
+    line: &str,
+    options: &ParseOptions,
+) -> Result<(ExecutionStep, Option<u64>), serde_json::Error> {
+    let parsed: StepLine = serde_json::from_str(line)?;
+    let mut step = parsed.step;
+    if options.capture_state {
+        step.stack = parsed.stack;
+        step.memory = parsed.memory;
+        step.storage = parsed.storage;
+    }
+    Ok((step, parsed.refund.as_ref().and_then(numeric_value)))
+}
+
+/// A JSON number, or a decimal/hex string
+///
+/// **Private** - gas and refund fields come in either form
+fn numeric_value(value: &serde_json::Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_str().and_then(|s| parse_gas_value(s).ok()))
+}
+
+/// Extract the gas refund counter
+///
+/// **Private** - a top-level refund field wins; otherwise the `refund`
+/// of the last step that reports one (geth structLogs carry the running
+/// counter on every step, so the last value is the final refund)
+fn extract_refund(
+    trace_obj: &serde_json::Map<String, serde_json::Value>,
+    steps: &[serde_json::Value],
+) -> Option<u64> {
+    REFUND_FIELDS
+        .iter()
+        .find_map(|field| trace_obj.get(*field).and_then(numeric_value))
+        .or_else(|| steps.iter().rev().find_map(|step| step.get("refund").and_then(numeric_value)))
+}
+
+/// Read the executed contract's address from the top-level trace fields
+///
+/// **Private** - call tracers report `to` for calls and the deployed
+/// address (also in `to`) for creates; a null `to` is skipped so a
+/// `contractAddress` field can still supply it
+fn detect_contract_address(trace_obj: &serde_json::Map<String, serde_json::Value>) -> Option<String> {
+    ADDRESS_FIELDS
+        .iter()
+        .filter_map(|field| trace_obj.get(*field)?.as_str())
+        .find(|address| !address.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Detect the 4-byte selector of the method being called
+///
+/// **Private** - the first 4 bytes of calldata, if the trace includes it.
+/// Without calldata there is no reliable answer (dispatch `PUSH4`s are
+/// table constants and masks, not the called method), so `None`.
+fn detect_entry_selector(trace_obj: &serde_json::Map<String, serde_json::Value>) -> Option<u32> {
+    CALLDATA_FIELDS
+        .iter()
+        .filter_map(|field| trace_obj.get(*field)?.as_str())
+        .find_map(|calldata| {
+            let hex = calldata.strip_prefix("0x").unwrap_or(calldata);
+            u32::from_str_radix(hex.get(..8)?, 16).ok()
+        })
+}
+
 /// Extract total gas used from trace
 ///
 /// **Private** - internal extraction logic
@@ -147,37 +422,24 @@ fn extract_total_gas(trace_obj: &serde_json::Map<String, serde_json::Value>) ->
     Ok(0) // Will be calculated later from steps
 }
 
-/// Extract execution steps from trace
-///
-/// **Private** - internal extraction logic
-fn extract_execution_steps(
-    trace_obj: &serde_json::Map<String, serde_json::Value>,
-) -> Result<Vec<ExecutionStep>, ParseError> {
-    // Try multiple possible field names
-    let step_fields = ["structLogs", "struct_logs", "steps", "trace"];
-    
-    for field in &step_fields {
-        if let Some(steps_value) = trace_obj.get(*field) {
-            if let Some(steps_array) = steps_value.as_array() {
-                return parse_steps_array(steps_array);
-            }
-        }
-    }
-    
-    // No steps found - this might be valid for very simple transactions
-    warn!("No execution steps found in trace");
-    Ok(Vec::new())
-}
-
 /// Parse array of execution steps
 ///
 /// **Private** - internal parsing logic
-fn parse_steps_array(steps_array: &[serde_json::Value]) -> Result<Vec<ExecutionStep>, ParseError> {
+fn parse_steps_array(
+    steps_array: &[serde_json::Value],
+    options: &ParseOptions,
+) -> Result<Vec<ExecutionStep>, ParseError> {
     let mut steps = Vec::with_capacity(steps_array.len());
     
     for (index, step_value) in steps_array.iter().enumerate() {
-        match serde_json::from_value::<ExecutionStep>(step_value.clone()) {
-            Ok(step) => steps.push(step),
+        // Deserialize by reference so large state arrays are never cloned
+        match ExecutionStep::deserialize(step_value) {
+            Ok(mut step) => {
+                if options.capture_state {
+                    attach_step_state(&mut step, step_value);
+                }
+                steps.push(step);
+            }
             Err(e) => {
                 // Log but don't fail - some steps may be malformed
                 warn!("Failed to parse step {}: {}", index, e);
@@ -194,6 +456,78 @@ fn parse_steps_array(steps_array: &[serde_json::Value]) -> Result<Vec<ExecutionS
     Ok(steps)
 }
 
+/// Flag the first step of every frame entered through a delegatecall
+///
+/// **Private** - a step one level deeper than a delegatecall step starts
+/// the callee frame; the stack builder names that frame `delegatecall`
+fn mark_delegated_frames(steps: &mut [ExecutionStep]) {
+    for index in 1..steps.len() {
+        let previous = &steps[index - 1];
+        let is_delegate = previous
+            .op
+            .as_deref()
+            .is_some_and(|op| DELEGATE_CALL_OPS.iter().any(|name| op.eq_ignore_ascii_case(name)));
+        if is_delegate && steps[index].depth > previous.depth {
+            steps[index].delegated = true;
+        }
+    }
+}
+
+/// Flag the first step of every constructor frame entered through a create
+///
+/// **Private** - a step one level deeper than a CREATE/CREATE2 step starts
+/// the constructor. The address is pushed onto the caller's stack once the
+/// constructor returns, so it is only known with `capture_state`;
+/// otherwise the frame is marked with `UNKNOWN_CREATE_ADDRESS`.
+fn mark_created_frames(steps: &mut [ExecutionStep]) {
+    for index in 1..steps.len() {
+        let previous = &steps[index - 1];
+        let is_create = previous
+            .op
+            .as_deref()
+            .is_some_and(|op| CREATE_OPS.iter().any(|name| op.eq_ignore_ascii_case(name)));
+        if !is_create || steps[index].depth <= previous.depth {
+            continue;
+        }
+        
+        let caller_depth = previous.depth;
+        let address = steps[index..]
+            .iter()
+            .find(|step| step.depth <= caller_depth)
+            .and_then(|step| step.stack.as_ref()?.last())
+            .and_then(|word| word_to_address(word))
+            .unwrap_or_else(|| UNKNOWN_CREATE_ADDRESS.to_string());
+        steps[index].created = Some(address);
+    }
+}
+
+/// Low 20 bytes of a 32-byte stack word as a `0x` address
+///
+/// **Private** - `None` for malformed words and for zero (a failed create)
+fn word_to_address(word: &str) -> Option<String> {
+    let hex = word.trim_start_matches("0x");
+    if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    if hex.chars().all(|c| c == '0') {
+        return None;
+    }
+    let padded = format!("{:0>40}", hex);
+    Some(format!("0x{}", &padded[padded.len() - 40..]).to_lowercase())
+}
+
+/// Copy geth `stack`/`memory`/`storage` from a raw step
+///
+/// **Private** - only called when `capture_state` is set; malformed
+/// fields are left as `None`
+fn attach_step_state(step: &mut ExecutionStep, step_value: &serde_json::Value) {
+    let field = |name: &str| step_value.get(name).filter(|value| !value.is_null());
+    
+    step.stack = field("stack").and_then(|v| Vec::<String>::deserialize(v).ok());
+    step.memory = field("memory").and_then(|v| Vec::<String>::deserialize(v).ok());
+    step.storage = field("storage").and_then(|v| BTreeMap::<String, String>::deserialize(v).ok());
+}
+
 /// Parse gas value from hex string or decimal
 ///
 /// **Private** - internal utility
@@ -213,6 +547,9 @@ fn parse_gas_value(value: &str) -> Result<u64, ParseError> {
 ///
 /// **Public** - used by commands to create final output
 ///
+/// `fingerprint` is left empty here; callers holding the collapsed stacks
+/// fill it in with `aggregator::compute_fingerprint`.
+///
 /// # Arguments
 /// * `parsed_trace` - Parsed trace data
 /// * `hot_paths` - Pre-calculated hot paths (from aggregator)
@@ -225,9 +562,12 @@ pub fn to_profile(
 ) -> Profile {
     use chrono::Utc;
     
-    Profile {
+    let profile = Profile {
         version: SCHEMA_VERSION.to_string(),
+        fingerprint: String::new(),
         transaction_hash: parsed_trace.transaction_hash.clone(),
+        contract_address: parsed_trace.contract_address.clone(),
+        label: None,
         total_gas: parsed_trace.total_gas_used,
         hostio_summary: super::schema::HostIoSummary {
             total_calls: parsed_trace.hostio_stats.total_calls(),
@@ -236,7 +576,72 @@ pub fn to_profile(
         },
         hot_paths,
         generated_at: Utc::now().to_rfc3339(),
+        metrics: super::schema::ProfileMetrics::compute(
+            parsed_trace.total_gas_used,
+            parsed_trace.execution_steps.len() as u64,
+            parsed_trace.hostio_stats.total_gas(),
+            parsed_trace.hostio_stats.total_calls(),
+        ),
+        unattributed: Default::default(),
+        gas_breakdown: None,
+        hot_steps: Vec::new(),
+        source: None,
+        collapsed_stacks: Vec::new(),
+        call_tree: None,
+        calldata: None,
+        gas_accounting: parsed_trace.gas_refund.map(|refund| {
+            let gross_gas = parsed_trace.execution_steps.iter().map(|step| step.gas_cost).sum();
+            super::schema::GasAccounting::new(gross_gas, parsed_trace.total_gas_used, refund)
+        }),
+    };
+    
+    if let Some(ratio) = profile.hostio_gas_excess() {
+        warn!(
+            "HostIO gas ({}) exceeds total gas ({}) by {:.2}x; HostIO costs are likely in \
+             ink or another unit (divide by ~{:.0} to convert)",
+            profile.hostio_summary.total_hostio_gas,
+            profile.total_gas,
+            ratio,
+            ratio
+        );
     }
+    
+    profile
+}
+
+/// Pick the most expensive steps that carry captured state
+///
+/// **Public** - fills `Profile::hot_steps` when state capture is on
+///
+/// # Arguments
+/// * `parsed_trace` - Trace parsed with `capture_state`
+/// * `limit` - Maximum number of steps to return; 0 (as with
+///   `--top-paths 0`) and anything larger mean `MAX_HOT_STEPS`
+///
+/// # Returns
+/// Steps ordered by gas cost (descending); empty if no state was captured
+pub fn collect_hot_steps(parsed_trace: &ParsedTrace, limit: usize) -> Vec<HotStep> {
+    let mut steps: Vec<&ExecutionStep> = parsed_trace
+        .execution_steps
+        .iter()
+        .filter(|step| step.stack.is_some() || step.memory.is_some() || step.storage.is_some())
+        .collect();
+    
+    steps.sort_by_key(|step| std::cmp::Reverse(step.gas_cost));
+    
+    steps
+        .into_iter()
+        .take(if limit == 0 { MAX_HOT_STEPS } else { limit.min(MAX_HOT_STEPS) })
+        .map(|step| HotStep {
+            pc: step.pc,
+            op: step.op.clone(),
+            depth: step.depth,
+            gas_cost: step.gas_cost,
+            stack: step.stack.clone(),
+            memory: step.memory.clone(),
+            storage: step.storage.clone(),
+        })
+        .collect()
 }
 
 /// Validate that we can parse a trace (quick check)
@@ -270,11 +675,71 @@ pub fn validate_trace_format(raw_trace: &serde_json::Value) -> Result<(), ParseE
     Ok(())
 }
 
+/// Check that gas remaining never rises within a call frame
+///
+/// **Public** - deeper validation behind `ParseOptions::strict_validate`
+///
+/// Each step is compared with the previous step at the same depth.
+/// Entering a call starts a fresh frame, and returning to a caller is
+/// compared with the caller's gas before the call, which can only have
+/// gone down.
+///
+/// # Errors
+/// * `ParseError::InvalidFormat` - Names the first offending step
+pub fn validate_gas_monotonic(steps: &[ExecutionStep]) -> Result<(), ParseError> {
+    // (depth, last gas remaining) for the frames currently active, kept
+    // sparse so a bogus depth costs one entry rather than `depth` of them
+    let mut last_gas: Vec<(u32, u64)> = Vec::new();
+    
+    for (index, step) in steps.iter().enumerate() {
+        while last_gas.last().is_some_and(|&(depth, _)| depth > step.depth) {
+            last_gas.pop();
+        }
+        
+        match last_gas.last_mut() {
+            Some((depth, previous)) if *depth == step.depth => {
+                if step.gas > *previous {
+                    return Err(ParseError::InvalidFormat(format!(
+                        "Gas remaining rose from {} to {} at step {} (pc {}, depth {})",
+                        previous, step.gas, index, step.pc, step.depth
+                    )));
+                }
+                *previous = step.gas;
+            }
+            _ => last_gas.push((step.depth, step.gas)),
+        }
+    }
+    
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_validate_gas_monotonic() {
+        let step = |gas: u64, depth: u32| ExecutionStep { gas, depth, ..Default::default() };
+        
+        // Call into depth 2 with less gas, then return with the caller's gas lower
+        let valid = [step(1000, 1), step(990, 1), step(500, 2), step(400, 2), step(800, 1)];
+        assert!(validate_gas_monotonic(&valid).is_ok());
+        assert!(validate_gas_monotonic(&[step(1000, 1), step(900, u32::MAX), step(800, 1)]).is_ok());
+        
+        let invalid = [step(1000, 1), step(990, 1), step(995, 1)];
+        let err = validate_gas_monotonic(&invalid).unwrap_err();
+        assert!(err.to_string().contains("step 2"));
+        
+        let raw = json!({ "gasUsed": 10, "structLogs": [
+            { "pc": 0, "gas": 100, "gasCost": 3, "depth": 1, "op": "PUSH1" },
+            { "pc": 2, "gas": 200, "gasCost": 3, "depth": 1, "op": "ADD" }
+        ]});
+        assert!(parse_trace("0x1", &raw).is_ok());
+        let strict = ParseOptions { strict_validate: true, ..Default::default() };
+        assert!(parse_trace_with_options("0x1", &raw, &strict).is_err());
+    }
+
     #[test]
     fn test_parse_gas_value() {
         assert_eq!(parse_gas_value("1000").unwrap(), 1000);
@@ -314,6 +779,156 @@ mod tests {
         assert_eq!(parsed.transaction_hash, "0xabc123");
     }
 
+    #[test]
+    fn test_parse_step_stream() {
+        let options = ParseOptions::default();
+        let line = |text: &str| parse_step_line(text, &options).unwrap().0;
+        let stream = StepStream {
+            steps: vec![
+                line(r#"{"pc": 0, "op": "DELEGATECALL", "gas": 1000, "gasCost": 700, "depth": 1}"#),
+                line(r#"{"pc": 0, "op": "SLOAD", "gas": 300, "gasCost": 100, "depth": 2}"#),
+            ],
+            refund: Some(4800),
+            sha256: String::new(),
+        };
+        
+        let parsed = parse_step_stream("0xabc", stream, &options).unwrap();
+        assert_eq!(parsed.total_gas_used, 800);
+        assert_eq!(parsed.gas_refund, Some(4800));
+        assert!(parsed.execution_steps[1].delegated);
+        assert_eq!(parsed.hostio_stats.total_calls(), 0);
+    }
+
+    #[test]
+    fn test_parse_raw_trace_rejects_ambiguous_steps() {
+        let raw_trace = json!({
+            "gasUsed": 100,
+            "structLogs": [{ "op": "ADD", "gasCost": 3, "depth": 1 }],
+            "trace": [{ "op": "MUL", "gasCost": 5, "depth": 1 }],
+            "hostio": [{ "type": "storage_load", "gas": 2100 }]
+        });
+        
+        let err = parse_trace("0xabc", &raw_trace).unwrap_err();
+        assert!(err.to_string().contains("structLogs, trace"), "{}", err);
+        
+        let typed = RawTraceData::deserialize(json!({
+            "gasUsed": 100,
+            "structLogs": [{ "op": "ADD", "gasCost": 3, "depth": 1 }],
+            "hostio": [{ "type": "storage_load", "gas": 2100 }]
+        }))
+        .unwrap();
+        let parsed = parse_raw_trace("0xabc", &typed, &ParseOptions::default()).unwrap();
+        assert_eq!(parsed.execution_steps.len(), 1);
+        assert_eq!(parsed.hostio_stats.total_calls(), 1);
+    }
+
+    #[test]
+    fn test_to_profile_metrics() {
+        let raw_trace = json!({
+            "gasUsed": 1000,
+            "structLogs": [
+                { "op": "ADD", "gasCost": 3, "depth": 1 },
+                { "op": "MUL", "gasCost": 5, "depth": 1 },
+                { "op": "SUB", "gasCost": 3, "depth": 1 },
+                { "op": "DIV", "gasCost": 5, "depth": 1 }
+            ],
+            "hostio": [
+                { "type": "storage_load", "gas": 2100 },
+                { "type": "storage_load", "gas": 100 }
+            ]
+        });
+        
+        let parsed = parse_trace("0xtest", &raw_trace).unwrap();
+        let profile = to_profile(&parsed, Vec::new());
+        
+        assert_eq!(profile.metrics.total_steps, 4);
+        assert_eq!(profile.metrics.gas_per_step, 250.0);
+        assert_eq!(profile.metrics.gas_per_hostio_call, 1100.0);
+    }
+
+    #[test]
+    fn test_detect_contract_address() {
+        let call = json!({ "gasUsed": 10, "to": "0xABCD", "structLogs": [] });
+        let parsed = parse_trace("0xtest", &call).unwrap();
+        assert_eq!(parsed.contract_address.as_deref(), Some("0xabcd"));
+        assert_eq!(to_profile(&parsed, Vec::new()).contract_address.as_deref(), Some("0xabcd"));
+        
+        // Creates have a null `to`; the deployed address is used instead
+        let create = json!({ "gasUsed": 10, "to": null, "contractAddress": "0xbeef", "structLogs": [] });
+        let parsed = parse_trace("0xtest", &create).unwrap();
+        assert_eq!(parsed.contract_address.as_deref(), Some("0xbeef"));
+        
+        let parsed = parse_trace("0xtest", &json!({ "gasUsed": 10, "structLogs": [] })).unwrap();
+        assert_eq!(parsed.contract_address, None);
+        
+        // callTracer output has the address but no steps
+        let tree = json!({ "type": "CALL", "from": "0x1", "to": "0xCAFE", "gasUsed": 21000, "input": "0xa9059cbb" });
+        let parsed = parse_trace("0xtest", &tree).unwrap();
+        assert_eq!(parsed.contract_address.as_deref(), Some("0xcafe"));
+        assert_eq!(parsed.entry_selector, Some(0xa9059cbb));
+        assert!(parsed.execution_steps.is_empty());
+        
+        assert!(parse_trace("0xtest", &json!("0x")).is_err());
+    }
+
+    #[test]
+    fn test_detect_entry_selector() {
+        let from_calldata = json!({
+            "gasUsed": 10,
+            "input": "0xa9059cbb000000000000000000000000",
+            "structLogs": []
+        });
+        let parsed = parse_trace("0xtest", &from_calldata).unwrap();
+        assert_eq!(parsed.entry_selector, Some(0xa9059cbb));
+        
+        // Dispatch-table constants are not the called method: no guessing
+        let without_calldata = json!({
+            "gasUsed": 10,
+            "structLogs": [
+                { "op": "PUSH4", "gasCost": 3, "depth": 1, "stack": ["0x0"] },
+                { "op": "EQ", "gasCost": 3, "depth": 1, "stack": ["0x0", "0x70a08231"] }
+            ]
+        });
+        let options = ParseOptions { capture_state: true, ..Default::default() };
+        let parsed = parse_trace_with_options("0xtest", &without_calldata, &options).unwrap();
+        assert_eq!(parsed.entry_selector, None);
+    }
+
+    #[test]
+    fn test_capture_state_opt_in() {
+        let raw_trace = json!({
+            "gasUsed": 100,
+            "structLogs": [
+                { "op": "PUSH1", "gasCost": 3, "depth": 1, "stack": [] },
+                {
+                    "op": "SSTORE", "gasCost": 20000, "depth": 1,
+                    "stack": ["0x1", "0x2"],
+                    "memory": ["00"],
+                    "storage": { "0x01": "0x02" }
+                }
+            ]
+        });
+        
+        let parsed = parse_trace("0xtest", &raw_trace).unwrap();
+        assert!(parsed.execution_steps[1].stack.is_none());
+        assert!(collect_hot_steps(&parsed, 10).is_empty());
+        
+        let options = ParseOptions { capture_state: true, ..Default::default() };
+        let parsed = parse_trace_with_options("0xtest", &raw_trace, &options).unwrap();
+        let hot = collect_hot_steps(&parsed, 1);
+        assert_eq!(hot.len(), 1);
+        assert_eq!(hot[0].op.as_deref(), Some("SSTORE"));
+        assert_eq!(hot[0].stack.as_ref().unwrap().len(), 2);
+        assert_eq!(hot[0].storage.as_ref().unwrap()["0x01"], "0x02");
+        
+        // No limit still stops at MAX_HOT_STEPS
+        let steps: Vec<serde_json::Value> = (0..MAX_HOT_STEPS + 5)
+            .map(|pc| json!({ "pc": pc, "op": "ADD", "gasCost": 3, "depth": 1, "stack": [] }))
+            .collect();
+        let parsed = parse_trace_with_options("0xtest", &json!({ "structLogs": steps }), &options).unwrap();
+        assert_eq!(collect_hot_steps(&parsed, 0).len(), MAX_HOT_STEPS);
+    }
+
     #[test]
     fn test_validate_trace_format() {
         let valid_trace = json!({
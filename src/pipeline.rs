@@ -0,0 +1,239 @@
+//! Library-level pipeline that runs the whole profiling flow.
+//!
+//! `Pipeline` mirrors what the `capture` command does
+//! (fetch → parse → aggregate → hot paths → profile → flamegraph)
+//! but returns the results as data instead of writing files.
+//!
+//! # Example
+//! ```ignore
+//! use stylus_trace_studio::Pipeline;
+//!
+//! let (profile, svg) = Pipeline::new()
+//!     .rpc("http://localhost:8547")
+//!     .tx("0xabc123...")
+//!     .top_paths(20)
+//!     .flamegraph(FlamegraphConfig::default())
+//!     .run()?;
+//! ```
+
+use crate::aggregator::{
+    build_collapsed_stacks_with_options, calculate_hot_paths, compute_fingerprint,
+    summarize_unattributed, StackBuildOptions,
+};
+use crate::flamegraph::{generate_flamegraph, FlamegraphConfig};
+use crate::parser::{parse_raw_trace, parse_trace, to_profile, ParseOptions, Profile};
+use crate::rpc::RpcClient;
+use anyhow::{Context, Result};
+use log::{debug, info};
+
+/// Default RPC endpoint (local Nitro dev node)
+const DEFAULT_RPC_URL: &str = "http://localhost:8547";
+
+/// Default number of hot paths to keep
+const DEFAULT_TOP_PATHS: usize = 20;
+
+/// Fluent builder that orchestrates the full profiling flow
+///
+/// **Public** - main entry point for embedding the crate as a library
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    rpc_url: String,
+    tx_hash: Option<String>,
+    tracer: Option<String>,
+    raw_trace: Option<serde_json::Value>,
+    top_paths: usize,
+    stack_options: StackBuildOptions,
+    flamegraph: Option<FlamegraphConfig>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            rpc_url: DEFAULT_RPC_URL.to_string(),
+            tx_hash: None,
+            tracer: None,
+            raw_trace: None,
+            top_paths: DEFAULT_TOP_PATHS,
+            stack_options: StackBuildOptions::default(),
+            flamegraph: None,
+        }
+    }
+}
+
+impl Pipeline {
+    /// Create a pipeline with default settings
+    ///
+    /// **Public** - constructor
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Set the RPC endpoint URL
+    ///
+    /// **Public** - builder pattern
+    pub fn rpc(mut self, url: impl Into<String>) -> Self {
+        self.rpc_url = url.into();
+        self
+    }
+    
+    /// Set the transaction hash to profile
+    ///
+    /// **Public** - builder pattern
+    pub fn tx(mut self, hash: impl Into<String>) -> Self {
+        self.tx_hash = Some(hash.into());
+        self
+    }
+    
+    /// Use a specific tracer instead of the default opcode tracer
+    ///
+    /// **Public** - builder pattern
+    pub fn tracer(mut self, tracer: impl Into<String>) -> Self {
+        self.tracer = Some(tracer.into());
+        self
+    }
+    
+    /// Use an already-fetched raw trace instead of calling the RPC
+    ///
+    /// **Public** - builder pattern (useful for tests and offline analysis)
+    pub fn trace(mut self, raw_trace: serde_json::Value) -> Self {
+        self.raw_trace = Some(raw_trace);
+        self
+    }
+    
+    /// Set the number of hot paths to keep in the profile
+    ///
+    /// **Public** - builder pattern
+    pub fn top_paths(mut self, top_paths: usize) -> Self {
+        self.top_paths = top_paths;
+        self
+    }
+    
+    /// Set options for collapsed stack building
+    ///
+    /// **Public** - builder pattern
+    pub fn stack_options(mut self, options: StackBuildOptions) -> Self {
+        self.stack_options = options;
+        self
+    }
+    
+    /// Request a flamegraph rendered with the given config
+    ///
+    /// **Public** - builder pattern
+    pub fn flamegraph(mut self, config: FlamegraphConfig) -> Self {
+        self.flamegraph = Some(config);
+        self
+    }
+    
+    /// Run the pipeline
+    ///
+    /// **Public** - executes every step and returns the results
+    ///
+    /// # Returns
+    /// The profile, plus the SVG content if a flamegraph was requested
+    ///
+    /// # Errors
+    /// * No transaction hash set
+    /// * RPC connection failures
+    /// * Trace parsing errors
+    /// * Flamegraph generation errors
+    pub fn run(&self) -> Result<(Profile, Option<String>)> {
+        let tx_hash = self.tx_hash.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("Pipeline requires a transaction hash (call .tx())"))?;
+        
+        let parsed_trace = match &self.raw_trace {
+            Some(raw_trace) => parse_trace(tx_hash, raw_trace),
+            None => {
+                info!("Fetching trace for {} from {}", tx_hash, self.rpc_url);
+                let client = RpcClient::new(&self.rpc_url)
+                    .context("Failed to create RPC client")?;
+                let trace = client.debug_trace_transaction_with_tracer(tx_hash, self.tracer.as_deref())
+                    .context(format!("Failed to fetch trace for transaction {}", tx_hash))?;
+                parse_raw_trace(tx_hash, &trace, &ParseOptions::default())
+            }
+        }
+        .context("Failed to parse trace data")?;
+        
+        let stacks = build_collapsed_stacks_with_options(&parsed_trace, &self.stack_options);
+        debug!("Pipeline built {} unique stacks", stacks.len());
+        
+        let hot_paths = calculate_hot_paths(&stacks, parsed_trace.total_gas_used, self.top_paths);
+        
+        let svg = match &self.flamegraph {
+            Some(config) => Some(
+                generate_flamegraph(&stacks, Some(config))
+                    .context("Failed to generate flamegraph")?,
+            ),
+            None => None,
+        };
+        
+        let mut profile = to_profile(&parsed_trace, hot_paths);
+        profile.fingerprint = compute_fingerprint(&stacks, parsed_trace.total_gas_used);
+        profile.unattributed = summarize_unattributed(&parsed_trace);
+        
+        Ok((profile, svg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::testgen::TraceBuilder;
+
+    fn sample_trace() -> serde_json::Value {
+        TraceBuilder::new()
+            .step("PUSH1", 1, 3)
+            .step("SLOAD", 1, 5000)
+            .step("ADD", 2, 5)
+            .build()
+    }
+
+    #[test]
+    fn test_pipeline_requires_tx() {
+        let result = Pipeline::new().trace(sample_trace()).run();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_pipeline_from_raw_trace() {
+        let (profile, svg) = Pipeline::new()
+            .tx("0xabc")
+            .trace(sample_trace())
+            .top_paths(2)
+            .run()
+            .unwrap();
+        
+        assert_eq!(profile.transaction_hash, "0xabc");
+        assert_eq!(profile.total_gas, 5008);
+        assert_eq!(profile.hot_paths.len(), 2);
+        assert_eq!(profile.hot_paths[0].stack, "call;SLOAD");
+        assert!(svg.is_none());
+    }
+
+    #[test]
+    fn test_pipeline_fingerprint_is_deterministic() {
+        let trace = TraceBuilder::new()
+            .function_step("main", "SLOAD", 1, 2100)
+            .step("ADD", 1, 3)
+            .build();
+        
+        let pipeline = Pipeline::new().tx("0xabc").trace(trace);
+        let (first, _) = pipeline.run().unwrap();
+        let (second, _) = pipeline.run().unwrap();
+        
+        assert!(!first.fingerprint.is_empty());
+        assert_eq!(first.fingerprint, second.fingerprint);
+    }
+
+    #[test]
+    fn test_pipeline_with_flamegraph() {
+        let (_, svg) = Pipeline::new()
+            .tx("0xabc")
+            .trace(sample_trace())
+            .flamegraph(FlamegraphConfig::default())
+            .run()
+            .unwrap();
+        
+        let svg = svg.expect("flamegraph requested");
+        assert!(svg.contains("<svg"));
+    }
+}
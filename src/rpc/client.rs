@@ -1,29 +1,55 @@
 //! HTTP client for communicating with Arbitrum Nitro node RPC endpoint.
 
-use super::types::{JsonRpcResponse, RawTraceData};
+use super::types::{
+    append_trace_page, parse_hex_quantity, BlockTransaction, JsonRpcRequest, JsonRpcResponse,
+    LogEntry, RawTraceData, TraceConfig, Transaction, TransactionReceipt,
+};
 use crate::utils::error::RpcError;
-use crate::utils::config::DEFAULT_RPC_TIMEOUT;
-use log::{debug, info};
-use reqwest::blocking::Client;
+use crate::utils::config::{
+    DEFAULT_RPC_RETRIES, DEFAULT_RPC_TIMEOUT, MAX_RPC_REDIRECTS, MAX_TRACE_PAGES, RPC_RETRY_BASE_DELAY,
+};
+use log::{debug, info, warn};
+use reqwest::blocking::{Client, Response};
+use reqwest::header::CONTENT_ENCODING;
+use reqwest::redirect;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
 use std::time::Duration;
 
+/// Substrings of `-32000` messages that signal a transient node condition
+const RETRYABLE_MESSAGES: &[&str] = &[
+    "resource temporarily unavailable",
+    "too many requests",
+    "rate limit",
+    "timeout",
+    "timed out",
+    "try again",
+    "busy",
+];
+
+/// Trace fields that may name the traced transaction (strict hash check)
+const TRACE_HASH_FIELDS: &[&str] = &["txHash", "transactionHash", "hash"];
+
 /// RPC client for fetching trace data from Nitro node
 pub struct RpcClient {
     client: Client,
     rpc_url: String,
+    max_retries: u32,
+    trace_config: TraceConfig,
+    strict_hash_check: bool,
 }
 
 impl RpcClient {
     /// Create a new RPC client
     pub fn new(rpc_url: impl Into<String>) -> Result<Self, RpcError> {
-        let client = Client::builder()
-            .timeout(DEFAULT_RPC_TIMEOUT)
-            .build()
-            .map_err(RpcError::RequestFailed)?;
+        let client = build_http_client(DEFAULT_RPC_TIMEOUT)?;
         
         Ok(Self {
             client,
             rpc_url: rpc_url.into(),
+            max_retries: DEFAULT_RPC_RETRIES,
+            trace_config: TraceConfig::default(),
+            strict_hash_check: false,
         })
     }
 
@@ -32,23 +58,48 @@ impl RpcClient {
         rpc_url: impl Into<String>,
         timeout: Duration,
     ) -> Result<Self, RpcError> {
-        let client = Client::builder()
-            .timeout(timeout)
-            .build()
-            .map_err(RpcError::RequestFailed)?;
+        let client = build_http_client(timeout)?;
         
         Ok(Self {
             client,
             rpc_url: rpc_url.into(),
+            max_retries: DEFAULT_RPC_RETRIES,
+            trace_config: TraceConfig::default(),
+            strict_hash_check: false,
         })
     }
 
+    /// Set how many times retryable errors are retried (0 disables)
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set tracer config options (e.g. disable storage/memory for gas-only traces)
+    pub fn with_trace_config(mut self, trace_config: TraceConfig) -> Self {
+        self.trace_config = trace_config;
+        self
+    }
+
+    /// Reject traces that name a different transaction than requested
+    ///
+    /// Guards against proxies serving a cached trace for another hash;
+    /// traces that carry no hash are accepted unchecked.
+    pub fn with_strict_hash_check(mut self, enabled: bool) -> Self {
+        self.strict_hash_check = enabled;
+        self
+    }
+
     /// Fetch trace with default (no tracer)
     pub fn debug_trace_transaction(&self, tx_hash: &str) -> Result<RawTraceData, RpcError> {
         self.debug_trace_transaction_with_tracer(tx_hash, None)
     }
     
     /// Fetch trace with optional tracer
+    ///
+    /// If the provider paginates the trace (a `cursor` in the response),
+    /// the remaining pages are fetched and their steps appended, so the
+    /// returned trace is complete.
     pub fn debug_trace_transaction_with_tracer(
         &self,
         tx_hash: &str,
@@ -58,23 +109,307 @@ impl RpcClient {
         
         info!("Fetching trace for transaction: {}", tx_hash);
         
-        // Build params based on tracer
-        let params = if let Some(tracer_name) = tracer {
-            serde_json::json!([
-                tx_hash,
-                { "tracer": tracer_name }
-            ])
+        let params = self.trace_params(&tx_hash, tracer);
+        let (mut trace, mut cursor) =
+            self.call_paged::<RawTraceData>("debug_traceTransaction", params, &tx_hash)?;
+        let mut pages = 1;
+        
+        while let Some(next) = cursor {
+            if pages >= MAX_TRACE_PAGES {
+                return Err(RpcError::InvalidResponse(format!(
+                    "Trace exceeds {} pages",
+                    MAX_TRACE_PAGES
+                )));
+            }
+            debug!("Fetching trace page {} (cursor {})", pages + 1, next);
+            
+            let mut options = self.trace_config.to_tracer_options(tracer);
+            options["cursor"] = next.into();
+            let (page, next_cursor) = self.call_paged(
+                "debug_traceTransaction",
+                serde_json::json!([tx_hash, options]),
+                &tx_hash,
+            )?;
+            
+            append_trace_page(&mut trace, page)?;
+            cursor = next_cursor;
+            pages += 1;
+        }
+        
+        if pages > 1 {
+            info!("Trace assembled from {} pages", pages);
+        }
+        
+        if self.strict_hash_check {
+            verify_trace_hash(&trace, &tx_hash)?;
+        }
+        
+        Ok(trace)
+    }
+    
+    /// Fetch traces for several transactions in one JSON-RPC batch
+    ///
+    /// **Public** - one HTTP round-trip instead of one per transaction
+    ///
+    /// Each request gets its index as id and responses are matched back
+    /// by id, so the result order follows `tx_hashes` whatever order the
+    /// node answers in. A trace that comes back paginated is completed
+    /// with `debug_trace_transaction`.
+    ///
+    /// # Returns
+    /// One result per hash. If the batch itself fails (transport error,
+    /// malformed body) every entry carries that error.
+    pub fn debug_trace_transactions(&self, tx_hashes: &[&str]) -> Vec<Result<RawTraceData, RpcError>> {
+        let hashes: Vec<String> = tx_hashes.iter().map(|hash| normalize_tx_hash(hash)).collect();
+        let requests: Vec<JsonRpcRequest> = hashes
+            .iter()
+            .enumerate()
+            .map(|(id, hash)| {
+                JsonRpcRequest::new("debug_traceTransaction", self.trace_params(hash, None), id as u64)
+            })
+            .collect();
+        
+        info!("Fetching {} traces in one batch", requests.len());
+        
+        let responses = match self.send_batch(&requests) {
+            Ok(responses) => responses,
+            Err(e) => {
+                let message = e.to_string();
+                return hashes
+                    .iter()
+                    .map(|_| Err(RpcError::InvalidResponse(format!("Batch request failed: {}", message))))
+                    .collect();
+            }
+        };
+        
+        demux_batch(responses, &hashes)
+            .into_iter()
+            .zip(&hashes)
+            .map(|(result, hash)| match result {
+                Ok((_, Some(_))) => self.debug_trace_transaction(hash),
+                Ok((trace, None)) if self.strict_hash_check => {
+                    verify_trace_hash(&trace, hash).map(|_| trace)
+                }
+                other => other.map(|(trace, _)| trace),
+            })
+            .collect()
+    }
+    
+    /// Fetch the latest block number
+    ///
+    /// **Public** - used by watch mode to poll for new blocks
+    pub fn block_number(&self) -> Result<u64, RpcError> {
+        let hex: String = self.call("eth_blockNumber", serde_json::json!([]), "latest")?;
+        parse_hex_quantity(&hex)
+    }
+    
+    /// Fetch the transactions included in a block
+    ///
+    /// **Public** - used by watch mode to find transactions to a contract
+    ///
+    /// # Arguments
+    /// * `number` - Block number
+    ///
+    /// # Returns
+    /// Transactions in the block
+    pub fn block_transactions(&self, number: u64) -> Result<Vec<BlockTransaction>, RpcError> {
+        #[derive(serde::Deserialize)]
+        struct Block {
+            #[serde(default)]
+            transactions: Vec<BlockTransaction>,
+        }
+        
+        let tag = format!("0x{:x}", number);
+        let block: serde_json::Value = self.call(
+            "eth_getBlockByNumber",
+            serde_json::json!([tag, true]),
+            &tag,
+        )?;
+        
+        let block: Block = serde_json::from_value(block)
+            .map_err(|e| RpcError::InvalidResponse(format!("Malformed block {}: {}", tag, e)))?;
+        Ok(block.transactions)
+    }
+    
+    /// Resolve the hash of the transaction at a position within a block
+    ///
+    /// **Public** - lets callers enumerate transactions by block and index
+    ///
+    /// # Arguments
+    /// * `block` - Block number
+    /// * `index` - Zero-based position of the transaction in the block
+    ///
+    /// # Errors
+    /// * `RpcError::TransactionNotFound` - Index past the end of the block
+    pub fn get_tx_hash_by_block_index(&self, block: u64, index: usize) -> Result<String, RpcError> {
+        let transactions = self.block_transactions(block)?;
+        let count = transactions.len();
+        
+        transactions
+            .into_iter()
+            .nth(index)
+            .map(|tx| tx.hash)
+            .ok_or_else(|| RpcError::TransactionNotFound(format!(
+                "index {} out of range for block {} ({} transactions)",
+                index, block, count
+            )))
+    }
+    
+    /// Fetch the logs a contract emitted in an inclusive block range
+    ///
+    /// **Public** - used by the range command to enumerate a contract's
+    /// transactions; callers keep the range small since nodes cap it
+    ///
+    /// # Arguments
+    /// * `address` - Emitting contract
+    /// * `from_block` - First block (inclusive)
+    /// * `to_block` - Last block (inclusive)
+    pub fn get_logs(&self, address: &str, from_block: u64, to_block: u64) -> Result<Vec<LogEntry>, RpcError> {
+        let range = format!("0x{:x}-0x{:x}", from_block, to_block);
+        self.call(
+            "eth_getLogs",
+            serde_json::json!([{
+                "address": address,
+                "fromBlock": format!("0x{:x}", from_block),
+                "toBlock": format!("0x{:x}", to_block),
+            }]),
+            &range,
+        )
+    }
+    
+    /// Fetch a transaction by hash
+    ///
+    /// **Public** - source of the calldata recorded by `--calldata`
+    pub fn get_transaction(&self, tx_hash: &str) -> Result<Transaction, RpcError> {
+        let tx_hash = normalize_tx_hash(tx_hash);
+        self.call("eth_getTransactionByHash", serde_json::json!([tx_hash]), &tx_hash)
+    }
+    
+    /// Fetch a transaction receipt
+    ///
+    /// **Public** - source of the L1/L2 gas split (traces lack L1 costs)
+    pub fn transaction_receipt(&self, tx_hash: &str) -> Result<TransactionReceipt, RpcError> {
+        let tx_hash = normalize_tx_hash(tx_hash);
+        self.call("eth_getTransactionReceipt", serde_json::json!([tx_hash]), &tx_hash)
+    }
+    
+    /// Number of blocks confirming a transaction (its own block counts as 1)
+    ///
+    /// **Public** - used by capture's `--confirmations` check
+    ///
+    /// # Returns
+    /// 0 while the transaction is pending (receipt without a block)
+    ///
+    /// # Errors
+    /// * `RpcError::InvalidResponse` - No receipt yet (unknown or not mined)
+    pub fn confirmations(&self, tx_hash: &str) -> Result<u64, RpcError> {
+        let Some(block) = self.transaction_receipt(tx_hash)?.block()? else {
+            return Ok(0);
+        };
+        let head = self.block_number()?;
+        Ok(head.checked_sub(block).map_or(0, |behind| behind + 1))
+    }
+    
+    /// Build debug_traceTransaction params for the configured tracer options
+    ///
+    /// **Private** - the options object is omitted when nothing is set so
+    /// the node's default tracer is used
+    fn trace_params(&self, tx_hash: &str, tracer: Option<&str>) -> serde_json::Value {
+        if tracer.is_some() || self.trace_config.disables_state() {
+            serde_json::json!([tx_hash, self.trace_config.to_tracer_options(tracer)])
         } else {
             serde_json::json!([tx_hash])
-        };
+        }
+    }
+    
+    /// POST a batch of requests, retrying only retryable failures
+    ///
+    /// **Private** - transport for `debug_trace_transactions`
+    fn send_batch(
+        &self,
+        requests: &[JsonRpcRequest],
+    ) -> Result<Vec<JsonRpcResponse<RawTraceData>>, RpcError> {
+        let mut attempt = 0;
         
+        loop {
+            let result = self
+                .client
+                .post(&self.rpc_url)
+                .json(requests)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .map_err(RpcError::RequestFailed)
+                .and_then(|response| {
+                    check_content_encoding(&response)?;
+                    response.json().map_err(RpcError::RequestFailed)
+                });
+            
+            match result {
+                Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                    let delay = RPC_RETRY_BASE_DELAY * 2u32.pow(attempt);
+                    attempt += 1;
+                    warn!("Batch request failed ({}); retry {}/{} in {:?}", e, attempt, self.max_retries, delay);
+                    std::thread::sleep(delay);
+                }
+                result => return result,
+            }
+        }
+    }
+    
+    /// Send a JSON-RPC request, retrying only retryable failures
+    ///
+    /// **Private** - shared transport for all RPC methods
+    ///
+    /// `subject` identifies the requested object (tx hash, block tag) in
+    /// mapped errors. Retries back off exponentially from
+    /// `RPC_RETRY_BASE_DELAY`.
+    fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+        subject: &str,
+    ) -> Result<T, RpcError> {
+        self.call_paged(method, params, subject).map(|(result, _)| result)
+    }
+    
+    /// Like `call`, also returning the response's pagination cursor
+    ///
+    /// **Private** - used for paginated traces
+    fn call_paged<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+        subject: &str,
+    ) -> Result<(T, Option<String>), RpcError> {
+        let mut attempt = 0;
+        
+        loop {
+            match self.call_once(method, &params, subject) {
+                Err(e) if e.is_retryable() && attempt < self.max_retries => {
+                    let delay = RPC_RETRY_BASE_DELAY * 2u32.pow(attempt);
+                    attempt += 1;
+                    warn!(
+                        "{} failed ({}); retry {}/{} in {:?}",
+                        method, e, attempt, self.max_retries, delay
+                    );
+                    std::thread::sleep(delay);
+                }
+                result => return result,
+            }
+        }
+    }
+    
+    /// Send a single JSON-RPC request and decode its result and cursor
+    ///
+    /// **Private** - one attempt, no retries
+    fn call_once<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: &serde_json::Value,
+        subject: &str,
+    ) -> Result<(T, Option<String>), RpcError> {
         // Build RPC request
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": "debug_traceTransaction",
-            "params": params,
-            "id": 1
-        });
+        let request = JsonRpcRequest::new(method, params.clone(), 1);
         
         debug!("RPC request: {:?}", request);
         
@@ -94,30 +429,147 @@ impl RpcClient {
                 response.text().unwrap_or_default()
             )));
         }
+        check_content_encoding(&response)?;
         
         // Parse JSON-RPC response
-        let rpc_response: JsonRpcResponse<RawTraceData> = response
+        let rpc_response: JsonRpcResponse<T> = response
             .json()
             .map_err(RpcError::RequestFailed)?;
         
         // Handle JSON-RPC error
         if let Some(error) = rpc_response.error {
-            return Err(map_rpc_error(error, &tx_hash));
+            return Err(map_rpc_error(error, subject));
         }
         
         // Extract result
-        rpc_response.result.ok_or_else(|| {
+        let result = rpc_response.result.ok_or_else(|| {
             RpcError::InvalidResponse("Missing result field".to_string())
-        })
+        })?;
+        
+        Ok((result, rpc_response.cursor))
+    }
+}
+
+/// A trace with the pagination cursor it was returned with
+type TracePage = (RawTraceData, Option<String>);
+
+/// Match batch responses to requests by id
+///
+/// **Private** - request `i` was sent with id `i` for `hashes[i]`; a
+/// missing or duplicated id yields an error for that entry only
+fn demux_batch(
+    responses: Vec<JsonRpcResponse<RawTraceData>>,
+    hashes: &[String],
+) -> Vec<Result<TracePage, RpcError>> {
+    let mut results: Vec<Option<Result<TracePage, RpcError>>> = hashes.iter().map(|_| None).collect();
+    
+    for response in responses {
+        let Some(slot) = results.get_mut(response.id as usize) else {
+            warn!("Ignoring batch response with unknown id {}", response.id);
+            continue;
+        };
+        if slot.is_some() {
+            warn!("Ignoring duplicate batch response for id {}", response.id);
+            continue;
+        }
+        
+        let hash = &hashes[response.id as usize];
+        *slot = Some(match (response.error, response.result) {
+            (Some(error), _) => Err(map_rpc_error(error, hash)),
+            (None, Some(trace)) => Ok((trace, response.cursor)),
+            (None, None) => Err(RpcError::InvalidResponse("Missing result field".to_string())),
+        });
     }
+    
+    results
+        .into_iter()
+        .zip(hashes)
+        .map(|(result, hash)| {
+            result.unwrap_or_else(|| {
+                Err(RpcError::InvalidResponse(format!("No batch response for {}", hash)))
+            })
+        })
+        .collect()
 }
 
 /// Normalize transaction hash to include 0x prefix
 fn normalize_tx_hash(tx_hash: &str) -> String {
-    if tx_hash.starts_with("0x") {
-        tx_hash.to_string()
-    } else {
-        format!("0x{}", tx_hash)
+    match tx_hash.strip_prefix("0X") {
+        Some(hex) => format!("0x{}", hex),
+        None if tx_hash.starts_with("0x") => tx_hash.to_string(),
+        None => format!("0x{}", tx_hash),
+    }
+}
+
+/// Build the HTTP client shared by all RPC calls
+///
+/// **Private** - gzip/deflate bodies are decompressed transparently.
+/// Only 307/308 redirects are followed (301/302 would turn the POST into
+/// a GET), at most `MAX_RPC_REDIRECTS` of them. A redirect loop, too many
+/// hops or a method-changing redirect fails the request with
+/// `RpcError::RequestFailed`.
+fn build_http_client(timeout: Duration) -> Result<Client, RpcError> {
+    Client::builder()
+        .timeout(timeout)
+        .gzip(true)
+        .deflate(true)
+        .redirect(redirect::Policy::custom(|attempt| {
+            let status = attempt.status();
+            if !matches!(status, StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT) {
+                let error = format!(
+                    "{} redirect to {} would resend the request as GET; point the RPC URL there directly",
+                    status, attempt.url()
+                );
+                attempt.error(error)
+            } else if attempt.previous().contains(attempt.url()) {
+                let error = format!("redirect loop at {}", attempt.url());
+                attempt.error(error)
+            } else if attempt.previous().len() >= MAX_RPC_REDIRECTS {
+                attempt.error(format!("more than {} redirects", MAX_RPC_REDIRECTS))
+            } else {
+                attempt.follow()
+            }
+        }))
+        .build()
+        .map_err(RpcError::RequestFailed)
+}
+
+/// Reject bodies in an encoding the client could not decompress
+///
+/// **Private** - gzip/deflate are decoded (and their header removed) by
+/// the client; anything left (e.g. `br`) is reported plainly instead of as
+/// a JSON parse error
+fn check_content_encoding(response: &Response) -> Result<(), RpcError> {
+    match response.headers().get(CONTENT_ENCODING).and_then(|value| value.to_str().ok()) {
+        Some(encoding) if !encoding.trim().eq_ignore_ascii_case("identity") => {
+            Err(RpcError::InvalidResponse(format!(
+                "RPC response uses unsupported {} content encoding",
+                encoding.trim()
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check that a trace naming its transaction names `tx_hash`
+///
+/// **Private** - `with_strict_hash_check`; looks for a `txHash`,
+/// `transactionHash` or `hash` field and passes traces without one
+fn verify_trace_hash(trace: &RawTraceData, tx_hash: &str) -> Result<(), RpcError> {
+    let returned = trace.fields().and_then(|fields| {
+        TRACE_HASH_FIELDS
+            .iter()
+            .find_map(|key| fields.get(*key).and_then(|value| value.as_str()))
+    });
+    
+    match returned {
+        Some(returned) if !normalize_tx_hash(returned).eq_ignore_ascii_case(tx_hash) => {
+            Err(RpcError::HashMismatch {
+                requested: tx_hash.to_string(),
+                returned: returned.to_string(),
+            })
+        }
+        _ => Ok(()),
     }
 }
 
@@ -125,7 +577,10 @@ fn normalize_tx_hash(tx_hash: &str) -> String {
 fn map_rpc_error(error: super::types::JsonRpcError, tx_hash: &str) -> RpcError {
     match error.code {
         -32000 => {
-            if error.message.to_lowercase().contains("not found") {
+            let message = error.message.to_lowercase();
+            if RETRYABLE_MESSAGES.iter().any(|pattern| message.contains(pattern)) {
+                RpcError::Transient(error.message)
+            } else if message.contains("not found") {
                 RpcError::TransactionNotFound(tx_hash.to_string())
             } else {
                 RpcError::InvalidResponse(error.message)
@@ -147,4 +602,113 @@ mod tests {
         assert_eq!(normalize_tx_hash("abc123"), "0xabc123");
         assert_eq!(normalize_tx_hash("0xdef456"), "0xdef456");
     }
+
+    fn rpc_error(code: i64, message: &str) -> crate::rpc::types::JsonRpcError {
+        crate::rpc::types::JsonRpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_demux_batch_matches_ids() {
+        let hashes = vec!["0xa".to_string(), "0xb".to_string(), "0xc".to_string()];
+        let responses: Vec<JsonRpcResponse<RawTraceData>> = serde_json::from_value(serde_json::json!([
+            { "jsonrpc": "2.0", "id": 1, "error": { "code": -32000, "message": "transaction not found" } },
+            { "jsonrpc": "2.0", "id": 0, "result": { "gasUsed": 7 } },
+            { "jsonrpc": "2.0", "id": 9, "result": {} }
+        ]))
+        .unwrap();
+        
+        let results = demux_batch(responses, &hashes);
+        
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().0.fields().unwrap()["gasUsed"], 7);
+        assert!(matches!(results[1], Err(RpcError::TransactionNotFound(ref h)) if h == "0xb"));
+        assert!(matches!(results[2], Err(RpcError::InvalidResponse(_))));
+    }
+
+    /// Serve `response` to every connection on a local port
+    fn serve(response: impl AsRef<[u8]> + Send + 'static) -> String {
+        use std::io::{Read, Write};
+        
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request);
+                let _ = stream.write_all(response.as_ref());
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_redirect_loop_and_compressed_body() {
+        let looping = serve("HTTP/1.1 307 Temporary Redirect\r\nLocation: /\r\nContent-Length: 0\r\n\r\n");
+        let client = RpcClient::new(looping).unwrap().with_max_retries(0);
+        assert!(matches!(client.block_number(), Err(RpcError::RequestFailed(ref e)) if e.is_redirect()));
+        
+        let moved = serve("HTTP/1.1 302 Found\r\nLocation: /other\r\nContent-Length: 0\r\n\r\n");
+        let client = RpcClient::new(moved).unwrap().with_max_retries(0);
+        assert!(matches!(client.block_number(), Err(RpcError::RequestFailed(ref e)) if e.is_redirect()));
+        
+        // {"jsonrpc":"2.0","id":1,"result":"0x10"}, gzipped
+        let body: &[u8] = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xab\x56\xca\x2a\xce\xcf\x2b\x2a\x48\x56\xb2\x52\x32\xd2\x33\x50\xd2\x51\xca\x4c\x51\xb2\x32\xd4\x51\x2a\x4a\x2d\x2e\xcd\x29\x01\x8a\x1a\x54\x18\x1a\x28\xd5\x02\x00\x1a\xd5\xbe\x45\x28\x00\x00\x00";
+        let mut gzipped = format!(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        ).into_bytes();
+        gzipped.extend_from_slice(body);
+        let client = RpcClient::new(serve(gzipped)).unwrap().with_max_retries(0);
+        assert_eq!(client.block_number().unwrap(), 16);
+        
+        let brotli = serve("HTTP/1.1 200 OK\r\nContent-Encoding: br\r\nContent-Length: 2\r\n\r\n{}");
+        let client = RpcClient::new(brotli).unwrap().with_max_retries(0);
+        assert!(matches!(client.block_number(), Err(RpcError::InvalidResponse(ref m)) if m.contains("br")));
+    }
+
+    #[test]
+    fn test_get_tx_hash_by_block_index() {
+        let body = r#"{"jsonrpc":"2.0","id":1,"result":{"transactions":[{"hash":"0xaa"},{"hash":"0xbb","to":"0xcc"}]}}"#;
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        let client = RpcClient::new(serve(response)).unwrap().with_max_retries(0);
+        
+        assert_eq!(client.get_tx_hash_by_block_index(7, 1).unwrap(), "0xbb");
+        assert!(matches!(
+            client.get_tx_hash_by_block_index(7, 2),
+            Err(RpcError::TransactionNotFound(ref m)) if m.contains("2 transactions")
+        ));
+    }
+
+    #[test]
+    fn test_verify_trace_hash() {
+        let trace = |value| serde_json::from_value::<RawTraceData>(value).unwrap();
+        let requested = "0xabc1";
+        
+        assert!(verify_trace_hash(&trace(serde_json::json!({ "txHash": "0xABC1", "structLogs": [] })), requested).is_ok());
+        assert!(verify_trace_hash(&trace(serde_json::json!({ "gasUsed": 7, "structLogs": [] })), requested).is_ok());
+        assert!(verify_trace_hash(&trace(serde_json::json!([])), requested).is_ok());
+        assert!(matches!(
+            verify_trace_hash(&trace(serde_json::json!({ "transactionHash": "0xdef2" })), requested),
+            Err(RpcError::HashMismatch { ref returned, .. }) if returned == "0xdef2"
+        ));
+    }
+
+    #[test]
+    fn test_map_rpc_error_retryable_vs_not_found() {
+        let transient = map_rpc_error(rpc_error(-32000, "Resource temporarily unavailable"), "0x1");
+        assert!(matches!(transient, RpcError::Transient(_)));
+        assert!(transient.is_retryable());
+        
+        let missing = map_rpc_error(rpc_error(-32000, "transaction not found"), "0x1");
+        assert!(matches!(missing, RpcError::TransactionNotFound(ref h) if h == "0x1"));
+        assert!(!missing.is_retryable());
+        
+        let other = map_rpc_error(rpc_error(-32000, "execution reverted"), "0x1");
+        assert!(matches!(other, RpcError::InvalidResponse(_)));
+        assert!(!other.is_retryable());
+    }
 }
\ No newline at end of file
@@ -5,4 +5,4 @@ pub mod types;
 
 // Re-export main types
 pub use client::RpcClient;
-pub use types::{RawTraceData, JsonRpcRequest, JsonRpcResponse};
\ No newline at end of file
+pub use types::{BlockTransaction, LogEntry, RawTraceData, TraceConfig, Transaction, TransactionReceipt, JsonRpcRequest, JsonRpcResponse};
\ No newline at end of file
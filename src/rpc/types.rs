@@ -2,6 +2,7 @@
 //!
 //! Based on Ethereum JSON-RPC spec and Arbitrum's debug_traceTransaction extension.
 
+use crate::utils::error::RpcError;
 use serde::{Deserialize, Serialize};
 
 /// JSON-RPC 2.0 request structure
@@ -14,28 +15,107 @@ pub struct JsonRpcRequest {
 }
 
 impl JsonRpcRequest {
+    /// Create a JSON-RPC 2.0 request for any method
+    ///
+    /// # Arguments
+    /// * `method` - RPC method name
+    /// * `params` - Positional params array
+    /// * `id` - Request ID (distinct per request within a batch)
+    pub fn new(method: impl Into<String>, params: serde_json::Value, id: u64) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.into(),
+            params,
+            id,
+        }
+    }
+    
     /// Create a new JSON-RPC request for debug_traceTransaction
     ///
     /// # Arguments
     /// * `tx_hash` - Transaction hash (with 0x prefix)
     /// * `id` - Request ID (for response correlation)
     pub fn debug_trace_transaction(tx_hash: String, id: u64) -> Self {
+        Self::debug_trace_transaction_with_config(tx_hash, id, &TraceConfig::default())
+    }
+    
+    /// Create a debug_traceTransaction request with tracer config options
+    ///
+    /// # Arguments
+    /// * `tx_hash` - Transaction hash (with 0x prefix)
+    /// * `id` - Request ID (for response correlation)
+    /// * `config` - Which parts of the state the node should leave out
+    pub fn debug_trace_transaction_with_config(tx_hash: String, id: u64, config: &TraceConfig) -> Self {
         Self {
             jsonrpc: "2.0".to_string(),
             method: "debug_traceTransaction".to_string(),
             params: serde_json::json!([
                 tx_hash,
-                {
-                    "tracer": "stylusTracer"
-                }
+                config.to_tracer_options(Some("stylusTracer"))
             ]),
             id,
         }
     }
 }
 
+/// Tracer config options sent with debug_traceTransaction
+///
+/// Disabling state the profiler does not need shrinks the node's response
+/// considerably for gas-only profiling. Unset options are omitted so the
+/// node keeps its own defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TraceConfig {
+    /// Leave storage out of each step (`disableStorage`)
+    pub disable_storage: bool,
+    
+    /// Leave memory out of each step (`disableMemory`)
+    pub disable_memory: bool,
+    
+    /// Leave the stack out of each step (`disableStack`)
+    pub disable_stack: bool,
+}
+
+impl TraceConfig {
+    /// Config for gas-only profiling: storage, memory and stack all disabled
+    pub fn gas_only() -> Self {
+        Self {
+            disable_storage: true,
+            disable_memory: true,
+            disable_stack: true,
+        }
+    }
+    
+    /// Whether any state is disabled
+    pub fn disables_state(&self) -> bool {
+        self.disable_storage || self.disable_memory || self.disable_stack
+    }
+    
+    /// Build the config object passed as the second debug_traceTransaction param
+    pub fn to_tracer_options(&self, tracer: Option<&str>) -> serde_json::Value {
+        let mut options = serde_json::Map::new();
+        if let Some(tracer) = tracer {
+            options.insert("tracer".to_string(), tracer.into());
+        }
+        if self.disable_storage {
+            options.insert("disableStorage".to_string(), true.into());
+        }
+        if self.disable_memory {
+            options.insert("disableMemory".to_string(), true.into());
+        }
+        if self.disable_stack {
+            options.insert("disableStack".to_string(), true.into());
+        }
+        
+        serde_json::Value::Object(options)
+    }
+}
+
 /// JSON-RPC 2.0 response structure
+///
+/// `cursor` is set by providers that paginate large results; the next page
+/// is requested by sending it back in the tracer options.
 #[derive(Debug, Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>"))]
 pub struct JsonRpcResponse<T> {
     pub jsonrpc: String,
     pub id: u64,
@@ -43,6 +123,8 @@ pub struct JsonRpcResponse<T> {
     pub result: Option<T>,
     #[serde(default)]
     pub error: Option<JsonRpcError>,
+    #[serde(default, alias = "nextCursor")]
+    pub cursor: Option<String>,
 }
 
 /// JSON-RPC error object
@@ -54,8 +136,311 @@ pub struct JsonRpcError {
     pub data: Option<serde_json::Value>,
 }
 
-/// Raw trace data from stylusTracer (opaque for now, parsed later)
+/// Transaction entry from `eth_getBlockByNumber` (full transaction objects)
+///
+/// Only the fields needed to select transactions are kept.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockTransaction {
+    pub hash: String,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+/// Transaction from `eth_getTransactionByHash`
+///
+/// Only the fields recorded in profiles are kept.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Transaction {
+    pub hash: String,
+    #[serde(default)]
+    pub to: Option<String>,
+    /// `0x`-prefixed calldata
+    #[serde(default)]
+    pub input: String,
+}
+
+/// Log entry from `eth_getLogs`
+///
+/// Only the fields needed to locate the emitting transaction are kept.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub transaction_hash: String,
+    pub block_number: String,
+}
+
+/// Transaction receipt from `eth_getTransactionReceipt`
 ///
-/// We keep this as `serde_json::Value` because the exact schema
-/// may vary between Nitro versions. The parser will handle validation.
-pub type RawTraceData = serde_json::Value;
\ No newline at end of file
+/// Only the gas and address fields are kept. Arbitrum nodes add
+/// `gasUsedForL1`, the part of `gasUsed` that pays for posting calldata
+/// to L1.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionReceipt {
+    pub gas_used: String,
+    #[serde(default, rename = "gasUsedForL1")]
+    pub gas_used_for_l1: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub contract_address: Option<String>,
+    #[serde(default)]
+    pub block_number: Option<String>,
+}
+
+impl TransactionReceipt {
+    /// Address of the contract that ran
+    ///
+    /// `to` for calls; contract creations have a null `to`, so the
+    /// deployed `contractAddress` is used instead.
+    pub fn target_address(&self) -> Option<&str> {
+        self.to.as_deref().or(self.contract_address.as_deref())
+    }
+    
+    /// Block the transaction was included in (None while pending)
+    pub fn block(&self) -> Result<Option<u64>, RpcError> {
+        self.block_number.as_deref().map(parse_hex_quantity).transpose()
+    }
+    
+    /// Split `gasUsed` into `(l1_gas, l2_gas)`
+    ///
+    /// L1 gas is 0 when the node does not report `gasUsedForL1`.
+    pub fn l1_l2_gas(&self) -> Result<(u64, u64), RpcError> {
+        let total = parse_hex_quantity(&self.gas_used)?;
+        let l1 = match &self.gas_used_for_l1 {
+            Some(value) => parse_hex_quantity(value)?,
+            None => 0,
+        };
+        
+        Ok((l1, total.saturating_sub(l1)))
+    }
+}
+
+/// Parse a `0x`-prefixed hex quantity
+pub(crate) fn parse_hex_quantity(value: &str) -> Result<u64, RpcError> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    u64::from_str_radix(digits, 16)
+        .map_err(|_| RpcError::InvalidResponse(format!("Invalid hex quantity: {}", value)))
+}
+
+/// Keys that may hold the opcode step array (`structLogs` and its aliases)
+pub const STEP_ARRAY_KEYS: [&str; 4] = ["structLogs", "struct_logs", "steps", "trace"];
+
+/// Raw trace data as returned by `debug_traceTransaction`
+///
+/// The layout depends on the tracer and Nitro version, so serde tries the
+/// variants in order and the parser dispatches on the one that matched.
+/// Individual steps stay as JSON; the parser deserializes them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RawTraceData {
+    /// Object with an opcode step array (default tracer), under
+    /// `structLogs` or one of its aliases, plus top-level fields such as
+    /// `gas`, `to`, `hostio`
+    StructLogs {
+        #[serde(rename = "structLogs", alias = "struct_logs", alias = "steps", alias = "trace")]
+        struct_logs: Vec<serde_json::Value>,
+        
+        #[serde(flatten)]
+        fields: serde_json::Map<String, serde_json::Value>,
+    },
+    
+    /// `callTracer` frame: nested calls, no opcode steps
+    CallTree {
+        #[serde(rename = "type")]
+        call_type: String,
+        
+        from: String,
+        
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        calls: Vec<serde_json::Value>,
+        
+        #[serde(flatten)]
+        fields: serde_json::Map<String, serde_json::Value>,
+    },
+    
+    /// Bare array of steps (stylusTracer); carries no gas total
+    Stylus(Vec<serde_json::Value>),
+    
+    /// Any other object, e.g. a gas summary without steps or a trace in
+    /// a foreign format (cast) that is parsed elsewhere
+    Other(serde_json::Map<String, serde_json::Value>),
+}
+
+impl RawTraceData {
+    /// Execution steps, if this layout has any
+    pub fn steps(&self) -> Option<&[serde_json::Value]> {
+        match self {
+            Self::StructLogs { struct_logs, .. } => Some(struct_logs),
+            Self::Stylus(steps) => Some(steps),
+            Self::CallTree { .. } | Self::Other(_) => None,
+        }
+    }
+    
+    fn steps_mut(&mut self) -> Option<&mut Vec<serde_json::Value>> {
+        match self {
+            Self::StructLogs { struct_logs, .. } => Some(struct_logs),
+            Self::Stylus(steps) => Some(steps),
+            Self::CallTree { .. } | Self::Other(_) => None,
+        }
+    }
+    
+    /// Top-level fields besides the steps (empty for a bare step array)
+    pub fn fields(&self) -> Option<&serde_json::Map<String, serde_json::Value>> {
+        match self {
+            Self::StructLogs { fields, .. } | Self::CallTree { fields, .. } | Self::Other(fields) => {
+                Some(fields)
+            }
+            Self::Stylus(_) => None,
+        }
+    }
+    
+    /// Step array keys present together in an object that therefore
+    /// matched no step layout (serde rejects the duplicate alias)
+    ///
+    /// Empty unless this is `Other` with two or more step arrays.
+    pub fn ambiguous_step_keys(&self) -> Vec<&'static str> {
+        let Self::Other(fields) = self else {
+            return Vec::new();
+        };
+        let present: Vec<&'static str> = STEP_ARRAY_KEYS
+            .into_iter()
+            .filter(|key| fields.get(*key).is_some_and(serde_json::Value::is_array))
+            .collect();
+        if present.len() > 1 { present } else { Vec::new() }
+    }
+}
+
+impl From<RawTraceData> for serde_json::Value {
+    fn from(trace: RawTraceData) -> Self {
+        match trace {
+            RawTraceData::Stylus(steps) => serde_json::Value::Array(steps),
+            RawTraceData::Other(fields) => serde_json::Value::Object(fields),
+            RawTraceData::StructLogs { struct_logs, mut fields } => {
+                fields.insert("structLogs".to_string(), serde_json::Value::Array(struct_logs));
+                serde_json::Value::Object(fields)
+            }
+            RawTraceData::CallTree { call_type, from, calls, mut fields } => {
+                fields.insert("type".to_string(), call_type.into());
+                fields.insert("from".to_string(), from.into());
+                if !calls.is_empty() {
+                    fields.insert("calls".to_string(), serde_json::Value::Array(calls));
+                }
+                serde_json::Value::Object(fields)
+            }
+        }
+    }
+}
+
+/// Append the steps of a later trace page to the trace fetched so far
+///
+/// Steps are concatenated for both the `structLogs` object format and the
+/// bare array format; other fields (e.g. `gasUsed`) are kept from the
+/// first page.
+///
+/// # Errors
+/// * `RpcError::InvalidResponse` - the page layout doesn't match the first page
+pub fn append_trace_page(trace: &mut RawTraceData, page: RawTraceData) -> Result<(), RpcError> {
+    let mut page = page;
+    let page_steps = page
+        .steps_mut()
+        .map(std::mem::take)
+        .ok_or_else(|| RpcError::InvalidResponse("Trace page has no structLogs".to_string()))?;
+    let trace_steps = trace
+        .steps_mut()
+        .ok_or_else(|| RpcError::InvalidResponse("Paginated trace has no structLogs".to_string()))?;
+    
+    trace_steps.extend(page_steps);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_quantity() {
+        assert_eq!(parse_hex_quantity("0x1b4").unwrap(), 436);
+        assert_eq!(parse_hex_quantity("0x0").unwrap(), 0);
+        assert!(parse_hex_quantity("0xzz").is_err());
+    }
+
+    #[test]
+    fn test_trace_config_options() {
+        let config = TraceConfig { disable_storage: true, disable_memory: true, ..Default::default() };
+        assert_eq!(
+            config.to_tracer_options(None),
+            serde_json::json!({ "disableStorage": true, "disableMemory": true })
+        );
+        assert_eq!(
+            TraceConfig::default().to_tracer_options(Some("callTracer")),
+            serde_json::json!({ "tracer": "callTracer" })
+        );
+        assert!(TraceConfig::gas_only().to_tracer_options(None)["disableStack"].as_bool().unwrap());
+    }
+
+    #[test]
+    fn test_append_trace_page() {
+        let raw = |value: serde_json::Value| RawTraceData::deserialize(value).unwrap();
+        
+        let mut trace = raw(serde_json::json!({ "gasUsed": 30, "structLogs": [{ "pc": 0 }] }));
+        append_trace_page(&mut trace, raw(serde_json::json!({ "steps": [{ "pc": 1 }, { "pc": 2 }] }))).unwrap();
+        assert_eq!(trace.steps().unwrap().len(), 3);
+        assert_eq!(trace.fields().unwrap()["gasUsed"], 30);
+        
+        let mut array = raw(serde_json::json!([{ "pc": 0 }]));
+        append_trace_page(&mut array, raw(serde_json::json!([{ "pc": 1 }]))).unwrap();
+        assert_eq!(array.steps().unwrap().len(), 2);
+        
+        assert!(append_trace_page(&mut array, raw(serde_json::json!({ "gasUsed": 1 }))).is_err());
+        
+        let response: JsonRpcResponse<RawTraceData> = serde_json::from_value(serde_json::json!({
+            "jsonrpc": "2.0", "id": 1, "result": [], "nextCursor": "abc"
+        }))
+        .unwrap();
+        assert_eq!(response.cursor.as_deref(), Some("abc"));
+    }
+
+    #[test]
+    fn test_raw_trace_variants() {
+        let raw = |value: serde_json::Value| RawTraceData::deserialize(value);
+        
+        let call = serde_json::json!({ "type": "CALL", "from": "0x1", "to": "0x2", "calls": [{}] });
+        assert!(matches!(raw(call.clone()).unwrap(), RawTraceData::CallTree { ref calls, .. } if calls.len() == 1));
+        assert_eq!(serde_json::Value::from(raw(call.clone()).unwrap()), call);
+        
+        let steps = raw(serde_json::json!({ "trace": [{ "pc": 0 }], "gas": "0x10" })).unwrap();
+        assert!(matches!(steps, RawTraceData::StructLogs { .. }));
+        assert_eq!(serde_json::Value::from(steps)["structLogs"][0]["pc"], 0);
+        
+        assert!(matches!(raw(serde_json::json!([])).unwrap(), RawTraceData::Stylus(_)));
+        assert!(matches!(raw(serde_json::json!({ "trace": {} })).unwrap(), RawTraceData::Other(_)));
+        assert!(raw(serde_json::json!("0x")).is_err());
+    }
+
+    #[test]
+    fn test_receipt_l1_l2_split() {
+        let receipt: TransactionReceipt = serde_json::from_value(serde_json::json!({
+            "gasUsed": "0x2710",
+            "gasUsedForL1": "0x3e8",
+            "status": "0x1"
+        }))
+        .unwrap();
+        assert_eq!(receipt.l1_l2_gas().unwrap(), (1000, 9000));
+        assert_eq!(receipt.block().unwrap(), None);
+        
+        let create: TransactionReceipt = serde_json::from_value(serde_json::json!({
+            "gasUsed": "0x10",
+            "to": null,
+            "contractAddress": "0xabc",
+            "blockNumber": "0x1f"
+        }))
+        .unwrap();
+        assert_eq!(create.target_address(), Some("0xabc"));
+        assert_eq!(create.block().unwrap(), Some(31));
+
+        let l2_only: TransactionReceipt =
+            serde_json::from_value(serde_json::json!({ "gasUsed": "0x64" })).unwrap();
+        assert_eq!(l2_only.l1_l2_gas().unwrap(), (0, 100));
+    }
+}
@@ -0,0 +1,152 @@
+//! Function names from a Solidity JSON ABI.
+//!
+//! `cargo stylus export-abi --json`, solc and forge all describe functions
+//! as `{"type": "function", "name": ..., "inputs": [...]}`. A function's
+//! selector is the first 4 bytes of the keccak-256 of its canonical
+//! signature `name(type,...)`, with tuples written out as `(type,...)`.
+//! Both a bare ABI array and an artifact object with an `abi` field are
+//! accepted.
+
+use crate::utils::error::ConfigError;
+use crate::utils::hash::keccak256;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// One ABI entry; only functions are kept
+#[derive(Debug, Deserialize)]
+struct AbiEntry {
+    /// `function`, `event`, `error`, ... (functions may omit it)
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+
+    #[serde(default)]
+    name: String,
+
+    #[serde(default)]
+    inputs: Vec<AbiParam>,
+}
+
+/// A function input; `components` describe tuple members
+#[derive(Debug, Deserialize)]
+struct AbiParam {
+    #[serde(rename = "type")]
+    kind: String,
+
+    #[serde(default)]
+    components: Vec<AbiParam>,
+}
+
+/// Function signatures keyed by 4-byte selector
+///
+/// **Public** - names the entry function of captured calldata
+#[derive(Debug, Clone, Default)]
+pub struct AbiSelectors {
+    functions: HashMap<u32, String>,
+}
+
+impl AbiSelectors {
+    /// Parse an ABI (or an artifact with an `abi` field) from JSON
+    ///
+    /// **Public** - constructor
+    ///
+    /// # Errors
+    /// * `ConfigError::Json` - Invalid JSON or malformed entries
+    /// * `ConfigError::InvalidRule` - Neither an array nor an object with `abi`
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let entries = match value {
+            serde_json::Value::Array(_) => value,
+            serde_json::Value::Object(mut obj) => obj.remove("abi").ok_or_else(|| {
+                ConfigError::InvalidRule("ABI object has no `abi` field".to_string())
+            })?,
+            _ => {
+                return Err(ConfigError::InvalidRule(
+                    "ABI must be a JSON array or an artifact with an `abi` field".to_string(),
+                ))
+            }
+        };
+
+        let functions = Vec::<AbiEntry>::deserialize(entries)?
+            .into_iter()
+            .filter(|entry| entry.kind.as_deref().unwrap_or("function") == "function")
+            .map(|entry| {
+                let signature = format!("{}({})", entry.name, canonical_types(&entry.inputs));
+                (function_selector(&signature), signature)
+            })
+            .collect();
+
+        Ok(Self { functions })
+    }
+
+    /// Load an ABI file
+    ///
+    /// **Public** - used for `--abi`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Number of functions
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Whether the ABI has no functions
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Signature of the function with this selector, if any
+    pub fn lookup(&self, selector: u32) -> Option<&str> {
+        self.functions.get(&selector).map(String::as_str)
+    }
+}
+
+/// 4-byte selector of a canonical signature like `transfer(address,uint256)`
+///
+/// **Public** - first 4 bytes of its keccak-256
+pub fn function_selector(signature: &str) -> u32 {
+    let digest = keccak256(signature.as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]])
+}
+
+/// Comma-separated canonical types of a parameter list
+///
+/// **Private** - `tuple[]` with components (uint256,bool) becomes
+/// `(uint256,bool)[]`
+fn canonical_types(params: &[AbiParam]) -> String {
+    params
+        .iter()
+        .map(|param| match param.kind.strip_prefix("tuple") {
+            Some(suffix) => format!("({}){}", canonical_types(&param.components), suffix),
+            None => param.kind.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abi_selectors() {
+        let abi = AbiSelectors::from_json(r#"{"abi": [
+            { "type": "function", "name": "transfer",
+              "inputs": [{ "type": "address" }, { "type": "uint256" }] },
+            { "type": "function", "name": "submit", "inputs": [
+                { "type": "tuple[]", "components": [{ "type": "uint256" }, { "type": "bool" }] }
+            ] },
+            { "type": "event", "name": "Transfer", "inputs": [] }
+        ]}"#)
+        .unwrap();
+
+        assert_eq!(abi.len(), 2);
+        assert_eq!(abi.lookup(0xa9059cbb), Some("transfer(address,uint256)"));
+        assert_eq!(
+            abi.lookup(function_selector("submit((uint256,bool)[])")),
+            Some("submit((uint256,bool)[])")
+        );
+        assert!(AbiSelectors::from_json("\"nope\"").is_err());
+    }
+}
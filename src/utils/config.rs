@@ -1,15 +1,56 @@
 //! Configuration and constants for the CLI.
+//!
+//! Also loads `stylus-trace.toml`, a file of default option values that
+//! explicit CLI flags override:
+//!
+//! ```toml
+//! rpc = "http://my-node:8547"
+//! palette = "mem"
+//! width = 1600
+//! top_paths = 50
+//! ```
 
+use crate::utils::error::ConfigError;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+/// Environment variable read when `--rpc` is not given
+///
+/// Precedence: `--rpc` flag, then this variable, then `rpc` in
+/// `stylus-trace.toml`, then `http://localhost:8547`. Keeps keyed URLs
+/// out of shell history and CI logs (`--help` hides its value).
+pub const RPC_ENV_VAR: &str = "STYLUS_TRACE_RPC";
+
 /// Default timeout for RPC requests
 pub const DEFAULT_RPC_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Retries for RPC requests failing with a retryable error
+pub const DEFAULT_RPC_RETRIES: u32 = 2;
+
+/// Delay before the first RPC retry (doubled for each further attempt)
+pub const RPC_RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Redirects followed per RPC request (gateways moving the endpoint)
+pub const MAX_RPC_REDIRECTS: usize = 5;
+
+/// Delay between head checks while waiting for `--confirmations`
+pub const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maximum pages fetched for one paginated trace (guards against cursor loops)
+pub const MAX_TRACE_PAGES: usize = 1000;
+
+/// Blocks requested per `eth_getLogs` call (most nodes cap the range)
+pub const LOG_BLOCK_CHUNK: u64 = 2000;
+
+/// Most `hot_steps` kept under `--capture-state` (each carries stack,
+/// memory and storage, so `--top-paths 0` must not keep every step)
+pub const MAX_HOT_STEPS: usize = 100;
+
 /// Maximum trace size we'll attempt to parse (10 MB)
 pub const MAX_TRACE_SIZE_BYTES: usize = 10 * 1024 * 1024;
 
 /// Current output schema version
-pub const SCHEMA_VERSION: &str = "1.0.0";
+pub const SCHEMA_VERSION: &str = "1.1.0";
 
 /// Configuration for the CLI (future extensibility)
 #[derive(Debug, Clone)]
@@ -32,4 +73,158 @@ impl Config {
     pub fn new() -> Self {
         Self::default()
     }
-}
\ No newline at end of file
+}
+
+/// File name searched for in the working directory and `~/.config`
+pub const CONFIG_FILE_NAME: &str = "stylus-trace.toml";
+
+/// Keys a config file may set (the clap argument ids they default)
+pub const FILE_CONFIG_KEYS: [&str; 5] = ["rpc", "palette", "width", "top_paths", "weight_scale"];
+
+/// Option defaults read from a config file
+///
+/// **Public** - layered under clap defaults by main.rs; every field is
+/// optional. Bad values and unknown keys are collected in `invalid`
+/// rather than failing the load, so only commands reading them fail.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileConfig {
+    /// RPC endpoint URL (`--rpc`)
+    pub rpc: Option<String>,
+    
+    /// Flamegraph palette (`--palette`)
+    pub palette: Option<String>,
+    
+    /// Flamegraph width in pixels (`--width`)
+    pub width: Option<usize>,
+    
+    /// Number of hot paths to keep (`--top-paths`)
+    pub top_paths: Option<usize>,
+    
+    /// Flamegraph weight unit (`--weight-scale`)
+    pub weight_scale: Option<String>,
+    
+    /// Keys that could not be used, with the reason (every known key
+    /// when the file is not valid TOML)
+    pub invalid: Vec<(String, String)>,
+}
+
+impl FileConfig {
+    /// Parse a config file from TOML
+    ///
+    /// **Public** - constructor; never fails, problems land in `invalid`
+    pub fn from_toml(content: &str) -> Self {
+        let mut config = Self::default();
+        
+        let table = match content.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(e) => {
+                let reason = ConfigError::Toml(e).to_string();
+                config.invalid = FILE_CONFIG_KEYS
+                    .iter()
+                    .map(|key| (key.to_string(), reason.clone()))
+                    .collect();
+                return config;
+            }
+        };
+        
+        for (key, value) in table {
+            let result = match key.as_str() {
+                "rpc" => value.try_into().map(|v| config.rpc = Some(v)),
+                "palette" => value.try_into().map(|v| config.palette = Some(v)),
+                "width" => value.try_into().map(|v| config.width = Some(v)),
+                "top_paths" => value.try_into().map(|v| config.top_paths = Some(v)),
+                "weight_scale" => value.try_into().map(|v| config.weight_scale = Some(v)),
+                _ => {
+                    config.invalid.push((key, "unknown key".to_string()));
+                    continue;
+                }
+            };
+            if let Err(e) = result {
+                config.invalid.push((key, ConfigError::Toml(e).to_string()));
+            }
+        }
+        
+        config
+    }
+    
+    /// Load a config file
+    ///
+    /// **Public** - used for `--config` and discovered files
+    ///
+    /// # Errors
+    /// * `ConfigError::Io` - The file cannot be read
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Ok(Self::from_toml(&std::fs::read_to_string(path)?))
+    }
+    
+    /// Find a config file: `./stylus-trace.toml`, then `~/.config/stylus-trace.toml`
+    ///
+    /// **Public** - used when no `--config` is given
+    pub fn discover() -> Option<PathBuf> {
+        let local = PathBuf::from(CONFIG_FILE_NAME);
+        if local.is_file() {
+            return Some(local);
+        }
+        
+        let home = std::env::var_os("HOME")?;
+        let global = Path::new(&home).join(".config").join(CONFIG_FILE_NAME);
+        global.is_file().then_some(global)
+    }
+    
+    /// Defaults as `(argument id, value)` pairs for the options that are set
+    ///
+    /// **Public** - ids match the clap argument names
+    pub fn defaults(&self) -> Vec<(&'static str, String)> {
+        let mut defaults = Vec::new();
+        if let Some(rpc) = &self.rpc {
+            defaults.push(("rpc", rpc.clone()));
+        }
+        if let Some(palette) = &self.palette {
+            defaults.push(("palette", palette.clone()));
+        }
+        if let Some(width) = self.width {
+            defaults.push(("width", width.to_string()));
+        }
+        if let Some(top_paths) = self.top_paths {
+            defaults.push(("top_paths", top_paths.to_string()));
+        }
+        if let Some(weight_scale) = &self.weight_scale {
+            defaults.push(("weight_scale", weight_scale.clone()));
+        }
+        defaults
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_config_defaults() {
+        let config = FileConfig::from_toml(
+            "rpc = \"http://node:8547\"\nwidth = 1600\ntop_paths = 50\n",
+        );
+        
+        assert_eq!(config.rpc.as_deref(), Some("http://node:8547"));
+        assert_eq!(config.defaults(), vec![
+            ("rpc", "http://node:8547".to_string()),
+            ("width", "1600".to_string()),
+            ("top_paths", "50".to_string()),
+        ]);
+        assert!(config.invalid.is_empty());
+    }
+    
+    #[test]
+    fn test_file_config_collects_invalid_keys() {
+        let config = FileConfig::from_toml("rpc = \"http://node:8547\"\nwidth = \"wide\"\nrpc_url = \"x\"\n");
+        
+        assert_eq!(config.rpc.as_deref(), Some("http://node:8547"));
+        assert_eq!(config.width, None);
+        let keys: Vec<&str> = config.invalid.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["rpc_url", "width"]);
+        
+        let broken = FileConfig::from_toml("rpc = ");
+        assert_eq!(broken.rpc, None);
+        assert_eq!(broken.invalid.len(), FILE_CONFIG_KEYS.len());
+    }
+}
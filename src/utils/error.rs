@@ -19,6 +19,26 @@ pub enum RpcError {
     
     #[error("Tracer not supported by this RPC endpoint")]
     TracerNotSupported,
+    
+    #[error("Transient RPC error (retryable): {0}")]
+    Transient(String),
+    
+    #[error("Trace is for transaction {returned}, not the requested {requested}")]
+    HashMismatch { requested: String, returned: String },
+}
+
+impl RpcError {
+    /// Whether retrying the same request may succeed
+    ///
+    /// **Public** - transient node errors and network timeouts/connect
+    /// failures are retryable; not-found and malformed responses are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transient(_) => true,
+            Self::RequestFailed(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
 }
 
 /// Errors that can occur during trace parsing
@@ -35,6 +55,9 @@ pub enum ParseError {
     
     #[error("Missing required field: {0}")]
     MissingField(String),
+    
+    #[error("Failed to read trace: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 /// Errors that can occur during flamegraph generation
@@ -50,6 +73,25 @@ pub enum FlamegraphError {
     IoError(#[from] std::io::Error),
 }
 
+/// Errors that can occur while loading user-supplied configuration
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Invalid rule: {0}")]
+    InvalidRule(String),
+    
+    #[error("Invalid regex: {0}")]
+    InvalidRegex(#[from] regex::Error),
+    
+    #[error("Failed to read config file: {0}")]
+    Io(#[from] std::io::Error),
+    
+    #[error("Invalid config JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    
+    #[error("Invalid config TOML: {0}")]
+    Toml(#[from] toml::de::Error),
+}
+
 /// Errors that can occur during file output
 #[derive(Error, Debug)]
 pub enum OutputError {
@@ -61,4 +103,7 @@ pub enum OutputError {
     
     #[error("Invalid output path: {0}")]
     InvalidPath(String),
+    
+    #[error("Unsupported profile schema version: {0}")]
+    UnsupportedVersion(String),
 }
\ No newline at end of file
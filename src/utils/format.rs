@@ -0,0 +1,77 @@
+//! Human-readable formatting for terminal output.
+//!
+//! Only for text meant to be read by people; JSON output keeps raw numbers.
+
+/// How `format_gas_as` renders a gas amount
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GasFormat {
+    /// Exact value with thousands separators (`123,456,789`)
+    #[default]
+    Grouped,
+
+    /// Two-decimal value with a K/M/B suffix (`123.46M`)
+    Compact,
+}
+
+/// Suffixes for compact formatting, largest first
+const COMPACT_UNITS: &[(u64, &str)] = &[
+    (1_000_000_000, "B"),
+    (1_000_000, "M"),
+    (1_000, "K"),
+];
+
+/// Format gas with thousands separators
+///
+/// **Public** - default for summaries and reports
+pub fn format_gas(gas: u64) -> String {
+    format_gas_as(gas, GasFormat::Grouped)
+}
+
+/// Format gas in the given mode
+///
+/// **Public** - use `GasFormat::Compact` where space is tight
+pub fn format_gas_as(gas: u64, mode: GasFormat) -> String {
+    match mode {
+        GasFormat::Grouped => group_thousands(gas),
+        GasFormat::Compact => COMPACT_UNITS
+            .iter()
+            .find(|(unit, _)| gas >= *unit)
+            .map(|(unit, suffix)| format!("{:.2}{}", gas as f64 / *unit as f64, suffix))
+            .unwrap_or_else(|| gas.to_string()),
+    }
+}
+
+/// Insert a comma every three digits from the right
+// `u64::is_multiple_of` needs Rust 1.87; keep building on older toolchains
+#[allow(clippy::manual_is_multiple_of)]
+fn group_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_gas_modes() {
+        assert_eq!(format_gas(0), "0");
+        assert_eq!(format_gas(999), "999");
+        assert_eq!(format_gas(1_000), "1,000");
+        assert_eq!(format_gas(123_456_789), "123,456,789");
+
+        assert_eq!(format_gas_as(999, GasFormat::Compact), "999");
+        assert_eq!(format_gas_as(1_234, GasFormat::Compact), "1.23K");
+        assert_eq!(format_gas_as(123_456_789, GasFormat::Compact), "123.46M");
+        assert_eq!(format_gas_as(2_500_000_000, GasFormat::Compact), "2.50B");
+    }
+}
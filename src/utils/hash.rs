@@ -0,0 +1,84 @@
+//! Content hashing helpers.
+//!
+//! Used for trace references and profile fingerprints, so identical
+//! inputs always produce identical digests. Keccak-256 (the original
+//! Keccak padding, as used by Ethereum, not NIST SHA3) derives function
+//! selectors from ABI signatures.
+
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+/// Hex-encoded SHA-256 digest of `bytes`
+///
+/// **Public** - shared by profile source hashes and fingerprints
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+/// SHA-256 fed in pieces, for input that is never held whole
+///
+/// **Public** - hashes NDJSON traces line by line
+#[derive(Default)]
+pub struct Sha256Stream(Sha256);
+
+impl Sha256Stream {
+    /// Append bytes to the digest
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    /// Hex-encoded digest of everything appended
+    pub fn finish(self) -> String {
+        to_hex(&self.0.finalize())
+    }
+}
+
+/// Lowercase hex encoding
+///
+/// **Private** - shared by the SHA-256 helpers
+fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Keccak-256 digest of `bytes`
+///
+/// **Public** - used to compute function selectors from ABI signatures
+pub fn keccak256(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(bytes).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_hex_known_vector() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_sha256_stream_matches_digest() {
+        let mut stream = Sha256Stream::default();
+        stream.update(b"a");
+        stream.update(b"bc");
+        assert_eq!(stream.finish(), sha256_hex(b"abc"));
+    }
+
+    #[test]
+    fn test_keccak256_known_vectors() {
+        let hex = |digest: [u8; 32]| digest.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+        assert_eq!(
+            hex(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(&hex(keccak256(b"transfer(address,uint256)"))[..8], "a9059cbb");
+        // Exactly one rate block, so padding spills into a second block
+        assert_eq!(
+            hex(keccak256(&[b'a'; 136])),
+            "a6c4d403279fe3e0af03729caada8374b5ca54d8065329a3ebcaeb4b60aa386e"
+        );
+    }
+}
@@ -1,7 +1,22 @@
 //! Utility modules for configuration, error handling, and logging.
+//!
+//! `testgen` (synthetic trace builder) is only compiled for tests or
+//! with the `testing` feature enabled.
 
+pub mod abi;
 pub mod error;
 pub mod config;
+pub mod format;
+pub mod hash;
+pub mod opcodes;
+pub mod symbols;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testgen;
 
 // Re-export commonly used error types for convenience
-pub use error::{RpcError, ParseError, FlamegraphError, OutputError};
\ No newline at end of file
+pub use error::{RpcError, ParseError, FlamegraphError, OutputError, ConfigError};
+pub use format::{format_gas, format_gas_as, GasFormat};
+pub use abi::AbiSelectors;
+pub use opcodes::{opcode_category, OpcodeCategory};
+pub use symbols::SymbolTable;
\ No newline at end of file
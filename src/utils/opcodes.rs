@@ -0,0 +1,114 @@
+//! Opcode categories for coarse gas rollups.
+//!
+//! Maps EVM opcode names (and the Stylus HostIO names that stand in for
+//! them) to a handful of categories, so a flamegraph keyed by category
+//! shows at a glance whether a contract is compute-, memory- or
+//! storage-bound. Matching is case-insensitive; anything unrecognized is
+//! `Other`.
+
+/// Broad class of an instruction
+///
+/// **Public** - frame key for `AggregateBy::Category`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OpcodeCategory {
+    /// Arithmetic, comparison, bitwise and hashing
+    Arithmetic,
+
+    /// Memory and calldata/returndata/code copies
+    Memory,
+
+    /// Persistent and transient storage
+    Storage,
+
+    /// Jumps and execution halts
+    Control,
+
+    /// PUSH/DUP/SWAP/POP
+    Stack,
+
+    /// Transaction, block and account context
+    Environment,
+
+    /// Calls into and creation of other contracts
+    Call,
+
+    /// Event logs
+    Log,
+
+    /// Anything not recognized
+    Other,
+}
+
+impl OpcodeCategory {
+    /// Frame name of the category
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Arithmetic => "arithmetic",
+            Self::Memory => "memory",
+            Self::Storage => "storage",
+            Self::Control => "control",
+            Self::Stack => "stack",
+            Self::Environment => "environment",
+            Self::Call => "call",
+            Self::Log => "log",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Category of an opcode or HostIO name
+///
+/// **Public** - unknown names map to `OpcodeCategory::Other`
+pub fn opcode_category(op: &str) -> OpcodeCategory {
+    let op = op.trim().to_ascii_uppercase();
+
+    if op.starts_with("PUSH") || op.starts_with("DUP") || op.starts_with("SWAP") || op == "POP" {
+        return OpcodeCategory::Stack;
+    }
+    if op.starts_with("LOG") || op == "EMIT_LOG" {
+        return OpcodeCategory::Log;
+    }
+    if op.starts_with("STORAGE_") {
+        return OpcodeCategory::Storage;
+    }
+
+    match op.as_str() {
+        "ADD" | "MUL" | "SUB" | "DIV" | "SDIV" | "MOD" | "SMOD" | "ADDMOD" | "MULMOD" | "EXP"
+        | "SIGNEXTEND" | "LT" | "GT" | "SLT" | "SGT" | "EQ" | "ISZERO" | "AND" | "OR" | "XOR"
+        | "NOT" | "BYTE" | "SHL" | "SHR" | "SAR" | "KECCAK256" | "SHA3" | "NATIVE_KECCAK256" => {
+            OpcodeCategory::Arithmetic
+        }
+        "MLOAD" | "MSTORE" | "MSTORE8" | "MSIZE" | "MCOPY" | "CALLDATALOAD" | "CALLDATACOPY"
+        | "CODECOPY" | "EXTCODECOPY" | "RETURNDATACOPY" | "READ_ARGS" | "WRITE_RESULT"
+        | "READ_RETURN_DATA" | "PAY_FOR_MEMORY_GROW" => OpcodeCategory::Memory,
+        "SLOAD" | "SSTORE" | "TLOAD" | "TSTORE" => OpcodeCategory::Storage,
+        "JUMP" | "JUMPI" | "JUMPDEST" | "PC" | "STOP" | "RETURN" | "REVERT" | "INVALID" => {
+            OpcodeCategory::Control
+        }
+        "ADDRESS" | "BALANCE" | "ORIGIN" | "CALLER" | "CALLVALUE" | "CALLDATASIZE"
+        | "CODESIZE" | "GASPRICE" | "EXTCODESIZE" | "EXTCODEHASH" | "RETURNDATASIZE"
+        | "BLOCKHASH" | "COINBASE" | "TIMESTAMP" | "NUMBER" | "DIFFICULTY" | "PREVRANDAO"
+        | "GASLIMIT" | "CHAINID" | "SELFBALANCE" | "BASEFEE" | "BLOBHASH" | "BLOBBASEFEE"
+        | "GAS" => OpcodeCategory::Environment,
+        "CALL" | "CALLCODE" | "DELEGATECALL" | "STATICCALL" | "CREATE" | "CREATE2"
+        | "SELFDESTRUCT" | "CALL_CONTRACT" | "DELEGATE_CALL_CONTRACT" | "STATIC_CALL_CONTRACT"
+        | "CREATE1" => OpcodeCategory::Call,
+        _ => OpcodeCategory::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opcode_category() {
+        assert_eq!(opcode_category("ADD"), OpcodeCategory::Arithmetic);
+        assert_eq!(opcode_category("push32"), OpcodeCategory::Stack);
+        assert_eq!(opcode_category("SSTORE"), OpcodeCategory::Storage);
+        assert_eq!(opcode_category("storage_load_bytes32"), OpcodeCategory::Storage);
+        assert_eq!(opcode_category("JUMPI"), OpcodeCategory::Control);
+        assert_eq!(opcode_category("LOG2"), OpcodeCategory::Log);
+        assert_eq!(opcode_category("FROBNICATE").name(), "other");
+    }
+}
@@ -0,0 +1,169 @@
+//! Lightweight symbol tables for naming frames.
+//!
+//! A symbols file is a flat JSON object mapping either a pc range or a
+//! 4-byte function selector to a name:
+//!
+//! ```json
+//! {
+//!   "0x0-0x40": "entrypoint",
+//!   "64-128": "transfer_impl",
+//!   "0xa9059cbb": "transfer(address,uint256)"
+//! }
+//! ```
+//!
+//! Range keys are `start-end` (end exclusive, decimal or `0x` hex);
+//! selector keys are exactly 8 hex digits after `0x`. This covers naming
+//! top-level dispatch functions without a full source map.
+
+use crate::utils::error::ConfigError;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Separator between the start and end of a pc range key
+const RANGE_SEPARATOR: char = '-';
+
+/// Names keyed by pc range or 4-byte selector
+///
+/// **Public** - consulted by the stack builder to name frames
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    /// Ranges keyed by start pc, holding (exclusive end, name)
+    ranges: BTreeMap<u64, (u64, String)>,
+
+    /// Names keyed by selector
+    selectors: HashMap<u32, String>,
+}
+
+impl SymbolTable {
+    /// Parse a symbols file from JSON
+    ///
+    /// **Public** - constructor
+    ///
+    /// # Errors
+    /// * `ConfigError::Json` - Not a JSON object of strings
+    /// * `ConfigError::InvalidRule` - A key is neither a range nor a selector
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        let raw: BTreeMap<String, String> = serde_json::from_str(json)?;
+        let mut table = Self::default();
+
+        for (key, name) in raw {
+            let key = key.trim();
+            if let Some(selector) = parse_selector(key) {
+                table.selectors.insert(selector, name);
+            } else if let Some((start, end)) = parse_range(key) {
+                table.ranges.insert(start, (end, name));
+            } else {
+                return Err(ConfigError::InvalidRule(format!(
+                    "symbol key '{}' is neither a pc range (start-end) nor a 4-byte selector",
+                    key
+                )));
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Load a symbols file
+    ///
+    /// **Public** - used for `--symbols`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Number of entries (ranges plus selectors)
+    pub fn len(&self) -> usize {
+        self.ranges.len() + self.selectors.len()
+    }
+
+    /// Whether the table has no entries
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty() && self.selectors.is_empty()
+    }
+
+    /// Name for the innermost pc range covering `pc`, if any
+    ///
+    /// Ranges may nest (a helper inside its caller's range); the covering
+    /// range that starts last wins.
+    pub fn lookup_pc(&self, pc: u64) -> Option<&str> {
+        self.ranges
+            .range(..=pc)
+            .rev()
+            .find(|(_, (end, _))| pc < *end)
+            .map(|(_, (_, name))| name.as_str())
+    }
+
+    /// Name for a 4-byte selector, if any
+    pub fn lookup_selector(&self, selector: u32) -> Option<&str> {
+        self.selectors.get(&selector).map(String::as_str)
+    }
+}
+
+/// Parse a `0x`-prefixed 4-byte selector key
+fn parse_selector(key: &str) -> Option<u32> {
+    let digits = key.strip_prefix("0x")?;
+    if digits.len() != 8 {
+        return None;
+    }
+    u32::from_str_radix(digits, 16).ok()
+}
+
+/// Parse a `start-end` range key (end exclusive, must exceed start)
+fn parse_range(key: &str) -> Option<(u64, u64)> {
+    let (start, end) = key.split_once(RANGE_SEPARATOR)?;
+    let (start, end) = (parse_number(start.trim())?, parse_number(end.trim())?);
+    (end > start).then_some((start, end))
+}
+
+/// Parse a decimal or `0x` hex number
+fn parse_number(value: &str) -> Option<u64> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYMBOLS: &str = r#"{
+        "0x0-0x40": "entrypoint",
+        "64-128": "transfer_impl",
+        "0xa9059cbb": "transfer(address,uint256)"
+    }"#;
+
+    #[test]
+    fn test_lookup_pc_and_selector() {
+        let table = SymbolTable::from_json(SYMBOLS).unwrap();
+
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.lookup_pc(0), Some("entrypoint"));
+        assert_eq!(table.lookup_pc(63), Some("entrypoint"));
+        assert_eq!(table.lookup_pc(64), Some("transfer_impl"));
+        assert_eq!(table.lookup_pc(128), None);
+        assert_eq!(table.lookup_selector(0xa9059cbb), Some("transfer(address,uint256)"));
+        assert_eq!(table.lookup_selector(0x12345678), None);
+    }
+
+    #[test]
+    fn test_lookup_pc_nested_ranges() {
+        let table = SymbolTable::from_json(r#"{
+            "0-1000": "dispatch",
+            "100-200": "transfer",
+            "120-140": "checked_sub"
+        }"#).unwrap();
+
+        assert_eq!(table.lookup_pc(50), Some("dispatch"));
+        assert_eq!(table.lookup_pc(110), Some("transfer"));
+        assert_eq!(table.lookup_pc(130), Some("checked_sub"));
+        assert_eq!(table.lookup_pc(150), Some("transfer"));
+        assert_eq!(table.lookup_pc(500), Some("dispatch"));
+        assert_eq!(table.lookup_pc(1000), None);
+    }
+
+    #[test]
+    fn test_invalid_key_rejected() {
+        assert!(SymbolTable::from_json(r#"{ "main": "x" }"#).is_err());
+        assert!(SymbolTable::from_json(r#"{ "10-5": "backwards" }"#).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+//! Synthetic trace generation for tests.
+//!
+//! `TraceBuilder` produces raw `serde_json::Value` traces in the same
+//! shape `debug_traceTransaction` returns, so parser and aggregator
+//! tests (and downstream crates, via the `testing` feature) can build
+//! minimal reproductions without hand-writing fixtures.
+//!
+//! # Example
+//! ```ignore
+//! let raw = TraceBuilder::new()
+//!     .step("PUSH1", 1, 3)
+//!     .function_step("transfer", "SLOAD", 2, 2100)
+//!     .hostio("storage_load", 2100)
+//!     .build();
+//! ```
+
+use crate::parser::schema::HotPath;
+use crate::parser::{parse_trace, to_profile, Profile};
+use serde_json::{json, Value};
+
+/// Default starting gas for generated traces
+const DEFAULT_START_GAS: u64 = 1_000_000;
+
+/// Builder for synthetic raw traces
+///
+/// **Public** - available in tests and behind the `testing` feature
+#[derive(Debug, Clone)]
+pub struct TraceBuilder {
+    gas_used: Option<u64>,
+    start_gas: u64,
+    steps: Vec<Value>,
+    hostio: Vec<Value>,
+    next_pc: u64,
+    spent: u64,
+}
+
+impl Default for TraceBuilder {
+    fn default() -> Self {
+        Self {
+            gas_used: None,
+            start_gas: DEFAULT_START_GAS,
+            steps: Vec::new(),
+            hostio: Vec::new(),
+            next_pc: 0,
+            spent: 0,
+        }
+    }
+}
+
+impl TraceBuilder {
+    /// Create an empty trace builder
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Set an explicit `gasUsed` (defaults to the sum of step costs)
+    pub fn gas_used(mut self, gas: u64) -> Self {
+        self.gas_used = Some(gas);
+        self
+    }
+    
+    /// Set the gas remaining at the first step
+    pub fn start_gas(mut self, gas: u64) -> Self {
+        self.start_gas = gas;
+        self
+    }
+    
+    /// Append an execution step with an opcode only
+    pub fn step(self, op: &str, depth: u32, gas_cost: u64) -> Self {
+        self.push_step(op, None, depth, gas_cost)
+    }
+    
+    /// Append an execution step attributed to a named function
+    pub fn function_step(self, function: &str, op: &str, depth: u32, gas_cost: u64) -> Self {
+        self.push_step(op, Some(function), depth, gas_cost)
+    }
+    
+    /// Append the same step `count` times (e.g., a loop body)
+    pub fn repeat(mut self, op: &str, depth: u32, gas_cost: u64, count: usize) -> Self {
+        for _ in 0..count {
+            self = self.step(op, depth, gas_cost);
+        }
+        self
+    }
+    
+    /// Append a raw step object verbatim (for malformed-input tests)
+    pub fn raw_step(mut self, step: Value) -> Self {
+        self.steps.push(step);
+        self
+    }
+    
+    /// Append a HostIO event
+    pub fn hostio(mut self, io_type: &str, gas: u64) -> Self {
+        self.hostio.push(json!({ "type": io_type, "gas": gas }));
+        self
+    }
+    
+    /// Number of steps added so far
+    pub fn step_count(&self) -> usize {
+        self.steps.len()
+    }
+    
+    /// Build the trace as a `{ gasUsed, structLogs, hostio }` object
+    pub fn build(&self) -> Value {
+        let mut trace = json!({
+            "gasUsed": self.gas_used.unwrap_or(self.spent),
+            "structLogs": self.steps,
+        });
+        
+        if !self.hostio.is_empty() {
+            trace["hostio"] = json!(self.hostio);
+        }
+        
+        trace
+    }
+    
+    /// Build the trace as a bare array of struct logs
+    pub fn build_steps_array(&self) -> Value {
+        json!(self.steps)
+    }
+    
+    fn push_step(mut self, op: &str, function: Option<&str>, depth: u32, gas_cost: u64) -> Self {
+        let mut step = json!({
+            "pc": self.next_pc,
+            "op": op,
+            "gas": self.start_gas.saturating_sub(self.spent),
+            "gasCost": gas_cost,
+            "depth": depth,
+        });
+        
+        if let Some(function) = function {
+            step["function"] = json!(function);
+        }
+        
+        self.steps.push(step);
+        self.next_pc += 1;
+        self.spent += gas_cost;
+        self
+    }
+}
+
+/// Build a profile with the given total gas and `(stack, gas)` hot paths
+///
+/// **Public** - for diff and baseline tests; every other field comes from
+/// a generated trace via `to_profile`, so it follows the schema
+pub fn profile_with_paths(total_gas: u64, paths: &[(&str, u64)]) -> Profile {
+    let raw = TraceBuilder::new().step("STOP", 1, 0).gas_used(total_gas).build();
+    let parsed = parse_trace("0xtest", &raw).expect("generated trace parses");
+    let hot_paths = paths
+        .iter()
+        .map(|(stack, gas)| HotPath {
+            stack: stack.to_string(),
+            gas: *gas,
+            percentage: if total_gas == 0 { 0.0 } else { *gas as f64 / total_gas as f64 * 100.0 },
+            source_hint: None,
+        })
+        .collect();
+    to_profile(&parsed, hot_paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults_gas_used_to_step_sum() {
+        let trace = TraceBuilder::new()
+            .step("PUSH1", 1, 3)
+            .step("SLOAD", 1, 2100)
+            .build();
+        
+        assert_eq!(trace["gasUsed"], 2103);
+        assert_eq!(trace["structLogs"].as_array().unwrap().len(), 2);
+        assert!(trace.get("hostio").is_none());
+    }
+
+    #[test]
+    fn test_builder_tracks_gas_remaining() {
+        let trace = TraceBuilder::new()
+            .start_gas(100)
+            .step("ADD", 1, 3)
+            .step("ADD", 1, 3)
+            .build();
+        
+        assert_eq!(trace["structLogs"][0]["gas"], 100);
+        assert_eq!(trace["structLogs"][1]["gas"], 97);
+    }
+
+    #[test]
+    fn test_builder_output_parses() {
+        let raw = TraceBuilder::new()
+            .function_step("transfer", "SLOAD", 2, 2100)
+            .repeat("ADD", 2, 3, 4)
+            .hostio("storage_load", 2100)
+            .gas_used(5000)
+            .build();
+        
+        let parsed = parse_trace("0xtest", &raw).unwrap();
+        
+        assert_eq!(parsed.total_gas_used, 5000);
+        assert_eq!(parsed.execution_steps.len(), 5);
+        assert_eq!(parsed.execution_steps[0].function.as_deref(), Some("transfer"));
+        assert_eq!(parsed.hostio_stats.total_calls(), 1);
+    }
+}